@@ -93,7 +93,8 @@ pub mod tcp {
 /// UDP transport
 pub mod udp {
     pub use ockam_transport_udp::{
-        RendezvousClient, RendezvousService, UdpBind, UdpBindArguments, UdpBindOptions,
+        PunctureEndpoint, PunctureSignaling, RendezvousClient, RendezvousService,
+        RoutePunctureSignaling, UdpBind, UdpBindArguments, UdpBindOptions, UdpCompression,
         UdpPuncture, UdpPunctureNegotiation, UdpPunctureNegotiationListener,
         UdpPunctureNegotiationListenerOptions, UdpTransport, UdpTransportExtension,
         MAX_MESSAGE_SIZE, UDP,
@@ -3,7 +3,7 @@ use core::ops::Deref;
 use ockam::identity::{Identifier, TimestampInSeconds};
 use ockam_core::compat::collections::BTreeMap;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PreTrustedIdentity {
     attrs: BTreeMap<Vec<u8>, Vec<u8>>,
     added_at: TimestampInSeconds,
@@ -39,7 +39,7 @@ impl PreTrustedIdentity {
     }
 }
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct PreTrustedIdentities(BTreeMap<Identifier, PreTrustedIdentity>);
 
 impl Deref for PreTrustedIdentities {
@@ -16,14 +16,15 @@ use ockam::identity::{
     SecureChannels, TrustEveryonePolicy,
 };
 use ockam::tcp::{TcpListenerOptions, TcpTransport};
-use ockam_core::compat::sync::Arc;
+use ockam_core::compat::sync::{Arc, Mutex};
 use ockam_core::env::get_env;
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::Result;
+use ockam_core::{Address, Result};
 use ockam_node::database::SqlxDatabase;
 use ockam_node::Context;
 
-use crate::authority_node::Configuration;
+use crate::authority_node::{Configuration, ConfigurationChanges};
 use crate::echoer::Echoer;
 use crate::nodes::service::default_address::DefaultAddress;
 
@@ -42,6 +43,22 @@ pub struct Authority {
     members: Arc<dyn AuthorityMembersRepository>,
     tokens: Arc<dyn AuthorityEnrollmentTokenRepository>,
     account_authority: Option<AccountAuthorityInfo>,
+    credential_issuer: Arc<Mutex<Option<CredentialIssuerHandle>>>,
+    services: Arc<Mutex<Option<ServicesHandle>>>,
+}
+
+/// Information about a running credential issuer, kept to be able to restart it
+#[derive(Clone)]
+struct CredentialIssuerHandle {
+    address: Address,
+    secure_channel_flow_control_id: FlowControlId,
+}
+
+/// Configuration of the running services, kept to be able to reload them
+#[derive(Clone)]
+struct ServicesHandle {
+    configuration: Configuration,
+    secure_channel_flow_control_id: FlowControlId,
 }
 
 /// Public functions to:
@@ -72,7 +89,7 @@ impl Authority {
         let database = if let Some(database) = database {
             database
         } else {
-            SqlxDatabase::create(&configuration.database_configuration).await?
+            SqlxDatabase::create(configuration.database_configuration()).await?
         };
 
         let members = AuthorityMembersSqlxDatabase::make_repository(database.clone());
@@ -90,7 +107,7 @@ impl Authority {
         let identifier = configuration.identifier();
         info!(identifier=%identifier, "retrieved the authority identifier");
         let account_authority =
-            if let Some(change_history) = configuration.account_authority.clone() {
+            if let Some(change_history) = configuration.account_authority().cloned() {
                 let acc_authority_identifier = identities
                     .identities_creation()
                     .identities_verification()
@@ -99,7 +116,7 @@ impl Authority {
                 Some(AccountAuthorityInfo::new(
                     acc_authority_identifier,
                     configuration.project_identifier(),
-                    configuration.enforce_admin_checks,
+                    configuration.enforce_admin_checks(),
                 ))
             } else {
                 None
@@ -110,6 +127,8 @@ impl Authority {
             members,
             tokens,
             account_authority,
+            credential_issuer: Default::default(),
+            services: Default::default(),
         })
     }
 
@@ -156,6 +175,11 @@ impl Authority {
             .await?;
 
         info!("started a TCP listener at {listener:?}");
+
+        *self.services.lock().unwrap() = Some(ServicesHandle {
+            configuration: configuration.clone(),
+            secure_channel_flow_control_id: secure_channel_listener_flow_control_id.clone(),
+        });
         Ok(secure_channel_listener_flow_control_id)
     }
 
@@ -166,7 +190,7 @@ impl Authority {
         secure_channel_flow_control_id: &FlowControlId,
         configuration: &Configuration,
     ) -> Result<()> {
-        if configuration.no_direct_authentication {
+        if configuration.no_direct_authentication() {
             return Ok(());
         }
 
@@ -194,7 +218,7 @@ impl Authority {
         secure_channel_flow_control_id: &FlowControlId,
         configuration: &Configuration,
     ) -> Result<()> {
-        if configuration.no_token_enrollment {
+        if configuration.no_token_enrollment() {
             return Ok(());
         }
 
@@ -257,7 +281,7 @@ impl Authority {
             configuration.project_identifier(),
             ttl,
             self.account_authority.clone(),
-            configuration.disable_trust_context_id,
+            configuration.disable_trust_context_id(),
         );
 
         let address = DefaultAddress::CREDENTIAL_ISSUER.to_string();
@@ -266,10 +290,123 @@ impl Authority {
 
         ctx.start_worker(address.clone(), issuer)?;
 
+        *self.credential_issuer.lock().unwrap() = Some(CredentialIssuerHandle {
+            address: address.clone().into(),
+            secure_channel_flow_control_id: secure_channel_flow_control_id.clone(),
+        });
+
         info!("started a credential issuer at '{address}'");
         Ok(())
     }
 
+    /// Reload the credential issuer with a new configuration.
+    ///
+    /// The trusted identities of the configuration replace the previous ones and the
+    /// credential issuer is restarted. The secure channel listener and the other services
+    /// keep running, so that existing secure channels are not dropped.
+    pub async fn reload_credential_issuer(
+        &self,
+        ctx: &Context,
+        configuration: &Configuration,
+    ) -> Result<()> {
+        let handle = self.credential_issuer.lock().unwrap().clone();
+        let handle = handle.ok_or_else(|| {
+            ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotReady,
+                "the credential issuer must be started before being reloaded",
+            )
+        })?;
+
+        Self::bootstrap_repository(self.members.clone(), configuration).await?;
+
+        ctx.stop_address(&handle.address)?;
+        self.start_credential_issuer(ctx, &handle.secure_channel_flow_control_id, configuration)?;
+
+        info!("reloaded the credential issuer at '{}'", handle.address);
+        Ok(())
+    }
+
+    /// Reload the authority with a new configuration.
+    ///
+    /// The new configuration is compared with the configuration used to start the services
+    /// and only the affected services are restarted. The secure channel listener keeps
+    /// running, so that existing secure channels are not dropped. If a setting which can only
+    /// be changed by restarting the node was modified, an error is returned and no service
+    /// is restarted.
+    pub async fn reload(
+        &self,
+        ctx: &Context,
+        new_configuration: &Configuration,
+    ) -> Result<ConfigurationChanges> {
+        let handle = self.services.lock().unwrap().clone();
+        let handle = handle.ok_or_else(|| {
+            ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotReady,
+                "the authority services must be started before being reloaded",
+            )
+        })?;
+        let old_configuration = &handle.configuration;
+        let secure_channel_flow_control_id = &handle.secure_channel_flow_control_id;
+
+        let changes = old_configuration.changes(new_configuration);
+        if !changes.requires_restart.is_empty() {
+            return Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "the authority node must be restarted to change: {}",
+                    changes.requires_restart.join(", ")
+                ),
+            ));
+        }
+
+        if changes.direct_authenticator {
+            if !old_configuration.no_direct_authentication() {
+                ctx.stop_address(&old_configuration.authenticator_name().into())?;
+            }
+            self.start_direct_authenticator(
+                ctx,
+                secure_channel_flow_control_id,
+                new_configuration,
+            )?;
+        }
+
+        if changes.enrollment_services {
+            if !old_configuration.no_token_enrollment() {
+                ctx.stop_address(&DefaultAddress::ENROLLMENT_TOKEN_ISSUER.into())?;
+                ctx.stop_address(&DefaultAddress::ENROLLMENT_TOKEN_ACCEPTOR.into())?;
+            }
+            self.start_enrollment_services(ctx, secure_channel_flow_control_id, new_configuration)?;
+        }
+
+        if changes.credential_issuer {
+            self.reload_credential_issuer(ctx, new_configuration)
+                .await?;
+        }
+
+        if changes.okta {
+            if let Some(okta) = old_configuration.okta() {
+                ctx.stop_address(&okta.address.clone().into())?;
+            }
+            self.start_okta(ctx, secure_channel_flow_control_id, new_configuration)?;
+        }
+
+        if changes.echo_service {
+            ctx.stop_address(&old_configuration.echo_service_name().into())?;
+            self.start_echo_service(ctx, secure_channel_flow_control_id, new_configuration)?;
+        }
+
+        *self.services.lock().unwrap() = Some(ServicesHandle {
+            configuration: new_configuration.clone(),
+            secure_channel_flow_control_id: secure_channel_flow_control_id.clone(),
+        });
+
+        info!(?changes, "reloaded the authority configuration");
+        Ok(changes)
+    }
+
     /// Start the Okta service to retrieve attributes authenticated by Okta
     pub fn start_okta(
         &self,
@@ -277,7 +414,7 @@ impl Authority {
         secure_channel_flow_control_id: &FlowControlId,
         configuration: &Configuration,
     ) -> Result<()> {
-        if let Some(okta) = &configuration.okta {
+        if let Some(okta) = configuration.okta() {
             let okta_worker = crate::okta::Server::new(
                 &self.identifier,
                 self.members.clone(),
@@ -299,11 +436,12 @@ impl Authority {
         &self,
         ctx: &Context,
         secure_channel_flow_control_id: &FlowControlId,
+        configuration: &Configuration,
     ) -> Result<()> {
-        let address = DefaultAddress::ECHO_SERVICE;
+        let address = configuration.echo_service_name();
 
         ctx.flow_controls()
-            .add_consumer(&address.into(), secure_channel_flow_control_id);
+            .add_consumer(&address.clone().into(), secure_channel_flow_control_id);
 
         ctx.start_worker(address, Echoer)
     }
@@ -346,8 +484,8 @@ impl Authority {
     ) -> Result<()> {
         members
             .bootstrap_pre_trusted_members(
-                &configuration.identifier,
-                &configuration.trusted_identities,
+                &configuration.identifier(),
+                configuration.trusted_identities(),
             )
             .await
     }
@@ -503,21 +641,14 @@ mod tests {
             );
         }
 
-        Ok(Configuration {
-            identifier: authority.clone(),
+        Configuration::builder(
+            authority.clone(),
             database_configuration,
-            project_identifier: "123456".to_string(),
-            tcp_listener_address: InternetAddress::new(&format!("127.0.0.1:{}", port)).unwrap(),
-            secure_channel_listener_name: None,
-            authenticator_name: None,
-            trusted_identities: PreTrustedIdentities::new(trusted_identities),
-            no_direct_authentication: false,
-            no_token_enrollment: false,
-            okta: None,
-            account_authority: None,
-            enforce_admin_checks: false,
-            disable_trust_context_id: false,
-        })
+            "123456",
+            InternetAddress::new(&format!("127.0.0.1:{}", port)).unwrap(),
+        )
+        .with_trusted_identities(PreTrustedIdentities::new(trusted_identities))
+        .build()
     }
 
     /// Make a client to access the services of an Authority
@@ -559,7 +690,7 @@ mod tests {
         let configuration =
             create_configuration(db.configuration.clone(), &authority, port, trusted)?;
         let authority = Authority::create(&configuration, Some(db.clone())).await?;
-        authority_node::start_node(ctx, &configuration, authority.clone()).await?;
+        authority_node::start_node(ctx, configuration, authority.clone()).await?;
         Ok(authority)
     }
 
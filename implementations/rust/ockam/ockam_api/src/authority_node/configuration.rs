@@ -5,6 +5,8 @@ use ockam::identity::Identifier;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::fmt;
 use ockam_core::compat::fmt::{Display, Formatter};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::Result;
 use ockam_node::database::DatabaseConfiguration;
 
 use crate::authenticator::PreTrustedIdentities;
@@ -12,60 +14,71 @@ use crate::config::lookup::InternetAddress;
 use crate::nodes::service::default_address::DefaultAddress;
 
 /// Configuration for the Authority node
+///
+/// A configuration can only be created with [`Configuration::builder`], which validates it
 #[derive(Debug, Clone)]
 pub struct Configuration {
     /// Authority identity or identity associated with the newly created node
-    pub identifier: Identifier,
+    identifier: Identifier,
 
     /// path where the database should be stored
-    pub database_configuration: DatabaseConfiguration,
+    database_configuration: DatabaseConfiguration,
 
     /// Project id on the Orchestrator node
-    pub project_identifier: String,
+    project_identifier: String,
 
     /// listener address for the TCP listener, for example "127.0.0.1:4000"
-    pub tcp_listener_address: InternetAddress,
+    tcp_listener_address: InternetAddress,
 
     /// service name for the secure channel listener, for example "api"
     /// The default is DefaultAddress::SECURE_CHANNEL_LISTENER
-    pub secure_channel_listener_name: Option<String>,
+    secure_channel_listener_name: Option<String>,
 
     /// Service name for the direct authenticator, for example "direct_authenticator"
     /// The default is DefaultAddress::DIRECT_AUTHENTICATOR
-    pub authenticator_name: Option<String>,
+    authenticator_name: Option<String>,
+
+    /// Service name for the echo service used to check the node health, for example "echo"
+    /// The default is DefaultAddress::ECHO_SERVICE
+    echo_service_name: Option<String>,
 
     /// list of trusted identities (identities with the ockam-role: enroller)
-    pub trusted_identities: PreTrustedIdentities,
+    trusted_identities: PreTrustedIdentities,
 
     /// If true don't start the direct authenticator service
-    pub no_direct_authentication: bool,
+    no_direct_authentication: bool,
 
     /// If true don't start the token enroller service
-    pub no_token_enrollment: bool,
+    no_token_enrollment: bool,
 
     /// optional configuration for the okta service
-    pub okta: Option<OktaConfiguration>,
+    okta: Option<OktaConfiguration>,
 
     /// Account Authority identity
-    pub account_authority: Option<ChangeHistory>,
+    account_authority: Option<ChangeHistory>,
 
     /// Differentiate between admins and enrollers
-    pub enforce_admin_checks: bool,
+    enforce_admin_checks: bool,
 
     /// Will not include trust_context_id and project id into credential
     /// Set to true after old clients are updated
-    pub disable_trust_context_id: bool,
+    disable_trust_context_id: bool,
 }
 
 /// Local and private functions for the authority configuration
 impl Configuration {
     /// Return the authority identity identifier
-    pub(crate) fn identifier(&self) -> Identifier {
+    pub fn identifier(&self) -> Identifier {
         self.identifier.clone()
     }
 
+    /// Return the configuration of the authority database
+    pub fn database_configuration(&self) -> &DatabaseConfiguration {
+        &self.database_configuration
+    }
+
     /// Return the project id as bytes
-    pub(crate) fn project_identifier(&self) -> String {
+    pub fn project_identifier(&self) -> String {
         self.project_identifier.clone()
     }
 
@@ -87,6 +100,293 @@ impl Configuration {
             .clone()
             .unwrap_or(DefaultAddress::DIRECT_AUTHENTICATOR.to_string())
     }
+
+    /// Return the service name for the echo service
+    pub(crate) fn echo_service_name(&self) -> String {
+        self.echo_service_name
+            .clone()
+            .unwrap_or(DefaultAddress::ECHO_SERVICE.to_string())
+    }
+
+    /// Return the identities trusted by the authority
+    pub(crate) fn trusted_identities(&self) -> &PreTrustedIdentities {
+        &self.trusted_identities
+    }
+
+    /// Return true if the direct authenticator service must not be started
+    pub(crate) fn no_direct_authentication(&self) -> bool {
+        self.no_direct_authentication
+    }
+
+    /// Return true if the token enrollment services must not be started
+    pub(crate) fn no_token_enrollment(&self) -> bool {
+        self.no_token_enrollment
+    }
+
+    /// Return the configuration of the Okta service, if it must be started
+    pub(crate) fn okta(&self) -> Option<&OktaConfiguration> {
+        self.okta.as_ref()
+    }
+
+    /// Return the Account Authority identity
+    pub(crate) fn account_authority(&self) -> Option<&ChangeHistory> {
+        self.account_authority.as_ref()
+    }
+
+    /// Return true if admins and enrollers are differentiated
+    pub(crate) fn enforce_admin_checks(&self) -> bool {
+        self.enforce_admin_checks
+    }
+
+    /// Return true if the trust_context_id and project id are not included into credentials
+    pub(crate) fn disable_trust_context_id(&self) -> bool {
+        self.disable_trust_context_id
+    }
+
+    /// Return the addresses of the services started by [`start_node`](crate::authority_node::start_node)
+    /// with this configuration
+    pub(crate) fn service_addresses(&self) -> Vec<String> {
+        let mut addresses = vec![self.secure_channel_listener_name()];
+        if !self.no_direct_authentication {
+            addresses.push(self.authenticator_name());
+        }
+        if !self.no_token_enrollment {
+            addresses.push(DefaultAddress::ENROLLMENT_TOKEN_ISSUER.to_string());
+            addresses.push(DefaultAddress::ENROLLMENT_TOKEN_ACCEPTOR.to_string());
+        }
+        addresses.push(DefaultAddress::CREDENTIAL_ISSUER.to_string());
+        if let Some(okta) = &self.okta {
+            addresses.push(okta.address.clone());
+        }
+        addresses.push(self.echo_service_name());
+        addresses
+    }
+}
+
+impl Configuration {
+    /// Return the services of an authority node which must be restarted to apply
+    /// a new configuration
+    pub fn changes(&self, new_configuration: &Configuration) -> ConfigurationChanges {
+        let new = new_configuration;
+        let mut requires_restart = vec![];
+        if self.identifier != new.identifier {
+            requires_restart.push("identifier");
+        }
+        if self.database_configuration != new.database_configuration {
+            requires_restart.push("database_configuration");
+        }
+        if self.project_identifier != new.project_identifier {
+            requires_restart.push("project_identifier");
+        }
+        if self.tcp_listener_address != new.tcp_listener_address {
+            requires_restart.push("tcp_listener_address");
+        }
+        if self.secure_channel_listener_name() != new.secure_channel_listener_name() {
+            requires_restart.push("secure_channel_listener_name");
+        }
+        if self.account_authority != new.account_authority {
+            requires_restart.push("account_authority");
+        }
+        if self.enforce_admin_checks != new.enforce_admin_checks {
+            requires_restart.push("enforce_admin_checks");
+        }
+
+        ConfigurationChanges {
+            requires_restart,
+            direct_authenticator: self.no_direct_authentication != new.no_direct_authentication
+                || self.authenticator_name() != new.authenticator_name(),
+            enrollment_services: self.no_token_enrollment != new.no_token_enrollment,
+            credential_issuer: self.trusted_identities != new.trusted_identities
+                || self.disable_trust_context_id != new.disable_trust_context_id,
+            okta: self.okta != new.okta,
+            echo_service: self.echo_service_name() != new.echo_service_name(),
+        }
+    }
+
+    /// Start building a new configuration from this one, for example to reload an authority
+    /// node with different settings
+    pub fn to_builder(&self) -> ConfigurationBuilder {
+        ConfigurationBuilder {
+            configuration: self.clone(),
+        }
+    }
+
+    /// Start building a configuration with the mandatory parameters.
+    ///
+    /// All the optional services are enabled by default
+    pub fn builder(
+        identifier: Identifier,
+        database_configuration: DatabaseConfiguration,
+        project_identifier: impl Into<String>,
+        tcp_listener_address: InternetAddress,
+    ) -> ConfigurationBuilder {
+        ConfigurationBuilder {
+            configuration: Configuration {
+                identifier,
+                database_configuration,
+                project_identifier: project_identifier.into(),
+                tcp_listener_address,
+                secure_channel_listener_name: None,
+                authenticator_name: None,
+                echo_service_name: None,
+                trusted_identities: Default::default(),
+                no_direct_authentication: false,
+                no_token_enrollment: false,
+                okta: None,
+                account_authority: None,
+                enforce_admin_checks: false,
+                disable_trust_context_id: false,
+            },
+        }
+    }
+}
+
+/// Builder for an authority [`Configuration`].
+///
+/// The configuration is validated when calling [`ConfigurationBuilder::build`] so that
+/// inconsistent settings are reported before any service is started
+#[derive(Debug, Clone)]
+pub struct ConfigurationBuilder {
+    configuration: Configuration,
+}
+
+impl ConfigurationBuilder {
+    /// Set the service name for the secure channel listener
+    pub fn with_secure_channel_listener_name(mut self, name: impl Into<String>) -> Self {
+        self.configuration.secure_channel_listener_name = Some(name.into());
+        self
+    }
+
+    /// Set the service name for the direct authenticator
+    pub fn with_authenticator_name(mut self, name: impl Into<String>) -> Self {
+        self.configuration.authenticator_name = Some(name.into());
+        self
+    }
+
+    /// Set the service name for the echo service
+    pub fn with_echo_service_name(mut self, name: impl Into<String>) -> Self {
+        self.configuration.echo_service_name = Some(name.into());
+        self
+    }
+
+    /// Set the identities trusted by the authority
+    pub fn with_trusted_identities(mut self, trusted_identities: PreTrustedIdentities) -> Self {
+        self.configuration.trusted_identities = trusted_identities;
+        self
+    }
+
+    /// Enable or disable the direct authenticator service
+    pub fn with_direct_authentication(mut self, enabled: bool) -> Self {
+        self.configuration.no_direct_authentication = !enabled;
+        self
+    }
+
+    /// Enable or disable the token enrollment services
+    pub fn with_token_enrollment(mut self, enabled: bool) -> Self {
+        self.configuration.no_token_enrollment = !enabled;
+        self
+    }
+
+    /// Start the Okta service with the given configuration
+    pub fn with_okta(mut self, okta: OktaConfiguration) -> Self {
+        self.configuration.okta = Some(okta);
+        self
+    }
+
+    /// Set the Account Authority identity
+    pub fn with_account_authority(mut self, account_authority: ChangeHistory) -> Self {
+        self.configuration.account_authority = Some(account_authority);
+        self
+    }
+
+    /// Differentiate between admins and enrollers
+    pub fn with_enforce_admin_checks(mut self, enforce_admin_checks: bool) -> Self {
+        self.configuration.enforce_admin_checks = enforce_admin_checks;
+        self
+    }
+
+    /// Don't include the trust_context_id and project id into credentials
+    pub fn with_disable_trust_context_id(mut self, disable_trust_context_id: bool) -> Self {
+        self.configuration.disable_trust_context_id = disable_trust_context_id;
+        self
+    }
+
+    /// Validate the configuration and return it
+    pub fn build(self) -> Result<Configuration> {
+        let configuration = self.configuration;
+
+        if let Some(okta) = &configuration.okta {
+            okta.validate()?;
+        }
+
+        for (identifier, trusted_identity) in configuration.trusted_identities.iter() {
+            if trusted_identity.attested_by() != &configuration.identifier {
+                return Err(invalid_configuration(format!(
+                    "the trusted identity {identifier} is attested by {} instead of the authority {}",
+                    trusted_identity.attested_by(),
+                    configuration.identifier
+                )));
+            }
+        }
+
+        let mut service_names = vec![
+            (
+                "secure channel listener",
+                configuration.secure_channel_listener_name(),
+            ),
+            ("direct authenticator", configuration.authenticator_name()),
+            ("echo", configuration.echo_service_name()),
+        ];
+        if let Some(okta) = &configuration.okta {
+            service_names.push(("okta", okta.address.clone()));
+        }
+        for (i, (service, name)) in service_names.iter().enumerate() {
+            if name.is_empty() {
+                return Err(invalid_configuration(format!(
+                    "the {service} service name must not be empty"
+                )));
+            }
+            if let Some((other_service, _)) = service_names[..i].iter().find(|(_, n)| n == name) {
+                return Err(invalid_configuration(format!(
+                    "the {service} and {other_service} services can't both use the name {name}"
+                )));
+            }
+        }
+
+        Ok(configuration)
+    }
+}
+
+/// Difference between two authority configurations, see [`Configuration::changes`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigurationChanges {
+    /// Changed settings which can only be applied by restarting the authority node
+    pub requires_restart: Vec<&'static str>,
+    /// True if the direct authenticator must be restarted
+    pub direct_authenticator: bool,
+    /// True if the enrollment token services must be restarted
+    pub enrollment_services: bool,
+    /// True if the credential issuer must be restarted
+    pub credential_issuer: bool,
+    /// True if the Okta service must be restarted
+    pub okta: bool,
+    /// True if the echo service must be restarted
+    pub echo_service: bool,
+}
+
+impl ConfigurationChanges {
+    /// Return true if no service needs to be restarted
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+fn invalid_configuration(message: String) -> ockam_core::Error {
+    ockam_core::Error::new(
+        Origin::Api,
+        Kind::Invalid,
+        format!("Invalid authority configuration: {message}"),
+    )
 }
 
 /// Configuration for the Okta service
@@ -115,6 +415,27 @@ impl OktaConfiguration {
     pub(crate) fn attributes(&self) -> Vec<String> {
         self.attributes.clone()
     }
+
+    /// Check that all the parameters needed to connect to Okta are present
+    fn validate(&self) -> Result<()> {
+        if self.tenant_base_url.is_empty() {
+            return Err(invalid_configuration(
+                "the Okta tenant base URL is missing".to_string(),
+            ));
+        }
+        if let Err(e) = url::Url::parse(&self.tenant_base_url) {
+            return Err(invalid_configuration(format!(
+                "the Okta tenant base URL {} is not a valid URL: {e}",
+                self.tenant_base_url
+            )));
+        }
+        if self.certificate.is_empty() {
+            return Err(invalid_configuration(
+                "the Okta certificate is missing".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// This struct represents an identity that the Authority accepts
@@ -147,3 +468,124 @@ impl TrustedIdentity {
         self.identifier.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authenticator::PreTrustedIdentity;
+    use ockam::identity::TimestampInSeconds;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_build_configuration() -> Result<()> {
+        let configuration = builder().with_okta(okta_configuration()).build()?;
+        assert!(configuration.okta.is_some());
+        assert!(!configuration.no_direct_authentication);
+        assert!(!configuration.no_token_enrollment);
+
+        let configuration = builder()
+            .with_direct_authentication(false)
+            .with_token_enrollment(false)
+            .build()?;
+        assert!(configuration.no_direct_authentication);
+        assert!(configuration.no_token_enrollment);
+        Ok(())
+    }
+
+    #[test]
+    fn test_okta_without_tenant_url_is_invalid() {
+        let okta = OktaConfiguration {
+            tenant_base_url: "".to_string(),
+            ..okta_configuration()
+        };
+        assert!(builder().with_okta(okta).build().is_err());
+
+        let okta = OktaConfiguration {
+            tenant_base_url: "not a url".to_string(),
+            ..okta_configuration()
+        };
+        assert!(builder().with_okta(okta).build().is_err());
+    }
+
+    #[test]
+    fn test_conflicting_service_names_are_invalid() -> Result<()> {
+        let result = builder()
+            .with_secure_channel_listener_name("api")
+            .with_authenticator_name("api")
+            .build();
+        assert!(result.is_err());
+
+        let result = builder().with_echo_service_name("api").build();
+        assert!(result.is_err());
+
+        let configuration = builder().with_echo_service_name("echo2").build()?;
+        assert_eq!(configuration.echo_service_name(), "echo2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_trusted_identity_attested_by_another_authority_is_invalid() -> Result<()> {
+        let other: Identifier =
+            "I0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcde0".try_into()?;
+        let trusted_identities = PreTrustedIdentities::new(BTreeMap::from([(
+            other.clone(),
+            PreTrustedIdentity::new(BTreeMap::new(), TimestampInSeconds(0), None, other),
+        )]));
+
+        let result = builder()
+            .with_trusted_identities(trusted_identities)
+            .build();
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_configuration_changes() -> Result<()> {
+        let configuration = builder().build()?;
+        assert!(configuration.changes(&configuration.clone()).is_empty());
+
+        let new_configuration = builder()
+            .with_okta(okta_configuration())
+            .with_token_enrollment(false)
+            .build()?;
+        let changes = configuration.changes(&new_configuration);
+        assert!(changes.requires_restart.is_empty());
+        assert!(changes.okta);
+        assert!(changes.enrollment_services);
+        assert!(!changes.direct_authenticator);
+        assert!(!changes.credential_issuer);
+        assert!(!changes.echo_service);
+
+        let new_configuration = builder()
+            .with_secure_channel_listener_name("other_api")
+            .with_echo_service_name("echo2")
+            .build()?;
+        let changes = configuration.changes(&new_configuration);
+        assert_eq!(
+            changes.requires_restart,
+            vec!["secure_channel_listener_name"]
+        );
+        assert!(changes.echo_service);
+        Ok(())
+    }
+
+    fn builder() -> ConfigurationBuilder {
+        Configuration::builder(
+            "I0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+                .try_into()
+                .unwrap(),
+            DatabaseConfiguration::sqlite_in_memory(),
+            "project",
+            InternetAddress::new("127.0.0.1:4000").unwrap(),
+        )
+    }
+
+    fn okta_configuration() -> OktaConfiguration {
+        OktaConfiguration {
+            address: DefaultAddress::OKTA_IDENTITY_PROVIDER.to_string(),
+            tenant_base_url: "https://example.okta.com".to_string(),
+            certificate: "certificate".to_string(),
+            attributes: vec!["email".to_string()],
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use crate::authority_node::{Authority, Configuration};
-use ockam_core::Result;
+use ockam_core::compat::time::Duration;
+use ockam_core::{Address, Result};
 use ockam_node::Context;
+use std::time::Instant;
 use tracing::info;
 
 /// Start all the necessary services for an authority node
+///
+/// The configuration is validated when built, see [`Configuration::builder`]
 pub async fn start_node(
     ctx: &Context,
-    configuration: &Configuration,
+    configuration: Configuration,
     authority: Authority,
 ) -> Result<()> {
     debug!("starting authority node");
@@ -14,26 +18,26 @@ pub async fn start_node(
     debug!("starting services");
     // start a secure channel listener (this also starts a TCP transport)
     let secure_channel_flow_control_id = authority
-        .start_secure_channel_listener(ctx, configuration)
+        .start_secure_channel_listener(ctx, &configuration)
         .await?;
     debug!("secure channel listener started");
 
     // start the authenticator services
-    authority.start_direct_authenticator(ctx, &secure_channel_flow_control_id, configuration)?;
+    authority.start_direct_authenticator(ctx, &secure_channel_flow_control_id, &configuration)?;
     debug!("direct authenticator started");
 
-    authority.start_enrollment_services(ctx, &secure_channel_flow_control_id, configuration)?;
+    authority.start_enrollment_services(ctx, &secure_channel_flow_control_id, &configuration)?;
     debug!("enrollment services started");
 
-    authority.start_credential_issuer(ctx, &secure_channel_flow_control_id, configuration)?;
+    authority.start_credential_issuer(ctx, &secure_channel_flow_control_id, &configuration)?;
     debug!("credential issuer started");
 
     // start the Okta service (if the optional configuration has been provided)
-    authority.start_okta(ctx, &secure_channel_flow_control_id, configuration)?;
+    authority.start_okta(ctx, &secure_channel_flow_control_id, &configuration)?;
     debug!("okta service started");
 
     // start an echo service so that the node can be queried as healthy
-    authority.start_echo_service(ctx, &secure_channel_flow_control_id)?;
+    authority.start_echo_service(ctx, &secure_channel_flow_control_id, &configuration)?;
 
     debug!("echo service started");
 
@@ -41,3 +45,36 @@ pub async fn start_node(
 
     Ok(())
 }
+
+/// Start all the necessary services for an authority node, and wait until they are ready to
+/// handle messages, see [`wait_ready`]
+pub async fn start_node_and_wait_ready(
+    ctx: &Context,
+    configuration: Configuration,
+    authority: Authority,
+    timeout: Duration,
+) -> Result<()> {
+    start_node(ctx, configuration.clone(), authority).await?;
+    wait_ready(ctx, &configuration, timeout).await
+}
+
+/// Wait until the initialization of all the services started by [`start_node`] completed
+///
+/// The services are started asynchronously, so an orchestrator can use this function to know
+/// when the authority node can actually serve requests. Fails if a service could not be
+/// initialized, or if the services are not ready before the timeout.
+pub async fn wait_ready(
+    ctx: &Context,
+    configuration: &Configuration,
+    timeout: Duration,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    for address in configuration.service_addresses() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        ctx.wait_for_initialization(&Address::from_string(address), remaining)
+            .await?;
+    }
+
+    info!("authority node is ready");
+    Ok(())
+}
@@ -3,6 +3,7 @@ use ockam_core::async_trait;
 use ockam_core::Result;
 use ockam_node::database::AutoRetry;
 use ockam_node::retry;
+use time::OffsetDateTime;
 
 /// This trait supports the storage of spaces as retrieved from the Controller
 ///
@@ -14,6 +15,9 @@ pub trait SpacesRepository: Send + Sync + 'static {
     /// Store a space
     async fn store_space(&self, space: &Space) -> Result<()>;
 
+    /// Store a space and set it as the default one, in a single transaction
+    async fn store_and_set_default(&self, space: &Space) -> Result<()>;
+
     /// Return a space for a given id
     async fn get_space(&self, space_id: &str) -> Result<Option<Space>>;
 
@@ -23,6 +27,11 @@ pub trait SpacesRepository: Send + Sync + 'static {
     /// Return the list of all spaces
     async fn get_spaces(&self) -> Result<Vec<Space>>;
 
+    /// Return the list of spaces stored at or after the given time
+    ///
+    /// The modification times are recorded with a precision of one second
+    async fn get_spaces_modified_since(&self, since: OffsetDateTime) -> Result<Vec<Space>>;
+
     /// Return the default space
     async fn get_default_space(&self) -> Result<Option<Space>>;
 
@@ -39,6 +48,10 @@ impl<T: SpacesRepository> SpacesRepository for AutoRetry<T> {
         retry!(self.wrapped.store_space(space))
     }
 
+    async fn store_and_set_default(&self, space: &Space) -> Result<()> {
+        retry!(self.wrapped.store_and_set_default(space))
+    }
+
     async fn get_space(&self, space_id: &str) -> Result<Option<Space>> {
         retry!(self.wrapped.get_space(space_id))
     }
@@ -51,6 +64,10 @@ impl<T: SpacesRepository> SpacesRepository for AutoRetry<T> {
         retry!(self.wrapped.get_spaces())
     }
 
+    async fn get_spaces_modified_since(&self, since: OffsetDateTime) -> Result<Vec<Space>> {
+        retry!(self.wrapped.get_spaces_modified_since(since))
+    }
+
     async fn get_default_space(&self) -> Result<Option<Space>> {
         retry!(self.wrapped.get_default_space())
     }
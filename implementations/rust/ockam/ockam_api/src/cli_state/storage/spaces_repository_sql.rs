@@ -46,30 +46,38 @@ impl SpacesSqlxDatabase {
         row.map(|r| r.subscription()).transpose()
     }
 
-    async fn set_as_default(&self, space_id: &str, transaction: &mut AnyConnection) -> Result<()> {
-        // set the space as the default one
-        let query1 = query("UPDATE space SET is_default = $1 WHERE space_id = $2")
-            .bind(true)
-            .bind(space_id);
-        query1.execute(&mut *transaction).await.void()?;
-
-        // set all the others as non-default
-        let query2 = query("UPDATE space SET is_default = $1 WHERE space_id <> $2")
-            .bind(false)
-            .bind(space_id);
-        query2.execute(&mut *transaction).await.void()?;
-
-        Ok(())
+    /// Complete the spaces read from the space table with their users and subscription
+    async fn load_spaces(
+        &self,
+        rows: Vec<SpaceRow>,
+        transaction: &mut AnyConnection,
+    ) -> Result<Vec<Space>> {
+        let mut spaces = vec![];
+        for row in rows {
+            let query2 =
+                query_as("SELECT space_id, user_email FROM user_space WHERE space_id = $1")
+                    .bind(&row.space_id);
+            let rows: Vec<UserSpaceRow> = query2.fetch_all(&mut *transaction).await.into_core()?;
+            let users = rows.into_iter().map(|r| r.user_email).collect();
+            let subscription = self.query_subscription(&row.space_id).await?;
+            let mut space = row.space();
+            space.users = users;
+            space.subscription = subscription;
+            spaces.push(space);
+        }
+        Ok(spaces)
     }
-}
-
-#[async_trait]
-impl SpacesRepository for SpacesSqlxDatabase {
-    async fn store_space(&self, space: &Space) -> Result<()> {
-        let mut transaction = self.database.begin().await.into_core()?;
 
-        // Set to default if there is no default space
-        let is_default = {
+    /// Store a space, it becomes the default space if `set_default` is true or if there is
+    /// no default space yet
+    async fn store_in_transaction(
+        &self,
+        space: &Space,
+        set_default: bool,
+        transaction: &mut AnyConnection,
+    ) -> Result<()> {
+        // Set to default if requested or if there is no default space
+        let is_default = set_default || {
             let default_space_id: Option<String> =
                 query("SELECT space_id FROM space WHERE is_default = $1")
                     .bind(true)
@@ -82,18 +90,19 @@ impl SpacesRepository for SpacesSqlxDatabase {
 
         let query2 = query(
             r#"
-             INSERT INTO space (space_id, space_name, is_default)
-             VALUES ($1, $2, $3)
+             INSERT INTO space (space_id, space_name, is_default, modified_at)
+             VALUES ($1, $2, $3, $4)
              ON CONFLICT (space_id)
-             DO UPDATE SET space_name = $2, is_default = $3"#,
+             DO UPDATE SET space_name = $2, is_default = $3, modified_at = $4"#,
         )
         .bind(&space.id)
         .bind(&space.name)
-        .bind(is_default);
+        .bind(is_default)
+        .bind(OffsetDateTime::now_utc().unix_timestamp());
         query2.execute(&mut *transaction).await.void()?;
 
         if is_default {
-            self.set_as_default(&space.id, &mut transaction).await?;
+            self.set_as_default(&space.id, transaction).await?;
         }
 
         // remove any existing users related to that space if any
@@ -138,6 +147,39 @@ impl SpacesRepository for SpacesSqlxDatabase {
             query.execute(&mut *transaction).await.void()?;
         }
 
+        Ok(())
+    }
+
+    async fn set_as_default(&self, space_id: &str, transaction: &mut AnyConnection) -> Result<()> {
+        // set the space as the default one
+        let query1 = query("UPDATE space SET is_default = $1 WHERE space_id = $2")
+            .bind(true)
+            .bind(space_id);
+        query1.execute(&mut *transaction).await.void()?;
+
+        // set all the others as non-default
+        let query2 = query("UPDATE space SET is_default = $1 WHERE space_id <> $2")
+            .bind(false)
+            .bind(space_id);
+        query2.execute(&mut *transaction).await.void()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SpacesRepository for SpacesSqlxDatabase {
+    async fn store_space(&self, space: &Space) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+        self.store_in_transaction(space, false, &mut transaction)
+            .await?;
+        transaction.commit().await.void()
+    }
+
+    async fn store_and_set_default(&self, space: &Space) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+        self.store_in_transaction(space, true, &mut transaction)
+            .await?;
         transaction.commit().await.void()
     }
 
@@ -189,20 +231,20 @@ impl SpacesRepository for SpacesSqlxDatabase {
 
         let query = query_as("SELECT space_id, space_name FROM space");
         let rows: Vec<SpaceRow> = query.fetch_all(&mut *transaction).await.into_core()?;
+        let spaces = self.load_spaces(rows, &mut transaction).await?;
 
-        let mut spaces = vec![];
-        for row in rows {
-            let query2 =
-                query_as("SELECT space_id, user_email FROM user_space WHERE space_id = $1")
-                    .bind(&row.space_id);
-            let rows: Vec<UserSpaceRow> = query2.fetch_all(&mut *transaction).await.into_core()?;
-            let users = rows.into_iter().map(|r| r.user_email).collect();
-            let subscription = self.query_subscription(&row.space_id).await?;
-            let mut space = row.space();
-            space.users = users;
-            space.subscription = subscription;
-            spaces.push(space);
-        }
+        transaction.commit().await.void()?;
+
+        Ok(spaces)
+    }
+
+    async fn get_spaces_modified_since(&self, since: OffsetDateTime) -> Result<Vec<Space>> {
+        let mut transaction = self.database.begin().await.into_core()?;
+
+        let query = query_as("SELECT space_id, space_name FROM space WHERE modified_at >= $1")
+            .bind(since.unix_timestamp());
+        let rows: Vec<SpaceRow> = query.fetch_all(&mut *transaction).await.into_core()?;
+        let spaces = self.load_spaces(rows, &mut transaction).await?;
 
         transaction.commit().await.void()?;
 
@@ -425,4 +467,94 @@ mod test {
         })
         .await
     }
+
+    #[tokio::test]
+    async fn test_store_and_set_default() -> Result<()> {
+        with_sqlite_dbs(|db| async move {
+            let repository = SpacesSqlxDatabase::new(db);
+
+            let space1 = Space {
+                id: "1".to_string(),
+                name: "name1".to_string(),
+                users: vec![],
+                subscription: None,
+            };
+            let space2 = Space {
+                id: "2".to_string(),
+                name: "name2".to_string(),
+                users: vec!["me@ockam.io".to_string()],
+                subscription: None,
+            };
+
+            repository.store_space(&space1).await?;
+            repository.store_and_set_default(&space2).await?;
+            let result = repository.get_default_space().await?;
+            assert_eq!(result, Some(space2.clone()));
+
+            // storing the other space again doesn't change the default space
+            repository.store_space(&space1).await?;
+            let result = repository.get_default_space().await?;
+            assert_eq!(result, Some(space2.clone()));
+
+            Ok(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_get_spaces_modified_since() -> Result<()> {
+        with_sqlite_dbs(|db| async move {
+            let repository = SpacesSqlxDatabase::new(db.clone());
+
+            let space1 = Space {
+                id: "1".to_string(),
+                name: "name1".to_string(),
+                users: vec!["me@ockam.io".to_string()],
+                subscription: None,
+            };
+            let space2 = Space {
+                id: "2".to_string(),
+                name: "name2".to_string(),
+                users: vec![],
+                subscription: None,
+            };
+            repository.store_space(&space1).await?;
+            repository.store_space(&space2).await?;
+
+            // simulate a space which was stored a day ago
+            let one_day_ago = OffsetDateTime::now_utc() - 1.days();
+            query("UPDATE space SET modified_at = $1 WHERE space_id = $2")
+                .bind(one_day_ago.unix_timestamp())
+                .bind(&space1.id)
+                .execute(&*db.pool)
+                .await
+                .void()?;
+
+            let result = repository
+                .get_spaces_modified_since(one_day_ago - 1.hours())
+                .await?;
+            assert_eq!(result.len(), 2);
+
+            let result = repository
+                .get_spaces_modified_since(one_day_ago + 1.hours())
+                .await?;
+            assert_eq!(result, vec![space2.clone()]);
+
+            // storing a space again marks it as modified
+            repository.store_space(&space1).await?;
+            let result = repository
+                .get_spaces_modified_since(one_day_ago + 1.hours())
+                .await?;
+            assert_eq!(result.len(), 2);
+            assert!(result.contains(&space1));
+
+            let result = repository
+                .get_spaces_modified_since(OffsetDateTime::now_utc() + 1.hours())
+                .await?;
+            assert!(result.is_empty());
+
+            Ok(())
+        })
+        .await
+    }
 }
@@ -1,4 +1,7 @@
 use minicbor::{CborLen, Decode, Encode};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_transport_tcp::MAX_MESSAGE_SIZE;
 use serde::Serialize;
 
 use super::RoleInShare;
@@ -8,6 +11,11 @@ use super::RoleInShare;
 #[rustfmt::skip]
 pub struct AcceptInvitation {
     #[n(1)] pub id: String,
+    /// Narrower role than the one granted by the invitation. The server may honor or reject it
+    #[n(2)] pub requested_scope: Option<RoleInShare>,
+    /// Generated by the client and kept across retries, so that the server can return the same
+    /// [`AcceptedInvitation`] when an accept is received twice
+    #[n(3)] pub idempotency_key: Option<String>,
 }
 
 #[derive(Clone, Debug, Encode, Decode, CborLen, Serialize)]
@@ -18,3 +26,153 @@ pub struct AcceptedInvitation {
     #[n(2)] pub scope: RoleInShare,
     #[n(3)] pub target_id: String,
 }
+
+/// Request the invitations which were accepted by the caller
+#[derive(Clone, Debug, Default, Encode, Decode, CborLen, Serialize)]
+#[cbor(map)]
+#[rustfmt::skip]
+pub struct ListAcceptedInvitations {
+    /// Only return the invitations accepted for this target
+    #[n(1)] pub target_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Encode, Decode, CborLen, Serialize)]
+#[cbor(map)]
+#[rustfmt::skip]
+pub struct AcceptedInvitationList {
+    #[n(1)] pub accepted: Vec<AcceptedInvitation>,
+}
+
+impl AcceptInvitation {
+    /// Default maximum size of an encoded [`AcceptInvitation`]
+    pub const DEFAULT_MAX_ENCODED_LEN: usize = MAX_MESSAGE_SIZE;
+
+    /// Return the length of the CBOR encoding of this request, or an error if it exceeds
+    /// `max_len` bytes
+    pub fn check_encoded_len(&self, max_len: usize) -> Result<usize> {
+        check_encoded_len(self, "invitation acceptance", &self.id, max_len)
+    }
+}
+
+impl AcceptedInvitation {
+    /// Default maximum size of an encoded [`AcceptedInvitation`]
+    pub const DEFAULT_MAX_ENCODED_LEN: usize = MAX_MESSAGE_SIZE;
+
+    /// Return the length of the CBOR encoding of this invitation, or an error if it exceeds
+    /// `max_len` bytes
+    pub fn check_encoded_len(&self, max_len: usize) -> Result<usize> {
+        check_encoded_len(self, "accepted invitation", &self.id, max_len)
+    }
+}
+
+fn check_encoded_len(
+    value: &impl CborLen<()>,
+    name: &str,
+    id: &str,
+    max_len: usize,
+) -> Result<usize> {
+    let len = minicbor::len(value);
+    if len > max_len {
+        return Err(Error::new(
+            Origin::Api,
+            Kind::Invalid,
+            format!(
+                "The {name} {id} is {len} bytes long once encoded, which exceeds the maximum of {max_len} bytes"
+            ),
+        ));
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Encode)]
+    #[cbor(map)]
+    #[rustfmt::skip]
+    struct AcceptInvitationWithoutScope {
+        #[n(1)] id: String,
+    }
+
+    #[test]
+    fn test_accept_invitation_optional_fields() {
+        let bytes = minicbor::to_vec(AcceptInvitationWithoutScope {
+            id: "invitation".to_string(),
+        })
+        .unwrap();
+        let decoded: AcceptInvitation = minicbor::decode(&bytes).unwrap();
+        assert_eq!(decoded.id, "invitation");
+        assert_eq!(decoded.requested_scope, None);
+
+        assert_eq!(decoded.idempotency_key, None);
+
+        let request = AcceptInvitation {
+            id: "invitation".to_string(),
+            requested_scope: Some(RoleInShare::Guest),
+            idempotency_key: Some("key".to_string()),
+        };
+        let bytes = minicbor::to_vec(&request).unwrap();
+        let decoded: AcceptInvitation = minicbor::decode(&bytes).unwrap();
+        assert_eq!(decoded.requested_scope, Some(RoleInShare::Guest));
+        assert_eq!(decoded.idempotency_key, Some("key".to_string()));
+    }
+
+    #[test]
+    fn test_accepted_invitation_list() {
+        let bytes = minicbor::to_vec(ListAcceptedInvitations::default()).unwrap();
+        let decoded: ListAcceptedInvitations = minicbor::decode(&bytes).unwrap();
+        assert_eq!(decoded.target_id, None);
+
+        let list = AcceptedInvitationList {
+            accepted: vec![AcceptedInvitation {
+                id: "invitation".to_string(),
+                scope: RoleInShare::Guest,
+                target_id: "target".to_string(),
+            }],
+        };
+        let bytes = minicbor::to_vec(&list).unwrap();
+        let decoded: AcceptedInvitationList = minicbor::decode(&bytes).unwrap();
+        assert_eq!(decoded.accepted.len(), 1);
+        assert_eq!(decoded.accepted[0].id, "invitation");
+        assert_eq!(decoded.accepted[0].scope, RoleInShare::Guest);
+        assert_eq!(decoded.accepted[0].target_id, "target");
+    }
+
+    #[test]
+    fn test_accepted_invitation_encoded_len() {
+        let invitation = AcceptedInvitation {
+            id: "invitation".to_string(),
+            scope: RoleInShare::Admin,
+            target_id: "t".repeat(100),
+        };
+        let len = invitation
+            .check_encoded_len(AcceptedInvitation::DEFAULT_MAX_ENCODED_LEN)
+            .unwrap();
+        assert_eq!(len, minicbor::to_vec(&invitation).unwrap().len());
+
+        assert!(invitation.check_encoded_len(len).is_ok());
+        assert!(invitation.check_encoded_len(len - 1).is_err());
+    }
+
+    #[test]
+    fn test_oversized_invitation_is_rejected() {
+        let request = AcceptInvitation {
+            id: "i".repeat(AcceptInvitation::DEFAULT_MAX_ENCODED_LEN),
+            requested_scope: None,
+            idempotency_key: None,
+        };
+        assert!(request
+            .check_encoded_len(AcceptInvitation::DEFAULT_MAX_ENCODED_LEN)
+            .is_err());
+
+        let invitation = AcceptedInvitation {
+            id: "invitation".to_string(),
+            scope: RoleInShare::Admin,
+            target_id: "t".repeat(AcceptedInvitation::DEFAULT_MAX_ENCODED_LEN),
+        };
+        assert!(invitation
+            .check_encoded_len(AcceptedInvitation::DEFAULT_MAX_ENCODED_LEN)
+            .is_err());
+    }
+}
@@ -1,17 +1,23 @@
 use crate::orchestrator::email_address::EmailAddress;
 use crate::orchestrator::share::{
-    AcceptInvitation, AcceptedInvitation, CreateInvitation, CreateServiceInvitation,
-    InvitationList, InvitationListKind, InvitationWithAccess, ListInvitations, RoleInShare,
-    SentInvitation, ShareScope,
+    AcceptInvitation, AcceptedInvitation, AcceptedInvitationList, CreateInvitation,
+    CreateServiceInvitation, InvitationList, InvitationListKind, InvitationWithAccess,
+    ListAcceptedInvitations, ListInvitations, ReceivedInvitation, RoleInShare, SentInvitation,
+    ShareScope,
 };
 use crate::orchestrator::{ControllerClient, HasSecureClient};
 use miette::IntoDiagnostic;
 use ockam::identity::Identifier;
-use ockam_core::api::Request;
+use ockam_core::api::{Reply, Request};
 use ockam_core::async_trait;
+use ockam_core::compat::rand::random_string;
 use ockam_node::Context;
+use tokio_retry::strategy::FixedInterval;
+use tokio_retry::Retry;
 
 const API_SERVICE: &str = "users";
+const ACCEPT_INVITATION_RETRIES: usize = 3;
+const ACCEPT_INVITATION_RETRY_INTERVAL_MS: u64 = 1000;
 
 #[async_trait]
 pub trait Invitations {
@@ -43,12 +49,22 @@ pub trait Invitations {
         enrollment_ticket: String,
     ) -> miette::Result<SentInvitation>;
 
+    /// Accept an invitation, optionally requesting a narrower role than the one it grants
     async fn accept_invitation(
         &self,
         ctx: &Context,
         invitation_id: String,
+        requested_scope: Option<RoleInShare>,
     ) -> miette::Result<AcceptedInvitation>;
 
+    /// List the invitations accepted by the caller, with the role they granted, optionally
+    /// restricted to one target
+    async fn list_accepted_invitations(
+        &self,
+        ctx: &Context,
+        target_id: Option<String>,
+    ) -> miette::Result<Vec<AcceptedInvitation>>;
+
     async fn show_invitation(
         &self,
         ctx: &Context,
@@ -61,6 +77,12 @@ pub trait Invitations {
         kind: InvitationListKind,
     ) -> miette::Result<InvitationList>;
 
+    /// List the received invitations which can still be accepted
+    async fn list_pending_invitations(
+        &self,
+        ctx: &Context,
+    ) -> miette::Result<Vec<ReceivedInvitation>>;
+
     async fn ignore_invitation(&self, ctx: &Context, invitation_id: String) -> miette::Result<()>;
 }
 
@@ -132,13 +154,44 @@ impl Invitations for ControllerClient {
         &self,
         ctx: &Context,
         invitation_id: String,
+        requested_scope: Option<RoleInShare>,
     ) -> miette::Result<AcceptedInvitation> {
-        let req = Request::post("/v0/redeem_invite").body(AcceptInvitation { id: invitation_id });
-        self.get_secure_client()
+        let req_body = AcceptInvitation {
+            id: invitation_id,
+            requested_scope,
+            idempotency_key: Some(random_string()),
+        };
+        req_body
+            .check_encoded_len(AcceptInvitation::DEFAULT_MAX_ENCODED_LEN)
+            .into_diagnostic()?;
+        // Only communication errors are retried. The same idempotency key is sent for every
+        // attempt, so that an accept which was processed, but whose reply was lost, is not
+        // processed twice
+        let retry_strategy = FixedInterval::from_millis(ACCEPT_INVITATION_RETRY_INTERVAL_MS)
+            .take(ACCEPT_INVITATION_RETRIES);
+        let reply: Reply<AcceptedInvitation> = Retry::spawn(retry_strategy, || async {
+            let req = Request::post("/v0/redeem_invite").body(req_body.clone());
+            self.get_secure_client().ask(ctx, API_SERVICE, req).await
+        })
+        .await
+        .into_diagnostic()?;
+        reply.miette_success("redeem invitation")
+    }
+
+    async fn list_accepted_invitations(
+        &self,
+        ctx: &Context,
+        target_id: Option<String>,
+    ) -> miette::Result<Vec<AcceptedInvitation>> {
+        debug!(?target_id, "Sending request to list accepted invitations");
+        let req = Request::get("/v0/redeemed_invites").body(ListAcceptedInvitations { target_id });
+        let list: AcceptedInvitationList = self
+            .get_secure_client()
             .ask(ctx, API_SERVICE, req)
             .await
             .into_diagnostic()?
-            .miette_success("redeem invitation")
+            .miette_success("list accepted invitations")?;
+        Ok(list.accepted)
     }
 
     async fn show_invitation(
@@ -169,6 +222,16 @@ impl Invitations for ControllerClient {
             .miette_success("list invitations")
     }
 
+    async fn list_pending_invitations(
+        &self,
+        ctx: &Context,
+    ) -> miette::Result<Vec<ReceivedInvitation>> {
+        Ok(self
+            .list_invitations(ctx, InvitationListKind::All)
+            .await?
+            .pending())
+    }
+
     async fn ignore_invitation(&self, ctx: &Context, invitation_id: String) -> miette::Result<()> {
         debug!(?invitation_id, "sending request to ignore invitation");
         let req = Request::post(format!("/v0/invites/{invitation_id}/ignore"));
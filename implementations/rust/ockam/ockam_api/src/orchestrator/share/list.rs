@@ -28,3 +28,67 @@ pub struct InvitationList {
     #[n(2)] pub received: Option<Vec<ReceivedInvitation>>,
     #[n(3)] pub accepted: Option<Vec<InvitationWithAccess>>,
 }
+
+impl InvitationList {
+    /// Received invitations which can still be accepted: they were not ignored, accepted yet,
+    /// or expired
+    pub fn pending(&self) -> Vec<ReceivedInvitation> {
+        let accepted = self.accepted.as_deref().unwrap_or_default();
+        self.received
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .filter(|i| !i.ignored)
+            .filter(|i| !accepted.iter().any(|a| a.invitation.id == i.id))
+            .filter(|i| !i.is_expired().unwrap_or(true))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::email_address::EmailAddress;
+    use crate::orchestrator::share::{RoleInShare, ShareScope};
+
+    fn received_invitation(id: &str, expires_at: &str, ignored: bool) -> ReceivedInvitation {
+        ReceivedInvitation {
+            id: id.to_string(),
+            expires_at: expires_at.to_string(),
+            grant_role: RoleInShare::Guest,
+            owner_email: EmailAddress::new_unsafe("owner@example.com"),
+            scope: ShareScope::Project,
+            target_id: "target".to_string(),
+            ignored,
+        }
+    }
+
+    #[test]
+    fn test_pending_invitations() {
+        let list = InvitationList {
+            sent: None,
+            received: Some(vec![
+                received_invitation("pending", "2100-01-01T00:00:00Z", false),
+                received_invitation("ignored", "2100-01-01T00:00:00Z", true),
+                received_invitation("expired", "2020-01-01T00:00:00Z", false),
+                received_invitation("accepted", "2100-01-01T00:00:00Z", false),
+            ]),
+            accepted: Some(vec![InvitationWithAccess {
+                invitation: received_invitation("accepted", "2100-01-01T00:00:00Z", false),
+                service_access_details: None,
+            }]),
+        };
+
+        let pending = list.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, "pending");
+
+        let empty = InvitationList {
+            sent: None,
+            received: None,
+            accepted: None,
+        };
+        assert!(empty.pending().is_empty());
+    }
+}
@@ -1,14 +1,27 @@
 use crate::common::common::{default_configuration, start_authority_node};
+use miette::IntoDiagnostic;
+use ockam::identity::models::CredentialAndPurposeKey;
+use ockam::identity::Identifier;
+use ockam::identity::TimestampInSeconds;
+use ockam_api::authenticator::{PreTrustedIdentities, PreTrustedIdentity};
+use ockam_api::authority_node;
+use ockam_api::authority_node::{Authority, Configuration};
+use ockam_api::enroll::enrollment::Enrollment;
 use ockam_api::nodes::service::default_address::DefaultAddress;
+use ockam_api::nodes::NodeManager;
+use ockam_core::compat::collections::BTreeMap;
 use ockam_core::{Address, Result};
+use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
+use ockam_transport_tcp::TcpTransport;
+use std::time::Duration;
 
 mod common;
 
 #[ockam_macros::test]
 async fn authority_starts_with_default_configuration(ctx: &mut Context) -> Result<()> {
     let configuration = default_configuration().await?;
-    start_authority_node(ctx, &configuration).await?;
+    start_authority_node(ctx, configuration).await?;
 
     let workers = ctx.list_workers()?;
 
@@ -22,11 +35,46 @@ async fn authority_starts_with_default_configuration(ctx: &mut Context) -> Resul
     Ok(())
 }
 
+#[ockam_macros::test]
+async fn authority_is_ready_once_started(ctx: &mut Context) -> Result<()> {
+    let configuration = default_configuration()
+        .await?
+        .to_builder()
+        .with_direct_authentication(true)
+        .with_token_enrollment(true)
+        .build()?;
+    let authority = Authority::create(&configuration, None).await?;
+    authority_node::start_node_and_wait_ready(
+        ctx,
+        configuration,
+        authority,
+        Duration::from_secs(10),
+    )
+    .await?;
+
+    for address in [
+        DefaultAddress::DIRECT_AUTHENTICATOR,
+        DefaultAddress::ENROLLMENT_TOKEN_ACCEPTOR,
+        DefaultAddress::ENROLLMENT_TOKEN_ISSUER,
+        DefaultAddress::CREDENTIAL_ISSUER,
+        DefaultAddress::SECURE_CHANNEL_LISTENER,
+        DefaultAddress::ECHO_SERVICE,
+    ] {
+        ctx.wait_for_initialization(&Address::from(address), Duration::ZERO)
+            .await?;
+    }
+
+    Ok(())
+}
+
 #[ockam_macros::test]
 async fn authority_starts_direct_authenticator(ctx: &mut Context) -> Result<()> {
-    let mut configuration = default_configuration().await?;
-    configuration.no_direct_authentication = false;
-    start_authority_node(ctx, &configuration).await?;
+    let configuration = default_configuration()
+        .await?
+        .to_builder()
+        .with_direct_authentication(true)
+        .build()?;
+    start_authority_node(ctx, configuration).await?;
 
     let workers = ctx.list_workers()?;
 
@@ -42,9 +90,12 @@ async fn authority_starts_direct_authenticator(ctx: &mut Context) -> Result<()>
 
 #[ockam_macros::test]
 async fn authority_starts_enrollment_token(ctx: &mut Context) -> Result<()> {
-    let mut configuration = default_configuration().await?;
-    configuration.no_token_enrollment = false;
-    start_authority_node(ctx, &configuration).await?;
+    let configuration = default_configuration()
+        .await?
+        .to_builder()
+        .with_token_enrollment(true)
+        .build()?;
+    start_authority_node(ctx, configuration).await?;
 
     let workers = ctx.list_workers()?;
 
@@ -57,3 +108,135 @@ async fn authority_starts_enrollment_token(ctx: &mut Context) -> Result<()> {
 
     Ok(())
 }
+
+#[ockam_macros::test]
+async fn authority_reloads_credential_issuer(ctx: &mut Context) -> Result<()> {
+    let configuration = default_configuration().await?;
+    let authority = Authority::create(&configuration, None).await?;
+
+    // the credential issuer can only be reloaded once started
+    assert!(authority
+        .reload_credential_issuer(ctx, &configuration)
+        .await
+        .is_err());
+
+    let identities_creation = authority
+        .secure_channels()
+        .identities()
+        .identities_creation();
+    let removed_member = identities_creation.create_identity().await?;
+    let added_member = identities_creation.create_identity().await?;
+
+    let configuration = configuration
+        .to_builder()
+        .with_trusted_identities(trusted_identities(&configuration, &removed_member))
+        .build()?;
+    let authority = Authority::create(&configuration, None).await?;
+    authority_node::start_node(ctx, configuration.clone(), authority.clone()).await?;
+    issue_credential(ctx, &authority, &configuration, &removed_member)
+        .await
+        .unwrap();
+
+    let configuration = configuration
+        .to_builder()
+        .with_trusted_identities(trusted_identities(&configuration, &added_member))
+        .build()?;
+    authority
+        .reload_credential_issuer(ctx, &configuration)
+        .await?;
+
+    let workers = ctx.list_workers()?;
+
+    assert!(workers.contains(&Address::from(DefaultAddress::CREDENTIAL_ISSUER)));
+    assert!(workers.contains(&Address::from(DefaultAddress::SECURE_CHANNEL_LISTENER)));
+    assert!(workers.contains(&Address::from(DefaultAddress::ECHO_SERVICE)));
+
+    // only the identities trusted by the new configuration get a credential
+    issue_credential(ctx, &authority, &configuration, &added_member)
+        .await
+        .unwrap();
+    assert!(
+        issue_credential(ctx, &authority, &configuration, &removed_member)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn authority_reloads_changed_services(ctx: &mut Context) -> Result<()> {
+    let configuration = default_configuration().await?;
+    let authority = Authority::create(&configuration, None).await?;
+
+    // the configuration can only be reloaded once the services are started
+    assert!(authority.reload(ctx, &configuration).await.is_err());
+
+    authority_node::start_node(ctx, configuration.clone(), authority.clone()).await?;
+
+    let configuration = configuration
+        .to_builder()
+        .with_token_enrollment(true)
+        .with_echo_service_name("echo2")
+        .build()?;
+    let changes = authority.reload(ctx, &configuration).await?;
+    assert!(changes.enrollment_services);
+    assert!(changes.echo_service);
+    assert!(!changes.credential_issuer);
+
+    let workers = ctx.list_workers()?;
+
+    assert!(workers.contains(&Address::from(DefaultAddress::ENROLLMENT_TOKEN_ACCEPTOR)));
+    assert!(workers.contains(&Address::from(DefaultAddress::ENROLLMENT_TOKEN_ISSUER)));
+    assert!(workers.contains(&Address::from("echo2")));
+    assert!(!workers.contains(&Address::from(DefaultAddress::ECHO_SERVICE)));
+    assert!(workers.contains(&Address::from(DefaultAddress::SECURE_CHANNEL_LISTENER)));
+
+    // a change of the secure channel listener requires a restart
+    let configuration = configuration
+        .to_builder()
+        .with_secure_channel_listener_name("other_api")
+        .build()?;
+    assert!(authority.reload(ctx, &configuration).await.is_err());
+    assert!(ctx
+        .list_workers()?
+        .contains(&Address::from(DefaultAddress::SECURE_CHANNEL_LISTENER)));
+
+    Ok(())
+}
+
+/// HELPERS
+
+/// Trust only the given member, attested by the authority
+fn trusted_identities(configuration: &Configuration, member: &Identifier) -> PreTrustedIdentities {
+    BTreeMap::from([(
+        member.clone(),
+        PreTrustedIdentity::new(
+            BTreeMap::from([(b"attr".to_vec(), b"value".to_vec())]),
+            TimestampInSeconds(0),
+            None,
+            configuration.identifier(),
+        ),
+    )])
+    .into()
+}
+
+/// Request a credential from the authority for the given member
+async fn issue_credential(
+    ctx: &Context,
+    authority: &Authority,
+    configuration: &Configuration,
+    member: &Identifier,
+) -> miette::Result<CredentialAndPurposeKey> {
+    let client = NodeManager::authority_node_client(
+        &TcpTransport::create(ctx).into_diagnostic()?,
+        authority.secure_channels(),
+        &configuration.identifier(),
+        &MultiAddr::try_from("/secure/api").into_diagnostic()?,
+        member,
+        None,
+    )
+    .await
+    .into_diagnostic()?;
+    client.issue_credential(ctx).await
+}
@@ -28,7 +28,7 @@ pub async fn default_configuration() -> Result<Configuration> {
     let database_configuration = DatabaseConfiguration::sqlite(database_path.as_path());
     let port = thread_rng().gen_range(10000..65535);
 
-    let mut configuration = create_configuration(
+    let configuration = create_configuration(
         "I4dba4b2e53b2ed95967b3bab350b6c9ad9c624e5a1b2c3d4e5f6a6b5c4d3e2f1",
         port,
         &database_configuration,
@@ -45,9 +45,11 @@ pub async fn default_configuration() -> Result<Configuration> {
         .create_identity()
         .await?;
 
-    configuration.identifier = authority_identifier;
-
-    Ok(configuration)
+    create_configuration(
+        &authority_identifier.to_string(),
+        port,
+        &database_configuration,
+    )
 }
 
 pub fn create_configuration(
@@ -55,21 +57,15 @@ pub fn create_configuration(
     port: u16,
     database_configuration: &DatabaseConfiguration,
 ) -> Result<Configuration> {
-    Ok(Configuration {
-        identifier: identifier.try_into()?,
-        database_configuration: database_configuration.clone(),
-        project_identifier: "123456".to_string(),
-        tcp_listener_address: InternetAddress::new(&format!("127.0.0.1:{}", port)).unwrap(),
-        secure_channel_listener_name: None,
-        authenticator_name: None,
-        trusted_identities: Default::default(),
-        no_direct_authentication: true,
-        no_token_enrollment: true,
-        okta: None,
-        account_authority: None,
-        enforce_admin_checks: false,
-        disable_trust_context_id: false,
-    })
+    Configuration::builder(
+        identifier.try_into()?,
+        database_configuration.clone(),
+        "123456",
+        InternetAddress::new(&format!("127.0.0.1:{}", port)).unwrap(),
+    )
+    .with_direct_authentication(false)
+    .with_token_enrollment(false)
+    .build()
 }
 
 pub struct AuthorityClient {
@@ -89,7 +85,7 @@ pub async fn start_authority(
     secure_channels: Arc<SecureChannels>,
     number_of_admins: usize,
 ) -> Result<AuthorityInfo> {
-    let mut configuration = default_configuration().await?;
+    let configuration = default_configuration().await?;
 
     let account_authority = secure_channels
         .identities()
@@ -107,7 +103,7 @@ pub async fn start_authority(
         .credentials()
         .credentials_creation();
     let admin_attrs = AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
-        .with_attribute("project", configuration.project_identifier.clone())
+        .with_attribute("project", configuration.project_identifier())
         .build();
     let mut admins = vec![];
     for _ in 0..number_of_admins {
@@ -128,7 +124,7 @@ pub async fn start_authority(
         let authority_node_client = NodeManager::authority_node_client(
             &TcpTransport::create(ctx)?,
             secure_channels.clone(),
-            &configuration.identifier,
+            &configuration.identifier(),
             &MultiAddr::try_from("/secure/api")?,
             &admin,
             Some(Arc::new(MemoryCredentialRetrieverCreator::new(cred))),
@@ -140,18 +136,19 @@ pub async fn start_authority(
         });
     }
 
-    configuration.account_authority = Some(account_authority_identity.change_history().clone());
-    configuration.no_direct_authentication = false;
-    configuration.no_token_enrollment = false;
+    let configuration = configuration
+        .to_builder()
+        .with_account_authority(account_authority_identity.change_history().clone())
+        .with_direct_authentication(true)
+        .with_token_enrollment(true)
+        .build()?;
+    let authority_identifier = configuration.identifier();
 
-    debug!(
-        "common.rs about to call authority::start_node with {:?}",
-        configuration.account_authority.is_some()
-    );
-    start_authority_node(ctx, &configuration).await?;
+    debug!("common.rs about to call authority::start_node with an account authority");
+    start_authority_node(ctx, configuration).await?;
 
     Ok(AuthorityInfo {
-        authority_identifier: configuration.identifier.clone(),
+        authority_identifier,
         admins,
     })
 }
@@ -175,7 +172,7 @@ pub fn change_client_identifier(
     AuthorityNodeClient::new(client)
 }
 
-pub async fn start_authority_node(ctx: &Context, configuration: &Configuration) -> Result<()> {
-    let authority = Authority::create(configuration, None).await?;
+pub async fn start_authority_node(ctx: &Context, configuration: Configuration) -> Result<()> {
+    let authority = Authority::create(&configuration, None).await?;
     authority_node::start_node(ctx, configuration, authority).await
 }
@@ -120,7 +120,7 @@ impl AppState {
 
         let controller = self.controller().await?;
         let res = controller
-            .accept_invitation(&self.context(), id.clone())
+            .accept_invitation(&self.context(), id.clone(), None)
             .await?;
 
         debug!(?res);
@@ -337,29 +337,32 @@ impl CreateCommand {
             None => None,
         };
 
-        let configuration = authority_node::Configuration {
-            identifier: node.identifier(),
-            database_configuration: opts.state.database_configuration()?,
-            project_identifier: self.project_identifier.clone(),
-            tcp_listener_address: self.tcp_listener_address.clone(),
-            secure_channel_listener_name: None,
-            authenticator_name: None,
-            trusted_identities,
-            no_direct_authentication: self.no_direct_authentication,
-            no_token_enrollment: self.no_token_enrollment,
-            okta: okta_configuration,
-            account_authority,
-            enforce_admin_checks: self.enforce_admin_checks,
-            disable_trust_context_id: self.disable_trust_context_id,
-        };
+        let mut configuration = authority_node::Configuration::builder(
+            node.identifier(),
+            opts.state.database_configuration()?,
+            self.project_identifier.clone(),
+            self.tcp_listener_address.clone(),
+        )
+        .with_trusted_identities(trusted_identities)
+        .with_direct_authentication(!self.no_direct_authentication)
+        .with_token_enrollment(!self.no_token_enrollment)
+        .with_enforce_admin_checks(self.enforce_admin_checks)
+        .with_disable_trust_context_id(self.disable_trust_context_id);
+        if let Some(okta_configuration) = okta_configuration {
+            configuration = configuration.with_okta(okta_configuration);
+        }
+        if let Some(account_authority) = account_authority {
+            configuration = configuration.with_account_authority(account_authority);
+        }
+        let configuration = configuration.build().into_diagnostic()?;
 
         // SQLite doesn't like when the same database is opened by multiple times
-        let database = if state.database_ref().configuration == configuration.database_configuration
-        {
-            Some(state.database())
-        } else {
-            None
-        };
+        let database =
+            if state.database_ref().configuration == *configuration.database_configuration() {
+                Some(state.database())
+            } else {
+                None
+            };
 
         // create the authority identity
         // or retrieve it from disk if the node has already been started before
@@ -369,7 +372,7 @@ impl CreateCommand {
             .await
             .into_diagnostic()?;
 
-        authority_node::start_node(ctx, &configuration, authority)
+        authority_node::start_node(ctx, configuration, authority)
             .await
             .into_diagnostic()?;
 
@@ -191,6 +191,28 @@ impl ArgsToCommands for ResourceNameOrMap {
     }
 }
 
+impl ResourceNameOrMap {
+    /// Apply a function to the arguments of each resource, along with the resource name if any.
+    ///
+    /// This is used to adjust or validate the arguments before they are converted into commands.
+    pub fn try_for_each_args<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(Option<&str>, &mut Args) -> Result<()>,
+    {
+        match self {
+            ResourceNameOrMap::Name(_) => Ok(()),
+            ResourceNameOrMap::NamedMap(resources) => resources
+                .items
+                .iter_mut()
+                .try_for_each(|(name, args)| f(Some(name), args)),
+            ResourceNameOrMap::RandomlyNamedMap(UnnamedResources::Single(args)) => f(None, args),
+            ResourceNameOrMap::RandomlyNamedMap(UnnamedResources::List(items)) => {
+                items.iter_mut().try_for_each(|args| f(None, args))
+            }
+        }
+    }
+}
+
 /// A list of resources identified by a set of arguments, without a name.
 ///
 /// E.g.
@@ -1,13 +1,61 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
 use miette::{miette, Result};
+use ockam_abac::PolicyExpression;
 use ockam_api::colors::color_primary;
 use serde::{Deserialize, Serialize};
 
-use crate::run::parser::building_blocks::{ArgsToCommands, ResourceNameOrMap};
+use crate::run::parser::building_blocks::{
+    ArgValue, Args, ArgsToCommands, ResourceNameOrMap, UnnamedResources,
+};
 
 use crate::run::parser::resource::utils::parse_cmd_from_args;
 use crate::tcp::inlet::create::CreateCommand;
 use crate::{tcp::inlet, Command, OckamSubcommand};
 
+/// TCP inlets section of a configuration file
+///
+/// When the inlets are named, an entry named `default_options` can be used as a template: its
+/// arguments are applied to every other inlet which doesn't define them.
+///
+/// ```yaml
+/// tcp_inlets:
+///   default_options:
+///     to: db-outlet
+///     allow: component.web
+///   db1:
+///     from: 6060
+///   db2:
+///     from: 6061
+///     to: db2-outlet
+/// ```
+///
+/// A range of ports can be given to declare one inlet per port. In the arguments of the
+/// inlet, `{port}` is replaced by the port of each inlet, and named inlets are named
+/// `<name>-<port>`. The following section declares the inlets `web-8000` to `web-8010`:
+///
+/// ```yaml
+/// tcp_inlets:
+///   web:
+///     from: 8000-8010
+///     to: web-outlet-{port}
+/// ```
+///
+/// When the outlet may not exist yet, for example because its node is declared by another
+/// configuration, `wait_for` sets how long the inlet waits for the outlet to be available, and
+/// `retry` sets the delay between two attempts at creating the inlet:
+///
+/// ```yaml
+/// tcp_inlets:
+///   db:
+///     from: 6060
+///     to: db-outlet
+///     wait_for: 30s
+///     retry: 5s
+/// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct TcpInlets {
     #[serde(alias = "tcp-inlets", alias = "tcp-inlet")]
@@ -15,6 +63,27 @@ pub struct TcpInlets {
 }
 
 impl TcpInlets {
+    /// Key which can be used instead of `allow` to define the policy of an inlet
+    const POLICY_ARG: &'static str = "policy";
+    /// Keys of the `CreateCommand` policy expression argument
+    const ALLOW_ARGS: [&'static str; 2] = ["allow", "expression"];
+    /// Name of the entry holding the arguments shared by all the named inlets
+    const DEFAULT_OPTIONS_NAME: &'static str = "default_options";
+    /// Key of the `CreateCommand` local address argument
+    const FROM_ARG: &'static str = "from";
+    /// Placeholder replaced by the port of each inlet declared with a range of ports
+    const PORT_PLACEHOLDER: &'static str = "{port}";
+    /// Maximum number of inlets which can be declared with a single range of ports
+    const MAX_RANGE_LEN: usize = 1024;
+    /// Key which can be used instead of `connection-wait` to set the time to wait for the outlet
+    const WAIT_FOR_ARG: &'static str = "wait_for";
+    /// Key of the `CreateCommand` argument setting the time to wait for the outlet
+    const CONNECTION_WAIT_ARG: &'static str = "connection-wait";
+    /// Key which can be used instead of `retry-wait` to set the delay between two attempts
+    const RETRY_ARG: &'static str = "retry";
+    /// Key of the `CreateCommand` argument setting the delay between two attempts
+    const RETRY_WAIT_ARG: &'static str = "retry-wait";
+
     fn get_subcommand(args: &[String]) -> Result<CreateCommand> {
         if let OckamSubcommand::TcpInlet(cmd) = parse_cmd_from_args(CreateCommand::NAME, args)? {
             if let inlet::TcpInletSubCommand::Create(c) = cmd.subcommand {
@@ -32,8 +101,14 @@ impl TcpInlets {
         default_node_name: Option<&String>,
     ) -> Result<Vec<CreateCommand>> {
         match self.tcp_inlets {
-            Some(c) => {
+            Some(mut c) => {
+                c.try_for_each_args(Self::parse_policy)?;
+                c.try_for_each_args(Self::parse_wait)?;
+                Self::apply_default_options(&mut c);
+                Self::expand_ranges(&mut c)?;
+                c.try_for_each_args(|name, args| Self::check_port(name, args))?;
                 let mut cmds = c.into_commands(Self::get_subcommand)?;
+                Self::check_duplicate_addresses(&cmds)?;
                 if let Some(node_name) = default_node_name.as_ref() {
                     for cmd in cmds.iter_mut() {
                         if cmd.at.is_none() {
@@ -46,12 +121,288 @@ impl TcpInlets {
             None => Ok(vec![]),
         }
     }
+
+    /// Check that the port of the inlet is in range when it's given as a number or a string
+    fn check_port(name: Option<&str>, args: &Args) -> Result<()> {
+        let port = match args.args.get(&Self::FROM_ARG.into()) {
+            Some(ArgValue::Int(port)) => port.to_string(),
+            Some(ArgValue::String(port))
+                if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                port.clone()
+            }
+            _ => return Ok(()),
+        };
+        if port.parse::<u16>().is_err() {
+            let inlet = Self::describe(name);
+            return Err(miette!(
+                "{inlet} has an invalid port: {port}. Ports must be between 0 and {}",
+                u16::MAX
+            ));
+        }
+        Ok(())
+    }
+
+    /// Replace each inlet declared with a range of ports by one inlet per port
+    fn expand_ranges(inlets: &mut ResourceNameOrMap) -> Result<()> {
+        match inlets {
+            ResourceNameOrMap::Name(_) => {}
+            ResourceNameOrMap::NamedMap(resources) => {
+                let items = std::mem::take(&mut resources.items);
+                // Explicitly named inlets are kept as they are, derived names must not collide
+                // with them, nor with the names derived from another range
+                let (ranges, mut expanded): (BTreeMap<_, _>, BTreeMap<_, _>) = items
+                    .into_iter()
+                    .partition(|(_, args)| Self::is_range(args));
+                for (name, args) in ranges {
+                    for (port, args) in Self::expand_range(Some(&name), &args)? {
+                        let derived_name = format!("{name}-{port}");
+                        if expanded.contains_key(&derived_name) {
+                            return Err(miette!(
+                                "The TCP inlet {} declared by the range {} is already declared",
+                                color_primary(&derived_name),
+                                color_primary(&name)
+                            ));
+                        }
+                        expanded.insert(derived_name, args);
+                    }
+                }
+                resources.items = expanded;
+            }
+            ResourceNameOrMap::RandomlyNamedMap(resources) => {
+                let items = match resources {
+                    UnnamedResources::Single(args) => vec![args.clone()],
+                    UnnamedResources::List(items) => std::mem::take(items),
+                };
+                let mut expanded = vec![];
+                for args in items {
+                    if Self::is_range(&args) {
+                        expanded.extend(
+                            Self::expand_range(None, &args)?
+                                .into_iter()
+                                .map(|(_, args)| args),
+                        );
+                    } else {
+                        expanded.push(args);
+                    }
+                }
+                *resources = UnnamedResources::List(expanded);
+            }
+        }
+        Ok(())
+    }
+
+    /// Return true if the inlet is declared with a range of ports, e.g. `from: 8000-8010`
+    fn is_range(args: &Args) -> bool {
+        Self::split_range(args).is_some()
+    }
+
+    fn split_range(args: &Args) -> Option<(&str, &str)> {
+        let is_port = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        match args.args.get(&Self::FROM_ARG.into()) {
+            Some(ArgValue::String(from)) => from
+                .split_once('-')
+                .filter(|(start, end)| is_port(start) && is_port(end)),
+            _ => None,
+        }
+    }
+
+    /// Parse and validate the range of ports of an inlet
+    fn parse_range(name: Option<&str>, args: &Args) -> Result<RangeInclusive<u16>> {
+        let inlet = Self::describe(name);
+        let (start, end) = Self::split_range(args)
+            .ok_or_else(|| miette!("{inlet} is not declared with a range of ports"))?;
+        let parse_port = |port: &str| {
+            port.parse::<u16>()
+                .ok()
+                .filter(|port| *port != 0)
+                .ok_or_else(|| {
+                    miette!(
+                        "{inlet} has an invalid port: {port}. Ports of a range must be between 1 and {}",
+                        u16::MAX
+                    )
+                })
+        };
+        let (start, end) = (parse_port(start)?, parse_port(end)?);
+        if start > end {
+            return Err(miette!(
+                "{inlet} has an invalid range of ports: {start}-{end}. The first port must not be greater than the last one"
+            ));
+        }
+        let len = usize::from(end - start) + 1;
+        if len > Self::MAX_RANGE_LEN {
+            return Err(miette!(
+                "{inlet} declares {len} inlets with the range {start}-{end}. A range can declare at most {} inlets",
+                Self::MAX_RANGE_LEN
+            ));
+        }
+        Ok(start..=end)
+    }
+
+    /// Return the arguments of each inlet of a range, along with its port
+    fn expand_range(name: Option<&str>, args: &Args) -> Result<Vec<(u16, Args)>> {
+        let range = Self::parse_range(name, args)?;
+        Ok(range
+            .map(|port| {
+                let mut args = args.clone();
+                for value in args.args.values_mut() {
+                    Self::replace_port_placeholder(value, port);
+                }
+                args.args
+                    .insert(Self::FROM_ARG.into(), ArgValue::Int(port as isize));
+                (port, args)
+            })
+            .collect())
+    }
+
+    fn replace_port_placeholder(value: &mut ArgValue, port: u16) {
+        match value {
+            ArgValue::String(s) => *s = s.replace(Self::PORT_PLACEHOLDER, &port.to_string()),
+            ArgValue::List(values) => values
+                .iter_mut()
+                .for_each(|value| Self::replace_port_placeholder(value, port)),
+            ArgValue::Int(_) | ArgValue::Bool(_) => {}
+        }
+    }
+
+    /// Check that no two inlets are bound to the same local address
+    fn check_duplicate_addresses(cmds: &[CreateCommand]) -> Result<()> {
+        for (i, cmd) in cmds.iter().enumerate() {
+            // The port 0 lets the OS pick a free port
+            if cmd.from.port() == 0 {
+                continue;
+            }
+            if let Some(other) = cmds[..i]
+                .iter()
+                .find(|other| Self::same_local_address(other, cmd))
+            {
+                return Err(miette!(
+                    "The TCP inlets {} and {} are both bound to {}",
+                    Self::describe_cmd(other),
+                    Self::describe_cmd(cmd),
+                    color_primary(cmd.from.hostname_port().to_string())
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Two inlets can't listen on the same port of the same IP address. An unspecified IP
+    /// address, e.g. `0.0.0.0`, overlaps with all the other IP addresses
+    fn same_local_address(cmd1: &CreateCommand, cmd2: &CreateCommand) -> bool {
+        let address1 = SocketAddr::from_str(&cmd1.from.hostname_port().to_string());
+        let address2 = SocketAddr::from_str(&cmd2.from.hostname_port().to_string());
+        match (address1, address2) {
+            (Ok(address1), Ok(address2)) => {
+                address1.port() == address2.port()
+                    && (address1.ip() == address2.ip()
+                        || address1.ip().is_unspecified()
+                        || address2.ip().is_unspecified())
+            }
+            _ => cmd1.from.hostname_port() == cmd2.from.hostname_port(),
+        }
+    }
+
+    fn describe(name: Option<&str>) -> String {
+        match name {
+            Some(name) => format!("The TCP inlet {}", color_primary(name)),
+            None => "A TCP inlet".to_string(),
+        }
+    }
+
+    fn describe_cmd(cmd: &CreateCommand) -> String {
+        match &cmd.name {
+            Some(name) => color_primary(name).to_string(),
+            None => color_primary(&cmd.from).to_string(),
+        }
+    }
+
+    /// Merge the arguments of the `default_options` entry into each named inlet. The arguments set on
+    /// an inlet take precedence
+    fn apply_default_options(inlets: &mut ResourceNameOrMap) {
+        if let ResourceNameOrMap::NamedMap(resources) = inlets {
+            if let Some(default_options) = resources.items.remove(Self::DEFAULT_OPTIONS_NAME) {
+                for args in resources.items.values_mut() {
+                    for (key, value) in default_options.args.iter() {
+                        args.args
+                            .entry(key.clone())
+                            .or_insert_with(|| value.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Accept the `wait_for` and `retry` keys as aliases of `connection-wait` and `retry-wait`
+    fn parse_wait(name: Option<&str>, args: &mut Args) -> Result<()> {
+        for (alias, key) in [
+            (Self::WAIT_FOR_ARG, Self::CONNECTION_WAIT_ARG),
+            (Self::RETRY_ARG, Self::RETRY_WAIT_ARG),
+        ] {
+            let Some(value) = args.args.remove(&alias.into()) else {
+                continue;
+            };
+            let inlet = Self::describe(name);
+            if args.args.contains_key(&key.into()) {
+                return Err(miette!(
+                    "{inlet} can't define both {} and {}",
+                    color_primary(alias),
+                    color_primary(key)
+                ));
+            }
+            // A number is a number of seconds
+            if !matches!(value, ArgValue::String(_) | ArgValue::Int(_)) {
+                return Err(miette!(
+                    "{inlet} must have a duration for {}, e.g. 30s",
+                    color_primary(alias)
+                ));
+            }
+            args.args.insert(key.into(), value);
+        }
+        Ok(())
+    }
+
+    /// Accept a `policy` key as an alias of `allow`, and check that the policy expression of the
+    /// inlet is valid
+    fn parse_policy(name: Option<&str>, args: &mut Args) -> Result<()> {
+        let inlet = Self::describe(name);
+
+        if let Some(policy) = args.args.remove(&Self::POLICY_ARG.into()) {
+            if Self::ALLOW_ARGS
+                .iter()
+                .any(|key| args.args.contains_key(&(*key).into()))
+            {
+                return Err(miette!(
+                    "{inlet} can't define both a {} and an {} policy expression",
+                    color_primary(Self::POLICY_ARG),
+                    color_primary(Self::ALLOW_ARGS[0])
+                ));
+            }
+            args.args.insert(Self::ALLOW_ARGS[0].into(), policy);
+        }
+
+        for key in Self::ALLOW_ARGS {
+            match args.args.get(&key.into()) {
+                None => {}
+                Some(ArgValue::String(expression)) => {
+                    PolicyExpression::from_str(expression)
+                        .map_err(|e| miette!("{inlet} has an invalid policy expression: {e}"))?;
+                }
+                Some(_) => {
+                    return Err(miette!("{inlet} must have a string policy expression"));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use ockam::transport::SchemeHostnamePort;
+    use std::time::Duration;
 
     #[test]
     fn tcp_inlet_config() {
@@ -104,4 +455,290 @@ mod tests {
         );
         assert_eq!(cmds[1].at.as_ref(), Some(&default_node_name));
     }
+
+    #[test]
+    fn tcp_inlet_config_with_policy() {
+        let valid = r#"
+            tcp_inlets:
+              ti1:
+                from: 6060
+                policy: (= subject.component "db")
+              ti2:
+                from: 6061
+                allow: component.web
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(valid).unwrap();
+        let cmds = parsed.into_parsed_commands(None).unwrap();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(
+            cmds[0].allow,
+            Some(PolicyExpression::from_str(r#"(= subject.component "db")"#).unwrap())
+        );
+        assert_eq!(
+            cmds[1].allow,
+            Some(PolicyExpression::from_str("component.web").unwrap())
+        );
+
+        let invalid = r#"
+            tcp_inlets:
+              ti1:
+                from: 6060
+                policy: (= subject.component
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(invalid).unwrap();
+        let err = parsed.into_parsed_commands(None).unwrap_err();
+        assert!(err.to_string().contains("invalid policy expression"));
+
+        let both = r#"
+            tcp_inlets:
+              - from: 6060
+                policy: component.db
+                allow: component.web
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(both).unwrap();
+        assert!(parsed.into_parsed_commands(None).is_err());
+    }
+
+    #[test]
+    fn tcp_inlet_config_with_default_options() {
+        let config = r#"
+            tcp_inlets:
+              default_options:
+                to: default-outlet
+                at: n
+                policy: component.web
+              ti1:
+                from: 6060
+              ti2:
+                from: 6061
+                to: other-outlet
+                allow: component.db
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        let cmds = parsed.into_parsed_commands(None).unwrap();
+        assert_eq!(cmds.len(), 2);
+
+        assert_eq!(cmds[0].name.as_ref().unwrap(), "ti1");
+        assert_eq!(cmds[0].to, "default-outlet");
+        assert_eq!(cmds[0].at.as_ref().unwrap(), "n");
+        assert_eq!(
+            cmds[0].allow,
+            Some(PolicyExpression::from_str("component.web").unwrap())
+        );
+
+        assert_eq!(cmds[1].name.as_ref().unwrap(), "ti2");
+        assert_eq!(cmds[1].to, "other-outlet");
+        assert_eq!(cmds[1].at.as_ref().unwrap(), "n");
+        assert_eq!(
+            cmds[1].allow,
+            Some(PolicyExpression::from_str("component.db").unwrap())
+        );
+
+        // An inlet named `defaults` is a regular inlet
+        let config = r#"
+            tcp_inlets:
+              defaults:
+                from: 6060
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        let cmds = parsed.into_parsed_commands(None).unwrap();
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].name.as_ref().unwrap(), "defaults");
+    }
+
+    #[test]
+    fn tcp_inlet_config_with_wait() {
+        let config = r#"
+            tcp_inlets:
+              default_options:
+                retry: 2s
+              ti1:
+                from: 6060
+                wait_for: 1m
+              ti2:
+                from: 6061
+                wait_for: 30
+                retry: 10s
+              ti3:
+                from: 6062
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        let cmds = parsed.into_parsed_commands(None).unwrap();
+        assert_eq!(cmds.len(), 3);
+
+        assert_eq!(cmds[0].connection_wait, Duration::from_secs(60));
+        assert_eq!(cmds[0].retry_wait, Duration::from_secs(2));
+        assert_eq!(cmds[1].connection_wait, Duration::from_secs(30));
+        assert_eq!(cmds[1].retry_wait, Duration::from_secs(10));
+        // The arguments which are not set keep the defaults of the command
+        assert_eq!(cmds[2].connection_wait, Duration::from_secs(5));
+        assert_eq!(cmds[2].retry_wait, Duration::from_secs(2));
+
+        let both = r#"
+            tcp_inlets:
+              - from: 6060
+                wait_for: 10s
+                connection-wait: 20s
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(both).unwrap();
+        assert!(parsed.into_parsed_commands(None).is_err());
+    }
+
+    #[test]
+    fn tcp_inlet_config_with_duplicate_ports() {
+        let config = r#"
+            tcp_inlets:
+              ti1:
+                from: 6060
+              ti2:
+                from: '6060'
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        let err = parsed.into_parsed_commands(None).unwrap_err().to_string();
+        assert!(err.contains("ti1"));
+        assert!(err.contains("ti2"));
+
+        let config = r#"
+            tcp_inlets:
+              - from: 0
+              - from: 0
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        assert_eq!(parsed.into_parsed_commands(None).unwrap().len(), 2);
+
+        // The same port can be used on different IP addresses
+        let config = r#"
+            tcp_inlets:
+              - from: 127.0.0.1:6060
+              - from: 127.0.0.2:6060
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        assert_eq!(parsed.into_parsed_commands(None).unwrap().len(), 2);
+
+        // But not on an unspecified IP address and another IP address
+        let config = r#"
+            tcp_inlets:
+              ti1:
+                from: 0.0.0.0:6060
+              ti2:
+                from: 127.0.0.1:6060
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        let err = parsed.into_parsed_commands(None).unwrap_err().to_string();
+        assert!(err.contains("ti1"));
+        assert!(err.contains("ti2"));
+    }
+
+    #[test]
+    fn tcp_inlet_config_with_range() {
+        let config = r#"
+            tcp_inlets:
+              web:
+                from: 8000-8002
+                to: web-outlet-{port}
+                at: n
+              db:
+                from: 6060
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+        let cmds = parsed.into_parsed_commands(None).unwrap();
+        assert_eq!(cmds.len(), 4);
+        assert_eq!(cmds[0].name.as_ref().unwrap(), "db");
+        for (cmd, port) in cmds[1..].iter().zip(8000..) {
+            assert_eq!(cmd.name.as_ref().unwrap(), &format!("web-{port}"));
+            assert_eq!(
+                cmd.from,
+                SchemeHostnamePort::new("tcp", "127.0.0.1", port).unwrap()
+            );
+            assert_eq!(cmd.to, format!("web-outlet-{port}"));
+            assert_eq!(cmd.at.as_ref().unwrap(), "n");
+        }
+
+        let unnamed = r#"
+            tcp_inlets:
+              - from: 8000-8001
+                to: web-outlet-{port}
+              - from: 6060
+        "#;
+        let parsed: TcpInlets = serde_yaml::from_str(unnamed).unwrap();
+        let cmds = parsed.into_parsed_commands(None).unwrap();
+        assert_eq!(cmds.len(), 3);
+        assert_eq!(cmds[0].to, "web-outlet-8000");
+        assert_eq!(cmds[1].to, "web-outlet-8001");
+        assert_eq!(cmds[2].from.port(), 6060);
+    }
+
+    #[test]
+    fn tcp_inlet_config_with_invalid_range() {
+        for (config, expected) in [
+            (
+                r#"
+            tcp_inlets:
+              web:
+                from: 8010-8000
+            "#,
+                "invalid range of ports: 8010-8000",
+            ),
+            (
+                r#"
+            tcp_inlets:
+              web:
+                from: 0-10
+            "#,
+                "invalid port: 0",
+            ),
+            (
+                r#"
+            tcp_inlets:
+              web:
+                from: 1-5000
+            "#,
+                "at most 1024 inlets",
+            ),
+            (
+                r#"
+            tcp_inlets:
+              web:
+                from: 8000-8002
+              web-8001:
+                from: 9000
+            "#,
+                "web-8001",
+            ),
+            (
+                r#"
+            tcp_inlets:
+              web:
+                from: 8000-8002
+              db:
+                from: 8001
+            "#,
+                "are both bound to",
+            ),
+        ] {
+            let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+            let err = parsed.into_parsed_commands(None).unwrap_err().to_string();
+            assert!(err.contains(expected), "{err}");
+        }
+    }
+
+    #[test]
+    fn tcp_inlet_config_with_out_of_range_port() {
+        for config in [
+            r#"
+            tcp_inlets:
+              ti1:
+                from: '70000'
+            "#,
+            r#"
+            tcp_inlets:
+              ti1:
+                from: 70000
+            "#,
+        ] {
+            let parsed: TcpInlets = serde_yaml::from_str(config).unwrap();
+            let err = parsed.into_parsed_commands(None).unwrap_err().to_string();
+            assert!(err.contains("invalid port: 70000"));
+        }
+    }
 }
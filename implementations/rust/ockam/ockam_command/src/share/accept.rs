@@ -5,7 +5,7 @@ use tokio::try_join;
 
 use ockam::Context;
 use ockam_api::nodes::InMemoryNode;
-use ockam_api::orchestrator::share::Invitations;
+use ockam_api::orchestrator::share::{Invitations, RoleInShare};
 
 use crate::shared_args::IdentityOpts;
 use crate::util::async_cmd;
@@ -21,6 +21,10 @@ pub struct AcceptCommand {
     #[command(flatten)]
     pub identity_opts: IdentityOpts,
     pub id: String,
+
+    /// Accept the invitation with a narrower role than the one it grants
+    #[arg(long, short = 'R', value_parser = clap::value_parser!(RoleInShare))]
+    pub role: Option<RoleInShare>,
 }
 
 impl AcceptCommand {
@@ -40,7 +44,9 @@ impl AcceptCommand {
         let controller = node.create_controller().await?;
 
         let get_accepted_invitation = async {
-            let invitation = controller.accept_invitation(ctx, self.id.clone()).await?;
+            let invitation = controller
+                .accept_invitation(ctx, self.id.clone(), self.role.clone())
+                .await?;
             *is_finished.lock().await = true;
             Ok(invitation)
         };
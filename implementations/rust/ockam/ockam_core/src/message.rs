@@ -1,10 +1,11 @@
 use crate::{
     compat::{
         string::{String, ToString},
+        sync::Arc,
         vec::Vec,
     },
     errcode::{Kind, Origin},
-    Address, Error, LocalMessage, Result, Route,
+    Address, CorrelationId, Error, LocalMessage, Result, Route,
 };
 use core::fmt::{self, Debug, Display, Formatter};
 use core::marker::PhantomData;
@@ -139,6 +140,18 @@ impl Decodable for NeutralMessage {
 
 impl Message for NeutralMessage {}
 
+/// A custom serialization format for the messages of a worker
+///
+/// By default, the payloads received by a worker are decoded with [`Decodable::decode`].
+/// A codec can be set when starting a worker to accept payloads written in another format.
+pub trait Codec<M: Message>: Send + Sync + 'static {
+    /// Decode a message from a payload
+    fn decode(&self, payload: &[u8]) -> Result<M>;
+
+    /// Encode a message into a payload
+    fn encode(&self, message: M) -> Result<Encoded>;
+}
+
 impl From<serde_bare::error::Error> for Error {
     #[track_caller]
     fn from(e: serde_bare::error::Error) -> Self {
@@ -197,6 +210,8 @@ pub struct Routed<M: Message> {
     src_addr: Address,
     /// A `LocalMessage` that contains routing information for the wrapped message.
     local_msg: LocalMessage,
+    /// Codec used to decode the payload, instead of [`Decodable::decode`]
+    codec: Option<Arc<dyn Codec<M>>>,
 }
 
 impl<M: Message> Routed<M> {
@@ -209,9 +224,16 @@ impl<M: Message> Routed<M> {
             msg_addr,
             src_addr,
             local_msg,
+            codec: None,
         }
     }
 
+    /// Decode the payload with the given codec, see [`Codec`]
+    pub fn with_codec(mut self, codec: Option<Arc<dyn Codec<M>>>) -> Self {
+        self.codec = codec;
+        self
+    }
+
     /// Return a copy of the message address.
     #[inline]
     pub fn msg_addr(&self) -> Address {
@@ -235,16 +257,34 @@ impl<M: Message> Routed<M> {
     pub fn return_route(&self) -> &Route {
         self.local_msg.return_route()
     }
+    /// Identifier of the request the wrapped message is, or responds to.
+    #[inline]
+    pub fn request_id(&self) -> Option<CorrelationId> {
+        self.local_msg.request_id()
+    }
+
     /// Return a copy of the sender address for the wrapped message.
     #[inline]
     pub fn sender(&self) -> Result<&Address> {
         self.local_msg.return_route().recipient()
     }
 
+    /// Decode and return the original message, without consuming the message wrapper.
+    ///
+    /// The routing information stays available, for example to reply using
+    /// [`Self::return_route`] after looking at the message.
+    #[inline]
+    pub fn body(&self) -> Result<M> {
+        match &self.codec {
+            Some(codec) => codec.decode(self.payload()),
+            None => M::decode(self.payload()),
+        }
+    }
+
     /// Consume the message wrapper and return the original message.
     #[inline]
     pub fn into_body(self) -> Result<M> {
-        M::decode(&self.into_payload())
+        self.body()
     }
 
     /// Consume the message wrapper and return the underlying local message.
@@ -341,3 +381,22 @@ impl Decodable for Any {
         Ok(Self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route;
+
+    #[test]
+    fn test_routed_body_keeps_routing_information() {
+        let local_msg = LocalMessage::new()
+            .with_onward_route(route!["receiver"])
+            .with_return_route(route!["sender"])
+            .with_payload("hello".to_string().encode().unwrap());
+        let routed = Routed::<String>::new("receiver".into(), "sender".into(), local_msg);
+
+        assert_eq!(routed.body().unwrap(), "hello");
+        assert_eq!(routed.return_route(), &route!["sender"]);
+        assert_eq!(routed.into_body().unwrap(), "hello");
+    }
+}
@@ -1,5 +1,17 @@
 use crate::compat::boxed::Box;
 use crate::{async_trait, Result};
+use futures_util::future::{select, Either};
+
+/// Signal telling a [`Processor`] that it is being stopped, see
+/// [`Processor::process_with_cancellation`]
+#[async_trait]
+pub trait Cancellation: Send + Sync {
+    /// Return true once the processor is being stopped
+    fn is_cancelled(&self) -> bool;
+
+    /// Wait until the processor is being stopped
+    async fn cancelled(&self);
+}
 
 /// Defines an interface for Ockam Workers that need to continuously
 /// perform background operations.
@@ -37,4 +49,25 @@ pub trait Processor: Send + 'static {
     async fn process(&mut self, _context: &mut Self::Context) -> Result<bool> {
         Ok(false)
     }
+
+    /// Variant of [`process`](Self::process) which can be stopped cooperatively
+    ///
+    /// `cancellation` is triggered when the processor is stopped. An implementation returning
+    /// promptly once cancelled, for example by awaiting
+    /// [`Cancellation::cancelled`] alongside a long operation, can finish its work cleanly.
+    /// Otherwise, the processing is aborted at an arbitrary `.await` point after a grace
+    /// period.
+    ///
+    /// The default implementation runs [`process`](Self::process) and aborts it as soon as
+    /// the processor is cancelled.
+    async fn process_with_cancellation(
+        &mut self,
+        context: &mut Self::Context,
+        cancellation: &dyn Cancellation,
+    ) -> Result<bool> {
+        match select(self.process(context), cancellation.cancelled()).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => Ok(false),
+        }
+    }
 }
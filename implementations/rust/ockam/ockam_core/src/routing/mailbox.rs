@@ -213,4 +213,19 @@ impl Mailboxes {
     pub fn additional_mailboxes(&self) -> &Vec<Mailbox> {
         &self.additional_mailboxes
     }
+
+    /// Add an additional [`Mailbox`]
+    pub fn add_mailbox(&mut self, mailbox: Mailbox) {
+        self.additional_mailboxes.push(mailbox);
+    }
+
+    /// Remove the additional [`Mailbox`] with the given [`Address`] and return it.
+    /// The primary [`Mailbox`] can't be removed
+    pub fn remove_mailbox(&mut self, address: &Address) -> Option<Mailbox> {
+        let index = self
+            .additional_mailboxes
+            .iter()
+            .position(|x| &x.address == address)?;
+        Some(self.additional_mailboxes.remove(index))
+    }
 }
@@ -0,0 +1,33 @@
+use crate::compat::rand::random;
+use core::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+
+/// Lightweight identifier shared by all the messages caused by the same initial message
+///
+/// Unlike the OpenTelemetry tracing context, it is available without `std`, so messages can
+/// be correlated across workers on constrained targets.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    /// Create a correlation id from a raw value
+    pub fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    /// Generate a new random correlation id
+    pub fn random() -> Self {
+        Self(random())
+    }
+
+    /// Raw value
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
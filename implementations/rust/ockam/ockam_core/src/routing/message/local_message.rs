@@ -1,6 +1,9 @@
 #[cfg(feature = "std")]
 use crate::OpenTelemetryContext;
-use crate::{compat::vec::Vec, route, Address, Message, Route, TransportMessage};
+use crate::{
+    compat::vec::Vec, route, Address, CorrelationId, Message, MessagePriority, Route,
+    TransportMessage,
+};
 
 use crate::{LocalInfo, Result};
 use cfg_if::cfg_if;
@@ -51,6 +54,12 @@ pub struct LocalMessage {
     /// independently of its payload. For example this can be used to store the identifier that
     /// was used to encrypt the payload
     pub local_info: Vec<LocalInfo>,
+    /// Identifier shared with the messages which caused this one, available without `std`
+    pub correlation_id: Option<CorrelationId>,
+    /// Identifier of the request this message is, or responds to
+    pub request_id: Option<CorrelationId>,
+    /// Priority of the message, which transports can map to their quality of service mechanism
+    pub priority: MessagePriority,
     /// Local tracing context
     #[cfg(feature = "std")]
     pub tracing_context: OpenTelemetryContext,
@@ -169,6 +178,21 @@ impl LocalMessage {
         self.local_info.clear()
     }
 
+    /// Get the correlation id associated to this local message
+    pub fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id
+    }
+
+    /// Get the identifier of the request this message is, or responds to
+    pub fn request_id(&self) -> Option<CorrelationId> {
+        self.request_id
+    }
+
+    /// Get the priority of this local message
+    pub fn priority(&self) -> MessagePriority {
+        self.priority
+    }
+
     /// Get the tracing context associated to this local message
     #[cfg(feature = "std")]
     pub fn tracing_context(&self) -> OpenTelemetryContext {
@@ -274,6 +298,9 @@ impl LocalMessage {
             return_route,
             payload,
             local_info,
+            correlation_id: None,
+            request_id: None,
+            priority: MessagePriority::default(),
             #[cfg(feature = "std")]
             tracing_context: OpenTelemetryContext::current(),
         }
@@ -311,6 +338,24 @@ impl LocalMessage {
         Self { local_info, ..self }
     }
 
+    /// Specify the correlation id
+    pub fn with_correlation_id(self, correlation_id: Option<CorrelationId>) -> Self {
+        Self {
+            correlation_id,
+            ..self
+        }
+    }
+
+    /// Specify the identifier of the request
+    pub fn with_request_id(self, request_id: Option<CorrelationId>) -> Self {
+        Self { request_id, ..self }
+    }
+
+    /// Specify the priority
+    pub fn with_priority(self, priority: MessagePriority) -> Self {
+        Self { priority, ..self }
+    }
+
     /// Specify the tracing context
     #[cfg(feature = "std")]
     pub fn with_tracing_context(self, tracing_context: OpenTelemetryContext) -> Self {
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// Priority of a message, which transports can map to their own quality of service mechanism
+///
+/// The priority is local to a node: it is not sent over the wire, but it is propagated to
+/// the messages sent by a worker while handling a message.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, Hash, Ord, PartialOrd, Eq, PartialEq,
+)]
+pub enum MessagePriority {
+    /// Background traffic which can be delayed
+    Low,
+    /// Default priority
+    #[default]
+    Normal,
+    /// Interactive traffic
+    High,
+    /// Latency sensitive traffic, for example control messages
+    Urgent,
+}
+
+impl MessagePriority {
+    /// Differentiated Services Code Point (RFC 2474) corresponding to this priority
+    pub fn dscp(&self) -> u8 {
+        match self {
+            // CS1, lower effort
+            MessagePriority::Low => 8,
+            // CS0, best effort
+            MessagePriority::Normal => 0,
+            // AF41
+            MessagePriority::High => 34,
+            // EF, expedited forwarding
+            MessagePriority::Urgent => 46,
+        }
+    }
+
+    /// Value of the IPv4 Type of Service byte corresponding to this priority
+    pub fn tos(&self) -> u8 {
+        self.dscp() << 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tos() {
+        assert_eq!(MessagePriority::default(), MessagePriority::Normal);
+        assert_eq!(MessagePriority::Normal.tos(), 0);
+        assert_eq!(MessagePriority::Urgent.tos(), 0xb8);
+        assert!(MessagePriority::Low < MessagePriority::Normal);
+        assert!(MessagePriority::High < MessagePriority::Urgent);
+    }
+}
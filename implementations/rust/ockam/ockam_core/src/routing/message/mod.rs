@@ -1,12 +1,16 @@
+mod correlation_id;
 mod local_info;
 mod local_message;
+mod message_priority;
 #[cfg(feature = "std")]
 mod opentelemetry;
 mod relay_message;
 mod transport_message;
 
+pub use correlation_id::*;
 pub use local_info::*;
 pub use local_message::*;
+pub use message_priority::*;
 #[cfg(feature = "std")]
 pub use opentelemetry::*;
 pub use relay_message::*;
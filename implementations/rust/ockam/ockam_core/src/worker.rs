@@ -1,4 +1,15 @@
-use crate::{async_trait, compat::boxed::Box, Message, Result, Routed};
+use crate::compat::vec::Vec;
+use crate::{async_trait, compat::boxed::Box, Address, Message, Result, Routed};
+
+/// Something a [`Worker`] must be allowed to do by its access controls, see
+/// [`Worker::required_capabilities`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Send messages to the given address, checked with the outgoing access control
+    SendTo(Address),
+    /// Receive messages from the given address, checked with the incoming access control
+    ReceiveFrom(Address),
+}
 
 /// Defines the core interface shared by all Ockam Workers.
 ///
@@ -19,6 +30,15 @@ pub trait Worker: Send + 'static {
     /// custom node implementations may use a different context type.
     type Context: Send + 'static;
 
+    /// Capabilities this worker needs from the access controls of its primary address
+    ///
+    /// They are checked when the worker is started, which fails if the access controls deny
+    /// one of them. Access controls which can't be evaluated without waiting, for example
+    /// because they query a database, are not checked.
+    fn required_capabilities(&self) -> Vec<Capability> {
+        Vec::new()
+    }
+
     /// Override initialisation behaviour.
     async fn initialize(&mut self, _context: &mut Self::Context) -> Result<()> {
         Ok(())
@@ -29,6 +49,28 @@ pub trait Worker: Send + 'static {
         Ok(())
     }
 
+    /// Called when an additional address was attached to this worker while it's running.
+    ///
+    /// Messages sent to that address can be received from now on.
+    async fn on_address_added(
+        &mut self,
+        _context: &mut Self::Context,
+        _address: &Address,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when an additional address was detached from this worker while it's running.
+    ///
+    /// Messages sent to that address won't be received anymore.
+    async fn on_address_removed(
+        &mut self,
+        _context: &mut Self::Context,
+        _address: &Address,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Try to open and handle a typed message.
     async fn handle_message(
         &mut self,
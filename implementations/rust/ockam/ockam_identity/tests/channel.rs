@@ -80,6 +80,59 @@ async fn test_channel(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
+#[ockam_macros::test]
+async fn test_channel_reply_after_reading_body(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels.create_secure_channel_listener(
+        ctx,
+        &bob,
+        "bob_listener",
+        SecureChannelListenerOptions::new(),
+    )?;
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx.new_detached_with_mailboxes(Mailboxes::primary(
+        "child",
+        Arc::new(AllowAll),
+        Arc::new(AllowAll),
+    ))?;
+    ctx.flow_controls()
+        .add_consumer(&"child".into(), bob_listener.flow_control_id());
+    ctx.flow_controls()
+        .add_consumer(&"child".into(), alice_channel.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.primary_address().clone()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    // The body is decoded while the routing information of the message is still available
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Bob!", msg.body()?);
+    child_ctx
+        .send(msg.return_route().clone(), "Hello, Alice!".to_string())
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Alice!", msg.into_body()?);
+
+    Ok(())
+}
+
 #[ockam_macros::test]
 async fn test_channel_send_credentials(context: &mut Context) -> Result<()> {
     let secure_channels = secure_channels().await?;
@@ -2,6 +2,7 @@ use crate::channel_types::MessageReceiver;
 use crate::tokio::runtime::Handle;
 use core::sync::atomic::AtomicUsize;
 use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::string::String;
 use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::compat::time::Duration;
 use ockam_core::compat::vec::Vec;
@@ -9,11 +10,18 @@ use ockam_core::flow_control::FlowControls;
 #[cfg(feature = "std")]
 use ockam_core::OpenTelemetryContext;
 use ockam_core::{
-    async_trait, Address, AddressMetadata, Error, Mailboxes, RelayMessage, Result, TransportType,
+    async_trait, Address, AddressMetadata, CorrelationId, Error, IncomingAccessControl, Mailbox,
+    Mailboxes, MessagePriority, OutgoingAccessControl, RelayMessage, Result, TransportType,
 };
 
+use crate::context::local_store::LocalStore;
+#[cfg(feature = "std")]
+use crate::relay::{RouterEvent, WorkerReplacement};
 use crate::router::Router;
 #[cfg(feature = "std")]
+use crate::tokio::sync::watch;
+use crate::MessageCapture;
+#[cfg(feature = "std")]
 use core::fmt::{Debug, Formatter};
 use ockam_core::compat::sync::Weak;
 use ockam_core::errcode::{Kind, Origin};
@@ -72,8 +80,34 @@ pub struct Context {
     pub(super) transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
     pub(super) flow_controls: FlowControls,
     pub(super) mode: ContextMode,
+    /// Key/value labels attached to the worker, reported as tracing span attributes
+    pub(super) labels: Vec<(String, String)>,
+    /// Correlation id of the message being handled, propagated to the messages sent
+    pub(super) correlation_id: Option<CorrelationId>,
+    /// Priority of the message being handled, propagated to the messages sent
+    pub(super) message_priority: MessagePriority,
     #[cfg(feature = "std")]
     pub(super) tracing_context: OpenTelemetryContext,
+    /// Changes of the set of addresses of this context, or of its worker, sent by the router
+    #[cfg(feature = "std")]
+    pub(super) router_events: MessageReceiver<RouterEvent>,
+    /// New worker implementation to run on this context, once the current one is shut down
+    #[cfg(feature = "std")]
+    pub(crate) replacement: Option<WorkerReplacement>,
+    /// Set to true when the worker or processor owning this context is being stopped
+    #[cfg(feature = "std")]
+    pub(crate) stopping: Arc<watch::Sender<bool>>,
+    /// Last messages delivered to the worker owning this context, if they are captured
+    pub(super) message_capture: Option<MessageCapture>,
+    /// Values attached to the worker owning this context, see [`Context::local_store`]
+    pub(super) local_store: LocalStore,
+    /// Time after which receiving a message fails, see [`Context::with_deadline`]
+    #[cfg(feature = "std")]
+    pub(super) deadline: Option<std::time::Instant>,
+    /// Last use of a detached context which is stopped when unused, see
+    /// [`Context::new_detached_with_ttl`]
+    #[cfg(feature = "std")]
+    pub(super) last_used: Option<Arc<super::ttl::LastUsed>>,
 }
 
 /// This trait can be used to integrate transports into a node
@@ -90,6 +124,7 @@ impl Debug for Context {
             .field("mailboxes", &self.mailboxes)
             .field("runtime", &self.runtime_handle)
             .field("mode", &self.mode)
+            .field("labels", &self.labels)
             .finish()
     }
 }
@@ -132,11 +167,77 @@ impl Context {
         &self.mailboxes
     }
 
+    /// Replace the incoming and outgoing access control of the primary address of this context
+    ///
+    /// The swap is atomic with respect to this context: messages that were already received or
+    /// sent were checked against the previous policies, while every message received or sent
+    /// after this call, including messages already waiting in the mailbox queue, is checked
+    /// against the new ones.
+    ///
+    /// This is mostly useful for detached contexts created with
+    /// [`new_detached()`](Self::new_detached), whose policies may need to be tightened
+    /// after, for example, a successful credential check.
+    pub fn set_access_control(
+        &mut self,
+        incoming: impl IncomingAccessControl,
+        outgoing: impl OutgoingAccessControl,
+    ) {
+        let primary_mailbox = self.mailboxes.primary_mailbox();
+        let primary_mailbox = Mailbox::new(
+            primary_mailbox.address().clone(),
+            primary_mailbox.metadata().clone(),
+            Arc::new(incoming),
+            Arc::new(outgoing),
+        );
+
+        self.mailboxes = Mailboxes::new(
+            primary_mailbox,
+            self.mailboxes.additional_mailboxes().clone(),
+        );
+    }
+
     /// Shared [`FlowControls`] instance
     pub fn flow_controls(&self) -> &FlowControls {
         &self.flow_controls
     }
 
+    /// Key/value labels attached to this worker, e.g. a tenant id or a role.
+    ///
+    /// Labels are reported as attributes of the tracing spans created when handling messages
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.labels
+    }
+
+    /// Set the labels of this worker
+    pub(crate) fn set_labels(&mut self, labels: Vec<(String, String)>) {
+        self.labels = labels
+    }
+
+    /// Correlation id of the message being handled by this context, if any
+    ///
+    /// It is attached to the messages sent from this context, a new one is generated
+    /// when there is none.
+    pub fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id
+    }
+
+    /// Set the correlation id attached to the messages sent from this context
+    pub fn set_correlation_id(&mut self, correlation_id: Option<CorrelationId>) {
+        self.correlation_id = correlation_id
+    }
+
+    /// Priority of the message being handled by this context
+    ///
+    /// It is attached to the messages sent from this context.
+    pub fn message_priority(&self) -> MessagePriority {
+        self.message_priority
+    }
+
+    /// Set the priority attached to the messages sent from this context
+    pub fn set_message_priority(&mut self, message_priority: MessagePriority) {
+        self.message_priority = message_priority
+    }
+
     /// Return the tracing context
     #[cfg(feature = "std")]
     pub fn tracing_context(&self) -> OpenTelemetryContext {
@@ -161,6 +262,15 @@ impl Context {
         Ok(self.router()?.is_worker_registered_at(address))
     }
 
+    /// Return true if a message sent to this address can be delivered right now
+    ///
+    /// Unlike [`Self::is_worker_registered_at`], secondary addresses of workers and
+    /// processors are found as well. An address of another transport exists if that
+    /// transport is registered on this node.
+    pub fn address_exists(&self, address: &Address) -> Result<bool> {
+        Ok(self.router()?.address_exists(address))
+    }
+
     /// Finds the terminal address of a route, if present
     pub fn find_terminal_address<'a>(
         &self,
@@ -15,7 +15,7 @@ use ockam_core::{
 use ockam_transport_core::Transport;
 
 use crate::channel_types::{message_channel, oneshot_channel, OneshotReceiver};
-use crate::router::Router;
+use crate::router::{Router, WorkerMeta};
 use crate::{debugger, Context, ContextMode};
 use crate::{relay::CtrlSignal, router::SenderPair};
 use tokio::runtime::Handle;
@@ -75,6 +75,8 @@ impl Context {
     ) -> (Self, SenderPair, OneshotReceiver<CtrlSignal>) {
         let (mailbox_tx, receiver) = message_channel();
         let (ctrl_tx, ctrl_rx) = oneshot_channel();
+        #[cfg(feature = "std")]
+        let (router_events_tx, router_events) = message_channel();
         (
             Self {
                 runtime_handle,
@@ -85,12 +87,29 @@ impl Context {
                 mailbox_count: Arc::new(0.into()),
                 transports,
                 flow_controls: flow_controls.clone(),
+                labels: Default::default(),
+                correlation_id: None,
+                message_priority: Default::default(),
                 #[cfg(feature = "std")]
                 tracing_context,
+                #[cfg(feature = "std")]
+                router_events,
+                #[cfg(feature = "std")]
+                replacement: None,
+                #[cfg(feature = "std")]
+                stopping: Arc::new(tokio::sync::watch::channel(false).0),
+                message_capture: None,
+                local_store: Default::default(),
+                #[cfg(feature = "std")]
+                deadline: None,
+                #[cfg(feature = "std")]
+                last_used: None,
             },
             SenderPair {
                 msgs: mailbox_tx,
                 ctrl: ctrl_tx,
+                #[cfg(feature = "std")]
+                router_events: router_events_tx,
             },
             ctrl_rx,
         )
@@ -138,11 +157,61 @@ impl Context {
     }
 
     /// Utility function to sleep tasks from other crates
+    ///
+    /// The sleep ends early if the worker or processor owning this context is stopped
+    /// meanwhile, so that it doesn't delay the shutdown. Use
+    /// [`sleep_uninterruptible`](Self::sleep_uninterruptible) to always sleep for the whole
+    /// duration.
     #[doc(hidden)]
     pub async fn sleep(&self, duration: Duration) {
+        #[cfg(feature = "std")]
+        {
+            let mut stopping = self.stopping.subscribe();
+            tokio::select! {
+                _ = tokio::time::sleep(duration) => {}
+                _ = stopping.wait_for(|stopping| *stopping) => {
+                    debug!(address=%self.primary_address(), "Sleep interrupted by the worker shutdown");
+                }
+            }
+        }
+
+        #[cfg(not(feature = "std"))]
         tokio::time::sleep(duration).await;
     }
 
+    /// Sleep for the whole duration, even if the worker or processor owning this context is
+    /// stopped meanwhile
+    #[doc(hidden)]
+    pub async fn sleep_uninterruptible(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    /// Return true if the worker or processor owning this context is being stopped
+    #[cfg(feature = "std")]
+    pub fn is_stopping(&self) -> bool {
+        *self.stopping.borrow()
+    }
+
+    /// Return true if the worker or processor owning this context is being stopped
+    #[cfg(not(feature = "std"))]
+    pub fn is_stopping(&self) -> bool {
+        false
+    }
+
+    /// Interrupt the current and future sleeps of this context, and of the contexts sharing
+    /// its stop signal
+    pub(crate) fn set_stopping(&self) {
+        #[cfg(feature = "std")]
+        self.stopping.send_replace(true);
+    }
+
+    /// Share the stop signal of another context, so that this context is considered to be
+    /// stopping when the other one is
+    #[cfg(feature = "std")]
+    pub(crate) fn share_stop_signal(&mut self, other: &Context) {
+        self.stopping = other.stopping.clone();
+    }
+
     /// Utility function to sleep tasks for long periods of time (seconds precision)
     /// Difference between this and `sleep` is that this sleeps in 1 second intervals and recalculates time left,
     /// which account for the time the device was in sleep state
@@ -167,7 +236,7 @@ impl Context {
 
         loop {
             self.sleep(Duration::from_secs(1)).await;
-            if now().unwrap() >= deadline_timestamp_seconds {
+            if self.is_stopping() || now().unwrap() >= deadline_timestamp_seconds {
                 return;
             }
         }
@@ -233,8 +302,10 @@ impl Context {
         self.router()?.add_worker(
             ctx.mailboxes(),
             sender,
-            true,
-            Default::default(),
+            WorkerMeta {
+                detached: true,
+                ..Default::default()
+            },
             self.mailbox_count.clone(),
         )?;
 
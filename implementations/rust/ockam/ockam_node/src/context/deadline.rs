@@ -0,0 +1,86 @@
+use crate::context::MessageWait;
+use crate::Context;
+use core::ops::{Deref, DerefMut};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use std::time::Instant;
+
+/// A [`Context`] whose receive calls fail once a deadline has passed
+///
+/// It uses the addresses, access controls and mailbox of the context it was created from,
+/// so that the replies sent to that context are received through it. The previous deadline
+/// of the context is restored when dropped.
+/// Obtained with [`Context::with_deadline`].
+pub struct ContextWithDeadline<'a> {
+    ctx: &'a mut Context,
+    previous_deadline: Option<Instant>,
+}
+
+impl Deref for ContextWithDeadline<'_> {
+    type Target = Context;
+
+    fn deref(&self) -> &Self::Target {
+        self.ctx
+    }
+}
+
+impl DerefMut for ContextWithDeadline<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ctx
+    }
+}
+
+impl Drop for ContextWithDeadline<'_> {
+    fn drop(&mut self) {
+        self.ctx.deadline = self.previous_deadline;
+    }
+}
+
+impl Context {
+    /// Bound the receive calls of this context by a deadline, until the returned
+    /// [`ContextWithDeadline`] is dropped
+    ///
+    /// The timeouts of [`receive_extended`](Self::receive_extended) and
+    /// [`send_and_receive_extended`](Self::send_and_receive_extended) are shortened to the
+    /// time remaining before the deadline, so that a multi-step exchange can share a single
+    /// deadline. If this context already has an earlier deadline, it is kept.
+    pub fn with_deadline(&mut self, deadline: Instant) -> ContextWithDeadline<'_> {
+        let previous_deadline = self.deadline;
+        self.deadline = Some(match previous_deadline {
+            Some(current) => current.min(deadline),
+            None => deadline,
+        });
+
+        ContextWithDeadline {
+            ctx: self,
+            previous_deadline,
+        }
+    }
+
+    /// Deadline of this context, see [`Self::with_deadline`]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Shorten the wait for a message to the time remaining before the deadline, or fail
+    /// if the deadline has passed
+    pub(super) fn bound_to_deadline(&self, message_wait: MessageWait) -> Result<MessageWait> {
+        let Some(deadline) = self.deadline else {
+            return Ok(message_wait);
+        };
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Timeout,
+                "The deadline of the context has passed",
+            ));
+        }
+
+        Ok(match message_wait {
+            MessageWait::Timeout(timeout) => MessageWait::Timeout(timeout.min(remaining)),
+            MessageWait::Blocking => MessageWait::Timeout(remaining),
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use crate::channel_types::MessageReceiver;
+use crate::Context;
+use ockam_core::{Address, Error, Result};
+
+/// Default number of [`WorkerError`]s buffered by the error sink
+pub const DEFAULT_ERROR_SINK_CAPACITY: usize = 64;
+
+/// An error returned by [`Worker::handle_message`](ockam_core::Worker::handle_message)
+#[derive(Debug)]
+pub struct WorkerError {
+    /// Primary address of the worker
+    pub address: Address,
+    /// Returned error
+    pub error: Error,
+}
+
+impl Context {
+    /// Receive the errors returned by the workers of this node when handling a message
+    ///
+    /// The errors are still logged. If the returned receiver doesn't keep up, the errors
+    /// which don't fit into its buffer of `capacity` errors are dropped. Only one sink can be
+    /// set for a node, setting a new one replaces the previous one.
+    pub fn set_error_sink(&self, capacity: usize) -> Result<MessageReceiver<WorkerError>> {
+        Ok(self.router()?.set_error_sink(capacity))
+    }
+
+    /// Stop sending worker errors to the error sink
+    pub fn remove_error_sink(&self) -> Result<()> {
+        self.router()?.remove_error_sink();
+        Ok(())
+    }
+
+    /// Send an error returned by the worker owning this context to the error sink, if any
+    pub(crate) fn report_worker_error(&self, error: Error) {
+        if let Ok(router) = self.router() {
+            router.report_worker_error(self.primary_address(), error)
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use core::time::Duration;
+
+/// Default maximum delay between two attempts at handling a message
+pub const DEFAULT_HANDLE_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Re-delivery of the messages that a worker failed to handle
+///
+/// When [`Worker::handle_message`](ockam_core::Worker::handle_message) returns an error, the
+/// same message is handled again, up to `max_retries` times. The delay between two attempts
+/// starts at `initial_backoff` and doubles after each attempt, up to `max_backoff`. The error
+/// of the last attempt is reported as usual.
+///
+/// NOTE: with a retry policy, messages are delivered *at least once*: a message may be
+/// handled several times, for example if the worker fails after sending a response. The
+/// handling of the messages must then be idempotent.
+#[derive(Clone, Debug)]
+pub struct HandleRetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl HandleRetryPolicy {
+    /// Handle a failed message again up to `max_retries` times, waiting `initial_backoff`
+    /// before the first retry
+    pub fn new(max_retries: u32, initial_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+            max_backoff: DEFAULT_HANDLE_RETRY_MAX_BACKOFF.max(initial_backoff),
+        }
+    }
+
+    /// Set the maximum delay between two attempts
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Maximum number of times a failed message is handled again
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Delay before the retry following the given number of failed retries, `None` if
+    /// the message must not be retried anymore
+    pub(crate) fn backoff(&self, retries: u32) -> Option<Duration> {
+        if retries >= self.max_retries {
+            return None;
+        }
+
+        let factor = 2u32.saturating_pow(retries);
+        Some(
+            self.initial_backoff
+                .saturating_mul(factor)
+                .min(self.max_backoff),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        let policy = HandleRetryPolicy::new(5, Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500));
+
+        let backoffs: Vec<Option<Duration>> =
+            (0..6).map(|retries| policy.backoff(retries)).collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(400)),
+                Some(Duration::from_millis(500)),
+                Some(Duration::from_millis(500)),
+                None,
+            ]
+        );
+
+        assert_eq!(HandleRetryPolicy::new(0, Duration::ZERO).backoff(0), None);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::Context;
+use core::any::{Any, TypeId};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+
+/// Values attached to a worker, at most one per type, see [`Context::local_store`]
+///
+/// The store is shared by the contexts handling the messages of the same worker, and
+/// cleared when the worker is shut down.
+#[derive(Clone, Default)]
+pub(crate) struct LocalStore {
+    values: Arc<Mutex<BTreeMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
+}
+
+impl LocalStore {
+    /// Return the value of type `T`, inserting a default value if there is none yet
+    pub(crate) fn get_or_default<T: Default + Send + Sync + 'static>(&self) -> Arc<T> {
+        let value = self
+            .values
+            .lock()
+            .unwrap()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(T::default()))
+            .clone();
+
+        // The values are indexed by their type id, so the downcast can't fail
+        value
+            .downcast::<T>()
+            .unwrap_or_else(|_| unreachable!("the local store value has the wrong type"))
+    }
+
+    /// Remove all the values
+    pub(crate) fn clear(&self) {
+        self.values.lock().unwrap().clear()
+    }
+}
+
+impl Context {
+    /// Value of type `T` attached to the worker owning this context, created with
+    /// `T::default()` the first time it is requested
+    ///
+    /// This lets middlewares and helpers keep some state per worker, across messages, without
+    /// modifying the worker. Use a type private to your module to avoid collisions, and
+    /// interior mutability to update the value. The values are dropped when the worker is
+    /// shut down.
+    pub fn local_store<T: Default + Send + Sync + 'static>(&self) -> Arc<T> {
+        self.local_store.get_or_default::<T>()
+    }
+
+    /// Share the local store of another context, so that both contexts see the same values
+    pub(crate) fn share_local_store(&mut self, other: &Context) {
+        self.local_store = other.local_store.clone();
+    }
+
+    /// Drop all the values of the local store
+    pub(crate) fn clear_local_store(&self) {
+        self.local_store.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    #[test]
+    fn test_local_store() {
+        let store = LocalStore::default();
+        store
+            .get_or_default::<Counter>()
+            .0
+            .fetch_add(1, Ordering::Relaxed);
+
+        // The values are shared between the clones, and indexed by type
+        let shared = store.clone();
+        assert_eq!(
+            shared.get_or_default::<Counter>().0.load(Ordering::Relaxed),
+            1
+        );
+        assert_eq!(*shared.get_or_default::<u64>(), 0);
+
+        store.clear();
+        assert_eq!(
+            shared.get_or_default::<Counter>().0.load(Ordering::Relaxed),
+            0
+        );
+    }
+}
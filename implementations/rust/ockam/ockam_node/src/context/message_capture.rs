@@ -0,0 +1,135 @@
+use crate::error::NodeError;
+use crate::Context;
+use ockam_core::compat::collections::VecDeque;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, RelayMessage, Result};
+
+/// Last messages delivered to a worker, kept in a ring buffer
+///
+/// This is meant for debugging and testing: the captured messages can be inspected, and
+/// replayed to reproduce a bug without re-running a whole scenario.
+/// See [`Context::start_worker_with_capture`].
+#[derive(Clone)]
+pub struct MessageCapture {
+    capacity: usize,
+    messages: Arc<Mutex<VecDeque<RelayMessage>>>,
+}
+
+impl MessageCapture {
+    /// Create a capture retaining the last `capacity` messages
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// Maximum number of retained messages
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Captured messages, from the oldest to the most recent one
+    pub fn messages(&self) -> Vec<RelayMessage> {
+        self.messages.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Forget all the captured messages
+    pub fn clear(&self) {
+        self.messages.lock().unwrap().clear()
+    }
+
+    /// Deliver the captured messages again to their original destination, in the same order
+    ///
+    /// The messages skip the outgoing access control of their original sender, but go
+    /// through the incoming access control of the worker again. Since they are delivered
+    /// again, they are captured again. Return the number of replayed messages.
+    pub async fn replay(&self, ctx: &Context) -> Result<usize> {
+        let messages = self.messages();
+        let router = ctx.router()?;
+
+        for relay_msg in messages.iter().cloned() {
+            let sender = router.resolve(relay_msg.destination())?;
+            sender
+                .send(relay_msg)
+                .await
+                .map_err(NodeError::from_send_err)?;
+        }
+
+        Ok(messages.len())
+    }
+
+    pub(crate) fn record(&self, relay_msg: &RelayMessage) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut messages = self.messages.lock().unwrap();
+        if messages.len() == self.capacity {
+            messages.pop_front();
+        }
+        messages.push_back(relay_msg.clone());
+    }
+}
+
+impl Context {
+    /// Messages captured for the worker owning this context, if it was started with
+    /// [`start_worker_with_capture`](Self::start_worker_with_capture)
+    pub fn message_capture(&self) -> Option<&MessageCapture> {
+        self.message_capture.as_ref()
+    }
+
+    /// Replay the messages captured for the worker owning this context
+    ///
+    /// See [`MessageCapture::replay`].
+    pub async fn replay(&self) -> Result<usize> {
+        match &self.message_capture {
+            Some(message_capture) => message_capture.replay(self).await,
+            None => Err(Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                format!("No messages are captured for {}", self.primary_address()),
+            )),
+        }
+    }
+
+    pub(crate) fn set_message_capture(&mut self, message_capture: Option<MessageCapture>) {
+        self.message_capture = message_capture
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::{route, Address, LocalMessage};
+
+    fn relay_message(payload: u8) -> RelayMessage {
+        RelayMessage::new(
+            Address::from_string("sender"),
+            Address::from_string("receiver"),
+            LocalMessage::new()
+                .with_onward_route(route!["receiver"])
+                .with_payload(vec![payload]),
+        )
+    }
+
+    #[test]
+    fn test_record_keeps_last_messages() {
+        let capture = MessageCapture::new(2);
+        for payload in 1..=3 {
+            capture.record(&relay_message(payload));
+        }
+
+        let payloads: Vec<u8> = capture.messages().iter().map(|m| m.payload()[0]).collect();
+        assert_eq!(payloads, vec![2, 3]);
+
+        capture.clear();
+        assert!(capture.messages().is_empty());
+
+        let capture = MessageCapture::new(0);
+        capture.record(&relay_message(1));
+        assert!(capture.messages().is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::Context;
+use ockam_core::compat::{sync::Arc, vec::Vec};
+use ockam_core::{async_trait, RelayMessage, Result};
+
+/// Code run on every message delivered to a worker of the node, before the worker handles it
+///
+/// This can be used for cross-cutting concerns, like logging, metrics or rate limiting,
+/// without modifying the workers. Middlewares are run in the order they were added, after the
+/// incoming access control of the worker.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    /// Inspect or transform a message, `ctx` is the context of the worker receiving it
+    ///
+    /// Return `None` to drop the message: the next middlewares are not run and the worker
+    /// doesn't handle it. An error is reported like an error returned by the worker.
+    async fn handle(&self, ctx: &Context, msg: RelayMessage) -> Result<Option<RelayMessage>>;
+}
+
+impl Context {
+    /// Run the given [`Middleware`] on every message delivered to a worker of this node,
+    /// after the middlewares added previously
+    pub fn add_middleware(&self, middleware: impl Middleware) -> Result<()> {
+        self.router()?.add_middleware(Arc::new(middleware));
+        Ok(())
+    }
+
+    /// Remove all the middlewares of this node
+    pub fn clear_middlewares(&self) -> Result<()> {
+        self.router()?.clear_middlewares();
+        Ok(())
+    }
+
+    /// Run the middlewares of the node on a message delivered to the worker owning this
+    /// context, `None` is returned if the message must be dropped
+    pub(crate) async fn apply_middlewares(
+        &self,
+        mut msg: RelayMessage,
+    ) -> Result<Option<RelayMessage>> {
+        let middlewares: Vec<Arc<dyn Middleware>> = self.router()?.middlewares();
+
+        for middleware in middlewares {
+            match middleware.handle(self, msg).await? {
+                Some(next) => msg = next,
+                None => {
+                    trace!(address=%self.primary_address(), "Message dropped by a middleware");
+                    return Ok(None);
+                }
+            }
+        }
+
+        Ok(Some(msg))
+    }
+}
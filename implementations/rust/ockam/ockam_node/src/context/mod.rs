@@ -1,13 +1,40 @@
 #[allow(clippy::module_inception)]
 mod context;
 mod context_lifecycle;
+#[cfg(feature = "std")]
+mod deadline;
+#[cfg(feature = "std")]
+mod error_sink;
+mod handle_retry;
+mod local_store;
+mod message_capture;
+mod middleware;
+#[cfg(feature = "std")]
+mod ping;
 mod receive_message;
 mod register_router;
+mod reply_channel;
+mod scoped_address;
+#[cfg(feature = "std")]
+mod send_and_collect;
 mod send_message;
 mod shutdown;
+mod topics;
 mod transports;
+#[cfg(feature = "std")]
+mod ttl;
 mod worker_lifecycle;
 
 pub use context::*;
+#[cfg(feature = "std")]
+pub use deadline::*;
+#[cfg(feature = "std")]
+pub use error_sink::*;
+pub use handle_retry::*;
+pub use message_capture::*;
+pub use middleware::*;
 pub use receive_message::*;
+pub use reply_channel::*;
+pub use scoped_address::*;
 pub use send_message::*;
+pub use worker_lifecycle::*;
@@ -0,0 +1,28 @@
+use crate::workers::PING_RESPONDER_ADDRESS;
+use crate::{Context, MessageSendReceiveOptions};
+use core::time::Duration;
+use ockam_core::{Result, Route};
+use std::time::Instant;
+
+impl Context {
+    /// Send a ping to the node at the end of the route and return the round-trip time
+    ///
+    /// The ping is answered by the [`PingResponder`](crate::workers::PingResponder) of that
+    /// node, the route must not include its address. This checks both that the node is
+    /// reachable and the latency of the route, whatever the transports used on the way.
+    /// Return a [`Kind::Timeout`](ockam_core::errcode::Kind::Timeout) error if there is no
+    /// reply within the timeout.
+    pub async fn ping(&self, route: impl Into<Route>, timeout: Duration) -> Result<Duration> {
+        let route = route.into().modify().append(PING_RESPONDER_ADDRESS).build();
+
+        let started_at = Instant::now();
+        self.send_and_receive_extended::<()>(
+            route,
+            (),
+            MessageSendReceiveOptions::new().with_timeout(timeout),
+        )
+        .await?;
+
+        Ok(started_at.elapsed())
+    }
+}
@@ -1,13 +1,30 @@
 use core::sync::atomic::Ordering;
 use core::time::Duration;
 
-use ockam_core::{Message, RelayMessage, Result, Routed};
+use ockam_core::compat::boxed::Box;
+use ockam_core::{Address, Message, RelayMessage, Result, Routed};
 
 use crate::debugger;
 use crate::error::*;
+#[cfg(feature = "std")]
+use crate::relay::RouterEvent;
 use crate::tokio::time::timeout;
 use crate::{Context, DEFAULT_TIMEOUT};
 
+/// Either a message, or a change of the addresses of a context
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) enum ContextEvent {
+    /// A message for one of the addresses of the context
+    Message(Box<RelayMessage>),
+    /// An additional address was attached to the context
+    AddressAdded(Address),
+    /// An additional address was detached from the context
+    AddressRemoved(Address),
+    /// The worker owning the context must be replaced, see [`Context::replace_worker`]
+    WorkerReplaced,
+}
+
+#[derive(Clone, Copy)]
 pub(super) enum MessageWait {
     Timeout(Duration),
     Blocking,
@@ -60,18 +77,36 @@ impl Context {
     /// Wait for the next message from the mailbox
     pub(crate) async fn receiver_next(&mut self) -> Result<Option<RelayMessage>> {
         loop {
-            let relay_msg = if let Some(msg) = self.receiver.recv().await.map(|msg| {
-                trace!(address=%self.primary_address(), "received new message!");
+            match self.receiver_next_event().await? {
+                Some(ContextEvent::Message(relay_msg)) => {
+                    #[cfg(feature = "std")]
+                    self.mark_used();
+                    return Ok(Some(*relay_msg));
+                }
+                // The mailboxes were already updated
+                Some(ContextEvent::AddressAdded(_))
+                | Some(ContextEvent::AddressRemoved(_))
+                | Some(ContextEvent::WorkerReplaced) => {}
+                None => return Ok(None),
+            }
+        }
+    }
 
-                // First we update the mailbox fill metrics
-                self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+    /// Wait for the next message or the next change of the addresses of this context
+    pub(crate) async fn receiver_next_event(&mut self) -> Result<Option<ContextEvent>> {
+        loop {
+            let relay_msg = match self.next_message_or_router_event().await {
+                Some(ContextEvent::Message(msg)) => {
+                    trace!(address=%self.primary_address(), "received new message!");
 
-                msg
-            }) {
-                msg
-            } else {
+                    // First we update the mailbox fill metrics
+                    self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+
+                    msg
+                }
+                Some(event) => return Ok(Some(event)),
                 // no more messages
-                return Ok(None);
+                None => return Ok(None),
             };
 
             debugger::log_incoming_message(self, &relay_msg);
@@ -90,7 +125,49 @@ impl Context {
                 continue;
             }
 
-            return Ok(Some(relay_msg));
+            if let Some(message_capture) = &self.message_capture {
+                message_capture.record(&relay_msg);
+            }
+
+            return Ok(Some(ContextEvent::Message(relay_msg)));
+        }
+    }
+
+    /// Address changes are always handled first: the router notifies the context before
+    /// routing any message to a new address
+    #[cfg(feature = "std")]
+    async fn next_message_or_router_event(&mut self) -> Option<ContextEvent> {
+        crate::tokio::select! {
+            biased;
+            Some(event) = self.router_events.recv() => Some(self.apply_router_event(event)),
+            msg = self.receiver.recv() => msg.map(|msg| ContextEvent::Message(Box::new(msg))),
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    async fn next_message_or_router_event(&mut self) -> Option<ContextEvent> {
+        self.receiver
+            .recv()
+            .await
+            .map(|msg| ContextEvent::Message(Box::new(msg)))
+    }
+
+    #[cfg(feature = "std")]
+    fn apply_router_event(&mut self, event: RouterEvent) -> ContextEvent {
+        match event {
+            RouterEvent::Added(mailbox) => {
+                let address = mailbox.address().clone();
+                self.mailboxes.add_mailbox(mailbox);
+                ContextEvent::AddressAdded(address)
+            }
+            RouterEvent::Removed(address) => {
+                self.mailboxes.remove_mailbox(&address);
+                ContextEvent::AddressRemoved(address)
+            }
+            RouterEvent::Replace(replacement) => {
+                self.replacement = Some(replacement);
+                ContextEvent::WorkerReplaced
+            }
         }
     }
 
@@ -126,6 +203,12 @@ impl Context {
         &mut self,
         options: MessageReceiveOptions,
     ) -> Result<Routed<M>> {
+        #[cfg(feature = "std")]
+        let options = {
+            let message_wait = self.bound_to_deadline(options.message_wait)?;
+            options.with_message_wait(message_wait)
+        };
+
         match options.message_wait {
             MessageWait::Timeout(timeout_duration) => {
                 timeout(timeout_duration, async { self.next_from_mailbox().await })
@@ -0,0 +1,76 @@
+use crate::{Context, MessageReceiveOptions, ScopedAddress};
+use core::marker::PhantomData;
+use ockam_core::{Address, Message, Result, Route, Routed};
+use serde::{Deserialize, Serialize};
+
+/// Route where the reply to a request must be sent, see [`Context::reply_channel`]
+///
+/// It can be embedded in the request, the server then replies with
+/// `ctx.send(reply_to, reply)`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReplyTo {
+    address: Address,
+}
+
+impl ReplyTo {
+    /// Address of the [`ReplyReceiver`], for example to add it as a flow control consumer
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Route to the [`ReplyReceiver`]
+    pub fn route(&self) -> Route {
+        self.address.clone().into()
+    }
+}
+
+impl From<ReplyTo> for Route {
+    fn from(reply_to: ReplyTo) -> Self {
+        reply_to.address.into()
+    }
+}
+
+/// Receiving end of a reply channel, resolved by the first message sent to its [`ReplyTo`]
+///
+/// The channel address is stopped when the receiver is dropped, or once the reply was
+/// received, so it is not leaked when the request is abandoned.
+pub struct ReplyReceiver<M> {
+    scoped_address: ScopedAddress,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M: Message> ReplyReceiver<M> {
+    /// Wait for the reply, with the default timeout
+    pub async fn receive(self) -> Result<M> {
+        self.receive_extended(MessageReceiveOptions::new())
+            .await?
+            .into_body()
+    }
+
+    /// Wait for the reply, with custom options
+    pub async fn receive_extended(mut self, options: MessageReceiveOptions) -> Result<Routed<M>> {
+        self.scoped_address.receive_extended::<M>(options).await
+    }
+}
+
+impl Context {
+    /// Create a one-shot channel to receive a reply of type `M`
+    ///
+    /// The returned [`ReplyTo`] can be embedded in an outgoing message, the reply sent to it
+    /// resolves the [`ReplyReceiver`]. This is an alternative to creating a detached context
+    /// and receiving on it manually.
+    pub fn reply_channel<M: Message>(&self) -> Result<(ReplyTo, ReplyReceiver<M>)> {
+        let scoped_address = self.scoped_address()?;
+        let reply_to = ReplyTo {
+            address: scoped_address.address().clone(),
+        };
+
+        Ok((
+            reply_to,
+            ReplyReceiver {
+                scoped_address,
+                _message: PhantomData,
+            },
+        ))
+    }
+}
@@ -0,0 +1,68 @@
+use crate::Context;
+use core::ops::{Deref, DerefMut};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    Address, AllowAll, IncomingAccessControl, Mailbox, Mailboxes, OutgoingAccessControl, Result,
+};
+
+/// A temporary address, for example to receive replies, which is stopped when dropped
+///
+/// The address is backed by a detached [`Context`] which can be used to send and receive
+/// messages. Since the address is stopped on drop, it is not leaked when a future waiting
+/// for a message on it is cancelled.
+/// Obtained with [`Context::scoped_address`].
+pub struct ScopedAddress {
+    ctx: Context,
+}
+
+impl ScopedAddress {
+    /// Address to use in the routes of the messages sent to this scoped address
+    pub fn address(&self) -> &Address {
+        self.ctx.primary_address()
+    }
+}
+
+impl Deref for ScopedAddress {
+    type Target = Context;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ctx
+    }
+}
+
+impl DerefMut for ScopedAddress {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.ctx
+    }
+}
+
+impl Context {
+    /// Create a random address which is stopped when the returned [`ScopedAddress`] is dropped
+    ///
+    /// Any message is accepted and can be sent from this address.
+    /// Use [`scoped_address_with_access_control`](Self::scoped_address_with_access_control)
+    /// to restrict them.
+    pub fn scoped_address(&self) -> Result<ScopedAddress> {
+        self.scoped_address_with_access_control(AllowAll, AllowAll)
+    }
+
+    /// Create a random address, with the given access controls, which is stopped when
+    /// the returned [`ScopedAddress`] is dropped
+    pub fn scoped_address_with_access_control(
+        &self,
+        incoming: impl IncomingAccessControl,
+        outgoing: impl OutgoingAccessControl,
+    ) -> Result<ScopedAddress> {
+        let address = Address::random_tagged("Context.scoped_address");
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(address, None, Arc::new(incoming), Arc::new(outgoing)),
+            vec![],
+        );
+
+        let mut ctx = self.new_detached_with_mailboxes(mailboxes)?;
+        ctx.set_correlation_id(self.correlation_id());
+        ctx.set_message_priority(self.message_priority());
+
+        Ok(ScopedAddress { ctx })
+    }
+}
@@ -0,0 +1,63 @@
+use crate::context::MessageWait;
+use crate::error::RequestTimeoutError;
+use crate::{Context, MessageReceiveOptions};
+use core::time::Duration;
+use futures::future::join_all;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Message, NeutralMessage, Result, Route};
+use std::time::Instant;
+
+impl Context {
+    /// Send the same message to several routes and collect the response of each of them
+    ///
+    /// The message is encoded once, and the responses are awaited concurrently, each one
+    /// from its own temporary context. The results are returned in the order of the routes,
+    /// a route which didn't reply within the timeout gets a
+    /// [`Kind::Timeout`](ockam_core::errcode::Kind::Timeout) error so a slow responder
+    /// doesn't delay the call beyond the timeout.
+    ///
+    /// An error is only returned for the whole call if the message can't be encoded, or if
+    /// the deadline of this context already passed.
+    pub async fn send_and_collect<M>(
+        &self,
+        routes: impl IntoIterator<Item = impl Into<Route>>,
+        msg: impl Message,
+        timeout: Duration,
+    ) -> Result<Vec<Result<M>>>
+    where
+        M: Message,
+    {
+        let payload = msg.encode()?;
+        let message_wait = self.bound_to_deadline(MessageWait::Timeout(timeout))?;
+
+        let started_at = Instant::now();
+        let requests = routes.into_iter().map(|route| {
+            let route: Route = route.into();
+            let payload = payload.clone();
+            async move {
+                let mut child_ctx = self.new_request_context(route.next()?)?;
+                child_ctx
+                    .send(route.clone(), NeutralMessage::from(payload))
+                    .await?;
+                child_ctx
+                    .receive_extended::<M>(
+                        MessageReceiveOptions::new().with_message_wait(message_wait),
+                    )
+                    .await
+                    .map_err(|error| {
+                        if error.code().kind != Kind::Timeout {
+                            return error;
+                        }
+                        Error::new(
+                            Origin::Node,
+                            Kind::Timeout,
+                            RequestTimeoutError::new(route, started_at.elapsed()),
+                        )
+                    })?
+                    .into_body()
+            }
+        });
+
+        Ok(join_all(requests).await)
+    }
+}
@@ -9,7 +9,7 @@ use ockam_core::{
     route, Address, AllowAll, AllowOnwardAddress, Error, LocalMessage, Mailboxes, Message,
     RelayMessage, Result, Route, Routed,
 };
-use ockam_core::{LocalInfo, Mailbox};
+use ockam_core::{CorrelationId, LocalInfo, Mailbox};
 
 /// Full set of options to `send_and_receive_extended` function
 pub struct MessageSendReceiveOptions {
@@ -83,7 +83,51 @@ impl Context {
     {
         let route: Route = route.into();
 
-        let next = route.next()?.clone();
+        // Don't send the request if the reply can't be received anymore
+        #[cfg(feature = "std")]
+        let options = MessageSendReceiveOptions {
+            message_wait: self.bound_to_deadline(options.message_wait)?,
+        };
+
+        let mut child_ctx = self.new_request_context(route.next()?)?;
+
+        cfg_if! {
+            if #[cfg(feature = "std")] {
+                let started_at = std::time::Instant::now();
+            }
+        }
+
+        child_ctx.send(route.clone(), msg).await?;
+        child_ctx
+            .receive_extended::<M>(
+                MessageReceiveOptions::new().with_message_wait(options.message_wait),
+            )
+            .await
+            .map_err(|error| {
+                if error.code().kind != Kind::Timeout {
+                    return error;
+                }
+
+                cfg_if! {
+                    if #[cfg(feature = "std")] {
+                        let elapsed = started_at.elapsed();
+                    } else {
+                        let elapsed = match options.message_wait {
+                            MessageWait::Timeout(timeout_duration) => timeout_duration,
+                            MessageWait::Blocking => Duration::ZERO,
+                        };
+                    }
+                }
+                Error::new(
+                    Origin::Node,
+                    Kind::Timeout,
+                    RequestTimeoutError::new(route, elapsed),
+                )
+            })
+    }
+
+    /// Create a temporary context to send a request to `next` and to receive its response
+    pub(super) fn new_request_context(&self, next: &Address) -> Result<Context> {
         let address = Address::random_tagged("Context.send_and_receive.detached");
         let mailboxes = Mailboxes::new(
             Mailbox::new(
@@ -97,7 +141,7 @@ impl Context {
 
         if let Some(flow_control_id) = self
             .flow_controls
-            .find_flow_control_with_producer_address(&next)
+            .find_flow_control_with_producer_address(next)
             .map(|x| x.flow_control_id().clone())
         {
             // To be able to receive the response
@@ -105,16 +149,16 @@ impl Context {
         }
 
         let mut child_ctx = self.new_detached_with_mailboxes(mailboxes)?;
+        child_ctx.set_correlation_id(self.correlation_id());
+        child_ctx.set_message_priority(self.message_priority());
 
         #[cfg(feature = "std")]
-        child_ctx.set_tracing_context(self.tracing_context());
+        {
+            child_ctx.set_tracing_context(self.tracing_context());
+            child_ctx.deadline = self.deadline;
+        }
 
-        child_ctx.send(route, msg).await?;
-        child_ctx
-            .receive_extended::<M>(
-                MessageReceiveOptions::new().with_message_wait(options.message_wait),
-            )
-            .await
+        Ok(child_ctx)
     }
 
     /// Send a message to another address associated with this worker
@@ -190,10 +234,69 @@ impl Context {
             msg,
             self.primary_address().clone(),
             local_info,
+            None,
+        )
+        .await
+    }
+
+    /// Send a request to an address or via a fully-qualified route
+    ///
+    /// A new request id is attached to the message and returned, the receiver can answer
+    /// with [`Self::send_response`] so that the response carries the same id, see
+    /// [`Routed::request_id`]. This allows matching responses with interleaved requests
+    /// without adding an identifier to each message type.
+    ///
+    /// The request id is local to the node, it isn't carried by transports.
+    pub async fn send_request<R, M>(&self, route: R, msg: M) -> Result<CorrelationId>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        let request_id = CorrelationId::random();
+        self.send_from_address_impl(
+            route.into(),
+            msg,
+            self.primary_address().clone(),
+            Vec::new(),
+            Some(request_id),
+        )
+        .await?;
+
+        Ok(request_id)
+    }
+
+    /// Send a response to a request received by this context
+    ///
+    /// The response is sent from the address which received the request, along its return
+    /// route, with the same request id.
+    pub async fn send_response<T, M>(&self, request: &Routed<T>, msg: M) -> Result<()>
+    where
+        T: Message,
+        M: Message + Send + 'static,
+    {
+        self.send_from_address_impl(
+            request.return_route().clone(),
+            msg,
+            request.msg_addr(),
+            Vec::new(),
+            request.request_id(),
         )
         .await
     }
 
+    /// Send a message back along the return route of a received message
+    ///
+    /// This is a shorthand for `ctx.send(msg.return_route().clone(), body)`. Unlike
+    /// [`Self::send_response`], the message is sent from the primary address of this context
+    /// and no request id is attached.
+    pub async fn reply<T, M>(&self, msg: &Routed<T>, body: M) -> Result<()>
+    where
+        T: Message,
+        M: Message + Send + 'static,
+    {
+        self.send(msg.return_route().clone(), body).await
+    }
+
     /// Send a message to an address or via a fully-qualified route
     ///
     /// Routes can be constructed from a set of [`Address`]es, or via
@@ -217,7 +320,7 @@ impl Context {
         R: Into<Route>,
         M: Message + Send + 'static,
     {
-        self.send_from_address_impl(route.into(), msg, sending_address, Vec::new())
+        self.send_from_address_impl(route.into(), msg, sending_address, Vec::new(), None)
             .await
     }
 
@@ -227,6 +330,7 @@ impl Context {
         msg: M,
         sending_address: Address,
         local_info: Vec<LocalInfo>,
+        request_id: Option<CorrelationId>,
     ) -> Result<()>
     where
         M: Message + Send + 'static,
@@ -236,6 +340,9 @@ impl Context {
             return Err(Error::new_without_cause(Origin::Node, Kind::Invalid));
         }
 
+        #[cfg(feature = "std")]
+        self.mark_used();
+
         // First resolve the next hop in the route
         let addr = match route.next() {
             Ok(next) => next.clone(),
@@ -251,6 +358,9 @@ impl Context {
         // Pack the payload into a TransportMessage
         let payload = msg.encode().map_err(|_| NodeError::Data.internal())?;
 
+        // Messages sent while handling a message share its correlation id
+        let correlation_id = self.correlation_id.unwrap_or_else(CorrelationId::random);
+
         // Pack transport message into a LocalMessage wrapper
         cfg_if! {
             if #[cfg(feature = "std")] {
@@ -260,13 +370,19 @@ impl Context {
                     .with_onward_route(route)
                     .with_return_route(route![sending_address.clone()])
                     .with_payload(payload)
-                    .with_local_info(local_info);
+                    .with_local_info(local_info)
+                    .with_correlation_id(Some(correlation_id))
+                    .with_request_id(request_id)
+                    .with_priority(self.message_priority);
             } else {
                 let local_msg = LocalMessage::new()
                     .with_onward_route(route)
                     .with_return_route(route![sending_address.clone()])
                     .with_payload(payload)
-                    .with_local_info(local_info);
+                    .with_local_info(local_info)
+                    .with_correlation_id(Some(correlation_id))
+                    .with_request_id(request_id)
+                    .with_priority(self.message_priority);
             }
         }
 
@@ -332,6 +448,9 @@ impl Context {
             return Err(Error::new_without_cause(Origin::Node, Kind::Invalid));
         }
 
+        #[cfg(feature = "std")]
+        self.mark_used();
+
         // First resolve the next hop in the route
         let addr = match local_msg.onward_route().next() {
             Ok(next) => next.clone(),
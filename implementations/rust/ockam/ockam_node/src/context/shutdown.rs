@@ -2,6 +2,17 @@ use crate::Context;
 use ockam_core::Result;
 
 impl Context {
+    /// Return true once the node started to shut down
+    ///
+    /// Workers can still handle the messages received before the shutdown, they can check
+    /// this to skip expensive work, and return before their shutdown is aborted. Stopping a
+    /// single worker doesn't shut the node down.
+    pub fn is_shutting_down(&self) -> bool {
+        self.router()
+            .map(|router| router.is_shutting_down())
+            .unwrap_or(true)
+    }
+
     /// Signal to the local runtime to shut down
     ///
     /// This call will hang until a safe shutdown has been completed.
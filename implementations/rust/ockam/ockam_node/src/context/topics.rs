@@ -0,0 +1,65 @@
+use crate::error::NodeError;
+use crate::Context;
+use ockam_core::compat::string::String;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{route, Address, Message, NeutralMessage, Result};
+use tracing::warn;
+
+impl Context {
+    /// Subscribe the primary address of this context to a topic, so that it receives the
+    /// messages published with [`Context::publish`]
+    ///
+    /// The subscription is removed automatically once the worker is stopped.
+    pub fn subscribe(&self, topic: impl Into<String>) -> Result<()> {
+        self.router()?
+            .subscribe(topic.into(), self.primary_address().clone());
+        Ok(())
+    }
+
+    /// Unsubscribe the primary address of this context from a topic
+    pub fn unsubscribe(&self, topic: &str) -> Result<()> {
+        self.router()?.unsubscribe(topic, self.primary_address());
+        Ok(())
+    }
+
+    /// Addresses currently subscribed to a topic
+    pub fn subscribers(&self, topic: &str) -> Result<Vec<Address>> {
+        Ok(self.router()?.subscribers(topic))
+    }
+
+    /// Send a message to all the subscribers of a topic, and return the number of subscribers
+    /// it was sent to
+    ///
+    /// The message is encoded only once. A failure to send it to one subscriber is logged and
+    /// doesn't prevent sending it to the others.
+    pub async fn publish<M>(&self, topic: &str, msg: M) -> Result<usize>
+    where
+        M: Message + Send + 'static,
+    {
+        let subscribers = self.router()?.subscribers(topic);
+        if subscribers.is_empty() {
+            return Ok(0);
+        }
+
+        let payload = msg.encode().map_err(|_| NodeError::Data.internal())?;
+
+        let mut sent = 0;
+        for subscriber in subscribers {
+            match self
+                .send(
+                    route![subscriber.clone()],
+                    NeutralMessage::from(payload.clone()),
+                )
+                .await
+            {
+                Ok(()) => sent += 1,
+                Err(err) => warn!(
+                    "Failed to publish a message on topic {} to {}: {}",
+                    topic, subscriber, err
+                ),
+            }
+        }
+
+        Ok(sent)
+    }
+}
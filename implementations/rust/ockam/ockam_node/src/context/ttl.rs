@@ -0,0 +1,92 @@
+use crate::Context;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Address, IncomingAccessControl, OutgoingAccessControl, Result};
+use std::time::Instant;
+
+/// Time of the last message sent or received by a detached context created with
+/// [`Context::new_detached_with_ttl`]
+#[derive(Debug)]
+pub(crate) struct LastUsed {
+    created_at: Instant,
+    /// Nanoseconds elapsed between `created_at` and the last use
+    elapsed: AtomicU64,
+}
+
+impl LastUsed {
+    fn new() -> Self {
+        Self {
+            created_at: Instant::now(),
+            elapsed: AtomicU64::new(0),
+        }
+    }
+
+    fn mark(&self) {
+        let elapsed = self.created_at.elapsed().as_nanos() as u64;
+        self.elapsed.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    /// Time elapsed since the last use
+    fn idle(&self) -> Duration {
+        let last_used = Duration::from_nanos(self.elapsed.load(Ordering::Relaxed));
+        self.created_at.elapsed().saturating_sub(last_used)
+    }
+}
+
+impl Context {
+    /// Create a new detached `Context`, see [`new_detached`](Self::new_detached), which is
+    /// stopped once it hasn't sent or received any message for the `ttl` duration
+    ///
+    /// This is a safety net for a detached context which is never dropped, for example when
+    /// the reply it waits for never arrives. Once stopped, receiving a message from this
+    /// context fails.
+    pub fn new_detached_with_ttl(
+        &self,
+        address: impl Into<Address>,
+        incoming: impl IncomingAccessControl,
+        outgoing: impl OutgoingAccessControl,
+        ttl: Duration,
+    ) -> Result<Context> {
+        let mut ctx = self.new_detached(address, incoming, outgoing)?;
+
+        let last_used = Arc::new(LastUsed::new());
+        let weak_last_used = Arc::downgrade(&last_used);
+        ctx.last_used = Some(last_used);
+
+        let router = ctx.router_weak();
+        let address = ctx.primary_address().clone();
+        self.runtime().spawn(async move {
+            let mut wait = ttl;
+            loop {
+                tokio::time::sleep(wait).await;
+                // The context was dropped, and its address stopped
+                let idle = match weak_last_used.upgrade() {
+                    Some(last_used) => last_used.idle(),
+                    None => return,
+                };
+                if idle >= ttl {
+                    break;
+                }
+                wait = ttl - idle;
+            }
+
+            if let Some(router) = router.upgrade() {
+                debug!(%address, ?ttl, "Stopping a detached context which was not used");
+                if let Err(err) = router.stop_address(&address, true) {
+                    debug!(%address, %err, "Can't stop a detached context which was not used");
+                }
+            }
+        });
+
+        Ok(ctx)
+    }
+
+    /// Record a message sent or received, which postpones the expiration of a detached
+    /// context created with [`Context::new_detached_with_ttl`]
+    pub(super) fn mark_used(&self) {
+        if let Some(last_used) = &self.last_used {
+            last_used.mark();
+        }
+    }
+}
@@ -1,8 +1,19 @@
-use crate::Context;
+#[cfg(feature = "std")]
+use crate::relay::{WorkerRelay, WorkerReplacement};
+#[cfg(feature = "std")]
+use crate::tokio::time::timeout;
+use crate::{Context, MessageCapture};
 use crate::{ProcessorBuilder, WorkerBuilder};
+use ockam_core::compat::boxed::Box;
+#[cfg(feature = "std")]
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{
-    Address, IncomingAccessControl, OutgoingAccessControl, Processor, Result, Worker,
+    Address, Error, IncomingAccessControl, OutgoingAccessControl, Processor, Result, Worker,
 };
+#[cfg(feature = "std")]
+use ockam_core::{Codec, Mailbox};
 
 impl Context {
     /// Start a new worker instance at the given address. Default AccessControl is AllowAll
@@ -18,7 +29,7 @@ impl Context {
     /// thread-safe.  Workers run asynchronously and will be scheduled
     /// independently of each other.  To wait for the initialisation
     /// of your worker to complete you can use
-    /// [`wait_for()`](Self::wait_for).
+    /// [`wait_for_initialization()`](Self::wait_for_initialization).
     ///
     /// ```rust
     /// use ockam_core::{Result, Worker, worker};
@@ -112,6 +123,103 @@ impl Context {
         Ok(())
     }
 
+    /// Start a new worker instance at the given address, retaining the last `capacity`
+    /// messages delivered to it. Default AccessControl is AllowAll
+    ///
+    /// The returned [`MessageCapture`] gives access to these messages, and can replay them
+    /// to the worker. This is meant for debugging and testing.
+    ///
+    /// ```rust
+    /// use ockam_core::{Result, Worker, worker};
+    /// use ockam_node::{Context, MessageCapture};
+    ///
+    /// struct MyWorker;
+    ///
+    /// #[worker]
+    /// impl Worker for MyWorker {
+    ///     type Context = Context;
+    ///     type Message = String;
+    /// }
+    ///
+    /// fn start_my_worker(ctx: &mut Context) -> Result<MessageCapture> {
+    ///     ctx.start_worker_with_capture("my-worker-address", MyWorker, 10)
+    /// }
+    /// ```
+    pub fn start_worker_with_capture<W>(
+        &self,
+        address: impl Into<Address>,
+        worker: W,
+        capacity: usize,
+    ) -> Result<MessageCapture>
+    where
+        W: Worker<Context = Context>,
+    {
+        let message_capture = MessageCapture::new(capacity);
+
+        WorkerBuilder::new(worker)
+            .with_address(address)
+            .with_message_capture(message_capture.clone())
+            .start(self)?;
+
+        Ok(message_capture)
+    }
+
+    /// Start several workers, at the given addresses, as a whole. Default AccessControl is AllowAll
+    ///
+    /// If one of the workers can't be started, the workers which were already started are
+    /// stopped and the first error is returned. This avoids leaving a partially started set
+    /// of interdependent workers.
+    ///
+    /// ```rust
+    /// use ockam_core::{Address, Result, Worker, worker};
+    /// use ockam_node::{Context, StartableWorker};
+    ///
+    /// struct Alice;
+    /// struct Bob;
+    ///
+    /// #[worker]
+    /// impl Worker for Alice {
+    ///     type Context = Context;
+    ///     type Message = String;
+    /// }
+    ///
+    /// #[worker]
+    /// impl Worker for Bob {
+    ///     type Context = Context;
+    ///     type Message = Vec<u8>;
+    /// }
+    ///
+    /// fn start_my_workers(ctx: &mut Context) -> Result<()> {
+    ///     let workers: Vec<(Address, Box<dyn StartableWorker>)> = vec![
+    ///         ("alice".into(), Box::new(Alice)),
+    ///         ("bob".into(), Box::new(Bob)),
+    ///     ];
+    ///     ctx.start_workers(workers)
+    /// }
+    /// ```
+    pub fn start_workers(&self, workers: Vec<(Address, Box<dyn StartableWorker>)>) -> Result<()> {
+        let mut started: Vec<Address> = Vec::with_capacity(workers.len());
+
+        for (address, worker) in workers {
+            if let Err(err) = worker.start(self, address.clone()) {
+                warn!(
+                    "Failed to start worker {}, stopping the {} workers already started",
+                    address,
+                    started.len()
+                );
+                for address in started.iter().rev() {
+                    if let Err(err) = self.stop_address(address) {
+                        warn!("Failed to stop worker {}: {}", address, err);
+                    }
+                }
+                return Err(err);
+            }
+            started.push(address);
+        }
+
+        Ok(())
+    }
+
     /// Start a new processor instance at the given address. Default AccessControl is DenyAll
     ///
     /// A processor is an asynchronous piece of code that runs a
@@ -172,6 +280,118 @@ impl Context {
         Ok(())
     }
 
+    /// Attach an additional address to the running Worker with the given primary address
+    ///
+    /// The Worker is notified via
+    /// [`Worker::on_address_added`](ockam_core::Worker::on_address_added) before any message
+    /// sent to the new address is delivered.
+    #[cfg(feature = "std")]
+    pub fn add_address(&self, primary_address: &Address, mailbox: Mailbox) -> Result<()> {
+        self.router()?.add_address(primary_address, mailbox)
+    }
+
+    /// Detach an additional address from the running Worker with the given primary address
+    ///
+    /// The primary address can't be detached. The Worker is notified via
+    /// [`Worker::on_address_removed`](ockam_core::Worker::on_address_removed).
+    #[cfg(feature = "std")]
+    pub fn remove_address(&self, primary_address: &Address, address: &Address) -> Result<()> {
+        self.router()?.remove_address(primary_address, address)
+    }
+
+    /// Replace the implementation of the running Worker with the given primary address
+    ///
+    /// The current Worker finishes handling its current message and its
+    /// [`shutdown`](ockam_core::Worker::shutdown) function is called. Then the new Worker
+    /// is initialized and handles the next messages, using the same context: the addresses,
+    /// access controls, queued messages and [`HandleRetryPolicy`](crate::HandleRetryPolicy) are
+    /// kept. The new Worker is not a concurrent Worker, even if the replaced one was.
+    ///
+    /// Use [`Context::replace_worker_with_codec`] if the new Worker decodes its messages with
+    /// a custom [`Codec`].
+    #[cfg(feature = "std")]
+    pub fn replace_worker<W>(&self, primary_address: &Address, worker: W) -> Result<()>
+    where
+        W: Worker<Context = Context>,
+    {
+        self.start_replacement(primary_address, worker, None)
+    }
+
+    /// Replace the implementation of the running Worker with the given primary address, see
+    /// [`Context::replace_worker`], decoding the messages of the new Worker with a custom
+    /// [`Codec`]
+    #[cfg(feature = "std")]
+    pub fn replace_worker_with_codec<W>(
+        &self,
+        primary_address: &Address,
+        worker: W,
+        codec: impl Codec<W::Message>,
+    ) -> Result<()>
+    where
+        W: Worker<Context = Context>,
+    {
+        self.start_replacement(primary_address, worker, Some(Arc::new(codec)))
+    }
+
+    #[cfg(feature = "std")]
+    fn start_replacement<W>(
+        &self,
+        primary_address: &Address,
+        worker: W,
+        codec: Option<Arc<dyn Codec<W::Message>>>,
+    ) -> Result<()>
+    where
+        W: Worker<Context = Context>,
+    {
+        let replacement = WorkerReplacement::new(Box::new(move |ctx, ctrl_rx, retry_policy| {
+            let rt = ctx.runtime().clone();
+            WorkerRelay::init(&rt, worker, ctx, ctrl_rx, codec, retry_policy)
+        }));
+
+        self.router()?.replace_worker(primary_address, replacement)
+    }
+
+    /// Wait until the `initialize` method of the Worker or Processor running at the given
+    /// address completed, so that it can handle messages
+    ///
+    /// Fails if there is no Worker or Processor at this address, if it was stopped, for example
+    /// because its initialization failed, or if it is not initialized before the timeout.
+    #[cfg(feature = "std")]
+    pub async fn wait_for_initialization(
+        &self,
+        address: &Address,
+        timeout_duration: core::time::Duration,
+    ) -> Result<()> {
+        let router = self.router()?;
+        let deadline = std::time::Instant::now() + timeout_duration;
+
+        loop {
+            // Created before checking the state, so that no notification is missed
+            let notified = router.initialization_notified();
+
+            match router.is_initialized(address) {
+                Some(true) => return Ok(()),
+                Some(false) => {}
+                None => {
+                    return Err(Error::new(
+                        Origin::Node,
+                        Kind::NotFound,
+                        format!("No worker or processor is running at {}", address),
+                    ))
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if timeout(remaining, notified).await.is_err() {
+                return Err(Error::new(
+                    Origin::Node,
+                    Kind::Timeout,
+                    format!("The worker or processor at {} is not initialized", address),
+                ));
+            }
+        }
+    }
+
     /// Stop a Worker or a Processor running on given Address
     pub fn stop_address(&self, address: &Address) -> Result<()> {
         self.router()?.stop_address(address, false)
@@ -181,4 +401,41 @@ impl Context {
     pub fn stop_primary_address(&self) -> Result<()> {
         self.stop_address(self.primary_address())
     }
+
+    /// Stop all the workers and processors started in the given group with the `with_group`
+    /// method of [`WorkerBuilder`] and [`ProcessorBuilder`], and wait until all of them are
+    /// stopped
+    ///
+    /// If this context belongs to a member of the group, that member is stopped as well but it
+    /// is not waited for.
+    pub async fn stop_group(&self, group: &str) -> Result<()> {
+        let receiver = self.router()?.stop_group(group, self.primary_address())?;
+
+        if let Some(receiver) = receiver {
+            receiver.await.map_err(|_| {
+                Error::new(
+                    Origin::Node,
+                    Kind::Cancelled,
+                    format!("The group {} was not stopped", group),
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`Worker`] of any message type, which can be started by [`Context::start_workers`]
+pub trait StartableWorker: Send + 'static {
+    /// Start the worker at the given address
+    fn start(self: Box<Self>, ctx: &Context, address: Address) -> Result<()>;
+}
+
+impl<W> StartableWorker for W
+where
+    W: Worker<Context = Context>,
+{
+    fn start(self: Box<Self>, ctx: &Context, address: Address) -> Result<()> {
+        ctx.start_worker(address, *self)
+    }
 }
@@ -4,7 +4,7 @@ use core::time::Duration;
 use ockam_core::{
     compat::error::Error as StdError,
     errcode::{Kind, Origin},
-    Address, Error,
+    Address, Error, Route,
 };
 
 /// Enumeration of error causes in ockam_node
@@ -87,6 +87,47 @@ impl fmt::Display for NodeError {
     }
 }
 
+/// Error raised when no response is received in time for a request sent with
+/// [`Context::send_and_receive`](crate::Context::send_and_receive)
+///
+/// It is kept as the cause of the returned [`Kind::Timeout`] error, so that the route of the
+/// request can be retrieved, for example to retry along another route:
+/// `error.source().and_then(|e| e.downcast_ref::<RequestTimeoutError>())`
+#[derive(Clone, Debug)]
+pub struct RequestTimeoutError {
+    route: Route,
+    elapsed: Duration,
+}
+
+impl RequestTimeoutError {
+    /// Constructor
+    pub(crate) fn new(route: Route, elapsed: Duration) -> Self {
+        Self { route, elapsed }
+    }
+
+    /// Route the request was sent to
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    /// Time elapsed between sending the request and giving up on the response
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+impl StdError for RequestTimeoutError {}
+
+impl fmt::Display for RequestTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no response received after {:?} for a request sent to {}",
+            self.elapsed, self.route
+        )
+    }
+}
+
 /// Reasons why adding an external router has failed
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, Debug)]
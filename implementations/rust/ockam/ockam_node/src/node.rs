@@ -1,4 +1,6 @@
+use crate::router::WorkerMeta;
 use crate::tokio::runtime::Runtime;
+use crate::workers::{PingResponder, PING_RESPONDER_ADDRESS};
 use crate::{debugger, Context, Executor};
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::FlowControls;
@@ -154,12 +156,17 @@ impl NodeBuilder {
             .add_worker(
                 ctx.mailboxes(),
                 sender,
-                true,
-                Default::default(),
+                WorkerMeta {
+                    detached: true,
+                    ..Default::default()
+                },
                 ctx.mailbox_count(),
             )
             .expect("router initialization failed");
 
+        ctx.start_worker(PING_RESPONDER_ADDRESS, PingResponder)
+            .expect("ping responder initialization failed");
+
         // Then return the root context and executor
         (ctx, exe)
     }
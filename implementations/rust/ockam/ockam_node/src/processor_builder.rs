@@ -1,7 +1,9 @@
+use crate::router::WorkerMeta;
 use crate::{debugger, ContextMode, WorkerShutdownPriority};
 use crate::{relay::ProcessorRelay, Context};
 use ockam_core::compat::string::String;
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::{
     Address, AddressMetadata, DenyAll, IncomingAccessControl, Mailbox, Mailboxes,
     OutgoingAccessControl, Processor, Result,
@@ -73,6 +75,8 @@ where
             address: address.into(),
             metadata,
             shutdown_priority: Default::default(),
+            shutdown_before: Default::default(),
+            group: None,
         }
     }
 
@@ -81,6 +85,8 @@ where
         ProcessorBuilderMultipleAddresses {
             mailboxes,
             shutdown_priority: Default::default(),
+            shutdown_before: Default::default(),
+            group: None,
             processor: self.processor,
         }
     }
@@ -92,6 +98,8 @@ where
 {
     mailboxes: Mailboxes,
     shutdown_priority: WorkerShutdownPriority,
+    shutdown_before: Vec<Address>,
+    group: Option<String>,
     processor: P,
 }
 
@@ -105,6 +113,8 @@ where
             context,
             self.mailboxes,
             self.shutdown_priority,
+            self.shutdown_before,
+            self.group,
             self.processor,
         )
     }
@@ -113,6 +123,22 @@ where
         self.shutdown_priority = shutdown_priority;
         self
     }
+
+    /// Shut this processor down, and wait for it to be stopped, before the worker (or processor)
+    /// with the given address is shut down on node shutdown
+    ///
+    /// Only applies to processors and workers with the same [`WorkerShutdownPriority`].
+    pub fn with_shutdown_before(mut self, address: impl Into<Address>) -> Self {
+        self.shutdown_before.push(address.into());
+        self
+    }
+
+    /// Add this processor to a group, all the members of a group can be stopped together with
+    /// [`Context::stop_group`]
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
 }
 
 pub struct ProcessorBuilderOneAddress<P>
@@ -125,6 +151,8 @@ where
     processor: P,
     metadata: Option<AddressMetadata>,
     shutdown_priority: WorkerShutdownPriority,
+    shutdown_before: Vec<Address>,
+    group: Option<String>,
 }
 
 impl<P> ProcessorBuilderOneAddress<P>
@@ -173,6 +201,8 @@ where
                 vec![],
             ),
             self.shutdown_priority,
+            self.shutdown_before,
+            self.group,
             self.processor,
         )
     }
@@ -222,6 +252,22 @@ where
         self.shutdown_priority = shutdown_priority;
         self
     }
+
+    /// Shut this processor down, and wait for it to be stopped, before the worker (or processor)
+    /// with the given address is shut down on node shutdown
+    ///
+    /// Only applies to processors and workers with the same [`WorkerShutdownPriority`].
+    pub fn with_shutdown_before(mut self, address: impl Into<Address>) -> Self {
+        self.shutdown_before.push(address.into());
+        self
+    }
+
+    /// Add this processor to a group, all the members of a group can be stopped together with
+    /// [`Context::stop_group`]
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
 }
 
 /// Consume this builder and start a new Ockam [`Processor`] from the given context
@@ -229,6 +275,8 @@ pub fn start<P>(
     context: &Context,
     mailboxes: Mailboxes,
     shutdown_priority: WorkerShutdownPriority,
+    shutdown_before: Vec<Address>,
+    group: Option<String>,
     processor: P,
 ) -> Result<()>
 where
@@ -247,7 +295,16 @@ where
     debugger::log_inherit_context("PROCESSOR", context, &ctx);
 
     let router = context.router()?;
-    router.add_processor(ctx.mailboxes(), sender, shutdown_priority)?;
+    router.add_processor(
+        ctx.mailboxes(),
+        sender,
+        WorkerMeta {
+            group,
+            shutdown_priority,
+            shutdown_before,
+            ..Default::default()
+        },
+    )?;
 
     // Then initialise the processor message relay
     ProcessorRelay::<P>::init(context.runtime(), processor, ctx, ctrl_rx);
@@ -0,0 +1,267 @@
+use crate::channel_types::OneshotReceiver;
+use crate::relay::worker_relay::shutdown_and_stop_ack;
+use crate::relay::CtrlSignal;
+use crate::tokio::runtime::Handle;
+use crate::tokio::sync::Semaphore;
+use crate::{Context, ContextEvent, ContextMode, HandleRetryPolicy};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Codec, Message, RelayMessage, Routed, Worker};
+use opentelemetry::trace::FutureExt;
+
+/// Worker relay handling up to `concurrency` messages at the same time
+///
+/// Each message is handled by a clone of the worker, with its own [`Context`] sharing the
+/// worker's addresses, so responses may be sent in a different order than the requests
+/// were received.
+pub struct ConcurrentWorkerRelay<W: Worker> {
+    worker: W,
+    ctx: Context,
+    concurrency: usize,
+    codec: Option<Arc<dyn Codec<W::Message>>>,
+    retry_policy: Option<HandleRetryPolicy>,
+}
+
+impl<W, M> ConcurrentWorkerRelay<W>
+where
+    W: Worker<Context = Context, Message = M> + Clone,
+    M: Message + Send + 'static,
+{
+    pub fn new(
+        worker: W,
+        ctx: Context,
+        concurrency: usize,
+        codec: Option<Arc<dyn Codec<M>>>,
+        retry_policy: Option<HandleRetryPolicy>,
+    ) -> Self {
+        Self {
+            worker,
+            ctx,
+            concurrency,
+            codec,
+            retry_policy,
+        }
+    }
+
+    /// Spawn a task handling a single message with a clone of the worker
+    fn spawn_handler(&self, relay_msg: RelayMessage, permit: impl Send + 'static) {
+        let mut worker = self.worker.clone();
+        let codec = self.codec.clone();
+        let retry_policy = self.retry_policy.clone();
+        let (mut ctx, _, _) = self
+            .ctx
+            .new_with_mailboxes(self.ctx.mailboxes().clone(), ContextMode::Attached);
+        ctx.set_labels(self.ctx.labels().to_vec());
+        ctx.share_stop_signal(&self.ctx);
+        ctx.share_local_store(&self.ctx);
+        ctx.set_correlation_id(relay_msg.local_message().correlation_id());
+        ctx.set_message_priority(relay_msg.local_message().priority());
+        ctx.set_message_capture(self.ctx.message_capture().cloned());
+
+        let tracing_context = relay_msg.local_message().tracing_context();
+        ctx.set_tracing_context(tracing_context.clone());
+
+        self.ctx.runtime().spawn(async move {
+            // Release the permit only once the message is handled
+            let _permit = permit;
+
+            let relay_msg = match ctx.apply_middlewares(relay_msg).await {
+                Ok(Some(relay_msg)) => relay_msg,
+                Ok(None) => return,
+                Err(e) => {
+                    error!(
+                        address = %ctx.primary_address(),
+                        error_code = ?e.code().kind,
+                        error_origin = ?e.code().origin,
+                        error = %e,
+                        "Error encountered during message handling"
+                    );
+                    ctx.report_worker_error(e);
+                    return;
+                }
+            };
+
+            let opentelemetry_context = tracing_context.update().extract();
+            let mut retries = 0;
+            loop {
+                let routed = Routed::new(
+                    relay_msg.destination().clone(),
+                    relay_msg.source().clone(),
+                    relay_msg.local_message().clone(),
+                )
+                .with_codec(codec.clone());
+
+                let e = match worker
+                    .handle_message(&mut ctx, routed)
+                    .with_context(opentelemetry_context.clone())
+                    .await
+                {
+                    Ok(()) => return,
+                    Err(e) => e,
+                };
+
+                let backoff = retry_policy
+                    .as_ref()
+                    .and_then(|retry_policy| retry_policy.backoff(retries));
+                match backoff {
+                    Some(backoff) if !ctx.is_stopping() => {
+                        warn!(
+                            "Error encountered during '{}' message handling, retrying in {:?}: {}",
+                            ctx.primary_address(),
+                            backoff,
+                            e
+                        );
+                        ctx.sleep(backoff).await;
+                        retries += 1;
+                    }
+                    _ => {
+                        error!(
+                            address = %ctx.primary_address(),
+                            error_code = ?e.code().kind,
+                            error_origin = ?e.code().origin,
+                            error = %e,
+                            "Error encountered during message handling"
+                        );
+                        ctx.report_worker_error(e);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    async fn run(mut self, mut ctrl_rx: OneshotReceiver<CtrlSignal>) {
+        match self.worker.initialize(&mut self.ctx).await {
+            Ok(()) => {}
+            Err(e) => {
+                error!(
+                    address = %self.ctx.primary_address(),
+                    error_code = ?e.code().kind,
+                    error_origin = ?e.code().origin,
+                    error = %e,
+                    "Failure during worker initialisation"
+                );
+                shutdown_and_stop_ack(&mut self.worker, &mut self.ctx, false).await;
+                return;
+            }
+        }
+
+        #[cfg(feature = "std")]
+        if let Ok(router) = self.ctx.router() {
+            router.mark_initialized(self.ctx.primary_address());
+        }
+
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+
+        loop {
+            // Wait until we are allowed to handle one more message
+            let permit = crate::tokio::select! {
+                permit = semaphore.clone().acquire_owned() => {
+                    match permit {
+                        Ok(permit) => permit,
+                        Err(_) => break,
+                    }
+                },
+                _ = &mut ctrl_rx => {
+                    debug!(primary_address=%self.ctx.primary_address(), "Relay received shutdown signal, terminating!");
+                    break;
+                }
+            };
+
+            let relay_msg = crate::tokio::select! {
+                result = self.ctx.receiver_next_event() => {
+                    match result {
+                        Ok(Some(ContextEvent::Message(relay_msg))) => *relay_msg,
+                        // Clones handling the next messages will see the new addresses
+                        Ok(Some(ContextEvent::AddressAdded(address))) => {
+                            if let Err(e) = self.worker.on_address_added(&mut self.ctx, &address).await {
+                                error!(
+                                    address = %self.ctx.primary_address(),
+                                    error_code = ?e.code().kind,
+                                    error_origin = ?e.code().origin,
+                                    error = %e,
+                                    "Error encountered during address change handling"
+                                );
+                            }
+                            continue;
+                        }
+                        Ok(Some(ContextEvent::AddressRemoved(address))) => {
+                            if let Err(e) = self.worker.on_address_removed(&mut self.ctx, &address).await {
+                                error!(
+                                    address = %self.ctx.primary_address(),
+                                    error_code = ?e.code().kind,
+                                    error_origin = ?e.code().origin,
+                                    error = %e,
+                                    "Error encountered during address change handling"
+                                );
+                            }
+                            continue;
+                        }
+                        Ok(Some(ContextEvent::WorkerReplaced)) => {
+                            debug!("Worker {} is being replaced", self.ctx.primary_address());
+                            break;
+                        }
+                        // No messages left -- stop now
+                        Ok(None) => {
+                            trace!("No more messages for worker {}", self.ctx.primary_address());
+                            break;
+                        }
+                        // An error occurred -- log and continue
+                        Err(e) => {
+                            error!(
+                                address = %self.ctx.primary_address(),
+                                error_code = ?e.code().kind,
+                                error_origin = ?e.code().origin,
+                                error = %e,
+                                "Error encountered during message handling"
+                            );
+                            continue;
+                        }
+                    }
+                },
+                _ = &mut ctrl_rx => {
+                    debug!(primary_address=%self.ctx.primary_address(), "Relay received shutdown signal, terminating!");
+                    break;
+                }
+            };
+
+            self.spawn_handler(relay_msg, permit);
+        }
+
+        if let Some(replacement) = self.ctx.replacement.take() {
+            // Let the messages that are still being handled complete, then hand the context
+            // over to the new worker, without stopping it
+            let _ = semaphore.acquire_many(self.concurrency as u32).await;
+            if let Err(e) = self.worker.shutdown(&mut self.ctx).await {
+                error!(
+                    address = %self.ctx.primary_address(),
+                    error_code = ?e.code().kind,
+                    error_origin = ?e.code().origin,
+                    error = %e,
+                    "Failure during worker shutdown"
+                );
+            }
+            replacement.start(self.ctx, ctrl_rx, self.retry_policy);
+            return;
+        }
+
+        // Wait for the messages that are still being handled, interrupting their sleeps
+        self.ctx.set_stopping();
+        let _ = semaphore.acquire_many(self.concurrency as u32).await;
+
+        shutdown_and_stop_ack(&mut self.worker, &mut self.ctx, true).await;
+    }
+
+    /// Build and spawn a new concurrent worker relay
+    pub(crate) fn init(
+        rt: &Handle,
+        worker: W,
+        ctx: Context,
+        ctrl_rx: OneshotReceiver<CtrlSignal>,
+        concurrency: usize,
+        codec: Option<Arc<dyn Codec<M>>>,
+        retry_policy: Option<HandleRetryPolicy>,
+    ) {
+        let relay = ConcurrentWorkerRelay::new(worker, ctx, concurrency, codec, retry_policy);
+        rt.spawn(relay.run(ctrl_rx));
+    }
+}
@@ -1,9 +1,67 @@
+#[cfg(feature = "std")]
+use ockam_core::compat::{boxed::Box, sync::Mutex};
+
+#[cfg(feature = "std")]
+mod concurrent_worker_relay;
 mod processor_relay;
 mod worker_relay;
 
+#[cfg(feature = "std")]
+pub use concurrent_worker_relay::*;
 pub use processor_relay::*;
 pub use worker_relay::*;
 
+/// An event sent by the router to the context of a running worker
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub(crate) enum RouterEvent {
+    /// An additional address was attached to the worker
+    Added(ockam_core::Mailbox),
+    /// An additional address was detached from the worker
+    Removed(ockam_core::Address),
+    /// The worker implementation must be replaced, keeping the same context
+    Replace(WorkerReplacement),
+}
+
+#[cfg(feature = "std")]
+type StartWorker = dyn FnOnce(
+        crate::Context,
+        crate::channel_types::OneshotReceiver<CtrlSignal>,
+        Option<crate::HandleRetryPolicy>,
+    ) + Send;
+
+/// Start of a new worker implementation, run on the context of the replaced worker
+///
+/// The start function is kept behind a [`Mutex`] so that the [`crate::Context`] holding it
+/// stays `Sync`
+#[cfg(feature = "std")]
+pub(crate) struct WorkerReplacement(Mutex<Box<StartWorker>>);
+
+#[cfg(feature = "std")]
+impl WorkerReplacement {
+    pub(crate) fn new(start: Box<StartWorker>) -> Self {
+        Self(Mutex::new(start))
+    }
+
+    /// Start the new worker on the given context, with the retry policy of the replaced worker
+    pub(crate) fn start(
+        self,
+        ctx: crate::Context,
+        ctrl_rx: crate::channel_types::OneshotReceiver<CtrlSignal>,
+        retry_policy: Option<crate::HandleRetryPolicy>,
+    ) {
+        let start = self.0.into_inner().unwrap_or_else(|e| e.into_inner());
+        start(ctx, ctrl_rx, retry_policy)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Debug for WorkerReplacement {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("WorkerReplacement")
+    }
+}
+
 /// A signal type used to communicate between router and worker relay
 #[derive(Clone, Debug)]
 pub enum CtrlSignal {
@@ -1,6 +1,17 @@
 use crate::channel_types::OneshotReceiver;
+#[cfg(feature = "std")]
+use crate::tokio::sync::watch;
 use crate::{relay::CtrlSignal, tokio::runtime::Handle, Context};
-use ockam_core::{Processor, Result};
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+#[cfg(feature = "std")]
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, Cancellation, Processor, Result};
+
+/// Time given to a processor to return from
+/// [`Processor::process_with_cancellation`] once it is stopped, before it is aborted
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+const PROCESSOR_STOP_GRACE_PERIOD: Duration = Duration::from_secs(1);
 
 pub struct ProcessorRelay<P>
 where
@@ -37,10 +48,26 @@ where
             }
         }
 
+        #[cfg(feature = "std")]
+        if let Ok(router) = ctx.router() {
+            router.mark_initialized(ctx.primary_address());
+        }
+
+        let cancellation = ContextCancellation::new(&ctx);
+        #[cfg(feature = "std")]
+        let address = ctx.primary_address().clone();
+
         // This future encodes the main processor run loop logic
         let run_loop = async {
             loop {
-                match processor.process(&mut ctx).await {
+                if cancellation.is_cancelled() {
+                    break;
+                }
+
+                match processor
+                    .process_with_cancellation(&mut ctx, &cancellation)
+                    .await
+                {
                     Ok(should_continue) => {
                         if !should_continue {
                             break;
@@ -69,14 +96,25 @@ where
         let mut stopped_from_router = false;
         #[cfg(feature = "std")]
         {
+            crate::tokio::pin!(run_loop);
+
             // Select over the two futures
             tokio::select! {
                 // This future resolves when a stop control signal is received
                 _ = ctrl_rx => {
-                    debug!("Shutting down processor {} due to shutdown signal", ctx.primary_address());
+                    debug!("Shutting down processor {} due to shutdown signal", address);
                     stopped_from_router = true;
+
+                    // Let the processor return from its current processing before aborting it
+                    cancellation.cancel();
+                    if tokio::time::timeout(PROCESSOR_STOP_GRACE_PERIOD, &mut run_loop)
+                        .await
+                        .is_err()
+                    {
+                        debug!("Processor {} didn't stop in time, aborting it", address);
+                    }
                 },
-                _ = run_loop => {}
+                _ = &mut run_loop => {}
             };
         }
 
@@ -107,6 +145,9 @@ async fn shutdown_and_stop_ack<P>(processor: &mut P, ctx: &mut Context, stopped_
 where
     P: Processor<Context = Context>,
 {
+    // Interrupt the sleeps of the processor, so that it shuts down promptly
+    ctx.set_stopping();
+
     match processor.shutdown(ctx).await {
         Ok(()) => {}
         Err(e) => {
@@ -149,3 +190,49 @@ where
         );
     });
 }
+
+/// [`Cancellation`] triggered when the processor owning a context is stopped
+struct ContextCancellation {
+    #[cfg(feature = "std")]
+    stopping: Arc<watch::Sender<bool>>,
+}
+
+impl ContextCancellation {
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn new(ctx: &Context) -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            stopping: ctx.stopping.clone(),
+        }
+    }
+
+    /// Cancel the processing, this also interrupts the sleeps of the processor context
+    #[cfg(feature = "std")]
+    fn cancel(&self) {
+        self.stopping.send_replace(true);
+    }
+}
+
+#[async_trait]
+impl Cancellation for ContextCancellation {
+    #[cfg(feature = "std")]
+    fn is_cancelled(&self) -> bool {
+        *self.stopping.borrow()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    async fn cancelled(&self) {
+        let mut stopping = self.stopping.subscribe();
+        let _ = stopping.wait_for(|stopping| *stopping).await;
+    }
+
+    #[cfg(not(feature = "std"))]
+    async fn cancelled(&self) {
+        core::future::pending::<()>().await
+    }
+}
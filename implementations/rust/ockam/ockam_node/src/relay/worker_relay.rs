@@ -1,25 +1,42 @@
 use crate::channel_types::OneshotReceiver;
 use crate::relay::CtrlSignal;
 use crate::tokio::runtime::Handle;
-use crate::Context;
+use crate::{Context, ContextEvent, HandleRetryPolicy};
 use cfg_if::cfg_if;
-use ockam_core::{Message, RelayMessage, Result, Routed, Worker};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Codec, Message, RelayMessage, Result, Routed, Worker};
 #[cfg(feature = "std")]
-use opentelemetry::trace::FutureExt;
+use ockam_core::{OpenTelemetryContext, OCKAM_TRACER_NAME};
+#[cfg(feature = "std")]
+use opentelemetry::trace::{FutureExt, SpanBuilder, TraceContextExt, Tracer};
+#[cfg(feature = "std")]
+use opentelemetry::{global, KeyValue};
 
 /// Worker relay machinery
 ///
 /// Every worker in the Ockam runtime needs a certain amount of logic
 /// and state attached to the lifecycle of the user's worker code.
 /// The relay manages this state and runtime behaviour.
-pub struct WorkerRelay<W> {
+pub struct WorkerRelay<W: Worker> {
     worker: W,
     ctx: Context,
+    codec: Option<Arc<dyn Codec<W::Message>>>,
+    retry_policy: Option<HandleRetryPolicy>,
 }
 
 impl<W: Worker> WorkerRelay<W> {
-    pub fn new(worker: W, ctx: Context) -> Self {
-        Self { worker, ctx }
+    pub fn new(
+        worker: W,
+        ctx: Context,
+        codec: Option<Arc<dyn Codec<W::Message>>>,
+        retry_policy: Option<HandleRetryPolicy>,
+    ) -> Self {
+        Self {
+            worker,
+            ctx,
+            codec,
+            retry_policy,
+        }
     }
 }
 
@@ -44,27 +61,103 @@ where
     /// 2. Introduce a Clone bound on the Message trait that allows us
     ///    to perform a cheaper clone on the message.
     ///
-    fn wrap_direct_message(relay_msg: RelayMessage) -> Routed<M> {
+    /// The payload is decoded by the worker, with the codec of the worker if it has one.
+    fn wrap_direct_message(relay_msg: RelayMessage, codec: Option<Arc<dyn Codec<M>>>) -> Routed<M> {
         Routed::new(
             relay_msg.destination().clone(),
             relay_msg.source().clone(),
             relay_msg.into_local_message(),
         )
+        .with_codec(codec)
     }
 
-    /// Receive and handle a single message
+    /// Receive and handle a single message, or a change of the worker addresses
     ///
     /// Report errors as they occur, and signal whether the loop should
     /// continue running or not
     async fn recv_message(&mut self) -> Result<bool> {
-        let relay_msg = match self.ctx.receiver_next().await? {
-            Some(msg) => msg,
+        let relay_msg = match self.ctx.receiver_next_event().await? {
+            Some(ContextEvent::Message(msg)) => *msg,
+            Some(ContextEvent::AddressAdded(address)) => {
+                self.worker
+                    .on_address_added(&mut self.ctx, &address)
+                    .await?;
+                return Ok(true);
+            }
+            Some(ContextEvent::AddressRemoved(address)) => {
+                self.worker
+                    .on_address_removed(&mut self.ctx, &address)
+                    .await?;
+                return Ok(true);
+            }
+            // Stop handling messages, the new worker will handle the next ones
+            Some(ContextEvent::WorkerReplaced) => {
+                debug!("Worker {} is being replaced", self.ctx.primary_address());
+                return Ok(false);
+            }
             None => {
                 trace!("No more messages for worker {}", self.ctx.primary_address());
                 return Ok(false);
             }
         };
 
+        let relay_msg = match self.ctx.apply_middlewares(relay_msg).await? {
+            Some(relay_msg) => relay_msg,
+            None => return Ok(true),
+        };
+
+        match self.retry_policy.clone() {
+            Some(retry_policy) => {
+                self.dispatch_message_with_retry(relay_msg, &retry_policy)
+                    .await?
+            }
+            None => self.dispatch_message(relay_msg).await?,
+        }
+
+        // Signal to the outer loop that we would like to run again
+        Ok(true)
+    }
+
+    /// Pass a message to the worker, sending it again after a backoff as long as it fails
+    /// and the retry policy allows it
+    async fn dispatch_message_with_retry(
+        &mut self,
+        relay_msg: RelayMessage,
+        retry_policy: &HandleRetryPolicy,
+    ) -> Result<()> {
+        let mut retries = 0;
+        loop {
+            let result = self.dispatch_message(relay_msg.clone()).await;
+            let error = match result {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+
+            match retry_policy.backoff(retries) {
+                Some(backoff) if !self.ctx.is_stopping() => {
+                    warn!(
+                        "Error encountered during '{}' message handling, retrying in {:?}: {}",
+                        self.ctx.primary_address(),
+                        backoff,
+                        error
+                    );
+                    self.ctx.sleep(backoff).await;
+                    retries += 1;
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// Pass a single message to the worker
+    async fn dispatch_message(&mut self, relay_msg: RelayMessage) -> Result<()> {
+        // Messages sent by the worker while handling this message will share its correlation id
+        // and priority
+        self.ctx
+            .set_correlation_id(relay_msg.local_message().correlation_id());
+        self.ctx
+            .set_message_priority(relay_msg.local_message().priority());
+
         // Call the worker handle function - pass errors up
         cfg_if! {
             if #[cfg(feature = "std")] {
@@ -75,22 +168,44 @@ where
                 // (see send_from_address_impl)
                 self.ctx.set_tracing_context(tracing_context.clone());
 
+                // make sure we are using the latest tracing context to handle the message
+                // the handle_message future
+                let mut opentelemetry_context = tracing_context.update().extract();
+
+                // report the worker labels as the attributes of a span covering the handling
+                // of the message, the span of the propagated context is not recording
+                if !self.ctx.labels().is_empty() {
+                    let tracer = global::tracer(OCKAM_TRACER_NAME);
+                    let span_builder = SpanBuilder::from_name(format!(
+                        "{}::handle_message",
+                        self.ctx.primary_address()
+                    ))
+                    .with_attributes(
+                        self.ctx
+                            .labels()
+                            .iter()
+                            .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+                    );
+                    let span = tracer.build_with_context(span_builder, &opentelemetry_context);
+                    opentelemetry_context = opentelemetry_context.with_span(span);
+                    self.ctx
+                        .set_tracing_context(OpenTelemetryContext::inject(&opentelemetry_context));
+                }
+
+                let routed = Self::wrap_direct_message(relay_msg, self.codec.clone());
                 self.worker
-                    .handle_message(&mut self.ctx, Self::wrap_direct_message(relay_msg))
-                    // make sure we are using the latest tracing context to handle the message
-                    // the handle_message future
-                    .with_context(tracing_context.update().extract())
+                    .handle_message(&mut self.ctx, routed)
+                    .with_context(opentelemetry_context)
                     .await?;
             } else {
-                let routed = Self::wrap_direct_message(relay_msg);
+                let routed = Self::wrap_direct_message(relay_msg, self.codec.clone());
                 self.worker
                     .handle_message(&mut self.ctx, routed)
                     .await?;
-                }
+            }
         }
 
-        // Signal to the outer loop that we would like to run again
-        Ok(true)
+        Ok(())
     }
 
     #[cfg_attr(not(feature = "std"), allow(unused_mut))]
@@ -100,15 +215,22 @@ where
             Ok(()) => {}
             Err(e) => {
                 error!(
-                    "Failure during '{}' worker initialisation: {}",
-                    self.ctx.primary_address(),
-                    e
+                    address = %self.ctx.primary_address(),
+                    error_code = ?e.code().kind,
+                    error_origin = ?e.code().origin,
+                    error = %e,
+                    "Failure during worker initialisation"
                 );
                 shutdown_and_stop_ack(&mut self.worker, &mut self.ctx, false).await;
                 return;
             }
         }
 
+        #[cfg(feature = "std")]
+        if let Ok(router) = self.ctx.router() {
+            router.mark_initialized(self.ctx.primary_address());
+        }
+
         #[cfg(feature = "std")]
         loop {
             crate::tokio::select! {
@@ -123,9 +245,22 @@ where
                         // An error occurred -- log and continue
                         Err(e) => {
                             #[cfg(feature = "debugger")]
-                            error!("Error encountered during '{}' message handling: {:?}", self.ctx.primary_address(), e);
+                            error!(
+                                address = %self.ctx.primary_address(),
+                                error_code = ?e.code().kind,
+                                error_origin = ?e.code().origin,
+                                error = ?e,
+                                "Error encountered during message handling"
+                            );
                             #[cfg(not(feature = "debugger"))]
-                            error!("Error encountered during '{}' message handling: {}", self.ctx.primary_address(), e);
+                            error!(
+                                address = %self.ctx.primary_address(),
+                                error_code = ?e.code().kind,
+                                error_origin = ?e.code().origin,
+                                error = %e,
+                                "Error encountered during message handling"
+                            );
+                            self.ctx.report_worker_error(e);
                         }
                     }
                 },
@@ -148,27 +283,58 @@ where
                 }
                 // An error occurred -- log and continue
                 Err(e) => error!(
-                    "Error encountered during '{}' message handling: {}",
-                    self.ctx.primary_address(),
-                    e
+                    address = %self.ctx.primary_address(),
+                    error_code = ?e.code().kind,
+                    error_origin = ?e.code().origin,
+                    error = %e,
+                    "Error encountered during message handling"
                 ),
             }
         }
 
+        #[cfg(feature = "std")]
+        if let Some(replacement) = self.ctx.replacement.take() {
+            // The context is handed over to the new worker: it must not be stopped
+            if let Err(e) = self.worker.shutdown(&mut self.ctx).await {
+                error!(
+                    address = %self.ctx.primary_address(),
+                    error_code = ?e.code().kind,
+                    error_origin = ?e.code().origin,
+                    error = %e,
+                    "Failure during worker shutdown"
+                );
+            }
+            replacement.start(self.ctx, ctrl_rx, self.retry_policy);
+            return;
+        }
+
         shutdown_and_stop_ack(&mut self.worker, &mut self.ctx, true).await;
     }
 
     /// Build and spawn a new worker relay, returning a send handle to it
-    pub(crate) fn init(rt: &Handle, worker: W, ctx: Context, ctrl_rx: OneshotReceiver<CtrlSignal>) {
-        let relay = WorkerRelay::new(worker, ctx);
+    pub(crate) fn init(
+        rt: &Handle,
+        worker: W,
+        ctx: Context,
+        ctrl_rx: OneshotReceiver<CtrlSignal>,
+        codec: Option<Arc<dyn Codec<M>>>,
+        retry_policy: Option<HandleRetryPolicy>,
+    ) {
+        let relay = WorkerRelay::new(worker, ctx, codec, retry_policy);
         rt.spawn(relay.run(ctrl_rx));
     }
 }
 
-async fn shutdown_and_stop_ack<W>(worker: &mut W, ctx: &mut Context, stopped_from_router: bool)
-where
+pub(crate) async fn shutdown_and_stop_ack<W>(
+    worker: &mut W,
+    ctx: &mut Context,
+    stopped_from_router: bool,
+) where
     W: Worker<Context = Context>,
 {
+    // Interrupt the sleeps of the worker, so that it shuts down promptly
+    ctx.set_stopping();
+
     // Run the shutdown hook for this worker
     // TODO: pass stopped_from_router to the shutdown, a Worker may choose different strategy on
     //  shutting down dependent workers based on that. E.g., TcpSender should stop TcpReceiver if
@@ -177,19 +343,22 @@ where
         Ok(()) => {}
         Err(e) => {
             error!(
-                "Failure during '{}' worker shutdown: {}",
-                ctx.primary_address(),
-                e
+                address = %ctx.primary_address(),
+                error_code = ?e.code().kind,
+                error_origin = ?e.code().origin,
+                error = %e,
+                "Failure during worker shutdown"
             );
         }
     }
+    ctx.clear_local_store();
 
     let router = match ctx.router() {
         Ok(router) => router,
         Err(_) => {
             error!(
-                "Failure during '{}' worker shutdown. Can't get router",
-                ctx.primary_address()
+                address = %ctx.primary_address(),
+                "Failure during worker shutdown. Can't get router"
             );
             return;
         }
@@ -198,9 +367,11 @@ where
     if !stopped_from_router {
         if let Err(e) = router.stop_address(ctx.primary_address(), !stopped_from_router) {
             error!(
-                "Failure during '{}' worker shutdown: {}",
-                ctx.primary_address(),
-                e
+                address = %ctx.primary_address(),
+                error_code = ?e.code().kind,
+                error_origin = ?e.code().origin,
+                error = %e,
+                "Failure during worker shutdown"
             );
         }
     }
@@ -209,9 +380,11 @@ where
     trace!("Sending shutdown ACK");
     router.stop_ack(ctx.primary_address()).unwrap_or_else(|e| {
         error!(
-            "Failed to send stop ACK for worker '{}': {}",
-            ctx.primary_address(),
-            e
+            address = %ctx.primary_address(),
+            error_code = ?e.code().kind,
+            error_origin = ?e.code().origin,
+            error = %e,
+            "Failed to send stop ACK for worker"
         )
     });
 }
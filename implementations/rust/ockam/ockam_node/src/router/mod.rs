@@ -5,4 +5,5 @@ mod router;
 mod shutdown;
 pub mod worker;
 
+pub(crate) use record::WorkerMeta;
 pub use router::*;
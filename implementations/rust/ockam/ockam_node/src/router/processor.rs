@@ -1,6 +1,5 @@
 use super::{Router, RouterState, SenderPair};
 use crate::router::record::{AddressRecord, WorkerMeta};
-use crate::WorkerShutdownPriority;
 use ockam_core::compat::sync::Arc;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Mailboxes, Result};
@@ -11,7 +10,7 @@ impl Router {
         &self,
         mailboxes: &Mailboxes,
         senders: SenderPair,
-        shutdown_priority: WorkerShutdownPriority,
+        meta: WorkerMeta,
     ) -> Result<()> {
         if *self.state.read().unwrap() != RouterState::Running {
             return Err(Error::new(
@@ -21,28 +20,35 @@ impl Router {
             ))?;
         }
 
-        self.add_processor_impl(mailboxes, senders, shutdown_priority)
+        self.add_processor_impl(mailboxes, senders, meta)
     }
 
     fn add_processor_impl(
         &self,
         mailboxes: &Mailboxes,
         senders: SenderPair,
-        shutdown_priority: WorkerShutdownPriority,
+        meta: WorkerMeta,
     ) -> Result<()> {
         debug!("Starting new processor '{}'", mailboxes.primary_address());
-        let SenderPair { msgs, ctrl } = senders;
+        let SenderPair {
+            msgs,
+            ctrl,
+            #[cfg(feature = "std")]
+            router_events,
+        } = senders;
 
         let record = AddressRecord::new(
             mailboxes.primary_address().clone(),
             mailboxes.additional_addresses().cloned().collect(),
             msgs,
             ctrl,
+            #[cfg(feature = "std")]
+            router_events,
             WorkerMeta {
                 processor: true,
                 detached: false,
+                ..meta
             },
-            shutdown_priority,
             // We don't keep track of the mailbox count for processors
             // because, while they are able to send and receive messages
             // via their mailbox, most likely this metric is going to be
@@ -1,6 +1,8 @@
 use crate::channel_types::{oneshot_channel, MessageSender, OneshotReceiver, OneshotSender};
 use crate::error::{NodeError, NodeReason};
 use crate::relay::CtrlSignal;
+#[cfg(feature = "std")]
+use crate::relay::{RouterEvent, WorkerReplacement};
 use crate::WorkerShutdownPriority;
 use core::default::Default;
 use core::fmt::Debug;
@@ -12,6 +14,7 @@ use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{
     compat::{
         collections::{HashMap, HashSet},
+        string::String,
         sync::Arc,
         vec::Vec,
     },
@@ -44,6 +47,9 @@ pub struct InternalMap {
     stopping_shutdown: SyncMutex<HashSet<Address>>,
     /// Channel to notify when stopping_shutdown map gets empty
     shutdown_yield_sender: SyncMutex<Option<OneshotSender<()>>>,
+    /// Members of the groups being stopped which didn't send their stop ACK yet, along with the
+    /// channel to notify when all of them are stopped
+    group_stop_waiters: SyncMutex<Vec<(HashSet<Address>, OneshotSender<()>)>>,
     /// Access to [`FlowControls`] to clean resources
     flow_controls: FlowControls,
     /// Metrics collection and sharing
@@ -92,6 +98,7 @@ impl InternalMap {
             stopping: Default::default(),
             stopping_shutdown: Default::default(),
             shutdown_yield_sender: Default::default(),
+            group_stop_waiters: Default::default(),
             flow_controls: flow_controls.clone(),
             #[cfg(feature = "metrics")]
             metrics: Default::default(),
@@ -163,6 +170,18 @@ impl InternalMap {
                 "Removing {} from stopping. Removed = {}",
                 primary_address, res
             );
+
+            // Notify the groups waiting for this worker, while holding the stopping lock so that
+            // a group can't start waiting for a worker which is already stopped
+            let mut group_stop_waiters = self.group_stop_waiters.lock().unwrap();
+            for (mut pending, sender) in core::mem::take(&mut *group_stop_waiters) {
+                pending.remove(primary_address);
+                if pending.is_empty() {
+                    let _ = sender.send(());
+                } else {
+                    group_stop_waiters.push((pending, sender));
+                }
+            }
         }
 
         let mut stopping_shutdown = self.stopping_shutdown.lock().unwrap();
@@ -183,6 +202,60 @@ impl InternalMap {
         }
     }
 
+    /// Stop all the members of a group, and return a channel notified once all of them sent
+    /// their stop ACK. `None` is returned if there is nothing to wait for.
+    ///
+    /// The `caller` is stopped if it is a member of the group, but it is not waited for, since
+    /// it can't send its stop ACK while waiting.
+    pub(super) fn stop_group(
+        &self,
+        group: &str,
+        caller: &Address,
+    ) -> Result<Option<OneshotReceiver<()>>> {
+        let members: Vec<Address> = self
+            .address_maps
+            .records
+            .read()
+            .unwrap()
+            .values()
+            .filter(|record| record.meta.group.as_deref() == Some(group))
+            .map(|record| record.primary_address.clone())
+            .collect();
+
+        debug!(
+            "Stopping the {} members of the group {}",
+            members.len(),
+            group
+        );
+
+        for member in &members {
+            match self.stop(member, false) {
+                Ok(()) => {}
+                // The member may have been stopped in the meantime
+                Err(err) if err.code().kind == Kind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let stopping = self.stopping.lock().unwrap();
+        let pending: HashSet<Address> = members
+            .into_iter()
+            .filter(|member| member != caller && stopping.contains(member))
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(None);
+        }
+
+        let (sender, receiver) = oneshot_channel();
+        self.group_stop_waiters
+            .lock()
+            .unwrap()
+            .push((pending, sender));
+
+        Ok(Some(receiver))
+    }
+
     pub(super) fn is_worker_registered_at(&self, primary_address: &Address) -> bool {
         self.address_maps
             .records
@@ -192,6 +265,26 @@ impl InternalMap {
         // TODO: we should also check aliases
     }
 
+    pub(super) fn address_exists(&self, address: &Address) -> bool {
+        let records = self.address_maps.records.read().unwrap();
+        let aliases = self.address_maps.aliases.read().unwrap();
+
+        aliases
+            .get(address)
+            .is_some_and(|primary_address| records.contains_key(primary_address))
+    }
+
+    /// Primary address of the worker or processor owning the given address, if it exists
+    pub(super) fn primary_address(&self, address: &Address) -> Option<Address> {
+        let records = self.address_maps.records.read().unwrap();
+        let aliases = self.address_maps.aliases.read().unwrap();
+
+        aliases
+            .get(address)
+            .filter(|primary_address| records.contains_key(*primary_address))
+            .cloned()
+    }
+
     pub(super) fn list_workers(&self) -> Vec<Address> {
         self.address_maps
             .records
@@ -292,6 +385,135 @@ impl InternalMap {
         }
     }
 
+    /// Attach an additional address to a running worker and notify its context.
+    ///
+    /// The context is notified before the address can be resolved, so that it knows the new
+    /// mailbox by the time a message for that address is received
+    #[cfg(feature = "std")]
+    pub(super) fn add_address(&self, primary_address: &Address, mailbox: Mailbox) -> Result<()> {
+        let mut records = self.address_maps.records.write().unwrap();
+        let mut aliases = self.address_maps.aliases.write().unwrap();
+        let mut metadata = self.address_maps.metadata.write().unwrap();
+
+        let record = Self::find_worker_record(&mut records, primary_address)?;
+
+        if aliases.contains_key(mailbox.address()) {
+            let node = NodeError::Address(mailbox.address().clone());
+            return Err(node.already_exists());
+        }
+
+        let address = mailbox.address().clone();
+        let mailbox_metadata = mailbox.metadata().clone();
+        Self::notify_router_event(record, RouterEvent::Added(mailbox))?;
+
+        if let Some(mailbox_metadata) = mailbox_metadata {
+            metadata.insert(address.clone(), mailbox_metadata);
+        }
+        aliases.insert(address.clone(), primary_address.clone());
+        record.additional_addresses.push(address);
+
+        Ok(())
+    }
+
+    /// Detach an additional address from a running worker and notify its context
+    #[cfg(feature = "std")]
+    pub(super) fn remove_address(
+        &self,
+        primary_address: &Address,
+        address: &Address,
+    ) -> Result<()> {
+        let mut records = self.address_maps.records.write().unwrap();
+        let mut aliases = self.address_maps.aliases.write().unwrap();
+        let mut metadata = self.address_maps.metadata.write().unwrap();
+
+        let record = Self::find_worker_record(&mut records, primary_address)?;
+
+        let index = record
+            .additional_addresses
+            .iter()
+            .position(|a| a == address)
+            .ok_or_else(|| {
+                Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    format!(
+                        "{} is not an additional address of {}",
+                        address, primary_address
+                    ),
+                )
+            })?;
+
+        record.additional_addresses.remove(index);
+        aliases.remove(address);
+        metadata.remove(address);
+        self.flow_controls.cleanup_address(address);
+
+        Self::notify_router_event(record, RouterEvent::Removed(address.clone()))
+    }
+
+    /// Ask a running worker to hand its context over to a new worker implementation
+    #[cfg(feature = "std")]
+    pub(super) fn replace_worker(
+        &self,
+        primary_address: &Address,
+        replacement: WorkerReplacement,
+    ) -> Result<()> {
+        let mut records = self.address_maps.records.write().unwrap();
+
+        let record = Self::find_worker_record(&mut records, primary_address)?;
+
+        if record.meta.detached {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Unsupported,
+                format!(
+                    "{} is a detached context, not a worker, it can't be replaced",
+                    primary_address
+                ),
+            ));
+        }
+
+        Self::notify_router_event(record, RouterEvent::Replace(replacement))
+    }
+
+    #[cfg(feature = "std")]
+    fn find_worker_record<'a>(
+        records: &'a mut HashMap<Address, AddressRecord>,
+        primary_address: &Address,
+    ) -> Result<&'a mut AddressRecord> {
+        let record = records.get_mut(primary_address).ok_or_else(|| {
+            Error::new(
+                Origin::Node,
+                Kind::NotFound,
+                format!("No such address: {}", primary_address),
+            )
+        })?;
+
+        if record.meta.processor {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Unsupported,
+                format!(
+                    "Addresses can't be changed for the processor {}",
+                    primary_address
+                ),
+            ));
+        }
+
+        Ok(record)
+    }
+
+    #[cfg(feature = "std")]
+    fn notify_router_event(record: &AddressRecord, event: RouterEvent) -> Result<()> {
+        record.router_events.try_send(event).map_err(|_| {
+            Error::new(
+                Origin::Node,
+                Kind::ResourceExhausted,
+                format!("Can't notify {} about a change", record.primary_address),
+            )
+        })
+    }
+
     pub(super) fn find_terminal_address<'a>(
         &self,
         addresses: impl Iterator<Item = &'a Address>,
@@ -337,18 +559,47 @@ impl InternalMap {
         self.metrics.0.load(Ordering::Acquire)
     }
 
-    /// Stop all workers with given priority
+    /// Return true if some workers with given priority are still running
+    pub(super) fn has_workers(&self, shutdown_priority: WorkerShutdownPriority) -> bool {
+        self.address_maps
+            .records
+            .read()
+            .unwrap()
+            .values()
+            .any(|record| record.meta.shutdown_priority == shutdown_priority)
+    }
+
+    /// Stop all workers with given priority which don't have to wait for another worker with
+    /// the same priority to be stopped first
     pub(super) fn stop_workers(
         &self,
         shutdown_priority: WorkerShutdownPriority,
     ) -> Option<OneshotReceiver<()>> {
         let records_to_stop: Vec<AddressRecord> = {
             let mut records = self.address_maps.records.write().unwrap();
+            let aliases = self.address_maps.aliases.read().unwrap();
+
+            let mut blocked =
+                Self::blocked_by_shutdown_order(&records, &aliases, shutdown_priority);
+
+            let has_unblocked = records.values().any(|record| {
+                record.meta.shutdown_priority == shutdown_priority
+                    && !blocked.contains(&record.primary_address)
+            });
+            if !has_unblocked && !blocked.is_empty() {
+                warn!(
+                    "Cyclic shutdown order between workers with priority: {:?}. Stopping them all",
+                    shutdown_priority
+                );
+                blocked.clear();
+            }
 
             // we remove address records, so workers to be stopped can no longer be found, therefore
             // can't be used to send messages
             records
-                .extract_if(|_addr, record| record.shutdown_order == shutdown_priority)
+                .extract_if(|addr, record| {
+                    record.meta.shutdown_priority == shutdown_priority && !blocked.contains(addr)
+                })
                 .map(|(_addr, record)| record)
                 .collect()
         };
@@ -390,6 +641,34 @@ impl InternalMap {
         }
     }
 
+    /// Primary addresses of the workers with given priority that must wait for another running
+    /// worker with the same priority to be stopped first
+    fn blocked_by_shutdown_order(
+        records: &HashMap<Address, AddressRecord>,
+        aliases: &HashMap<Address, Address>,
+        shutdown_priority: WorkerShutdownPriority,
+    ) -> HashSet<Address> {
+        records
+            .values()
+            .filter(|record| record.meta.shutdown_priority == shutdown_priority)
+            .flat_map(|record| {
+                record
+                    .meta
+                    .shutdown_before
+                    .iter()
+                    .filter_map(|address| aliases.get(address))
+                    .filter(|primary_address| **primary_address != record.primary_address)
+            })
+            .filter(|primary_address| {
+                records
+                    .get(*primary_address)
+                    .map(|record| record.meta.shutdown_priority == shutdown_priority)
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+
     pub(super) fn force_clear_records(&self) -> Vec<Address> {
         let mut records = self.address_maps.records.write().unwrap();
 
@@ -398,11 +677,17 @@ impl InternalMap {
 }
 
 /// Additional metadata for worker records
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct WorkerMeta {
     #[allow(dead_code)]
     pub processor: bool,
     pub detached: bool,
+    /// Group which can be stopped at once with [`Context::stop_group`](crate::Context::stop_group)
+    pub group: Option<String>,
+    /// Order in which the worker is stopped on node shutdown
+    pub shutdown_priority: WorkerShutdownPriority,
+    /// Addresses of the workers that must be stopped after this one
+    pub shutdown_before: Vec<Address>,
 }
 
 pub struct AddressRecord {
@@ -410,8 +695,9 @@ pub struct AddressRecord {
     additional_addresses: Vec<Address>,
     sender: MessageSender<RelayMessage>,
     ctrl_tx: OneshotSender<CtrlSignal>,
+    #[cfg(feature = "std")]
+    router_events: MessageSender<RouterEvent>,
     meta: WorkerMeta,
-    shutdown_order: WorkerShutdownPriority,
     msg_count: Arc<AtomicUsize>,
 }
 
@@ -434,8 +720,8 @@ impl AddressRecord {
         additional_addresses: Vec<Address>,
         sender: MessageSender<RelayMessage>,
         ctrl_tx: OneshotSender<CtrlSignal>,
+        #[cfg(feature = "std")] router_events: MessageSender<RouterEvent>,
         meta: WorkerMeta,
-        shutdown_order: WorkerShutdownPriority,
         msg_count: Arc<AtomicUsize>,
     ) -> Self {
         AddressRecord {
@@ -443,8 +729,9 @@ impl AddressRecord {
             additional_addresses,
             sender,
             ctrl_tx,
+            #[cfg(feature = "std")]
+            router_events,
             meta,
-            shutdown_order,
             msg_count,
         }
     }
@@ -2,15 +2,26 @@
 use core::sync::atomic::AtomicUsize;
 
 use super::record::InternalMap;
-use crate::channel_types::{MessageSender, OneshotSender};
+#[cfg(feature = "std")]
+use crate::channel_types::MessageReceiver;
+use crate::channel_types::{MessageSender, OneshotReceiver, OneshotSender};
 use crate::relay::CtrlSignal;
-use crate::{NodeError, NodeReason};
+#[cfg(feature = "std")]
+use crate::relay::{RouterEvent, WorkerReplacement};
+#[cfg(feature = "std")]
+use crate::WorkerError;
+use crate::{Middleware, NodeError, NodeReason};
+use alloc::string::String;
 use alloc::vec::Vec;
 use ockam_core::compat::collections::hash_map::Entry;
 use ockam_core::compat::collections::HashMap;
-use ockam_core::compat::sync::RwLock as SyncRwLock;
+#[cfg(feature = "std")]
+use ockam_core::compat::collections::HashSet;
+use ockam_core::compat::sync::{Arc, RwLock as SyncRwLock};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControls;
+#[cfg(feature = "std")]
+use ockam_core::Mailbox;
 use ockam_core::{Address, AddressMetadata, Error, RelayMessage, Result, TransportType};
 
 /// A pair of senders to a worker relay
@@ -18,6 +29,8 @@ use ockam_core::{Address, AddressMetadata, Error, RelayMessage, Result, Transpor
 pub struct SenderPair {
     pub msgs: MessageSender<RelayMessage>,
     pub ctrl: OneshotSender<CtrlSignal>,
+    #[cfg(feature = "std")]
+    pub(crate) router_events: MessageSender<RouterEvent>,
 }
 
 enum RouteType {
@@ -51,6 +64,19 @@ pub struct Router {
     pub(super) external: SyncRwLock<HashMap<TransportType, Address>>,
     #[cfg(feature = "std")]
     pub(super) shutdown_broadcast_sender: SyncRwLock<Option<tokio::sync::broadcast::Sender<()>>>,
+    /// Receives the errors returned by the workers when handling messages
+    #[cfg(feature = "std")]
+    pub(super) error_sink: SyncRwLock<Option<MessageSender<WorkerError>>>,
+    /// Run on every message delivered to a worker, in order
+    pub(super) middlewares: SyncRwLock<Vec<Arc<dyn Middleware>>>,
+    /// Addresses subscribed to each topic, see [`Context::subscribe`](crate::Context::subscribe)
+    pub(super) topics: SyncRwLock<HashMap<String, Vec<Address>>>,
+    /// Primary addresses of the workers and processors whose initialization completed
+    #[cfg(feature = "std")]
+    pub(super) initialized: SyncRwLock<HashSet<Address>>,
+    /// Notified when a worker or processor completes its initialization, or is stopped
+    #[cfg(feature = "std")]
+    pub(super) initialized_notify: tokio::sync::Notify,
 }
 
 /// Node state
@@ -72,9 +98,108 @@ impl Router {
             external: Default::default(),
             #[cfg(feature = "std")]
             shutdown_broadcast_sender: SyncRwLock::new(Some(shutdown_broadcast_sender)),
+            #[cfg(feature = "std")]
+            error_sink: Default::default(),
+            middlewares: Default::default(),
+            topics: Default::default(),
+            #[cfg(feature = "std")]
+            initialized: Default::default(),
+            #[cfg(feature = "std")]
+            initialized_notify: Default::default(),
         }
     }
 
+    #[cfg(feature = "std")]
+    pub fn set_error_sink(&self, capacity: usize) -> MessageReceiver<WorkerError> {
+        let (sender, receiver) = tokio::sync::mpsc::channel(capacity.max(1));
+        *self.error_sink.write().unwrap() = Some(sender);
+        receiver
+    }
+
+    #[cfg(feature = "std")]
+    pub fn remove_error_sink(&self) {
+        *self.error_sink.write().unwrap() = None;
+    }
+
+    #[cfg(feature = "std")]
+    pub fn report_worker_error(&self, address: &Address, error: Error) {
+        let error_sink = self.error_sink.read().unwrap();
+        let Some(sender) = error_sink.as_ref() else {
+            return;
+        };
+
+        let worker_error = WorkerError {
+            address: address.clone(),
+            error,
+        };
+
+        match sender.try_send(worker_error) {
+            Ok(()) => {}
+            Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                debug!("Error sink is full, dropping the error of {}", address);
+            }
+            Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                debug!("Error sink is closed, dropping the error of {}", address);
+            }
+        }
+    }
+
+    pub fn add_middleware(&self, middleware: Arc<dyn Middleware>) {
+        self.middlewares.write().unwrap().push(middleware);
+    }
+
+    pub fn clear_middlewares(&self) {
+        self.middlewares.write().unwrap().clear();
+    }
+
+    pub fn middlewares(&self) -> Vec<Arc<dyn Middleware>> {
+        self.middlewares.read().unwrap().clone()
+    }
+
+    pub fn subscribe(&self, topic: String, address: Address) {
+        let mut topics = self.topics.write().unwrap();
+        let subscribers = topics.entry(topic).or_default();
+        if !subscribers.contains(&address) {
+            subscribers.push(address);
+        }
+    }
+
+    pub fn unsubscribe(&self, topic: &str, address: &Address) {
+        let mut topics = self.topics.write().unwrap();
+        if let Some(subscribers) = topics.get_mut(topic) {
+            subscribers.retain(|subscriber| subscriber != address);
+            if subscribers.is_empty() {
+                topics.remove(topic);
+            }
+        }
+    }
+
+    /// Subscribers of a topic
+    ///
+    /// The stopped subscribers were already removed by [`Self::remove_stopped_subscribers`]
+    /// when their workers were stopped.
+    pub fn subscribers(&self, topic: &str) -> Vec<Address> {
+        self.topics
+            .read()
+            .unwrap()
+            .get(topic)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Unsubscribe the addresses which were stopped from all the topics
+    fn remove_stopped_subscribers(&self) {
+        let mut topics = self.topics.write().unwrap();
+        topics.retain(|_, subscribers| {
+            subscribers.retain(|subscriber| self.map.address_exists(subscriber));
+            !subscribers.is_empty()
+        });
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.state.read().unwrap() != RouterState::Running
+    }
+
     pub fn list_workers(&self) -> Vec<Address> {
         self.map.list_workers()
     }
@@ -83,14 +208,55 @@ impl Router {
         self.map.is_worker_registered_at(address)
     }
 
+    pub fn address_exists(&self, address: &Address) -> bool {
+        match determine_type(address) {
+            RouteType::Internal => self.map.address_exists(address),
+            RouteType::External(tt) => self
+                .address_for_transport(tt)
+                .is_ok_and(|address| self.map.address_exists(&address)),
+        }
+    }
+
     pub fn stop_ack(&self, primary_address: &Address) -> Result<()> {
         debug!("Handling shutdown ACK for {}", primary_address);
 
         self.map.stop_ack(primary_address);
+        self.remove_stopped_subscribers();
+
+        #[cfg(feature = "std")]
+        {
+            self.initialized.write().unwrap().remove(primary_address);
+            self.initialized_notify.notify_waiters();
+        }
 
         Ok(())
     }
 
+    /// Record that a worker or processor completed its initialization
+    #[cfg(feature = "std")]
+    pub fn mark_initialized(&self, primary_address: &Address) {
+        self.initialized
+            .write()
+            .unwrap()
+            .insert(primary_address.clone());
+        self.initialized_notify.notify_waiters();
+    }
+
+    /// Future completed on the next change of the initialization state of the workers and
+    /// processors
+    #[cfg(feature = "std")]
+    pub fn initialization_notified(&self) -> tokio::sync::futures::Notified<'_> {
+        self.initialized_notify.notified()
+    }
+
+    /// Return `None` if there is no worker or processor at this address, otherwise whether
+    /// its initialization completed
+    #[cfg(feature = "std")]
+    pub fn is_initialized(&self, address: &Address) -> Option<bool> {
+        let primary_address = self.map.primary_address(address)?;
+        Some(self.initialized.read().unwrap().contains(&primary_address))
+    }
+
     pub fn find_terminal_address<'a>(
         &self,
         addresses: impl Iterator<Item = &'a Address>,
@@ -138,10 +304,51 @@ impl Router {
         debug!("Stopping address '{}'", addr);
 
         self.map.stop(addr, skip_sending_stop_signal)?;
+        self.remove_stopped_subscribers();
 
         Ok(())
     }
 
+    /// Stop all the members of a group, see [`InternalMap::stop_group`]
+    pub fn stop_group(&self, group: &str, caller: &Address) -> Result<Option<OneshotReceiver<()>>> {
+        let receiver = self.map.stop_group(group, caller)?;
+        self.remove_stopped_subscribers();
+
+        Ok(receiver)
+    }
+
+    /// Attach an additional address to a running worker
+    #[cfg(feature = "std")]
+    pub fn add_address(&self, primary_address: &Address, mailbox: Mailbox) -> Result<()> {
+        debug!(
+            "Adding address '{}' to '{}'",
+            mailbox.address(),
+            primary_address
+        );
+
+        self.map.add_address(primary_address, mailbox)
+    }
+
+    /// Detach an additional address from a running worker
+    #[cfg(feature = "std")]
+    pub fn remove_address(&self, primary_address: &Address, address: &Address) -> Result<()> {
+        debug!("Removing address '{}' from '{}'", address, primary_address);
+
+        self.map.remove_address(primary_address, address)
+    }
+
+    /// Replace the implementation of a running worker
+    #[cfg(feature = "std")]
+    pub(crate) fn replace_worker(
+        &self,
+        primary_address: &Address,
+        replacement: WorkerReplacement,
+    ) -> Result<()> {
+        debug!("Replacing worker '{}'", primary_address);
+
+        self.map.replace_worker(primary_address, replacement)
+    }
+
     #[cfg(feature = "std")]
     pub async fn wait_termination(&self) {
         let mut receiver = match self.shutdown_broadcast_sender.read().unwrap().as_ref() {
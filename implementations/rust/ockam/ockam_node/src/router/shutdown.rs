@@ -60,31 +60,35 @@ impl Router {
         let r = self.clone();
         let shutdown = async move {
             for shutdown_priority in WorkerShutdownPriority::all_descending_order() {
-                debug!("Stopping workers with priority: {:?}", shutdown_priority);
-                let shutdown_yield_receiver = r.map.stop_workers(shutdown_priority);
-
-                if let Some(shutdown_yield_receiver) = shutdown_yield_receiver {
-                    debug!(
-                        "Waiting for yield for workers with priority: {:?}",
-                        shutdown_priority
-                    );
-                    // Wait for stop ack
-                    match shutdown_yield_receiver.await {
-                        Ok(_) => {
-                            debug!(
-                                "Received yield for workers with priority: {:?}",
-                                shutdown_priority
-                            );
-                        }
-                        Err(err) => {
-                            error!("Error receiving shutdown yield: {}", err);
+                // Workers with the same priority are stopped in several rounds if some of them
+                // have to be stopped before others
+                while r.map.has_workers(shutdown_priority) {
+                    debug!("Stopping workers with priority: {:?}", shutdown_priority);
+                    let shutdown_yield_receiver = r.map.stop_workers(shutdown_priority);
+
+                    if let Some(shutdown_yield_receiver) = shutdown_yield_receiver {
+                        debug!(
+                            "Waiting for yield for workers with priority: {:?}",
+                            shutdown_priority
+                        );
+                        // Wait for stop ack
+                        match shutdown_yield_receiver.await {
+                            Ok(_) => {
+                                debug!(
+                                    "Received yield for workers with priority: {:?}",
+                                    shutdown_priority
+                                );
+                            }
+                            Err(err) => {
+                                error!("Error receiving shutdown yield: {}", err);
+                            }
                         }
+                    } else {
+                        debug!(
+                            "There were no running workers to wait for with priority: {:?}",
+                            shutdown_priority
+                        );
                     }
-                } else {
-                    debug!(
-                        "There was no workers with priority: {:?}",
-                        shutdown_priority
-                    );
                 }
             }
 
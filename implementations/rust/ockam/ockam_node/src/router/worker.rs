@@ -1,6 +1,5 @@
 use crate::router::record::{AddressRecord, WorkerMeta};
 use crate::router::{Router, RouterState, SenderPair};
-use crate::WorkerShutdownPriority;
 use core::sync::atomic::AtomicUsize;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{compat::sync::Arc, Error, Mailboxes, Result};
@@ -11,8 +10,7 @@ impl Router {
         &self,
         mailboxes: &Mailboxes,
         senders: SenderPair,
-        detached: bool,
-        shutdown_priority: WorkerShutdownPriority,
+        meta: WorkerMeta,
         metrics: Arc<AtomicUsize>,
     ) -> Result<()> {
         if *self.state.read().unwrap() != RouterState::Running {
@@ -23,19 +21,23 @@ impl Router {
             ))?;
         }
 
-        self.add_worker_impl(mailboxes, senders, detached, shutdown_priority, metrics)
+        self.add_worker_impl(mailboxes, senders, meta, metrics)
     }
 
     fn add_worker_impl(
         &self,
         mailboxes: &Mailboxes,
         senders: SenderPair,
-        detached: bool,
-        shutdown_priority: WorkerShutdownPriority,
+        meta: WorkerMeta,
         metrics: Arc<AtomicUsize>,
     ) -> Result<()> {
         debug!("Starting new worker '{}'", mailboxes.primary_address());
-        let SenderPair { msgs, ctrl } = senders;
+        let SenderPair {
+            msgs,
+            ctrl,
+            #[cfg(feature = "std")]
+            router_events,
+        } = senders;
 
         // Create an address record and insert it into the internal map
         let address_record = AddressRecord::new(
@@ -43,11 +45,12 @@ impl Router {
             mailboxes.additional_addresses().cloned().collect(),
             msgs,
             ctrl,
+            #[cfg(feature = "std")]
+            router_events,
             WorkerMeta {
                 processor: false,
-                detached,
+                ..meta
             },
-            shutdown_priority,
             metrics,
         );
 
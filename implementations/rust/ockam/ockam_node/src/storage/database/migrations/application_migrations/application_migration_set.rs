@@ -49,4 +49,17 @@ mod tests {
 
         Ok(())
     }
+
+    /// Released migrations must not be edited, see `Migrator::verify_checksums`
+    #[test]
+    fn test_checksums() -> Result<()> {
+        ApplicationMigrationSet::new(DatabaseType::Sqlite)
+            .create_migrator()?
+            .verify_checksums(include_str!("./sql/sqlite_checksums.txt"))?;
+        ApplicationMigrationSet::new(DatabaseType::Postgres)
+            .create_migrator()?
+            .verify_checksums(include_str!("./sql/postgres_checksums.txt"))?;
+
+        Ok(())
+    }
 }
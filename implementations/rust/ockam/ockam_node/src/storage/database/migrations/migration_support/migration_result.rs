@@ -1,5 +1,8 @@
+use crate::database::Version;
 use core::fmt::{Display, Formatter};
+use core::time::Duration;
 use serde::Serialize;
+use sqlx::migrate::MigrateError;
 
 /// This enum models the result of executing one migration.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -68,3 +71,96 @@ impl Display for MigrationFailure {
         Ok(())
     }
 }
+
+/// Error raised when a sql migration could not be executed.
+///
+/// It is kept as the cause of the error returned by the [`Migrator`](crate::database::Migrator),
+/// so that the underlying sqlx error can be inspected, for example to distinguish an invalid
+/// statement from a lost connection:
+/// `error.source().and_then(|e| e.downcast_ref::<SqlMigrationError>())`
+#[derive(Debug)]
+pub struct SqlMigrationError {
+    version: Version,
+    description: String,
+    error: MigrateError,
+}
+
+impl SqlMigrationError {
+    /// Constructor
+    pub(crate) fn new(version: Version, description: String, error: MigrateError) -> Self {
+        Self {
+            version,
+            description,
+            error,
+        }
+    }
+
+    /// Version of the failed migration
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Description of the failed migration
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Error returned by sqlx when applying the migration
+    pub fn migrate_error(&self) -> &MigrateError {
+        &self.error
+    }
+
+    /// Error returned by the database driver, if the migration statements could be sent
+    /// to the database
+    pub fn sqlx_error(&self) -> Option<&sqlx::Error> {
+        match &self.error {
+            MigrateError::Execute(e) | MigrateError::ExecuteMigration(e, _) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Return true if the database rejected the migration statements, for example because
+    /// of a syntax error or a constraint violation, rather than because of a connection issue
+    pub fn is_rejected_by_database(&self) -> bool {
+        matches!(self.sqlx_error(), Some(sqlx::Error::Database(_)))
+    }
+}
+
+impl Display for SqlMigrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Failed to run the migration {} ({}): {}",
+            self.description, self.version, self.error
+        )
+    }
+}
+
+impl std::error::Error for SqlMigrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Time it took to execute a single migration
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct MigrationTiming {
+    /// Version of the migration
+    pub version: Version,
+    /// Description of a sql migration or name of a rust migration
+    pub name: String,
+    /// Time it took to execute the migration
+    pub duration: Duration,
+}
+
+impl Display for MigrationTiming {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}ms",
+            self.name,
+            self.version,
+            self.duration.as_millis()
+        )
+    }
+}
@@ -43,4 +43,151 @@ impl MigrationStatus {
     pub fn up_to_date(&self) -> bool {
         matches!(self, MigrationStatus::UpToDate(_))
     }
+
+    /// Multi-line, human-readable description of the status, meant to be displayed as is by
+    /// the command line or any other tool
+    pub fn to_report(&self) -> String {
+        match self {
+            MigrationStatus::UpToDate(version) => {
+                format!("The database is up to date\n  Current version: {version}")
+            }
+            MigrationStatus::Todo(current_version, next_version) => format!(
+                "The database needs to be updated\n  Current version: {}\n  Next version: {next_version}",
+                current_version
+                    .map(|v| v.to_string())
+                    .unwrap_or("none".to_string()),
+            ),
+            MigrationStatus::Failed(version, reason) => format!(
+                "The database failed to be updated\n  Failed version: {version}\n  Reason: {reason}"
+            ),
+        }
+    }
+
+    /// Same as [`Self::to_report`], followed by the number of migrations by kind
+    pub fn to_report_with_summary(&self, summary: &MigrationSummary) -> String {
+        format!(
+            "{}\n  Sql migrations: {}\n  Rust migrations: {}",
+            self.to_report(),
+            summary.sql,
+            summary.rust
+        )
+    }
+}
+
+/// Number of migrations known by a [`Migrator`](crate::database::Migrator), broken down by kind
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct MigrationSummary {
+    /// Sql migrations
+    pub sql: MigrationCounts,
+    /// Rust migrations
+    pub rust: MigrationCounts,
+}
+
+/// Number of migrations of one kind, by state
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct MigrationCounts {
+    /// Migrations known by the migrator, down migrations excluded
+    pub known: usize,
+    /// Migrations which were applied before the migrator ran
+    pub already_applied: usize,
+    /// Migrations applied by the migrator
+    pub applied_now: usize,
+    /// Migrations which still need to be applied
+    pub pending: usize,
+}
+
+impl MigrationSummary {
+    /// Summary of a migration, given the state of the database before and after it
+    pub(crate) fn between(before: &MigrationSummary, after: &MigrationSummary) -> Self {
+        Self {
+            sql: MigrationCounts::between(&before.sql, &after.sql),
+            rust: MigrationCounts::between(&before.rust, &after.rust),
+        }
+    }
+
+    /// Total number of known migrations
+    pub fn known(&self) -> usize {
+        self.sql.known + self.rust.known
+    }
+
+    /// Total number of migrations applied by the migrator
+    pub fn applied_now(&self) -> usize {
+        self.sql.applied_now + self.rust.applied_now
+    }
+
+    /// Total number of migrations which were applied before the migrator ran
+    pub fn already_applied(&self) -> usize {
+        self.sql.already_applied + self.rust.already_applied
+    }
+
+    /// Total number of migrations which still need to be applied
+    pub fn pending(&self) -> usize {
+        self.sql.pending + self.rust.pending
+    }
+}
+
+impl MigrationCounts {
+    fn between(before: &MigrationCounts, after: &MigrationCounts) -> Self {
+        Self {
+            known: after.known,
+            already_applied: before.already_applied,
+            applied_now: before.pending.saturating_sub(after.pending),
+            pending: after.pending,
+        }
+    }
+}
+
+impl Display for MigrationSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "sql migrations: {}, rust migrations: {}",
+            self.sql, self.rust
+        )
+    }
+}
+
+impl Display for MigrationCounts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} known, {} already applied, {} applied now, {} pending",
+            self.known, self.already_applied, self.applied_now, self.pending
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_status_report() {
+        assert_eq!(
+            MigrationStatus::UpToDate(Version(3)).to_report(),
+            "The database is up to date\n  Current version: 3"
+        );
+        assert_eq!(
+            MigrationStatus::Todo(None, Version(1)).to_report(),
+            "The database needs to be updated\n  Current version: none\n  Next version: 1"
+        );
+        assert_eq!(
+            MigrationStatus::Failed(Version(2), MigrationFailure::DirtyVersion).to_report(),
+            "The database failed to be updated\n  Failed version: 2\n  Reason: This migration has already been executed and it failed"
+        );
+
+        let summary = MigrationSummary {
+            sql: MigrationCounts {
+                known: 3,
+                already_applied: 3,
+                applied_now: 0,
+                pending: 0,
+            },
+            rust: MigrationCounts::default(),
+        };
+        assert_eq!(
+            MigrationStatus::UpToDate(Version(3)).to_report_with_summary(&summary),
+            "The database is up to date\n  Current version: 3\n  Sql migrations: 3 known, 3 already applied, 0 applied now, 0 pending\n  Rust migrations: 0 known, 0 already applied, 0 applied now, 0 pending"
+        );
+    }
 }
@@ -1,10 +1,15 @@
-use crate::database::migrations::migration_support::migration_status::MigrationStatus;
+use crate::database::migrations::migration_support::migration_status::{
+    MigrationStatus, MigrationSummary,
+};
 use crate::database::migrations::migration_support::rust_migration::RustMigration;
 use crate::database::postgres::migration_20250116100000_sqlite_initialization::InitializeFromSqlite;
 use crate::database::MigrationResult::MigrationSuccess;
-use crate::database::{FromSqlxError, MigrationFailure, MigrationResult, SqlxDatabase, ToVoid};
-use core::fmt::{Display, Formatter};
-use ockam_core::compat::collections::HashSet;
+use crate::database::{
+    FromSqlxError, MigrationFailure, MigrationResult, MigrationTiming, SqlMigrationError,
+    SqlxDatabase, ToVoid,
+};
+use core::fmt::{Display, Formatter, Write};
+use ockam_core::compat::collections::{HashMap, HashSet};
 use ockam_core::compat::time::now;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Result;
@@ -14,6 +19,7 @@ use sqlx::migrate::{AppliedMigration, Migrate, Migration as SqlxMigration};
 use sqlx::{query, Any, AnyConnection, Pool, Row};
 use sqlx_core::executor::Executor;
 use std::cmp::Ordering;
+use std::time::Instant;
 use time::OffsetDateTime;
 
 /// Migrator is responsible for running Sql and Rust migrations side by side in the correct order,
@@ -25,6 +31,28 @@ pub struct Migrator {
     sql_migrator: sqlx::migrate::Migrator,
     // A legacy sqlite database to potentially import to postgres
     legacy_sqlite_database: Option<SqlxDatabase>,
+    // Sql statements executed before applying the migrations
+    pre_migration_statements: Vec<String>,
+    // Sql statements executed after applying the migrations
+    post_migration_statements: Vec<String>,
+    // Maximum number of migrations expected to be pending on an already migrated database
+    max_pending_migrations: Option<(usize, TooManyPendingMigrations)>,
+    // Versions of the sql and rust migrations which must not be applied
+    skipped_migrations: HashSet<Version>,
+    // Features of the migrations which can be applied, all the migrations are applied if unset
+    enabled_features: Option<HashSet<String>>,
+    // Features of the sql migrations, which can't declare them themselves
+    sql_migration_features: HashMap<Version, String>,
+}
+
+/// Action taken when more migrations than expected are pending on a database which was
+/// already migrated, see [`Migrator::set_max_pending_migrations`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TooManyPendingMigrations {
+    /// Log a warning and apply the migrations
+    Warn,
+    /// Return an error without applying any migration
+    Fail,
 }
 
 impl Migrator {
@@ -37,6 +65,12 @@ impl Migrator {
             rust_migrations: vec![],
             sql_migrator,
             legacy_sqlite_database: None,
+            pre_migration_statements: vec![],
+            post_migration_statements: vec![],
+            max_pending_migrations: None,
+            skipped_migrations: HashSet::new(),
+            enabled_features: None,
+            sql_migration_features: HashMap::new(),
         })
     }
 
@@ -71,6 +105,17 @@ impl Migrator {
         Ok(())
     }
 
+    /// Return the latest migration version known by this migrator
+    fn latest_known_version(&self) -> Version {
+        let sql_versions = self.sql_migrator.iter().map(|m| Version(m.version));
+        let rust_versions = self.rust_migrations.iter().map(|m| m.version());
+
+        sql_versions
+            .chain(rust_versions)
+            .max()
+            .unwrap_or(Version::MIN)
+    }
+
     /// Set the database configuration
     pub fn set_legacy_sqlite_database(
         &mut self,
@@ -79,6 +124,79 @@ impl Migrator {
         self.legacy_sqlite_database = legacy_sqlite_database;
         Ok(())
     }
+
+    /// Set sql statements to execute, in order, on the migration connection before the
+    /// migrations are applied. For example to set session parameters or to create extensions.
+    ///
+    /// They are only executed if some migrations need to be applied, once the database is locked.
+    pub fn set_pre_migration_statements(&mut self, statements: Vec<String>) {
+        self.pre_migration_statements = statements;
+    }
+
+    /// Set sql statements to execute, in order, on the migration connection after the
+    /// migrations were applied, before the database is unlocked.
+    ///
+    /// They are not executed if a migration returned an error.
+    pub fn set_post_migration_statements(&mut self, statements: Vec<String>) {
+        self.post_migration_statements = statements;
+    }
+
+    /// Set the maximum number of migrations expected to be pending at once on a database
+    /// which was already migrated.
+    ///
+    /// An upgrade usually only brings a handful of new migrations, so many pending migrations
+    /// can indicate that the migrator is pointed at another database than the one it manages.
+    /// A new database, where no migration was applied yet, is never checked.
+    pub fn set_max_pending_migrations(
+        &mut self,
+        max_pending_migrations: usize,
+        action: TooManyPendingMigrations,
+    ) {
+        self.max_pending_migrations = Some((max_pending_migrations, action));
+    }
+
+    /// Skip the migrations with the given versions, for example while the fix of a faulty
+    /// migration is being rolled out. They are not considered as pending, and are applied by
+    /// a migrator which doesn't skip them anymore.
+    ///
+    /// The migration fails if a version is not a known migration, or if a rust migration
+    /// which is not skipped depends on a skipped one, see [`RustMigration::depends_on`].
+    /// Sql migrations don't declare their dependencies: a skipped sql migration must not be
+    /// needed by the next sql migrations.
+    pub fn with_skipped_migrations(mut self, skipped_migrations: HashSet<Version>) -> Self {
+        self.skipped_migrations = skipped_migrations;
+        self
+    }
+
+    /// Set the features of the migrations which must be applied. A migration tagged with a
+    /// feature, see [`RustMigration::feature`] and [`Migrator::set_sql_migration_features`],
+    /// is only applied when that feature is enabled and is not considered as pending
+    /// otherwise. Untagged migrations are always applied.
+    ///
+    /// All the migrations are applied when no features are set.
+    pub fn set_enabled_features(&mut self, features: HashSet<String>) {
+        self.enabled_features = Some(features);
+    }
+
+    /// Tag sql migrations with a feature, see [`Migrator::set_enabled_features`]
+    pub fn set_sql_migration_features(&mut self, features: HashMap<Version, String>) -> Result<()> {
+        for version in features.keys() {
+            if !self
+                .sql_migrator
+                .iter()
+                .any(|m| Version(m.version) == *version)
+            {
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    format!("Can't tag the migration {version} which is not a known sql migration"),
+                ));
+            }
+        }
+
+        self.sql_migration_features = features;
+        Ok(())
+    }
 }
 
 enum Mode {
@@ -93,7 +211,7 @@ impl Migrator {
         up_to: Version,
     ) -> Result<bool> {
         let status = self
-            .run_migrations_impl(connection, up_to, Mode::DryRun)
+            .run_migrations_impl(connection, up_to, Mode::DryRun, &mut vec![])
             .await?;
         match status {
             MigrationStatus::UpToDate(_) => Ok(false),
@@ -113,9 +231,33 @@ impl Migrator {
         &self,
         connection: &mut AnyConnection,
         up_to: Version,
+        timings: &mut Vec<MigrationTiming>,
     ) -> Result<MigrationStatus> {
-        self.run_migrations_impl(connection, up_to, Mode::ApplyMigrations)
-            .await
+        Self::execute_statements(connection, &self.pre_migration_statements).await?;
+        let status = self
+            .run_migrations_impl(connection, up_to, Mode::ApplyMigrations, timings)
+            .await?;
+        Self::execute_statements(connection, &self.post_migration_statements).await?;
+
+        Ok(status)
+    }
+
+    async fn execute_statements(
+        connection: &mut AnyConnection,
+        statements: &[String],
+    ) -> Result<()> {
+        for statement in statements {
+            debug!("Executing sql statement around the migrations: {statement}");
+            connection.execute(statement.as_str()).await.map_err(|e| {
+                ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Io,
+                    format!("Failed to execute the statement '{statement}': {e:?}"),
+                )
+            })?;
+        }
+
+        Ok(())
     }
 
     async fn run_migrations_impl(
@@ -123,6 +265,7 @@ impl Migrator {
         connection: &mut AnyConnection,
         up_to: Version,
         mode: Mode,
+        timings: &mut Vec<MigrationTiming>,
     ) -> Result<MigrationStatus> {
         connection.ensure_migrations_table().await.into_core()?;
 
@@ -134,25 +277,7 @@ impl Migrator {
             ));
         }
 
-        let migrations = {
-            let sql_iterator = self.sql_migrator.migrations.iter().filter_map(|m| {
-                if Version(m.version) <= up_to {
-                    Some(NextMigration::Sql(m))
-                } else {
-                    None
-                }
-            });
-            let rust_iterator = self.rust_migrations.iter().filter_map(|m| {
-                if m.version() <= up_to {
-                    Some(NextMigration::Rust(m.as_ref()))
-                } else {
-                    None
-                }
-            });
-            let mut migrations: Vec<NextMigration> = sql_iterator.chain(rust_iterator).collect();
-            migrations.sort();
-            migrations
-        };
+        let migrations = self.migrations_up_to(up_to);
 
         // sqlx Migrator also optionally checks for missing migrations (ones that had been run and
         // marked as migrated in the db but now don't exist). Skipping that check for now.
@@ -202,10 +327,15 @@ impl Migrator {
                     match migration {
                         NextMigration::Sql(sql_migration) => {
                             match self
-                                .apply_sql_migration(sql_migration, connection, &applied_migrations)
+                                .apply_sql_migration(
+                                    sql_migration,
+                                    connection,
+                                    &applied_migrations,
+                                    timings,
+                                )
                                 .await?
                             {
-                                MigrationSuccess => (),
+                                MigrationSuccess => {}
                                 MigrationResult::MigrationFailure(failure) => {
                                     return Ok(MigrationStatus::Failed(
                                         Version(sql_migration.version),
@@ -216,10 +346,10 @@ impl Migrator {
                         }
                         NextMigration::Rust(rust_migration) => {
                             match self
-                                .apply_rust_migration(rust_migration, connection)
+                                .apply_rust_migration(rust_migration, connection, timings)
                                 .await?
                             {
-                                MigrationSuccess => (),
+                                MigrationSuccess => {}
                                 MigrationResult::MigrationFailure(failure) => {
                                     return Ok(MigrationStatus::Failed(
                                         rust_migration.version(),
@@ -236,6 +366,239 @@ impl Migrator {
         }
     }
 
+    /// Sql and Rust migrations up to the specified version (inclusive), in the order they
+    /// must be applied
+    fn migrations_up_to(&self, up_to: Version) -> Vec<NextMigration<'_>> {
+        let sql_iterator = self.sql_migrator.migrations.iter().filter_map(|m| {
+            if Version(m.version) <= up_to
+                && !self.skipped_migrations.contains(&Version(m.version))
+                && self.is_sql_migration_enabled(Version(m.version))
+            {
+                Some(NextMigration::Sql(m))
+            } else {
+                None
+            }
+        });
+        let rust_iterator = self.rust_migrations.iter().filter_map(|m| {
+            if m.version() <= up_to
+                && !self.skipped_migrations.contains(&m.version())
+                && self.is_feature_enabled(m.feature())
+            {
+                Some(NextMigration::Rust(m.as_ref()))
+            } else {
+                None
+            }
+        });
+        let mut migrations: Vec<NextMigration> = sql_iterator.chain(rust_iterator).collect();
+        migrations.sort();
+        migrations
+    }
+
+    /// Return true if migrations tagged with this feature must be applied,
+    /// see [`Migrator::set_enabled_features`]
+    fn is_feature_enabled(&self, feature: Option<&str>) -> bool {
+        match (feature, &self.enabled_features) {
+            (Some(feature), Some(enabled_features)) => enabled_features.contains(feature),
+            _ => true,
+        }
+    }
+
+    fn is_sql_migration_enabled(&self, version: Version) -> bool {
+        self.is_feature_enabled(
+            self.sql_migration_features
+                .get(&version)
+                .map(|f| f.as_str()),
+        )
+    }
+
+    /// Return an error if a migration which is applied depends on a migration
+    /// whose feature is disabled, see [`Migrator::set_enabled_features`]
+    fn check_disabled_features(&self) -> Result<()> {
+        for migration in &self.rust_migrations {
+            if !self.is_feature_enabled(migration.feature()) {
+                continue;
+            }
+            for version in migration.depends_on() {
+                let dependency = self
+                    .rust_migrations
+                    .iter()
+                    .find(|m| m.version() == version)
+                    .map(|m| m.feature().map(|f| f.to_string()))
+                    .unwrap_or_else(|| self.sql_migration_features.get(&version).cloned());
+                if let Some(feature) = dependency {
+                    if !self.is_feature_enabled(Some(&feature)) {
+                        return Err(ockam_core::Error::new(
+                            Origin::Node,
+                            Kind::Conflict,
+                            format!(
+                                "The migration {} ({}) depends on the migration {} whose feature '{}' is disabled",
+                                migration.name(),
+                                migration.version(),
+                                version,
+                                feature
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count the applied and pending migrations, without applying them
+    async fn summary_impl(
+        &self,
+        connection: &mut AnyConnection,
+        up_to: Version,
+    ) -> Result<MigrationSummary> {
+        connection.ensure_migrations_table().await.into_core()?;
+        let applied_migrations = connection.list_applied_migrations().await.into_core()?;
+        // The table recording the rust migrations is created by a sql migration
+        let has_rust_migrations_table = Self::has_table(connection, "_rust_migrations").await?;
+
+        let mut summary = MigrationSummary::default();
+        for migration in self.migrations_up_to(up_to) {
+            let (counts, needs_migration) = match migration {
+                NextMigration::Sql(sql_migration) => {
+                    if sql_migration.migration_type.is_down_migration() {
+                        continue;
+                    }
+                    let needs_migration = self
+                        .needs_sql_migration(sql_migration, connection, &applied_migrations)
+                        .await?;
+                    (&mut summary.sql, needs_migration)
+                }
+                NextMigration::Rust(rust_migration) => {
+                    let needs_migration = if has_rust_migrations_table {
+                        self.needs_rust_migration(rust_migration, connection, &applied_migrations)
+                            .await?
+                    } else {
+                        Self::applies_to_backend(rust_migration, connection)
+                    };
+                    (&mut summary.rust, needs_migration)
+                }
+            };
+            counts.known += 1;
+            if needs_migration {
+                counts.pending += 1;
+            } else {
+                counts.already_applied += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Return an error if a skipped migration is unknown, or is needed by a migration which
+    /// is not skipped, see [`Migrator::with_skipped_migrations`]
+    fn check_skipped_migrations(&self) -> Result<()> {
+        for version in &self.skipped_migrations {
+            let name = match self
+                .rust_migrations
+                .iter()
+                .find(|m| m.version() == *version)
+            {
+                Some(migration) => migration.name().to_string(),
+                None => match self
+                    .sql_migrator
+                    .iter()
+                    .find(|m| Version(m.version) == *version)
+                {
+                    Some(migration) => migration.description.to_string(),
+                    None => {
+                        return Err(ockam_core::Error::new(
+                            Origin::Node,
+                            Kind::NotFound,
+                            format!(
+                                "Can't skip the migration {version} which is not a known migration"
+                            ),
+                        ))
+                    }
+                },
+            };
+
+            let dependent = self.rust_migrations.iter().find(|m| {
+                !self.skipped_migrations.contains(&m.version()) && m.depends_on().contains(version)
+            });
+            if let Some(dependent) = dependent {
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Conflict,
+                    format!(
+                        "Can't skip the migration {} ({}) since the migration {} ({}) depends on it",
+                        name,
+                        version,
+                        dependent.name(),
+                        dependent.version()
+                    ),
+                ));
+            }
+
+            warn!("Skipping the migration {} ({})", name, version);
+        }
+
+        Ok(())
+    }
+
+    /// Return an error if the database was migrated by a more recent version of the code,
+    /// which most likely means that an older binary is being run against a newer schema
+    async fn check_no_downgrade(&self, connection: &mut AnyConnection) -> Result<()> {
+        connection.ensure_migrations_table().await.into_core()?;
+
+        let applied_migrations = connection.list_applied_migrations().await.into_core()?;
+        let last_applied_migration = applied_migrations.iter().map(|m| Version(m.version)).max();
+        let latest_known_version = self.latest_known_version();
+
+        match last_applied_migration {
+            Some(last_applied_migration) if last_applied_migration > latest_known_version => {
+                Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Conflict,
+                    format!(
+                        "The database was migrated up to version {} but the latest known migration version is {}. This is likely due to running an older binary against a database updated by a newer one",
+                        last_applied_migration, latest_known_version
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Warn, or return an error, if more migrations than expected are pending on a database
+    /// which was already migrated, see [`Migrator::set_max_pending_migrations`]
+    async fn check_pending_migrations(
+        &self,
+        connection: &mut AnyConnection,
+        up_to: Version,
+    ) -> Result<()> {
+        let Some((max_pending_migrations, action)) = self.max_pending_migrations else {
+            return Ok(());
+        };
+
+        let summary = self.summary_impl(connection, up_to).await?;
+        let pending = summary.pending();
+        if summary.already_applied() == 0 || pending <= max_pending_migrations {
+            return Ok(());
+        }
+
+        let message = format!(
+            "{} database migrations are pending while at most {} were expected ({}). Please check that the node is using the right database",
+            pending, max_pending_migrations, summary
+        );
+        match action {
+            TooManyPendingMigrations::Warn => {
+                warn!("{message}");
+                Ok(())
+            }
+            TooManyPendingMigrations::Fail => Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Conflict,
+                message,
+            )),
+        }
+    }
+
     async fn needs_sql_migration<'a>(
         &self,
         migration: &'a SqlxMigration,
@@ -271,6 +634,7 @@ impl Migrator {
         migration: &'a SqlxMigration,
         connection: &mut AnyConnection,
         applied_migrations: &[AppliedMigration],
+        timings: &mut Vec<MigrationTiming>,
     ) -> Result<MigrationResult> {
         if migration.migration_type.is_down_migration() {
             return Ok(MigrationResult::down_migration());
@@ -291,17 +655,25 @@ impl Migrator {
                         ),
                     ))
                 } else {
-                    Ok(MigrationSuccess)
+                    Ok(MigrationResult::success())
                 }
             }
-            None => match connection.apply(migration).await.into_core() {
-                Ok(_) => Ok(MigrationSuccess),
+            None => match connection.apply(migration).await {
+                Ok(duration) => {
+                    timings.push(MigrationTiming {
+                        version: Version(migration.version),
+                        name: migration.description.to_string(),
+                        duration,
+                    });
+                    Ok(MigrationResult::success())
+                }
                 Err(e) => Err(ockam_core::Error::new(
                     Origin::Node,
                     Kind::Conflict,
-                    format!(
-                        "Failed to run the migration {}: {e:?}",
-                        migration.description
+                    SqlMigrationError::new(
+                        Version(migration.version),
+                        migration.description.to_string(),
+                        e,
                     ),
                 )),
             },
@@ -314,14 +686,37 @@ impl Migrator {
         connection: &mut AnyConnection,
         _applied_migrations: &[AppliedMigration],
     ) -> Result<bool> {
+        if !Self::applies_to_backend(migration, connection) {
+            return Ok(false);
+        }
         Ok(!self.has_migrated(connection, migration.name()).await?)
     }
 
+    /// Return true if the rust migration must be executed on the backend of the connection
+    fn applies_to_backend(migration: &dyn RustMigration, connection: &AnyConnection) -> bool {
+        migration
+            .applies_to()
+            .contains_backend_name(connection.backend_name())
+    }
+
     async fn apply_rust_migration(
         &self,
         migration: &dyn RustMigration,
         connection: &mut AnyConnection,
+        timings: &mut Vec<MigrationTiming>,
     ) -> Result<MigrationResult> {
+        if !Self::applies_to_backend(migration, connection) {
+            debug!(
+                "Skipping the rust migration {} which doesn't apply to {}",
+                migration.name(),
+                connection.backend_name()
+            );
+            return Ok(MigrationResult::success());
+        }
+
+        let start = Instant::now();
+        let mut applied = false;
+
         // If we are migrating data from a legacy sqlite db, we check the SQLite database
         // to know if that import happened already
         if migration.name() == InitializeFromSqlite::name() {
@@ -336,6 +731,7 @@ impl Migrator {
                         .await?;
                     self.mark_as_migrated(&mut sqlite_connection, migration.name())
                         .await?;
+                    applied = true;
                 };
             };
         } else {
@@ -346,9 +742,34 @@ impl Migrator {
                     .migrate(self.legacy_sqlite_database.clone(), connection)
                     .await?;
                 self.mark_as_migrated(connection, migration.name()).await?;
+                applied = true;
             }
         };
-        Ok(MigrationSuccess)
+
+        if applied {
+            timings.push(MigrationTiming {
+                version: migration.version(),
+                name: migration.name().to_string(),
+                duration: start.elapsed(),
+            });
+        }
+        Ok(MigrationResult::success())
+    }
+
+    /// Return true if the table with the given name exists
+    async fn has_table(connection: &mut AnyConnection, table_name: &str) -> Result<bool> {
+        let statement = if connection.backend_name() == "SQLite" {
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = $1"
+        } else {
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = $1"
+        };
+        let row: AnyRow = query(statement)
+            .bind(table_name)
+            .fetch_one(&mut *connection)
+            .await
+            .into_core()?;
+        let count: i64 = row.get(0);
+        Ok(count != 0)
     }
 
     async fn has_migrated(
@@ -390,6 +811,17 @@ impl Migrator {
 
         Ok(())
     }
+
+    async fn unmark_as_migrated(
+        &self,
+        connection: &mut AnyConnection,
+        migration_name: &str,
+    ) -> Result<()> {
+        let query = query("DELETE FROM _rust_migrations WHERE name = $1").bind(migration_name);
+        query.execute(&mut *connection).await.void()?;
+
+        Ok(())
+    }
 }
 
 impl Migrator {
@@ -399,13 +831,31 @@ impl Migrator {
         pool: &Pool<Any>,
         up_to: Version,
     ) -> Result<MigrationStatus> {
+        Ok(self.migrate_up_to_with_timings(pool, up_to).await?.0)
+    }
+
+    /// Run migrations up to the specified version (inclusive) and return the time it took
+    /// to execute each migration that was applied
+    pub(crate) async fn migrate_up_to_with_timings(
+        &self,
+        pool: &Pool<Any>,
+        up_to: Version,
+    ) -> Result<(MigrationStatus, Vec<MigrationTiming>)> {
+        self.check_skipped_migrations()?;
+        self.check_disabled_features()?;
+
         let mut connection = pool.acquire().await.into_core()?;
 
+        self.check_no_downgrade(&mut connection).await?;
+
         if !self.needs_migration(&mut connection, up_to).await? {
             debug!("No database migrations was required");
-            return Ok(MigrationStatus::UpToDate(up_to));
+            return Ok((MigrationStatus::UpToDate(up_to), vec![]));
         }
 
+        self.check_pending_migrations(&mut connection, up_to)
+            .await?;
+
         let is_sqlite = connection.backend_name() == "SQLite";
         if is_sqlite {
             debug!("Migrating SQLite database with exclusive locking");
@@ -418,21 +868,41 @@ impl Migrator {
             connection.lock().await.into_core()?;
         };
 
-        let result = self.run_migrations(&mut connection, up_to).await;
+        // Another node may have migrated the database while we were waiting for the lock
+        let mut timings = vec![];
+        let result = match self.needs_migration(&mut connection, up_to).await {
+            Ok(true) => {
+                self.run_migrations(&mut connection, up_to, &mut timings)
+                    .await
+            }
+            Ok(false) => {
+                debug!("The database was migrated while waiting for the migration lock");
+                Ok(MigrationStatus::UpToDate(up_to))
+            }
+            Err(e) => Err(e),
+        };
+        for timing in &timings {
+            debug!("Applied database migration {timing}");
+        }
+
         if is_sqlite {
             debug!("Migration completed, unlocking database");
             // This is not enough to unlock the database, according to the documentation,
             // we also need an arbitrary read or write operation to release the
-            // exclusive lock.
-            // In practice, we are closing the connection to release the lock.
+            // exclusive lock. Otherwise, the connection returned to the pool keeps the lock
+            // and the next connections can't be configured.
             connection
                 .execute("PRAGMA locking_mode = NORMAL;")
                 .await
                 .into_core()?;
+            connection
+                .execute("SELECT count(*) FROM sqlite_master;")
+                .await
+                .into_core()?;
         } else {
             connection.unlock().await.into_core()?;
         }
-        result
+        Ok((result?, timings))
     }
 
     /// Run all migrations
@@ -440,12 +910,233 @@ impl Migrator {
         self.migrate_up_to(pool, Version::MAX).await
     }
 
+    /// Run all migrations and return the time it took to execute each migration that was applied
+    pub async fn migrate_with_timings(
+        &self,
+        pool: &Pool<Any>,
+    ) -> Result<(MigrationStatus, Vec<MigrationTiming>)> {
+        self.migrate_up_to_with_timings(pool, Version::MAX).await
+    }
+
     /// Return the migration status
     pub async fn migration_status(&self, pool: &Pool<Any>) -> Result<MigrationStatus> {
         let mut connection = pool.acquire().await.into_core()?;
-        self.run_migrations_impl(&mut connection, Version::MAX, Mode::DryRun)
+        self.run_migrations_impl(&mut connection, Version::MAX, Mode::DryRun, &mut vec![])
             .await
     }
+
+    /// Return the number of known, applied and pending migrations, by kind
+    pub async fn summary(&self, pool: &Pool<Any>) -> Result<MigrationSummary> {
+        let mut connection = pool.acquire().await.into_core()?;
+        self.summary_impl(&mut connection, Version::MAX).await
+    }
+
+    /// Run all migrations and return a summary of the migrations which were already applied,
+    /// and of the ones applied by this call
+    ///
+    /// The migrations applied by this call are counted by comparing the state of the database
+    /// before and after the migration, they include the migrations applied concurrently by
+    /// another node.
+    pub async fn migrate_with_summary(
+        &self,
+        pool: &Pool<Any>,
+    ) -> Result<(MigrationStatus, MigrationSummary)> {
+        let before = self.summary(pool).await?;
+        let status = self.migrate(pool).await?;
+        let after = self.summary(pool).await?;
+
+        Ok((status, MigrationSummary::between(&before, &after)))
+    }
+
+    /// Return true if the Rust migration with the given name was applied to the database
+    ///
+    /// This is a read-only query, meant for tooling and health checks. The table recording
+    /// the Rust migrations must exist, which is the case once the database was migrated.
+    pub async fn has_run_migration(&self, pool: &Pool<Any>, migration_name: &str) -> Result<bool> {
+        let mut connection = pool.acquire().await.into_core()?;
+        self.has_migrated(&mut connection, migration_name).await
+    }
+
+    /// Execute again a Rust migration which was already applied, for example when its effect
+    /// was partially rolled back out-of-band
+    ///
+    /// Only the migrations declared as idempotent, see [`RustMigration::is_idempotent`],
+    /// can be executed again.
+    pub async fn rerun_rust_migration(&self, pool: &Pool<Any>, migration_name: &str) -> Result<()> {
+        let migration = self
+            .rust_migrations
+            .iter()
+            .find(|m| m.name() == migration_name)
+            .ok_or_else(|| {
+                ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    format!("Unknown rust migration '{migration_name}'"),
+                )
+            })?;
+
+        if !migration.is_idempotent() {
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                format!("The rust migration '{migration_name}' can't be executed again since it isn't idempotent"),
+            ));
+        }
+
+        let mut connection = pool.acquire().await.into_core()?;
+        let is_sqlite = connection.backend_name() == "SQLite";
+        if !is_sqlite {
+            // Don't interfere with a node migrating the database
+            connection.lock().await.into_core()?;
+        }
+
+        // The import from a legacy sqlite database is tracked in that database
+        let result = match &self.legacy_sqlite_database {
+            Some(sqlite_db) if migration_name == InitializeFromSqlite::name() => {
+                let mut sqlite_connection = sqlite_db.pool.acquire().await.into_core()?;
+                self.unmark_as_migrated(&mut sqlite_connection, migration_name)
+                    .await
+            }
+            _ => {
+                self.unmark_as_migrated(&mut connection, migration_name)
+                    .await
+            }
+        };
+        let result = match result {
+            Ok(()) => {
+                info!("Executing the rust migration '{migration_name}' again");
+                self.apply_rust_migration(migration.as_ref(), &mut connection, &mut vec![])
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        if !is_sqlite {
+            connection.unlock().await.into_core()?;
+        }
+
+        match result? {
+            MigrationResult::MigrationFailure(failure) => Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Internal,
+                format!("The rust migration '{migration_name}' failed: {failure}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the database is up to date without applying any migration.
+    ///
+    /// This is meant for read-only consumers of a database owned by another node:
+    /// no migration is run, no table is created and no lock is taken. An error is returned if
+    /// some migrations are still pending or if a previous migration failed.
+    pub async fn verify_only(&self, pool: &Pool<Any>) -> Result<()> {
+        let mut connection = pool.acquire().await.into_core()?;
+
+        // No migration was ever applied to this database
+        if !Self::has_table(&mut connection, "_sqlx_migrations").await? {
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Conflict,
+                "The database schema is not up to date. All the migrations are pending",
+            ));
+        }
+
+        self.check_no_downgrade(&mut connection).await?;
+
+        let status = self
+            .run_migrations_impl(&mut connection, Version::MAX, Mode::DryRun, &mut vec![])
+            .await?;
+        match status {
+            MigrationStatus::UpToDate(_) => Ok(()),
+            MigrationStatus::Todo(_, _) | MigrationStatus::Failed(_, _) => {
+                Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Conflict,
+                    format!("The database schema is not up to date. {status}"),
+                ))
+            }
+        }
+    }
+}
+
+impl Migrator {
+    /// Checksums of the sql migrations, as hexadecimal strings, sorted by version.
+    ///
+    /// The checksum of an applied migration is stored in the database and must not change
+    /// afterwards, see [`Self::verify_checksums`].
+    pub fn expected_checksums(&self) -> Vec<(Version, String)> {
+        let mut checksums: Vec<(Version, String)> = self
+            .sql_migrator
+            .iter()
+            .map(|m| {
+                let checksum = m.checksum.iter().fold(String::new(), |mut checksum, b| {
+                    let _ = write!(checksum, "{b:02x}");
+                    checksum
+                });
+                (Version(m.version), checksum)
+            })
+            .collect();
+        checksums.sort();
+        checksums
+    }
+
+    /// Format the checksums of the sql migrations as a baseline for [`Self::verify_checksums`],
+    /// with one `<version> <checksum>` line per migration
+    pub fn checksums_baseline(&self) -> String {
+        self.expected_checksums().into_iter().fold(
+            String::new(),
+            |mut baseline, (version, checksum)| {
+                let _ = writeln!(baseline, "{version} {checksum}");
+                baseline
+            },
+        )
+    }
+
+    /// Check that the sql migrations of a committed baseline, see [`Self::checksums_baseline`],
+    /// were neither modified nor removed.
+    ///
+    /// This is meant to be asserted in a test, so that an edit to a migration which was
+    /// already released is detected before it fails on the databases of the users.
+    /// The migrations which are not in the baseline yet are new ones, and are accepted.
+    pub fn verify_checksums(&self, baseline: &str) -> Result<()> {
+        let checksums = self.expected_checksums();
+
+        let mut errors = vec![];
+        for line in baseline.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (version, expected) = line
+                .split_once(' ')
+                .and_then(|(version, checksum)| {
+                    Some((Version(version.parse().ok()?), checksum.trim()))
+                })
+                .ok_or_else(|| {
+                    ockam_core::Error::new(
+                        Origin::Node,
+                        Kind::Invalid,
+                        format!("Invalid checksums baseline line: {line}"),
+                    )
+                })?;
+
+            match checksums.iter().find(|(v, _)| *v == version) {
+                Some((_, actual)) if actual == expected => {}
+                Some(_) => errors.push(format!("migration {version} was modified")),
+                None => errors.push(format!("migration {version} was removed")),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Conflict,
+                format!(
+                    "Released migrations must not be changed: {}",
+                    errors.join(", ")
+                ),
+            ))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -533,8 +1224,645 @@ impl PartialOrd for NextMigration<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::node_migration_set::NodeMigrationSet;
+    use crate::database::{BackendSet, DatabaseConfiguration, DatabaseType, MigrationSet};
     use ockam_core::async_trait;
     use sqlx::migrate::MigrationType;
+    use std::borrow::Cow;
+    use std::error::Error as _;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn migrate_with_older_migrator_should_fail() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        migration_set.create_migrator()?.migrate(&db.pool).await?;
+
+        // simulate an older binary which only knows about the first sql migration
+        let mut older_migrator = migration_set.create_migrator()?;
+        let first_migration = older_migrator.sql_migrator.migrations[0].clone();
+        older_migrator.sql_migrator.migrations = Cow::Owned(vec![first_migration]);
+        older_migrator.rust_migrations = vec![];
+
+        let result = older_migrator.migrate(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Conflict);
+
+        Ok(())
+    }
+
+    #[test]
+    fn modified_or_removed_migrations_should_fail_the_checksums_verification() -> Result<()> {
+        let migrator = NodeMigrationSet::new(DatabaseType::Sqlite).create_migrator()?;
+        let checksums = migrator.expected_checksums();
+        assert!(checksums.windows(2).all(|w| w[0].0 < w[1].0));
+
+        // the current migrations match their own baseline
+        let baseline = migrator.checksums_baseline();
+        migrator.verify_checksums(&baseline)?;
+
+        // a migration which is not released yet is not in the baseline
+        let (last, _) = baseline.trim_end().rsplit_once('\n').unwrap();
+        migrator.verify_checksums(last)?;
+
+        // a released migration was edited
+        let (version, checksum) = &checksums[0];
+        let modified = baseline.replace(checksum, &"0".repeat(checksum.len()));
+        let error = migrator.verify_checksums(&modified).unwrap_err();
+        assert_eq!(error.code().kind, Kind::Conflict);
+        assert!(error
+            .to_string()
+            .contains(&format!("{version} was modified")));
+
+        // a released migration was removed
+        let removed = format!("{baseline}1 {checksum}\n");
+        let error = migrator.verify_checksums(&removed).unwrap_err();
+        assert!(error.to_string().contains("1 was removed"));
+
+        assert!(migrator.verify_checksums("not a baseline").is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn verify_only_should_not_migrate() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let migrator = migration_set.create_migrator()?;
+
+        // pending migrations are reported as an error and are not applied
+        let result = migrator.verify_only(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Conflict);
+        let mut connection = db.pool.acquire().await.into_core()?;
+        assert!(!Migrator::has_table(&mut connection, "_sqlx_migrations").await?);
+        drop(connection);
+        assert!(matches!(
+            migrator.migration_status(&db.pool).await?,
+            MigrationStatus::Todo(_, _)
+        ));
+
+        migrator.migrate(&db.pool).await?;
+        migrator.verify_only(&db.pool).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn too_many_pending_migrations_should_be_reported() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+
+        // a new database is not checked
+        let mut migrator = migration_set.create_migrator()?;
+        migrator.set_max_pending_migrations(0, TooManyPendingMigrations::Fail);
+        let first_version = Version(migrator.sql_migrator.migrations[0].version);
+        migrator.migrate_up_to(&db.pool, first_version).await?;
+
+        // a database which is far behind the code is rejected, and left untouched
+        let result = migrator.migrate(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Conflict);
+        assert_eq!(migrator.summary(&db.pool).await?.already_applied(), 1);
+
+        // or only reported, if configured so
+        migrator.set_max_pending_migrations(0, TooManyPendingMigrations::Warn);
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrate_with_summary_should_count_sql_and_rust_migrations() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let migrator = migration_set.create_migrator()?;
+        let sql_count = migrator
+            .sql_migrator
+            .migrations
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .count();
+        let rust_count = migrator.rust_migrations.len();
+
+        let summary = migrator.summary(&db.pool).await?;
+        assert_eq!(summary.sql.known, sql_count);
+        assert_eq!(summary.sql.pending, sql_count);
+        assert_eq!(summary.rust.known, rust_count);
+        assert_eq!(summary.rust.pending, rust_count);
+        assert_eq!(summary.applied_now(), 0);
+
+        let (status, summary) = migrator.migrate_with_summary(&db.pool).await?;
+        assert!(status.up_to_date());
+        assert_eq!(summary.known(), sql_count + rust_count);
+        assert_eq!(summary.applied_now(), sql_count + rust_count);
+        assert_eq!(summary.sql.already_applied, 0);
+        assert_eq!(summary.sql.pending, 0);
+        assert_eq!(summary.rust.applied_now, rust_count);
+
+        // Nothing is left to apply on a second run
+        let (_, summary) = migrator.migrate_with_summary(&db.pool).await?;
+        assert_eq!(summary.applied_now(), 0);
+        assert_eq!(summary.sql.already_applied, sql_count);
+        assert_eq!(summary.rust.already_applied, rust_count);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn has_run_migration_should_report_applied_rust_migrations() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let migrator = migration_set.create_migrator()?;
+        let migration_name = migrator.rust_migrations[0].name().to_string();
+
+        migrator.migrate(&db.pool).await?;
+        assert!(
+            migrator
+                .has_run_migration(&db.pool, &migration_name)
+                .await?
+        );
+        assert!(
+            !migrator
+                .has_run_migration(&db.pool, "UnknownMigration")
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rerun_rust_migration_should_only_rerun_idempotent_migrations() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        let idempotent_runs = Arc::new(AtomicUsize::new(0));
+        let other_runs = Arc::new(AtomicUsize::new(0));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "IdempotentMigration",
+                version: Version(i64::MAX - 2),
+                idempotent: true,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![],
+                feature: None,
+                runs: idempotent_runs.clone(),
+            }));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "OtherMigration",
+                version: Version(i64::MAX - 1),
+                idempotent: false,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![],
+                feature: None,
+                runs: other_runs.clone(),
+            }));
+
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(idempotent_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(other_runs.load(Ordering::Relaxed), 1);
+
+        migrator
+            .rerun_rust_migration(&db.pool, "IdempotentMigration")
+            .await?;
+        assert_eq!(idempotent_runs.load(Ordering::Relaxed), 2);
+        assert!(
+            migrator
+                .has_run_migration(&db.pool, "IdempotentMigration")
+                .await?
+        );
+
+        let error = migrator
+            .rerun_rust_migration(&db.pool, "OtherMigration")
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::Invalid);
+        assert_eq!(other_runs.load(Ordering::Relaxed), 1);
+
+        let error = migrator
+            .rerun_rust_migration(&db.pool, "UnknownMigration")
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::NotFound);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rust_migrations_should_only_run_on_their_backends() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        let sqlite_runs = Arc::new(AtomicUsize::new(0));
+        let postgres_runs = Arc::new(AtomicUsize::new(0));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "SqliteMigration",
+                version: Version(i64::MAX - 2),
+                idempotent: false,
+                applies_to: BackendSet::SQLITE,
+                depends_on: vec![],
+                feature: None,
+                runs: sqlite_runs.clone(),
+            }));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "PostgresMigration",
+                version: Version(i64::MAX - 1),
+                idempotent: false,
+                applies_to: BackendSet::POSTGRES,
+                depends_on: vec![],
+                feature: None,
+                runs: postgres_runs.clone(),
+            }));
+
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(sqlite_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(postgres_runs.load(Ordering::Relaxed), 0);
+        assert!(
+            !migrator
+                .has_run_migration(&db.pool, "PostgresMigration")
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skipped_rust_migrations_should_not_be_applied() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        let skipped_runs = Arc::new(AtomicUsize::new(0));
+        let dependent_runs = Arc::new(AtomicUsize::new(0));
+        let skipped_version = Version(i64::MAX - 2);
+        let dependent_version = Version(i64::MAX - 1);
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "SkippedMigration",
+                version: skipped_version,
+                idempotent: false,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![],
+                feature: None,
+                runs: skipped_runs.clone(),
+            }));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "DependentMigration",
+                version: dependent_version,
+                idempotent: false,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![skipped_version],
+                feature: None,
+                runs: dependent_runs.clone(),
+            }));
+
+        // a migration needed by another one can't be skipped alone
+        migrator = migrator.with_skipped_migrations(HashSet::from([skipped_version]));
+        let result = migrator.migrate(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Conflict);
+
+        // unknown versions are rejected
+        migrator = migrator.with_skipped_migrations(HashSet::from([Version(1)]));
+        let result = migrator.migrate(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::NotFound);
+        assert_eq!(skipped_runs.load(Ordering::Relaxed), 0);
+
+        // the skipped migrations are neither applied nor pending
+        migrator =
+            migrator.with_skipped_migrations(HashSet::from([skipped_version, dependent_version]));
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(skipped_runs.load(Ordering::Relaxed), 0);
+        assert_eq!(dependent_runs.load(Ordering::Relaxed), 0);
+        assert!(migrator.migration_status(&db.pool).await?.up_to_date());
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 0);
+
+        // they are applied once they are not skipped anymore
+        migrator = migrator.with_skipped_migrations(HashSet::new());
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 2);
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(skipped_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(dependent_runs.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skipped_sql_migrations_should_not_be_applied() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        let last_sql_version = migrator
+            .sql_migrator
+            .iter()
+            .map(|m| Version(m.version))
+            .max()
+            .unwrap();
+        let dependent_runs = Arc::new(AtomicUsize::new(0));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "DependentMigration",
+                version: Version(i64::MAX - 1),
+                idempotent: false,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![last_sql_version],
+                feature: None,
+                runs: dependent_runs.clone(),
+            }));
+
+        // a sql migration needed by a rust migration can't be skipped
+        migrator = migrator.with_skipped_migrations(HashSet::from([last_sql_version]));
+        let result = migrator.migrate(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Conflict);
+
+        // the skipped sql migration is neither applied nor pending
+        migrator.rust_migrations.pop();
+        migrator.migrate(&db.pool).await?;
+        assert!(migrator.migration_status(&db.pool).await?.up_to_date());
+        let mut connection = db.pool.acquire().await.into_core()?;
+        let applied_migrations = connection.list_applied_migrations().await.into_core()?;
+        assert!(!applied_migrations
+            .iter()
+            .any(|m| Version(m.version) == last_sql_version));
+        drop(connection);
+
+        // it is applied once it is not skipped anymore
+        migrator = migrator.with_skipped_migrations(HashSet::new());
+        assert_eq!(migrator.summary(&db.pool).await?.sql.pending, 1);
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 0);
+        assert_eq!(dependent_runs.load(Ordering::Relaxed), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrations_of_disabled_features_should_not_be_applied() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        let tagged_runs = Arc::new(AtomicUsize::new(0));
+        let dependent_runs = Arc::new(AtomicUsize::new(0));
+        let tagged_version = Version(i64::MAX - 2);
+        let dependent_version = Version(i64::MAX - 1);
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "TaggedMigration",
+                version: tagged_version,
+                idempotent: false,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![],
+                feature: Some("experimental"),
+                runs: tagged_runs.clone(),
+            }));
+        migrator
+            .rust_migrations
+            .push(Box::new(CountingRustMigration {
+                name: "DependentMigration",
+                version: dependent_version,
+                idempotent: false,
+                applies_to: BackendSet::ALL,
+                depends_on: vec![tagged_version],
+                feature: None,
+                runs: dependent_runs.clone(),
+            }));
+
+        // an untagged migration can't depend on a migration of a disabled feature
+        migrator.set_enabled_features(HashSet::new());
+        let result = migrator.migrate(&db.pool).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Conflict);
+
+        // unknown sql versions can't be tagged
+        let result = migrator
+            .set_sql_migration_features(HashMap::from([(Version(1), "experimental".into())]));
+        assert_eq!(result.unwrap_err().code().kind, Kind::NotFound);
+
+        // the migrations of a disabled feature are neither applied nor pending
+        migrator.rust_migrations.pop();
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(tagged_runs.load(Ordering::Relaxed), 0);
+        assert!(migrator.migration_status(&db.pool).await?.up_to_date());
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 0);
+
+        // they are applied once the feature is enabled
+        migrator.set_enabled_features(HashSet::from(["experimental".to_string()]));
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 1);
+        migrator.migrate(&db.pool).await?;
+        assert_eq!(tagged_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(dependent_runs.load(Ordering::Relaxed), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sql_migrations_of_disabled_features_should_be_applied_once_enabled() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        // a tagged migration older than all the other migrations
+        let tagged_version = Version(1);
+        migrator
+            .sql_migrator
+            .migrations
+            .to_mut()
+            .push(SqlxMigration::new(
+                tagged_version.0,
+                "experimental table".into(),
+                MigrationType::Simple,
+                "CREATE TABLE experimental (name TEXT NOT NULL)".into(),
+                false,
+            ));
+        migrator.set_sql_migration_features(HashMap::from([(
+            tagged_version,
+            "experimental".to_string(),
+        )]))?;
+
+        // the migration of a disabled feature is neither applied nor pending
+        migrator.set_enabled_features(HashSet::new());
+        migrator.migrate(&db.pool).await?;
+        assert!(migrator.migration_status(&db.pool).await?.up_to_date());
+        assert_eq!(migrator.summary(&db.pool).await?.pending(), 0);
+        let mut connection = db.pool.acquire().await.into_core()?;
+        assert!(!Migrator::has_table(&mut connection, "experimental").await?);
+        drop(connection);
+
+        // it is applied once the feature is enabled, after the more recent migrations
+        migrator.set_enabled_features(HashSet::from(["experimental".to_string()]));
+        assert_eq!(migrator.summary(&db.pool).await?.sql.pending, 1);
+        migrator.migrate(&db.pool).await?;
+        assert!(migrator.migration_status(&db.pool).await?.up_to_date());
+        let mut connection = db.pool.acquire().await.into_core()?;
+        assert!(Migrator::has_table(&mut connection, "experimental").await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pre_and_post_migration_statements_should_be_executed() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        migrator.set_pre_migration_statements(vec![
+            "CREATE TABLE migration_hooks (name TEXT NOT NULL)".to_string(),
+            "INSERT INTO migration_hooks (name) VALUES ('pre')".to_string(),
+        ]);
+        migrator.set_post_migration_statements(vec![
+            "INSERT INTO migration_hooks (name) VALUES ('post')".to_string(),
+        ]);
+        migrator.migrate(&db.pool).await?;
+
+        let rows = query("SELECT name FROM migration_hooks")
+            .fetch_all(&*db.pool)
+            .await
+            .into_core()?;
+        let names: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+        assert_eq!(names, vec!["pre", "post"]);
+
+        // the statements are not executed when there is nothing to migrate
+        migrator.migrate(&db.pool).await?;
+        let rows = query("SELECT name FROM migration_hooks")
+            .fetch_all(&*db.pool)
+            .await
+            .into_core()?;
+        assert_eq!(rows.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_migrations_should_only_migrate_once() -> Result<()> {
+        // The migration lock is only effective for Postgres
+        let Some(configuration) = DatabaseConfiguration::postgres()? else {
+            return Ok(());
+        };
+        let db = SqlxDatabase::create_no_migration(&configuration).await?;
+        db.drop_all_postgres_tables().await?;
+        query("CREATE TABLE migration_runs (run INTEGER NOT NULL)")
+            .execute(&*db.pool)
+            .await
+            .void()?;
+
+        let mut handles = vec![];
+        for run in 0..5 {
+            let pool = db.pool.clone();
+            handles.push(tokio::spawn(async move {
+                let migration_set = NodeMigrationSet::new(DatabaseType::Postgres);
+                let mut migrator = migration_set.create_migrator()?;
+                migrator.set_post_migration_statements(vec![format!(
+                    "INSERT INTO migration_runs (run) VALUES ({run})"
+                )]);
+                migrator.migrate(&pool).await
+            }));
+        }
+        for handle in handles {
+            let status = handle.await.unwrap()?;
+            assert!(matches!(status, MigrationStatus::UpToDate(_)));
+        }
+
+        let rows = query("SELECT run FROM migration_runs")
+            .fetch_all(&*db.pool)
+            .await
+            .into_core()?;
+        assert_eq!(rows.len(), 1);
+
+        db.drop_all_postgres_tables().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failing_pre_migration_statement_should_fail_the_migration() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        migrator.set_pre_migration_statements(vec!["NOT A SQL STATEMENT".to_string()]);
+
+        assert!(migrator.migrate(&db.pool).await.is_err());
+        assert!(matches!(
+            migrator.migration_status(&db.pool).await?,
+            MigrationStatus::Todo(_, _)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failing_sql_migration_should_keep_the_sqlx_error() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migration_set = NodeMigrationSet::new(DatabaseType::Sqlite);
+        let mut migrator = migration_set.create_migrator()?;
+        let mut migrations = migrator.sql_migrator.migrations.to_vec();
+        migrations.push(SqlxMigration::new(
+            i64::MAX - 1,
+            "invalid_migration".into(),
+            MigrationType::Simple,
+            "NOT A SQL STATEMENT".into(),
+            false,
+        ));
+        migrator.sql_migrator.migrations = Cow::Owned(migrations);
+
+        let error = migrator.migrate(&db.pool).await.unwrap_err();
+        let migration_error = error
+            .source()
+            .and_then(|e| e.downcast_ref::<SqlMigrationError>())
+            .unwrap();
+        assert_eq!(migration_error.version(), Version(i64::MAX - 1));
+        assert_eq!(migration_error.description(), "invalid_migration");
+        assert!(migration_error.is_rejected_by_database());
+
+        Ok(())
+    }
 
     #[test]
     fn ordering_of_migrations() {
@@ -605,4 +1933,51 @@ mod tests {
             Ok(())
         }
     }
+
+    #[derive(Debug)]
+    struct CountingRustMigration {
+        name: &'static str,
+        version: Version,
+        idempotent: bool,
+        applies_to: BackendSet,
+        depends_on: Vec<Version>,
+        feature: Option<&'static str>,
+        runs: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl RustMigration for CountingRustMigration {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn version(&self) -> Version {
+            self.version
+        }
+
+        fn is_idempotent(&self) -> bool {
+            self.idempotent
+        }
+
+        fn applies_to(&self) -> BackendSet {
+            self.applies_to
+        }
+
+        fn depends_on(&self) -> Vec<Version> {
+            self.depends_on.clone()
+        }
+
+        fn feature(&self) -> Option<&str> {
+            self.feature
+        }
+
+        async fn migrate(
+            &self,
+            _legacy_sqlite_database: Option<SqlxDatabase>,
+            _connection: &mut AnyConnection,
+        ) -> Result<()> {
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use core::fmt::Debug;
 use sqlx::AnyConnection;
 
-use crate::database::{SqlxDatabase, Version};
+use crate::database::{DatabaseType, SqlxDatabase, Version};
 use ockam_core::{async_trait, Result};
 
 /// Individual rust migration
@@ -13,6 +13,30 @@ pub trait RustMigration: Debug + Send + Sync {
     /// Version if format "yyyymmddnumber"
     fn version(&self) -> Version;
 
+    /// Return true if the migration can safely be executed again on a database where it
+    /// was already applied, see [`Migrator::rerun_rust_migration`](crate::database::Migrator::rerun_rust_migration)
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+
+    /// Versions of the migrations which must be applied for this migration to work. They
+    /// can't be skipped, see [`Migrator::with_skipped_migrations`](crate::database::Migrator::with_skipped_migrations)
+    fn depends_on(&self) -> Vec<Version> {
+        vec![]
+    }
+
+    /// Database backends this migration must be executed on. The migration is skipped on
+    /// the other backends
+    fn applies_to(&self) -> BackendSet {
+        BackendSet::ALL
+    }
+
+    /// Feature this migration belongs to. A tagged migration is only applied when its
+    /// feature is enabled, see [`Migrator::set_enabled_features`](crate::database::Migrator::set_enabled_features)
+    fn feature(&self) -> Option<&str> {
+        None
+    }
+
     /// Execute the migration
     async fn migrate(
         &self,
@@ -20,3 +44,48 @@ pub trait RustMigration: Debug + Send + Sync {
         connection: &mut AnyConnection,
     ) -> Result<()>;
 }
+
+/// Set of database backends, see [`RustMigration::applies_to`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackendSet {
+    sqlite: bool,
+    postgres: bool,
+}
+
+impl BackendSet {
+    /// All the database backends
+    pub const ALL: BackendSet = BackendSet {
+        sqlite: true,
+        postgres: true,
+    };
+
+    /// SQLite only
+    pub const SQLITE: BackendSet = BackendSet {
+        sqlite: true,
+        postgres: false,
+    };
+
+    /// Postgres only
+    pub const POSTGRES: BackendSet = BackendSet {
+        sqlite: false,
+        postgres: true,
+    };
+
+    /// Return true if the set contains the given type of database
+    pub fn contains(&self, database_type: &DatabaseType) -> bool {
+        match database_type {
+            DatabaseType::Sqlite => self.sqlite,
+            DatabaseType::Postgres => self.postgres,
+        }
+    }
+
+    /// Return true if the set contains the backend with the given name, as returned by
+    /// `AnyConnection::backend_name`. Unknown backends are only part of [`Self::ALL`]
+    pub(crate) fn contains_backend_name(&self, backend_name: &str) -> bool {
+        match backend_name {
+            "SQLite" => self.sqlite,
+            "PostgreSQL" => self.postgres,
+            _ => *self == Self::ALL,
+        }
+    }
+}
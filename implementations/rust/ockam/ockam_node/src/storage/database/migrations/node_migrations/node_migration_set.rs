@@ -93,4 +93,41 @@ mod tests {
 
         Ok(())
     }
+
+    /// If this test fails because a migration was added, append the new line of
+    /// `Migrator::checksums_baseline` to the baseline file. Released migrations must not be
+    /// edited, a new migration must be added instead.
+    #[test]
+    fn test_checksums() -> Result<()> {
+        NodeMigrationSet::new(DatabaseType::Sqlite)
+            .create_migrator()?
+            .verify_checksums(include_str!("./sql/sqlite_checksums.txt"))?;
+        NodeMigrationSet::new(DatabaseType::Postgres)
+            .create_migrator()?
+            .verify_checksums(include_str!("./sql/postgres_checksums.txt"))?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timings() -> Result<()> {
+        let db_file = NamedTempFile::new().unwrap();
+
+        let db = SqlxDatabase::create_no_migration(&DatabaseConfiguration::sqlite(db_file.path()))
+            .await?;
+
+        let migrator = NodeMigrationSet::new(DatabaseType::Sqlite).create_migrator()?;
+
+        // all the migrations are applied the first time
+        let (status, timings) = migrator.migrate_with_timings(&db.pool).await?;
+        assert!(status.up_to_date());
+        assert!(!timings.is_empty());
+
+        // nothing is applied the second time
+        let (status, timings) = migrator.migrate_with_timings(&db.pool).await?;
+        assert!(status.up_to_date());
+        assert!(timings.is_empty());
+
+        Ok(())
+    }
 }
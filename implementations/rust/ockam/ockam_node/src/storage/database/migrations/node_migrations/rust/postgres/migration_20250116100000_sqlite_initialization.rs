@@ -1,4 +1,6 @@
-use crate::database::{Boolean, FromSqlxError, RustMigration, SqlxDatabase, ToVoid, Version};
+use crate::database::{
+    BackendSet, Boolean, FromSqlxError, RustMigration, SqlxDatabase, ToVoid, Version,
+};
 use ockam_core::{async_trait, Result};
 use sqlx::*;
 
@@ -16,6 +18,10 @@ impl RustMigration for InitializeFromSqlite {
         Self::version()
     }
 
+    fn applies_to(&self) -> BackendSet {
+        BackendSet::POSTGRES
+    }
+
     async fn migrate(
         &self,
         legacy_sqlite_database: Option<SqlxDatabase>,
@@ -1,5 +1,5 @@
 use crate::database::{
-    Boolean, FromSqlxError, Nullable, RustMigration, SqlxDatabase, ToVoid, Version,
+    BackendSet, Boolean, FromSqlxError, Nullable, RustMigration, SqlxDatabase, ToVoid, Version,
 };
 use ockam_core::{async_trait, Result};
 use sqlx::any::AnyRow;
@@ -19,6 +19,10 @@ impl RustMigration for NodeNameIdentityAttributes {
         Self::version()
     }
 
+    fn applies_to(&self) -> BackendSet {
+        BackendSet::SQLITE
+    }
+
     async fn migrate(
         &self,
         _legacy_sqlite_database: Option<SqlxDatabase>,
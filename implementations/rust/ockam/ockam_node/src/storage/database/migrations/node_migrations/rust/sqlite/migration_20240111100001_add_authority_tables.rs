@@ -1,4 +1,4 @@
-use crate::database::migrations::RustMigration;
+use crate::database::migrations::{BackendSet, RustMigration};
 use crate::database::{Boolean, FromSqlxError, Nullable, SqlxDatabase, ToVoid, Version};
 use ockam_core::{async_trait, Result};
 use sqlx::*;
@@ -17,6 +17,10 @@ impl RustMigration for AuthorityAttributes {
         Self::version()
     }
 
+    fn applies_to(&self) -> BackendSet {
+        BackendSet::SQLITE
+    }
+
     async fn migrate(
         &self,
         _legacy_sqlite_database: Option<SqlxDatabase>,
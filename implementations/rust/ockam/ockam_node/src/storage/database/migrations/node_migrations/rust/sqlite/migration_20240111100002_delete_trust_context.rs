@@ -1,4 +1,4 @@
-use crate::database::migrations::RustMigration;
+use crate::database::migrations::{BackendSet, RustMigration};
 use crate::database::{FromSqlxError, SqlxDatabase, ToVoid, Version};
 use core::fmt;
 use minicbor::{CborLen, Decode, Encode};
@@ -21,6 +21,10 @@ impl RustMigration for PolicyTrustContextId {
         Self::version()
     }
 
+    fn applies_to(&self) -> BackendSet {
+        BackendSet::SQLITE
+    }
+
     async fn migrate(
         &self,
         _legacy_sqlite_database: Option<SqlxDatabase>,
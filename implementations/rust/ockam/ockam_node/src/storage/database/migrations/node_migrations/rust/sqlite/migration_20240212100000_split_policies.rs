@@ -1,4 +1,4 @@
-use crate::database::migrations::RustMigration;
+use crate::database::migrations::{BackendSet, RustMigration};
 use crate::database::{FromSqlxError, SqlxDatabase, ToVoid, Version};
 use ockam_core::{async_trait, Result};
 use sqlx::*;
@@ -18,6 +18,10 @@ impl RustMigration for SplitPolicies {
         Self::version()
     }
 
+    fn applies_to(&self) -> BackendSet {
+        BackendSet::SQLITE
+    }
+
     async fn migrate(
         &self,
         _legacy_sqlite_database: Option<SqlxDatabase>,
@@ -0,0 +1,3 @@
+-- Add a column to track when a space was last stored, as a unix timestamp in seconds
+ALTER TABLE space
+    ADD modified_at INTEGER NOT NULL DEFAULT 0;
@@ -1,10 +1,18 @@
-use crate::{debugger, ContextMode, WorkerShutdownPriority};
-use crate::{relay::WorkerRelay, Context};
+use crate::channel_types::OneshotReceiver;
+#[cfg(feature = "std")]
+use crate::relay::ConcurrentWorkerRelay;
+use crate::relay::{CtrlSignal, WorkerRelay};
+use crate::router::WorkerMeta;
+use crate::Context;
+use crate::{debugger, ContextMode, HandleRetryPolicy, MessageCapture, WorkerShutdownPriority};
+use futures::FutureExt;
 use ockam_core::compat::string::String;
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{
-    Address, AddressMetadata, AllowAll, IncomingAccessControl, Mailbox, Mailboxes,
-    OutgoingAccessControl, Result, Worker,
+    route, Address, AddressMetadata, AllowAll, Capability, Codec, Error, IncomingAccessControl,
+    LocalMessage, Mailbox, Mailboxes, Message, OutgoingAccessControl, RelayMessage, Result, Worker,
 };
 
 /// Start a [`Worker`] with a custom configuration
@@ -18,6 +26,31 @@ where
     worker: W,
 }
 
+/// Options of a worker, set with the builders and passed through when the worker is started
+struct WorkerOptions<M: Message> {
+    shutdown_priority: WorkerShutdownPriority,
+    shutdown_before: Vec<Address>,
+    group: Option<String>,
+    labels: Vec<(String, String)>,
+    message_capture: Option<MessageCapture>,
+    codec: Option<Arc<dyn Codec<M>>>,
+    retry_policy: Option<HandleRetryPolicy>,
+}
+
+impl<M: Message> Default for WorkerOptions<M> {
+    fn default() -> Self {
+        Self {
+            shutdown_priority: Default::default(),
+            shutdown_before: Default::default(),
+            group: None,
+            labels: Default::default(),
+            message_capture: None,
+            codec: None,
+            retry_policy: None,
+        }
+    }
+}
+
 impl<W> WorkerBuilder<W>
 where
     W: Worker<Context = Context>,
@@ -69,7 +102,7 @@ where
             worker: self.worker,
             address: address.into(),
             metadata,
-            shutdown_priority: Default::default(),
+            options: Default::default(),
         }
     }
 
@@ -77,7 +110,7 @@ where
     pub fn with_mailboxes(self, mailboxes: Mailboxes) -> WorkerBuilderMultipleAddresses<W> {
         WorkerBuilderMultipleAddresses {
             mailboxes,
-            shutdown_priority: Default::default(),
+            options: Default::default(),
             worker: self.worker,
         }
     }
@@ -88,7 +121,7 @@ where
     W: Worker<Context = Context>,
 {
     mailboxes: Mailboxes,
-    shutdown_priority: WorkerShutdownPriority,
+    options: WorkerOptions<W::Message>,
     worker: W,
 }
 
@@ -98,15 +131,77 @@ where
 {
     /// Consume this builder and start a new Ockam [`Worker`] from the given context
     pub fn start(self, context: &Context) -> Result<()> {
-        start(context, self.mailboxes, self.shutdown_priority, self.worker)
+        start(context, self.mailboxes, self.options, self.worker)
     }
 
     pub fn with_shutdown_priority(mut self, shutdown_priority: WorkerShutdownPriority) -> Self {
-        self.shutdown_priority = shutdown_priority;
+        self.options.shutdown_priority = shutdown_priority;
+        self
+    }
+
+    /// Shut this worker down, and wait for it to be stopped, before the worker (or processor)
+    /// with the given address is shut down on node shutdown
+    ///
+    /// Only applies to workers with the same [`WorkerShutdownPriority`].
+    pub fn with_shutdown_before(mut self, address: impl Into<Address>) -> Self {
+        self.options.shutdown_before.push(address.into());
+        self
+    }
+
+    /// Add this worker to a group, all the members of a group can be stopped together with
+    /// [`Context::stop_group`]
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.options.group = Some(group.into());
+        self
+    }
+
+    /// Adds a label reported as an attribute of the tracing spans of this worker
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.labels.push((key.into(), value.into()));
+        self
+    }
+
+    /// Retain the last messages delivered to this worker in the given [`MessageCapture`]
+    pub fn with_message_capture(mut self, message_capture: MessageCapture) -> Self {
+        self.options.message_capture = Some(message_capture);
+        self
+    }
+
+    /// Decode the messages delivered to this worker with the given [`Codec`] instead of
+    /// the default encoding of its message type
+    pub fn with_codec(mut self, codec: impl Codec<W::Message>) -> Self {
+        self.options.codec = Some(Arc::new(codec));
+        self
+    }
+
+    /// Handle the messages again, with a backoff, when this worker fails to handle them
+    ///
+    /// NOTE: the messages are then delivered at least once, see [`HandleRetryPolicy`].
+    pub fn with_handle_retry(mut self, retry_policy: HandleRetryPolicy) -> Self {
+        self.options.retry_policy = Some(retry_policy);
         self
     }
 }
 
+#[cfg(feature = "std")]
+impl<W> WorkerBuilderMultipleAddresses<W>
+where
+    W: Worker<Context = Context> + Clone,
+{
+    /// Handle up to `concurrency` messages at the same time, each one with a clone of the worker.
+    ///
+    /// NOTE: Since messages are handled concurrently, responses may be sent in a different order
+    /// than the corresponding requests were received.
+    pub fn with_concurrency(self, concurrency: usize) -> WorkerBuilderConcurrent<W> {
+        WorkerBuilderConcurrent {
+            mailboxes: self.mailboxes,
+            options: self.options,
+            worker: self.worker,
+            concurrency: concurrency.max(1),
+        }
+    }
+}
+
 pub struct WorkerBuilderOneAddress<W>
 where
     W: Worker<Context = Context>,
@@ -116,7 +211,7 @@ where
     address: Address,
     worker: W,
     metadata: Option<AddressMetadata>,
-    shutdown_priority: WorkerShutdownPriority,
+    options: WorkerOptions<W::Message>,
 }
 
 impl<W> WorkerBuilderOneAddress<W>
@@ -152,7 +247,50 @@ where
     }
 
     pub fn with_shutdown_priority(mut self, shutdown_priority: WorkerShutdownPriority) -> Self {
-        self.shutdown_priority = shutdown_priority;
+        self.options.shutdown_priority = shutdown_priority;
+        self
+    }
+
+    /// Shut this worker down, and wait for it to be stopped, before the worker (or processor)
+    /// with the given address is shut down on node shutdown
+    ///
+    /// Only applies to workers with the same [`WorkerShutdownPriority`].
+    pub fn with_shutdown_before(mut self, address: impl Into<Address>) -> Self {
+        self.options.shutdown_before.push(address.into());
+        self
+    }
+
+    /// Add this worker to a group, all the members of a group can be stopped together with
+    /// [`Context::stop_group`]
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.options.group = Some(group.into());
+        self
+    }
+
+    /// Adds a label reported as an attribute of the tracing spans of this worker
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.labels.push((key.into(), value.into()));
+        self
+    }
+
+    /// Retain the last messages delivered to this worker in the given [`MessageCapture`]
+    pub fn with_message_capture(mut self, message_capture: MessageCapture) -> Self {
+        self.options.message_capture = Some(message_capture);
+        self
+    }
+
+    /// Decode the messages delivered to this worker with the given [`Codec`] instead of
+    /// the default encoding of its message type
+    pub fn with_codec(mut self, codec: impl Codec<W::Message>) -> Self {
+        self.options.codec = Some(Arc::new(codec));
+        self
+    }
+
+    /// Handle the messages again, with a backoff, when this worker fails to handle them
+    ///
+    /// NOTE: the messages are then delivered at least once, see [`HandleRetryPolicy`].
+    pub fn with_handle_retry(mut self, retry_policy: HandleRetryPolicy) -> Self {
+        self.options.retry_policy = Some(retry_policy);
         self
     }
 
@@ -169,7 +307,7 @@ where
                 ),
                 vec![],
             ),
-            self.shutdown_priority,
+            self.options,
             self.worker,
         )
     }
@@ -216,16 +354,174 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<W> WorkerBuilderOneAddress<W>
+where
+    W: Worker<Context = Context> + Clone,
+{
+    /// Handle up to `concurrency` messages at the same time, each one with a clone of the worker.
+    ///
+    /// NOTE: Since messages are handled concurrently, responses may be sent in a different order
+    /// than the corresponding requests were received.
+    pub fn with_concurrency(self, concurrency: usize) -> WorkerBuilderConcurrent<W> {
+        WorkerBuilderConcurrent {
+            mailboxes: Mailboxes::new(
+                Mailbox::new(
+                    self.address,
+                    self.metadata,
+                    self.incoming_ac,
+                    self.outgoing_ac,
+                ),
+                vec![],
+            ),
+            options: self.options,
+            worker: self.worker,
+            concurrency: concurrency.max(1),
+        }
+    }
+}
+
+/// Start a [`Worker`] handling several messages at the same time
+#[cfg(feature = "std")]
+pub struct WorkerBuilderConcurrent<W>
+where
+    W: Worker<Context = Context> + Clone,
+{
+    mailboxes: Mailboxes,
+    options: WorkerOptions<W::Message>,
+    worker: W,
+    concurrency: usize,
+}
+
+#[cfg(feature = "std")]
+impl<W> WorkerBuilderConcurrent<W>
+where
+    W: Worker<Context = Context> + Clone,
+{
+    /// Consume this builder and start a new Ockam [`Worker`] from the given context
+    pub fn start(self, context: &Context) -> Result<()> {
+        start_concurrent(
+            context,
+            self.mailboxes,
+            self.options,
+            self.worker,
+            self.concurrency,
+        )
+    }
+}
+
 /// Consume this builder and start a new Ockam [`Worker`] from the given context
 fn start<W>(
     context: &Context,
     mailboxes: Mailboxes,
-    shutdown_priority: WorkerShutdownPriority,
+    mut options: WorkerOptions<W::Message>,
     worker: W,
 ) -> Result<()>
 where
     W: Worker<Context = Context>,
 {
+    check_capabilities(&mailboxes, worker.required_capabilities())?;
+
+    let codec = options.codec.take();
+    let retry_policy = options.retry_policy.take();
+    let (ctx, ctrl_rx) = register(context, mailboxes, options)?;
+
+    // Then initialise the worker message relay
+    WorkerRelay::init(context.runtime(), worker, ctx, ctrl_rx, codec, retry_policy);
+
+    Ok(())
+}
+
+/// Consume this builder and start a new Ockam [`Worker`] handling messages concurrently
+#[cfg(feature = "std")]
+fn start_concurrent<W>(
+    context: &Context,
+    mailboxes: Mailboxes,
+    mut options: WorkerOptions<W::Message>,
+    worker: W,
+    concurrency: usize,
+) -> Result<()>
+where
+    W: Worker<Context = Context> + Clone,
+{
+    check_capabilities(&mailboxes, worker.required_capabilities())?;
+
+    let codec = options.codec.take();
+    let retry_policy = options.retry_policy.take();
+    let (ctx, ctrl_rx) = register(context, mailboxes, options)?;
+
+    ConcurrentWorkerRelay::init(
+        context.runtime(),
+        worker,
+        ctx,
+        ctrl_rx,
+        concurrency,
+        codec,
+        retry_policy,
+    );
+
+    Ok(())
+}
+
+/// Check that the access controls of the primary mailbox grant the capabilities required by
+/// a worker, see [`Worker::required_capabilities`]
+///
+/// The access controls are evaluated with a message between the primary address and the
+/// address of the capability. The ones which can't be evaluated without waiting are skipped.
+fn check_capabilities(mailboxes: &Mailboxes, capabilities: Vec<Capability>) -> Result<()> {
+    let mailbox = mailboxes.primary_mailbox();
+    let primary_address = mailbox.address();
+
+    for capability in capabilities {
+        let (source, destination) = match &capability {
+            Capability::SendTo(address) => (primary_address.clone(), address.clone()),
+            Capability::ReceiveFrom(address) => (address.clone(), primary_address.clone()),
+        };
+        let local_message = LocalMessage::new()
+            .with_onward_route(route![destination.clone()])
+            .with_return_route(route![source.clone()]);
+        let relay_msg = RelayMessage::new(source, destination, local_message);
+
+        let authorized = match &capability {
+            Capability::SendTo(_) => mailbox
+                .outgoing_access_control()
+                .is_authorized(&relay_msg)
+                .now_or_never(),
+            Capability::ReceiveFrom(_) => mailbox
+                .incoming_access_control()
+                .is_authorized(&relay_msg)
+                .now_or_never(),
+        };
+
+        match authorized {
+            Some(Ok(true)) => {}
+            Some(Ok(false)) => {
+                return Err(Error::new(
+                    Origin::Node,
+                    Kind::Misuse,
+                    format!(
+                        "The worker at {} requires {:?}, which is denied by its access control",
+                        primary_address, capability
+                    ),
+                ))
+            }
+            Some(Err(err)) => return Err(err),
+            None => debug!(
+                "The access control of the worker at {} can't be checked for {:?} before it starts",
+                primary_address, capability
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Create the worker [`Context`] and register its addresses in the router
+fn register<M: Message>(
+    context: &Context,
+    mailboxes: Mailboxes,
+    options: WorkerOptions<M>,
+) -> Result<(Context, OneshotReceiver<CtrlSignal>)> {
     debug!(
         "Initializing ockam worker '{}' with access control in:{:?} out:{:?}",
         mailboxes.primary_address(),
@@ -234,7 +530,9 @@ where
     );
 
     // Pass it to the context
-    let (ctx, sender, ctrl_rx) = context.new_with_mailboxes(mailboxes, ContextMode::Attached);
+    let (mut ctx, sender, ctrl_rx) = context.new_with_mailboxes(mailboxes, ContextMode::Attached);
+    ctx.set_labels(options.labels);
+    ctx.set_message_capture(options.message_capture);
 
     debugger::log_inherit_context("WORKER", context, &ctx);
 
@@ -242,13 +540,14 @@ where
     router.add_worker(
         ctx.mailboxes(),
         sender,
-        false,
-        shutdown_priority,
+        WorkerMeta {
+            group: options.group,
+            shutdown_priority: options.shutdown_priority,
+            shutdown_before: options.shutdown_before,
+            ..Default::default()
+        },
         context.mailbox_count(),
     )?;
 
-    // Then initialise the worker message relay
-    WorkerRelay::init(context.runtime(), worker, ctx, ctrl_rx);
-
-    Ok(())
+    Ok((ctx, ctrl_rx))
 }
@@ -1,7 +1,12 @@
 //! A collection of utility workers for various use cases.
 //!
 //! Currently, this contains an echoer worker which is used in many examples,
-//! and is useful for debugging.
+//! and is useful for debugging, the ping responder started on every node, and
+//! a processor forwarding the messages of a stream.
 mod echoer;
+mod ping;
+mod stream;
 
 pub use echoer::*;
+pub use ping::*;
+pub use stream::*;
@@ -0,0 +1,24 @@
+use crate::Context;
+use ockam_core::compat::boxed::Box;
+use ockam_core::{Result, Routed, Worker};
+
+/// Address of the [`PingResponder`] started on every node
+pub const PING_RESPONDER_ADDRESS: &str = "ockam.ping_responder";
+
+/// A worker which replies to each message with an empty message, used by
+/// [`Context::ping`](crate::Context::ping) to measure the round-trip time to a node.
+///
+/// It is started by [`NodeBuilder::build`](crate::NodeBuilder::build) at
+/// [`PING_RESPONDER_ADDRESS`]. To answer pings coming from a transport, it must be added
+/// as a consumer of the flow control of that transport.
+pub struct PingResponder;
+
+#[ockam_core::worker]
+impl Worker for PingResponder {
+    type Context = Context;
+    type Message = ();
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<()>) -> Result<()> {
+        ctx.send(msg.return_route().clone(), ()).await
+    }
+}
@@ -0,0 +1,69 @@
+use crate::Context;
+use core::marker::PhantomData;
+use futures::stream::{Stream, StreamExt};
+use ockam_core::compat::boxed::Box;
+use ockam_core::{async_trait, Message, Processor, Result, Route};
+
+/// A processor sending each message produced by a [`Stream`] to its route, for example to
+/// bridge the messages received from a websocket into the node.
+///
+/// The processor stops once the stream ends, and when the node shuts down. A message which
+/// can't be sent is logged and the following ones are still sent.
+///
+/// ```rust
+/// # use {ockam_node::Context, ockam_core::{route, AllowAll, DenyAll, Result}};
+/// # async fn test(ctx: &mut Context) -> Result<()> {
+/// use ockam_node::workers::StreamProcessor;
+///
+/// let stream = futures::stream::iter([(route!["app"], "Hello".to_string())]);
+/// // The processor sends messages, and doesn't receive any
+/// ctx.start_processor_with_access_control(
+///     "stream",
+///     StreamProcessor::new(stream),
+///     DenyAll,
+///     AllowAll,
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamProcessor<S, M> {
+    stream: S,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<S, M> StreamProcessor<S, M>
+where
+    S: Stream<Item = (Route, M)> + Send + Unpin + 'static,
+    M: Message + Send + 'static,
+{
+    /// Constructor
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            _message: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<S, M> Processor for StreamProcessor<S, M>
+where
+    S: Stream<Item = (Route, M)> + Send + Unpin + 'static,
+    M: Message + Send + 'static,
+{
+    type Context = Context;
+
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let Some((route, msg)) = self.stream.next().await else {
+            debug!(
+                "The stream of the processor {} ended",
+                ctx.primary_address()
+            );
+            return Ok(false);
+        };
+
+        ctx.send(route, msg).await?;
+
+        Ok(true)
+    }
+}
@@ -1,4 +1,4 @@
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::time::Duration;
 use ockam_core::compat::{
     boxed::Box,
@@ -6,13 +6,22 @@ use ockam_core::compat::{
     sync::Arc,
 };
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{async_trait, Address, AllowAll, Any, Decodable, DenyAll, Message};
-use ockam_core::{route, Processor, Result, Routed, Worker};
+use ockam_core::{
+    async_trait, Address, AllowAll, AllowOnwardAddress, AllowSourceAddress, Any, Capability, Codec,
+    CorrelationId, Decodable, DenyAll, Encodable, Mailbox, Message, MessagePriority,
+    NeutralMessage, RelayMessage,
+};
+use ockam_core::{route, Cancellation, Processor, Result, Routed, Worker};
 use ockam_node::compat::futures::FutureExt;
-use ockam_node::{Context, MessageReceiveOptions, NodeBuilder};
+use ockam_node::{
+    Context, HandleRetryPolicy, MessageReceiveOptions, MessageSendReceiveOptions, Middleware,
+    NodeBuilder, NullWorker, ProcessorBuilder, ReplyTo, RequestTimeoutError, StartableWorker,
+    WorkerBuilder, DEFAULT_ERROR_SINK_CAPACITY,
+};
 use serde::{Deserialize, Serialize};
+use std::error::Error as _;
 use std::sync::atomic::AtomicI8;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tokio::time::sleep;
 
 #[allow(non_snake_case)]
@@ -35,6 +44,51 @@ async fn receive_timeout__1_sec__should_return_from_call(ctx: &mut Context) -> R
     Ok(())
 }
 
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn set_access_control__deny_to_allow__should_receive(ctx: &mut Context) -> Result<()> {
+    let mut child_ctx = ctx.new_detached("child", DenyAll, AllowAll)?;
+
+    ctx.send(route!["child"], "Denied".to_string()).await?;
+    let res = child_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "Should not receive the message");
+
+    child_ctx.set_access_control(AllowAll, AllowAll);
+
+    ctx.send(route!["child"], "Allowed".to_string()).await?;
+    let m = child_ctx.receive::<String>().await?.into_body()?;
+    assert_eq!(m, "Allowed");
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn set_access_control__allow_to_deny__should_not_receive(ctx: &mut Context) -> Result<()> {
+    let mut child_ctx = ctx.new_detached("child", AllowAll, AllowAll)?;
+    let mut other_ctx = ctx.new_detached("other", AllowAll, AllowAll)?;
+
+    child_ctx.set_access_control(DenyAll, DenyAll);
+
+    ctx.send(route!["child"], "Denied".to_string()).await?;
+    let res = child_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "Should not receive the message");
+
+    child_ctx
+        .send(route!["other"], "Denied".to_string())
+        .await?;
+    let res = other_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "Should not receive the message");
+
+    Ok(())
+}
+
 #[allow(non_snake_case)]
 #[test]
 fn start_and_shutdown_node__many_iterations__should_not_fail() {
@@ -237,6 +291,64 @@ async fn processor_initialize_fail_should_shutdown(ctx: &mut Context) -> Result<
     Ok(())
 }
 
+struct SlowInitializationWorker {
+    initialized: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Worker for SlowInitializationWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn initialize(&mut self, _context: &mut Self::Context) -> Result<()> {
+        sleep(Duration::from_millis(300)).await;
+        self.initialized.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        _ctx: &mut Self::Context,
+        _msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn wait_for_initialization__slow_initialize__should_wait_until_completed(
+    ctx: &mut Context,
+) -> Result<()> {
+    let initialized = Arc::new(AtomicBool::new(false));
+    let address = Address::from_string("slow_worker");
+    ctx.start_worker(
+        address.clone(),
+        SlowInitializationWorker {
+            initialized: initialized.clone(),
+        },
+    )?;
+
+    ctx.wait_for_initialization(&address, Duration::from_secs(5))
+        .await?;
+    assert!(initialized.load(Ordering::Relaxed));
+
+    // A failed initialization stops the worker
+    let failing_address = Address::from_string("failing_worker");
+    ctx.start_worker(
+        failing_address.clone(),
+        FailingWorkerProcessor {
+            shutdown_was_called: Arc::new(AtomicBool::new(false)),
+        },
+    )?;
+    let result = ctx
+        .wait_for_initialization(&failing_address, Duration::from_secs(5))
+        .await;
+    assert_eq!(result.unwrap_err().code().kind, Kind::NotFound);
+
+    Ok(())
+}
+
 struct DummyProcessor;
 
 #[async_trait]
@@ -367,6 +479,58 @@ async fn waiting_processor__shutdown__should_be_interrupted(ctx: &mut Context) -
     Ok(())
 }
 
+/// Processor waiting in `process` until it is cancelled
+struct CancellableProcessor {
+    cancelled_cleanly: Arc<AtomicBool>,
+    shutdown_was_called: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Processor for CancellableProcessor {
+    type Context = Context;
+
+    async fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
+        self.shutdown_was_called.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn process_with_cancellation(
+        &mut self,
+        _ctx: &mut Self::Context,
+        cancellation: &dyn Cancellation,
+    ) -> Result<bool> {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(10)) => {}
+            _ = cancellation.cancelled() => {
+                self.cancelled_cleanly.store(true, Ordering::Relaxed);
+            }
+        }
+        Ok(!cancellation.is_cancelled())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn cancellable_processor__stop__should_return_from_process(ctx: &mut Context) -> Result<()> {
+    let cancelled_cleanly = Arc::new(AtomicBool::new(false));
+    let shutdown_was_called = Arc::new(AtomicBool::new(false));
+
+    let processor = CancellableProcessor {
+        cancelled_cleanly: cancelled_cleanly.clone(),
+        shutdown_was_called: shutdown_was_called.clone(),
+    };
+
+    ctx.start_processor("cancellable_processor", processor)?;
+    sleep(Duration::from_millis(100)).await;
+
+    ctx.stop_address(&"cancellable_processor".into())?;
+    sleep(Duration::from_millis(500)).await;
+
+    assert!(cancelled_cleanly.load(Ordering::Relaxed));
+    assert!(shutdown_was_called.load(Ordering::Relaxed));
+    Ok(())
+}
+
 struct MessagingProcessor {
     initialize_was_called: Arc<AtomicBool>,
     shutdown_was_called: Arc<AtomicBool>,
@@ -459,7 +623,7 @@ impl Worker for BadWorker {
 
     /// This shutdown function takes _way_ too long to complete
     async fn shutdown(&mut self, ctx: &mut Context) -> Result<()> {
-        ctx.sleep(Duration::from_secs(10)).await;
+        ctx.sleep_uninterruptible(Duration::from_secs(10)).await;
         Ok(())
     }
 }
@@ -476,6 +640,94 @@ async fn abort_blocked_shutdown(ctx: &mut Context) -> Result<()> {
         .unwrap()
 }
 
+struct ShutdownObserverWorker {
+    node_was_shutting_down: Arc<AtomicBool>,
+}
+
+#[ockam_core::worker]
+impl Worker for ShutdownObserverWorker {
+    type Context = Context;
+    type Message = ();
+
+    async fn shutdown(&mut self, ctx: &mut Context) -> Result<()> {
+        self.node_was_shutting_down
+            .store(ctx.is_shutting_down(), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn is_shutting_down__node_shutdown__should_be_seen_by_workers(
+    ctx: &mut Context,
+) -> Result<()> {
+    assert!(!ctx.is_shutting_down());
+
+    // Stopping a worker doesn't shut the node down
+    let stopped_was_shutting_down = Arc::new(AtomicBool::new(true));
+    ctx.start_worker(
+        "stopped",
+        ShutdownObserverWorker {
+            node_was_shutting_down: stopped_was_shutting_down.clone(),
+        },
+    )?;
+    ctx.stop_address(&"stopped".into())?;
+    sleep(Duration::from_millis(100)).await;
+    assert!(!stopped_was_shutting_down.load(Ordering::Relaxed));
+    assert!(!ctx.is_shutting_down());
+
+    let running_was_shutting_down = Arc::new(AtomicBool::new(false));
+    ctx.start_worker(
+        "running",
+        ShutdownObserverWorker {
+            node_was_shutting_down: running_was_shutting_down.clone(),
+        },
+    )?;
+    ctx.shutdown_node().await?;
+    assert!(running_was_shutting_down.load(Ordering::Relaxed));
+    assert!(ctx.is_shutting_down());
+
+    Ok(())
+}
+
+struct SleepingWorker {
+    shutdown_finished: Arc<AtomicBool>,
+}
+
+#[ockam_core::worker]
+impl Worker for SleepingWorker {
+    type Context = Context;
+    type Message = ();
+
+    async fn shutdown(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.sleep(Duration::from_secs(10)).await;
+        self.shutdown_finished.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// A sleep during the shutdown of a worker returns as soon as the worker is stopped
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn sleeping_worker__shutdown_node__sleep_should_be_interrupted(
+    ctx: &mut Context,
+) -> Result<()> {
+    let shutdown_finished = Arc::new(AtomicBool::new(false));
+    ctx.start_worker(
+        "sleeping",
+        SleepingWorker {
+            shutdown_finished: shutdown_finished.clone(),
+        },
+    )?;
+
+    ockam_node::tokio::time::timeout(Duration::from_secs(2), ctx.shutdown_node())
+        .await
+        .unwrap()?;
+    assert!(shutdown_finished.load(Ordering::Relaxed));
+
+    Ok(())
+}
+
 struct SendReceiveWorker;
 
 #[async_trait]
@@ -556,50 +808,1374 @@ async fn starting_worker_with_dup_address_should_fail(ctx: &mut Context) -> Resu
     Ok(())
 }
 
-struct CountingErrorWorker {
-    pub(crate) counter: Arc<AtomicI8>,
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn start_workers__one_fails__should_stop_started_workers(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("taken", DummyWorker)?;
+
+    let workers: Vec<(Address, Box<dyn StartableWorker>)> = vec![
+        ("first".into(), Box::new(DummyWorker)),
+        ("second".into(), Box::new(LabelsWorker)),
+        ("taken".into(), Box::new(DummyWorker)),
+        ("last".into(), Box::new(DummyWorker)),
+    ];
+    assert!(ctx.start_workers(workers).is_err());
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    for address in ["first", "second", "last"] {
+        assert!(!ctx.is_worker_registered_at(&address.into())?);
+    }
+    assert!(ctx.is_worker_registered_at(&"taken".into())?);
+
+    let workers: Vec<(Address, Box<dyn StartableWorker>)> = vec![
+        ("first".into(), Box::new(DummyWorker)),
+        ("second".into(), Box::new(LabelsWorker)),
+    ];
+    ctx.start_workers(workers)?;
+
+    for address in ["first", "second"] {
+        assert!(ctx.is_worker_registered_at(&address.into())?);
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn address_exists__primary_and_secondary_addresses__should_be_found(
+    ctx: &mut Context,
+) -> Result<()> {
+    let primary: Address = "existing".into();
+    let secondary: Address = "existing_secondary".into();
+    assert!(!ctx.address_exists(&primary)?);
+
+    ctx.start_worker(primary.clone(), DummyWorker)?;
+    ctx.add_address(
+        &primary,
+        Mailbox::new(
+            secondary.clone(),
+            None,
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ),
+    )?;
+
+    assert!(ctx.address_exists(&primary)?);
+    assert!(ctx.address_exists(&secondary)?);
+    assert!(!ctx.is_worker_registered_at(&secondary)?);
+    assert!(!ctx.address_exists(&"unknown".into())?);
+
+    ctx.stop_address(&primary)?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(!ctx.address_exists(&primary)?);
+    assert!(!ctx.address_exists(&secondary)?);
+
+    Ok(())
+}
+
+struct VersionedWorker {
+    version: &'static str,
+    handling_time: Duration,
+    shutdown_was_called: Arc<AtomicBool>,
 }
 
 #[async_trait]
-impl Worker for CountingErrorWorker {
+impl Worker for VersionedWorker {
     type Context = Context;
-    type Message = Any;
+    type Message = String;
 
     async fn handle_message(
         &mut self,
-        _context: &mut Self::Context,
-        _msg: Routed<Self::Message>,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
     ) -> Result<()> {
-        let _ = self.counter.fetch_add(1, Ordering::Relaxed);
+        ctx.sleep(self.handling_time).await;
+        let return_route = msg.return_route().clone();
+        let reply = format!("{}:{}", self.version, msg.into_body()?);
+        ctx.send(return_route, reply).await
+    }
 
-        Err(ockam_core::Error::new(Origin::Core, Kind::Misuse, "test"))
+    async fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
+        self.shutdown_was_called.store(true, Ordering::Relaxed);
+        Ok(())
     }
 }
 
 #[allow(non_snake_case)]
 #[ockam_macros::test]
-async fn message_handle__error_during_handling__keep_worker_running(
+async fn replace_worker__queued_messages__should_be_handled_by_new_worker(
     ctx: &mut Context,
 ) -> Result<()> {
-    let counter = Arc::new(AtomicI8::new(0));
+    let old_shutdown_was_called = Arc::new(AtomicBool::new(false));
+    let new_shutdown_was_called = Arc::new(AtomicBool::new(false));
     ctx.start_worker(
-        "counter",
-        CountingErrorWorker {
-            counter: counter.clone(),
+        "versioned",
+        VersionedWorker {
+            version: "v1",
+            handling_time: Duration::from_millis(200),
+            shutdown_was_called: old_shutdown_was_called.clone(),
         },
     )?;
 
-    ctx.send("counter", "test".to_string()).await?;
-    ctx.sleep(Duration::from_millis(100)).await;
-    assert_eq!(1, counter.load(Ordering::Relaxed));
+    ctx.send("versioned", "1".to_string()).await?;
+    // Let the old worker start handling the first message, the next ones are queued
+    ctx.sleep(Duration::from_millis(50)).await;
+    ctx.send("versioned", "2".to_string()).await?;
+    ctx.send("versioned", "3".to_string()).await?;
+
+    ctx.replace_worker(
+        &"versioned".into(),
+        VersionedWorker {
+            version: "v2",
+            handling_time: Duration::ZERO,
+            shutdown_was_called: new_shutdown_was_called.clone(),
+        },
+    )?;
+    ctx.send("versioned", "4".to_string()).await?;
 
-    ctx.send("counter", "test".to_string()).await?;
-    ctx.sleep(Duration::from_millis(100)).await;
-    assert_eq!(2, counter.load(Ordering::Relaxed));
+    let mut replies = vec![];
+    for _ in 0..4 {
+        replies.push(ctx.receive::<String>().await?.into_body()?);
+    }
+    assert_eq!(replies, vec!["v1:1", "v2:2", "v2:3", "v2:4"]);
+    assert!(old_shutdown_was_called.load(Ordering::Relaxed));
+    assert!(!new_shutdown_was_called.load(Ordering::Relaxed));
+    assert!(ctx.is_worker_registered_at(&"versioned".into())?);
 
-    ctx.send("counter", "test".to_string()).await?;
+    ctx.stop_address(&"versioned".into())?;
     ctx.sleep(Duration::from_millis(100)).await;
-    assert_eq!(3, counter.load(Ordering::Relaxed));
+    assert!(new_shutdown_was_called.load(Ordering::Relaxed));
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn replace_worker__unknown_address__should_fail(ctx: &mut Context) -> Result<()> {
+    let result = ctx.replace_worker(
+        &"unknown".into(),
+        VersionedWorker {
+            version: "v2",
+            handling_time: Duration::ZERO,
+            shutdown_was_called: Arc::new(AtomicBool::new(false)),
+        },
+    );
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn scoped_address__dropped__should_be_stopped(ctx: &mut Context) -> Result<()> {
+    let mut scoped = ctx.scoped_address()?;
+    let address = scoped.address().clone();
+    assert!(ctx.is_worker_registered_at(&address)?);
+
+    ctx.send(address.clone(), "hello".to_string()).await?;
+    let msg = scoped.receive::<String>().await?;
+    assert_eq!(msg.into_body()?, "hello");
+
+    drop(scoped);
+    assert!(!ctx.is_worker_registered_at(&address)?);
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn scoped_address__receive_cancelled__should_be_stopped(ctx: &mut Context) -> Result<()> {
+    let scoped = ctx.scoped_address()?;
+    let address = scoped.address().clone();
+
+    let receive = async move {
+        let mut scoped = scoped;
+        scoped
+            .receive_extended::<String>(MessageReceiveOptions::new().without_timeout())
+            .await
+    };
+    assert!(tokio::time::timeout(Duration::from_millis(100), receive)
+        .await
+        .is_err());
+
+    assert!(!ctx.is_worker_registered_at(&address)?);
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Message)]
+struct ReplyToRequest {
+    text: String,
+    reply_to: ReplyTo,
+}
+
+struct ReplyToEchoer;
+
+#[async_trait]
+impl Worker for ReplyToEchoer {
+    type Context = Context;
+    type Message = ReplyToRequest;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let request = msg.into_body()?;
+        ctx.send(request.reply_to, request.text).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn reply_channel__reply_sent__should_resolve_receiver(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("echoer", ReplyToEchoer)?;
+
+    let (reply_to, reply_receiver) = ctx.reply_channel::<String>()?;
+    let address = reply_to.address().clone();
+    ctx.send(
+        "echoer",
+        ReplyToRequest {
+            text: "hello".to_string(),
+            reply_to,
+        },
+    )
+    .await?;
+
+    assert_eq!(reply_receiver.receive().await?, "hello");
+    assert!(!ctx.is_worker_registered_at(&address)?);
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn reply_channel__receiver_dropped__should_be_stopped(ctx: &mut Context) -> Result<()> {
+    let (reply_to, reply_receiver) = ctx.reply_channel::<String>()?;
+    assert!(ctx.is_worker_registered_at(reply_to.address())?);
+
+    drop(reply_receiver);
+    assert!(!ctx.is_worker_registered_at(reply_to.address())?);
+
+    Ok(())
+}
+
+struct UppercaseWorker;
+
+#[async_trait]
+impl Worker for UppercaseWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let reply = msg.body()?.to_uppercase();
+        ctx.reply(&msg, reply).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn reply__message_received__should_be_sent_along_return_route(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("uppercase", UppercaseWorker)?;
+    let mut client = ctx.new_detached("client", AllowAll, AllowAll)?;
+
+    client.send("uppercase", "hello".to_string()).await?;
+    let reply = client.receive::<String>().await?;
+    assert_eq!(reply.return_route(), &route!["uppercase"]);
+    assert_eq!(reply.into_body()?, "HELLO");
+
+    Ok(())
+}
+
+struct CountingErrorWorker {
+    pub(crate) counter: Arc<AtomicI8>,
+}
+
+#[async_trait]
+impl Worker for CountingErrorWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(
+        &mut self,
+        _context: &mut Self::Context,
+        _msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let _ = self.counter.fetch_add(1, Ordering::Relaxed);
+
+        Err(ockam_core::Error::new(Origin::Core, Kind::Misuse, "test"))
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn message_handle__error_during_handling__keep_worker_running(
+    ctx: &mut Context,
+) -> Result<()> {
+    let counter = Arc::new(AtomicI8::new(0));
+    ctx.start_worker(
+        "counter",
+        CountingErrorWorker {
+            counter: counter.clone(),
+        },
+    )?;
+
+    ctx.send("counter", "test".to_string()).await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(1, counter.load(Ordering::Relaxed));
+
+    ctx.send("counter", "test".to_string()).await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(2, counter.load(Ordering::Relaxed));
+
+    ctx.send("counter", "test".to_string()).await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(3, counter.load(Ordering::Relaxed));
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn message_handle__error_during_handling__should_be_sent_to_error_sink(
+    ctx: &mut Context,
+) -> Result<()> {
+    let mut error_sink = ctx.set_error_sink(DEFAULT_ERROR_SINK_CAPACITY)?;
+
+    let counter = Arc::new(AtomicI8::new(0));
+    ctx.start_worker(
+        "failing",
+        CountingErrorWorker {
+            counter: counter.clone(),
+        },
+    )?;
+
+    for _ in 0..2 {
+        ctx.send("failing", "test".to_string()).await?;
+        let worker_error = tokio::time::timeout(Duration::from_secs(1), error_sink.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(worker_error.address, "failing".into());
+        assert_eq!(worker_error.error.code().kind, Kind::Misuse);
+    }
+
+    // Errors are not reported anymore once the sink is removed
+    ctx.remove_error_sink()?;
+    ctx.send("failing", "test".to_string()).await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(3, counter.load(Ordering::Relaxed));
+    assert!(error_sink.recv().await.is_none());
+
+    Ok(())
+}
+
+struct LabelsWorker;
+
+#[ockam_core::worker]
+impl Worker for LabelsWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let labels = ctx
+            .labels()
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        ctx.send(msg.return_route().clone(), labels).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_with_labels__handle_message__labels_should_be_available(
+    ctx: &mut Context,
+) -> Result<()> {
+    WorkerBuilder::new(LabelsWorker)
+        .with_address("labels")
+        .with_label("tenant", "acme")
+        .with_label("role", "relay")
+        .start(ctx)?;
+
+    let labels: String = ctx
+        .send_and_receive(route!["labels"], "".to_string())
+        .await?;
+    assert_eq!(labels, "tenant=acme,role=relay");
+
+    Ok(())
+}
+
+#[derive(Clone)]
+struct ConcurrentWorker {
+    in_flight: Arc<AtomicI8>,
+    max_in_flight: Arc<AtomicI8>,
+}
+
+#[ockam_core::worker]
+impl Worker for ConcurrentWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+        sleep(Duration::from_millis(200)).await;
+
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        ctx.send(msg.return_route().clone(), msg.into_body()?).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn concurrent_worker__many_messages__should_be_handled_concurrently(
+    ctx: &mut Context,
+) -> Result<()> {
+    let max_in_flight = Arc::new(AtomicI8::new(0));
+    WorkerBuilder::new(ConcurrentWorker {
+        in_flight: Arc::new(AtomicI8::new(0)),
+        max_in_flight: max_in_flight.clone(),
+    })
+    .with_address("concurrent")
+    .with_concurrency(3)
+    .start(ctx)?;
+
+    for i in 0..6 {
+        ctx.send(route!["concurrent"], i.to_string()).await?;
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..6 {
+        received.push(ctx.receive::<String>().await?.into_body()?);
+    }
+    received.sort();
+
+    assert_eq!(received, vec!["0", "1", "2", "3", "4", "5"]);
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
+
+struct AddressesWorker {
+    events: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[ockam_core::worker]
+impl Worker for AddressesWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let destination = msg.msg_addr().to_string();
+        ctx.send(msg.return_route().clone(), destination).await
+    }
+
+    async fn on_address_added(&mut self, _ctx: &mut Context, address: &Address) -> Result<()> {
+        self.events.lock().unwrap().push(format!("added {address}"));
+        Ok(())
+    }
+
+    async fn on_address_removed(&mut self, _ctx: &mut Context, address: &Address) -> Result<()> {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("removed {address}"));
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_addresses__add_and_remove__should_notify_worker(ctx: &mut Context) -> Result<()> {
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    WorkerBuilder::new(AddressesWorker {
+        events: events.clone(),
+    })
+    .with_address("addresses")
+    .start(ctx)?;
+
+    let primary: Address = "addresses".into();
+    let additional: Address = "additional".into();
+    ctx.add_address(
+        &primary,
+        Mailbox::new(
+            additional.clone(),
+            None,
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ),
+    )?;
+
+    // The new address can be used right away
+    let destination: String = ctx
+        .send_and_receive(route!["additional"], "".to_string())
+        .await?;
+    assert_eq!(destination, "0#additional");
+    assert_eq!(*events.lock().unwrap(), vec!["added 0#additional"]);
+
+    // The same address can't be attached twice
+    assert!(ctx
+        .add_address(
+            &primary,
+            Mailbox::new(
+                additional.clone(),
+                None,
+                Arc::new(AllowAll),
+                Arc::new(AllowAll)
+            ),
+        )
+        .is_err());
+
+    ctx.remove_address(&primary, &additional)?;
+    assert!(ctx.remove_address(&primary, &primary).is_err());
+
+    // The removed address can't be resolved anymore
+    assert!(ctx
+        .send(route!["additional"], "".to_string())
+        .await
+        .is_err());
+    let destination: String = ctx
+        .send_and_receive(route!["addresses"], "".to_string())
+        .await?;
+    assert_eq!(destination, "0#addresses");
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec!["added 0#additional", "removed 0#additional"]
+    );
+
+    Ok(())
+}
+
+struct ShutdownOrderWorker {
+    name: &'static str,
+    shutdown_delay: Duration,
+    stopped: Arc<std::sync::Mutex<Vec<&'static str>>>,
+}
+
+#[ockam_core::worker]
+impl Worker for ShutdownOrderWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
+        sleep(self.shutdown_delay).await;
+        self.stopped.lock().unwrap().push(self.name);
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn workers_with_shutdown_order__shutdown_node__should_stop_in_order(
+    ctx: &mut Context,
+) -> Result<()> {
+    let stopped = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    // The slowest worker to shut down must be stopped first
+    for (name, delay, before) in [
+        ("socket", 0, None),
+        ("receiver", 50, Some("socket")),
+        ("sender", 200, Some("receiver")),
+    ] {
+        let builder = WorkerBuilder::new(ShutdownOrderWorker {
+            name,
+            shutdown_delay: Duration::from_millis(delay),
+            stopped: stopped.clone(),
+        })
+        .with_address(name);
+
+        match before {
+            Some(before) => builder.with_shutdown_before(before).start(ctx)?,
+            None => builder.start(ctx)?,
+        }
+    }
+
+    ctx.shutdown_node().await?;
+    // Wait till tokio Runtime is shut down
+    sleep(Duration::new(1, 0)).await;
+
+    assert_eq!(
+        *stopped.lock().unwrap(),
+        vec!["sender", "receiver", "socket"]
+    );
+
+    Ok(())
+}
+
+struct RecordingWorker {
+    received: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[ockam_core::worker]
+impl Worker for RecordingWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(
+        &mut self,
+        _context: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        self.received.lock().unwrap().push(msg.into_body()?);
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_with_capture__replay__should_handle_last_messages_again(
+    ctx: &mut Context,
+) -> Result<()> {
+    let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let capture = ctx.start_worker_with_capture(
+        "recording",
+        RecordingWorker {
+            received: received.clone(),
+        },
+        2,
+    )?;
+
+    for msg in ["1", "2", "3"] {
+        ctx.send("recording", msg.to_string()).await?;
+    }
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    let captured: Vec<String> = capture
+        .messages()
+        .into_iter()
+        .map(|m| String::decode(m.payload()))
+        .collect::<Result<_>>()?;
+    assert_eq!(captured, vec!["2", "3"]);
+
+    assert_eq!(capture.replay(ctx).await?, 2);
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(*received.lock().unwrap(), vec!["1", "2", "3", "2", "3"]);
+
+    Ok(())
+}
+
+struct CorrelationWorker {
+    next: Option<&'static str>,
+    correlation_ids: Arc<std::sync::Mutex<Vec<Option<CorrelationId>>>>,
+    priorities: Arc<std::sync::Mutex<Vec<MessagePriority>>>,
+}
+
+#[ockam_core::worker]
+impl Worker for CorrelationWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        self.correlation_ids
+            .lock()
+            .unwrap()
+            .push(msg.local_message().correlation_id());
+        self.priorities
+            .lock()
+            .unwrap()
+            .push(msg.local_message().priority());
+
+        if let Some(next) = self.next {
+            ctx.send(next, msg.into_body()?).await?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn correlation_id__forward_between_workers__should_be_propagated(
+    ctx: &mut Context,
+) -> Result<()> {
+    let correlation_ids = Arc::new(std::sync::Mutex::new(Vec::new()));
+    for (address, next) in [("first", Some("second")), ("second", None)] {
+        ctx.start_worker(
+            address,
+            CorrelationWorker {
+                next,
+                correlation_ids: correlation_ids.clone(),
+                priorities: Default::default(),
+            },
+        )?;
+    }
+
+    for _ in 0..2 {
+        ctx.send("first", "hello".to_string()).await?;
+        ctx.sleep(Duration::from_millis(100)).await;
+    }
+
+    let correlation_ids = correlation_ids.lock().unwrap().clone();
+    assert_eq!(correlation_ids.len(), 4);
+    assert!(correlation_ids.iter().all(|id| id.is_some()));
+
+    // Each message sent from the app starts a new correlation
+    assert_ne!(correlation_ids[0], correlation_ids[2]);
+    for chain in correlation_ids.chunks(2) {
+        assert_eq!(chain[0], chain[1]);
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn message_priority__forward_between_workers__should_be_propagated(
+    ctx: &mut Context,
+) -> Result<()> {
+    let priorities = Arc::new(std::sync::Mutex::new(Vec::new()));
+    for (address, next) in [("first", Some("second")), ("second", None)] {
+        ctx.start_worker(
+            address,
+            CorrelationWorker {
+                next,
+                correlation_ids: Default::default(),
+                priorities: priorities.clone(),
+            },
+        )?;
+    }
+
+    ctx.send("first", "hello".to_string()).await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    ctx.set_message_priority(MessagePriority::Urgent);
+    ctx.send("first", "hello".to_string()).await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(
+        *priorities.lock().unwrap(),
+        vec![
+            MessagePriority::Normal,
+            MessagePriority::Normal,
+            MessagePriority::Urgent,
+            MessagePriority::Urgent
+        ]
+    );
+
+    Ok(())
+}
+
+struct RequestResponseWorker;
+
+#[ockam_core::worker]
+impl Worker for RequestResponseWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let response = format!("{} handled", msg.body()?);
+        ctx.send_response(&msg, response).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_request__interleaved_requests__responses_should_have_request_ids(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("responder", RequestResponseWorker)?;
+    let mut requester = ctx.new_detached("requester", AllowAll, AllowAll)?;
+
+    let first_id = requester
+        .send_request("responder", "first".to_string())
+        .await?;
+    let second_id = requester
+        .send_request("responder", "second".to_string())
+        .await?;
+    assert_ne!(first_id, second_id);
+
+    for _ in 0..2 {
+        let response = requester.receive::<String>().await?;
+        let expected = if response.request_id() == Some(first_id) {
+            "first handled"
+        } else {
+            assert_eq!(response.request_id(), Some(second_id));
+            "second handled"
+        };
+        assert_eq!(response.into_body()?, expected);
+    }
+
+    // Messages which are not requests don't have a request id
+    requester.send("responder", "other".to_string()).await?;
+    assert_eq!(requester.receive::<String>().await?.request_id(), None);
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn stop_group__workers_and_processor__should_be_stopped(ctx: &mut Context) -> Result<()> {
+    let new_flags = || {
+        (
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+        )
+    };
+
+    let mut shutdown_flags = vec![];
+    for address in ["member1", "member2", "other"] {
+        let (initialize_was_called, shutdown_was_called) = new_flags();
+        shutdown_flags.push(shutdown_was_called.clone());
+        let worker = SimpleWorker {
+            initialize_was_called,
+            shutdown_was_called,
+        };
+        let builder = WorkerBuilder::new(worker).with_address(address);
+        if address == "other" {
+            builder.start(ctx)?;
+        } else {
+            builder.with_group("group").start(ctx)?;
+        }
+    }
+
+    let (initialize_was_called, processor_shutdown_was_called) = new_flags();
+    ProcessorBuilder::new(WaitingProcessor {
+        initialize_was_called,
+        shutdown_was_called: processor_shutdown_was_called.clone(),
+    })
+    .with_address("member_processor")
+    .with_group("group")
+    .start(ctx)?;
+
+    // Let the processor start waiting
+    sleep(Duration::from_millis(100)).await;
+
+    ctx.stop_group("group").await?;
+
+    // The members are already stopped when stop_group returns
+    assert!(shutdown_flags[0].load(Ordering::Relaxed));
+    assert!(shutdown_flags[1].load(Ordering::Relaxed));
+    assert!(processor_shutdown_was_called.load(Ordering::Relaxed));
+    for address in ["member1", "member2", "member_processor"] {
+        assert!(!ctx.is_worker_registered_at(&address.into())?);
+    }
+
+    assert!(!shutdown_flags[2].load(Ordering::Relaxed));
+    assert!(ctx.is_worker_registered_at(&"other".into())?);
+
+    // Stopping an empty group returns immediately
+    ctx.stop_group("group").await?;
+
+    Ok(())
+}
+
+/// Upper-case the messages sent to the "uppercase" worker, and drop the ones saying "drop"
+struct UppercaseMiddleware;
+
+#[async_trait]
+impl Middleware for UppercaseMiddleware {
+    async fn handle(&self, _ctx: &Context, msg: RelayMessage) -> Result<Option<RelayMessage>> {
+        if msg.destination() != &Address::from("uppercase") {
+            return Ok(Some(msg));
+        }
+
+        let body = String::decode(msg.payload())?;
+        if body == "drop" {
+            return Ok(None);
+        }
+
+        let source = msg.source().clone();
+        let destination = msg.destination().clone();
+        let local_message = msg
+            .into_local_message()
+            .set_payload(body.to_uppercase().encode()?);
+        Ok(Some(RelayMessage::new(source, destination, local_message)))
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn middleware__transform_and_drop__worker_should_handle_transformed_messages(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.add_middleware(UppercaseMiddleware)?;
+
+    for address in ["uppercase", "unchanged"] {
+        let worker = SimpleWorker {
+            initialize_was_called: Arc::new(AtomicBool::new(false)),
+            shutdown_was_called: Arc::new(AtomicBool::new(false)),
+        };
+        ctx.start_worker(address, worker)?;
+    }
+
+    let msg: String = ctx
+        .send_and_receive(route!["uppercase"], "hello".to_string())
+        .await?;
+    assert_eq!(msg, "HELLO");
+
+    let msg: String = ctx
+        .send_and_receive(route!["unchanged"], "hello".to_string())
+        .await?;
+    assert_eq!(msg, "hello");
+
+    // The dropped message is not echoed, only the next one is
+    ctx.send(route!["uppercase"], "drop".to_string()).await?;
+    ctx.send(route!["uppercase"], "next".to_string()).await?;
+    assert_eq!(ctx.receive::<String>().await?.into_body()?, "NEXT");
+
+    ctx.clear_middlewares()?;
+    let msg: String = ctx
+        .send_and_receive(route!["uppercase"], "hello".to_string())
+        .await?;
+    assert_eq!(msg, "hello");
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct MessageCounter(AtomicUsize);
+
+struct CountingMiddleware;
+
+#[async_trait]
+impl Middleware for CountingMiddleware {
+    async fn handle(&self, ctx: &Context, msg: RelayMessage) -> Result<Option<RelayMessage>> {
+        ctx.local_store::<MessageCounter>()
+            .0
+            .fetch_add(1, Ordering::Relaxed);
+        Ok(Some(msg))
+    }
+}
+
+struct MessageCountWorker;
+
+#[async_trait]
+impl Worker for MessageCountWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let count = ctx
+            .local_store::<MessageCounter>()
+            .0
+            .load(Ordering::Relaxed);
+        ctx.send(msg.return_route().clone(), count.to_string())
+            .await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn local_store__set_by_middleware__should_be_scoped_to_the_worker(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.add_middleware(CountingMiddleware)?;
+    ctx.start_worker("counted1", MessageCountWorker)?;
+    ctx.start_worker("counted2", MessageCountWorker)?;
+
+    for expected in ["1", "2"] {
+        let msg: String = ctx
+            .send_and_receive(route!["counted1"], "hello".to_string())
+            .await?;
+        assert_eq!(msg, expected);
+    }
+
+    let msg: String = ctx
+        .send_and_receive(route!["counted2"], "hello".to_string())
+        .await?;
+    assert_eq!(msg, "1");
+
+    ctx.clear_middlewares()?;
+    Ok(())
+}
+
+/// Plain UTF-8 payloads, without the default length prefix of the strings
+struct Utf8Codec;
+
+impl Codec<String> for Utf8Codec {
+    fn decode(&self, payload: &[u8]) -> Result<String> {
+        String::from_utf8(payload.to_vec())
+            .map_err(|e| ockam_core::Error::new(Origin::Node, Kind::Serialization, e))
+    }
+
+    fn encode(&self, message: String) -> Result<Vec<u8>> {
+        Ok(message.into_bytes())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_with_codec__raw_payloads__should_be_decoded_with_codec(
+    ctx: &mut Context,
+) -> Result<()> {
+    let worker = SimpleWorker {
+        initialize_was_called: Arc::new(AtomicBool::new(false)),
+        shutdown_was_called: Arc::new(AtomicBool::new(false)),
+    };
+    WorkerBuilder::new(worker)
+        .with_address("utf8")
+        .with_codec(Utf8Codec)
+        .start(ctx)?;
+
+    let payload = Utf8Codec.encode("hello".to_string())?;
+    let msg: String = ctx
+        .send_and_receive(route!["utf8"], NeutralMessage::from(payload))
+        .await?;
+    assert_eq!(msg, "hello");
+
+    // A payload which can't be decoded is reported as an error, the worker keeps running
+    ctx.send(route!["utf8"], NeutralMessage::from(vec![0xff, 0xfe]))
+        .await?;
+    let msg: String = ctx
+        .send_and_receive(route!["utf8"], NeutralMessage::from(b"next".to_vec()))
+        .await?;
+    assert_eq!(msg, "next");
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_with_handle_retry__error_during_handling__should_handle_message_again(
+    ctx: &mut Context,
+) -> Result<()> {
+    let mut error_sink = ctx.set_error_sink(DEFAULT_ERROR_SINK_CAPACITY)?;
+
+    let counter = Arc::new(AtomicI8::new(0));
+    WorkerBuilder::new(CountingErrorWorker {
+        counter: counter.clone(),
+    })
+    .with_address("retried")
+    .with_handle_retry(HandleRetryPolicy::new(2, Duration::from_millis(10)))
+    .start(ctx)?;
+
+    // The message is handled once, then retried twice, and only the last error is reported
+    ctx.send("retried", "test".to_string()).await?;
+    let worker_error = tokio::time::timeout(Duration::from_secs(1), error_sink.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(worker_error.address, "retried".into());
+    assert_eq!(3, counter.load(Ordering::Relaxed));
+
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(3, counter.load(Ordering::Relaxed));
+    assert!(error_sink.try_recv().is_err());
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_and_receive__no_response__timeout_error_should_contain_route(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("silent", NullWorker)?;
+
+    let error = ctx
+        .send_and_receive_extended::<()>(
+            route!["silent"],
+            (),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error.code().kind, Kind::Timeout);
+
+    let timeout_error = error
+        .source()
+        .and_then(|e| e.downcast_ref::<RequestTimeoutError>())
+        .unwrap();
+    assert_eq!(timeout_error.route(), &route!["silent"]);
+    assert!(timeout_error.elapsed() >= Duration::from_millis(100));
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn context_with_deadline__deadline_passed__receive_should_fail(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("echoer", ockam_node::workers::Echoer)?;
+    let mut child = ctx.with_deadline(Instant::now() + Duration::from_millis(500));
+
+    let reply: String = child
+        .send_and_receive("echoer", "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello");
+
+    // A longer timeout is shortened to the remaining time
+    let started_at = Instant::now();
+    let result = child
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_secs(10)),
+        )
+        .await;
+    assert_eq!(result.unwrap_err().code().kind, Kind::Timeout);
+    assert!(started_at.elapsed() < Duration::from_secs(5));
+
+    // Once the deadline has passed, nothing is sent
+    let result = child
+        .send_and_receive::<String>("echoer", "Hello".to_string())
+        .await;
+    assert_eq!(result.unwrap_err().code().kind, Kind::Timeout);
+
+    // A later deadline doesn't extend the deadline of the parent
+    let deadline = child.deadline();
+    let grandchild = child.with_deadline(Instant::now() + Duration::from_secs(60));
+    assert_eq!(grandchild.deadline(), deadline);
+    drop(grandchild);
+    assert_eq!(child.deadline(), deadline);
+
+    // The deadline is removed once the child is dropped
+    drop(child);
+    assert_eq!(ctx.deadline(), None);
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn context_with_deadline__reply_sent_to_parent__should_be_received(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("echoer", ockam_node::workers::Echoer)?;
+
+    // The reply of the echoer is routed to the address of the parent
+    ctx.send(route!["echoer"], "Hello".to_string()).await?;
+
+    let parent_address = ctx.primary_address().clone();
+    let mut child = ctx.with_deadline(Instant::now() + Duration::from_secs(5));
+    assert_eq!(child.primary_address(), &parent_address);
+
+    let reply = child.receive::<String>().await?.into_body()?;
+    assert_eq!(reply, "Hello");
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn new_detached_with_ttl__unused__should_be_stopped(ctx: &mut Context) -> Result<()> {
+    let address = Address::from("detached_with_ttl");
+    let mut detached = ctx.new_detached_with_ttl(
+        address.clone(),
+        AllowAll,
+        AllowAll,
+        Duration::from_millis(500),
+    )?;
+    assert!(ctx.is_worker_registered_at(&address)?);
+
+    // Receiving a message postpones the expiration
+    sleep(Duration::from_millis(300)).await;
+    ctx.send(route![address.clone()], "Hello".to_string())
+        .await?;
+    detached.receive::<String>().await?;
+
+    sleep(Duration::from_millis(300)).await;
+    assert!(ctx.is_worker_registered_at(&address)?);
+
+    sleep(Duration::from_millis(600)).await;
+    assert!(!ctx.is_worker_registered_at(&address)?);
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn ping__node_with_responder__should_return_round_trip_time(ctx: &mut Context) -> Result<()> {
+    let timeout = Duration::from_secs(1);
+    let rtt = ctx.ping(route![], timeout).await?;
+    assert!(rtt < timeout);
+
+    // A worker which doesn't forward the ping on the route
+    ctx.start_worker("sink", NullWorker)?;
+    let err = ctx
+        .ping(route!["sink"], Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Timeout);
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_and_collect__slow_responder__should_only_fail_its_route(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("echoer1", ockam_node::workers::Echoer)?;
+    ctx.start_worker("echoer2", ockam_node::workers::Echoer)?;
+    // A worker which never replies
+    ctx.start_worker("sink", NullWorker)?;
+
+    let started_at = Instant::now();
+    let timeout = Duration::from_millis(200);
+    let responses = ctx
+        .send_and_collect::<String>(
+            [route!["echoer1"], route!["sink"], route!["echoer2"]],
+            "Hello".to_string(),
+            timeout,
+        )
+        .await?;
+    assert!(started_at.elapsed() < timeout * 2);
+
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0].as_ref().unwrap(), "Hello");
+    let err = responses[1].as_ref().unwrap_err();
+    assert_eq!(err.code().kind, Kind::Timeout);
+    assert!(err
+        .source()
+        .and_then(|e| e.downcast_ref::<RequestTimeoutError>())
+        .is_some());
+    assert_eq!(responses[2].as_ref().unwrap(), "Hello");
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn stream_processor__stream_ends__should_forward_all_messages_and_stop(
+    ctx: &mut Context,
+) -> Result<()> {
+    let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll)?;
+    let stream =
+        futures::stream::iter((0..3).map(|i| (route!["receiver"], format!("message {i}"))));
+    ctx.start_processor_with_access_control(
+        "stream",
+        ockam_node::workers::StreamProcessor::new(stream),
+        DenyAll,
+        AllowAll,
+    )?;
+
+    for i in 0..3 {
+        let msg = receiver.receive::<String>().await?.into_body()?;
+        assert_eq!(msg, format!("message {i}"));
+    }
+
+    // The processor stops by itself once the stream is over
+    let started_at = Instant::now();
+    while ctx.list_workers()?.contains(&"stream".into()) {
+        assert!(started_at.elapsed() < Duration::from_secs(1));
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn publish__topic_with_subscribers__should_be_received_by_all(
+    ctx: &mut Context,
+) -> Result<()> {
+    let mut subscriber1 = ctx.new_detached("subscriber1", AllowAll, AllowAll)?;
+    let mut subscriber2 = ctx.new_detached("subscriber2", AllowAll, AllowAll)?;
+    let mut other = ctx.new_detached("other", AllowAll, AllowAll)?;
+    subscriber1.subscribe("news")?;
+    subscriber2.subscribe("news")?;
+    other.subscribe("weather")?;
+
+    let sent = ctx.publish("news", "Hello".to_string()).await?;
+    assert_eq!(sent, 2);
+    assert_eq!(subscriber1.receive::<String>().await?.into_body()?, "Hello");
+    assert_eq!(subscriber2.receive::<String>().await?.into_body()?, "Hello");
+    let result = other
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await;
+    assert_eq!(result.unwrap_err().code().kind, Kind::Timeout);
+
+    // Unsubscribed and stopped addresses don't receive the next messages
+    subscriber1.unsubscribe("news")?;
+    ctx.stop_address(subscriber2.primary_address())?;
+    assert!(ctx.subscribers("news")?.is_empty());
+    assert_eq!(ctx.publish("news", "Hello".to_string()).await?, 0);
+    assert_eq!(ctx.subscribers("weather")?, vec![Address::from("other")]);
+
+    Ok(())
+}
+
+struct SubscribingWorker;
+
+#[async_trait]
+impl Worker for SubscribingWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        ctx.subscribe("news")
+    }
+
+    async fn handle_message(&mut self, _ctx: &mut Context, _msg: Routed<String>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn subscribers__group_stopped__should_be_unsubscribed(ctx: &mut Context) -> Result<()> {
+    WorkerBuilder::new(SubscribingWorker)
+        .with_address("subscriber")
+        .with_group("group")
+        .start(ctx)?;
+    ctx.wait_for_initialization(&"subscriber".into(), Duration::from_secs(5))
+        .await?;
+    assert_eq!(ctx.subscribers("news")?, vec![Address::from("subscriber")]);
+
+    ctx.stop_group("group").await?;
+
+    assert!(ctx.subscribers("news")?.is_empty());
+
+    Ok(())
+}
+
+struct CapabilitiesWorker;
+
+#[async_trait]
+impl Worker for CapabilitiesWorker {
+    type Context = Context;
+    type Message = String;
+
+    fn required_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::SendTo(Address::from_string("destination")),
+            Capability::ReceiveFrom(Address::from_string("source")),
+        ]
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn start_worker__capability_denied__should_fail(ctx: &mut Context) -> Result<()> {
+    let result = WorkerBuilder::new(CapabilitiesWorker)
+        .with_address("denied_worker")
+        .with_outgoing_access_control(DenyAll)
+        .start(ctx);
+    assert_eq!(result.unwrap_err().code().kind, Kind::Misuse);
+    assert!(!ctx.list_workers()?.contains(&"denied_worker".into()));
+
+    let result = WorkerBuilder::new(CapabilitiesWorker)
+        .with_address("denied_worker")
+        .with_incoming_access_control(AllowSourceAddress("other".into()))
+        .start(ctx);
+    assert_eq!(result.unwrap_err().code().kind, Kind::Misuse);
+
+    WorkerBuilder::new(CapabilitiesWorker)
+        .with_address("allowed_worker")
+        .with_incoming_access_control(AllowSourceAddress("source".into()))
+        .with_outgoing_access_control(AllowOnwardAddress::new("destination"))
+        .start(ctx)?;
+    assert!(ctx.list_workers()?.contains(&"allowed_worker".into()));
 
     Ok(())
 }
@@ -0,0 +1,52 @@
+use ockam_core::{async_trait, route, Result, Routed, Worker};
+use ockam_node::{Context, WorkerBuilder};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+use opentelemetry_sdk::trace::TracerProvider;
+
+/// This test needs to be an integration test of its own
+/// It sets up a global spans exporter that might interact with other tests
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_with_labels__handle_message__labels_should_be_span_attributes(
+    ctx: &mut Context,
+) -> Result<()> {
+    let spans_exporter = InMemorySpanExporter::default();
+    let tracer_provider = TracerProvider::builder()
+        .with_simple_exporter(spans_exporter.clone())
+        .build();
+    global::set_tracer_provider(tracer_provider);
+
+    WorkerBuilder::new(EchoWorker)
+        .with_address("labels")
+        .with_label("tenant", "acme")
+        .with_label("role", "relay")
+        .start(ctx)?;
+
+    let reply: String = ctx
+        .send_and_receive(route!["labels"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello");
+
+    let spans = spans_exporter.get_finished_spans().unwrap();
+    let span = spans
+        .iter()
+        .find(|span| span.name == "0#labels::handle_message")
+        .unwrap_or_else(|| panic!("no span for the labelled worker in {spans:?}"));
+    assert!(span.attributes.contains(&KeyValue::new("tenant", "acme")));
+    assert!(span.attributes.contains(&KeyValue::new("role", "relay")));
+
+    Ok(())
+}
+
+struct EchoWorker;
+
+#[async_trait]
+impl Worker for EchoWorker {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route().clone(), msg.into_body()?).await
+    }
+}
@@ -109,6 +109,8 @@ impl TcpSendWorker {
         WorkerBuilder::new(sender_worker)
             .with_mailboxes(Mailboxes::new(main_mailbox.clone(), vec![internal_mailbox]))
             .with_shutdown_priority(WorkerShutdownPriority::Priority1)
+            // Stop sending before the receiver, which owns the other half of the socket
+            .with_shutdown_before(addresses.receiver_address().clone())
             .start(ctx)?;
 
         Ok(())
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Datagrams are received from arbitrary peers, decoding them must never panic
+fuzz_target!(|data: &[u8]| {
+    if let Ok(frame) = ockam_transport_udp::decode_frame(data) {
+        assert!(frame.offset < frame.total);
+    }
+});
@@ -0,0 +1,112 @@
+use core::fmt::Debug;
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::sync::Arc;
+use std::time::Instant;
+use tokio::sync::watch;
+
+/// Source of the current time for the UDP workers and punctures
+///
+/// [`SystemClock`] is used by default, [`MockClock`] can be set with
+/// [`UdpBindOptions::with_clock`](crate::UdpBindOptions::with_clock) to test
+/// time-dependent behaviour without waiting.
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// Current time
+    fn now(&self) -> Instant;
+
+    /// Wait until the given duration has elapsed on this clock, the deadline is computed
+    /// when this function is called
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// [`Clock`] returning the time of the system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// [`Clock`] which only moves forward when it is advanced explicitly
+///
+/// Clones share the same time. Advancing the clock wakes up the tasks sleeping on it
+/// whose deadline is reached.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    now: Arc<watch::Sender<Instant>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockClock {
+    /// Create a clock starting at the current time of the system
+    pub fn new() -> Self {
+        let (now, _) = watch::channel(Instant::now());
+        Self { now: Arc::new(now) }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.borrow()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let deadline = self.now() + duration;
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            while *now.borrow_and_update() < deadline {
+                if now.changed().await.is_err() {
+                    // The clock was dropped, it won't move anymore
+                    return;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_only_moves_when_advanced() {
+        let clock = MockClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        clock.clone().advance(Duration::from_secs(5));
+        assert_eq!(clock.now() - start, Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_mock_clock_sleep_ends_when_advanced() {
+        let clock = MockClock::new();
+        let mut sleep = clock.sleep(Duration::from_secs(5));
+
+        clock.advance(Duration::from_secs(4));
+        let res = tokio::time::timeout(Duration::from_millis(50), &mut sleep).await;
+        assert!(res.is_err(), "The deadline is not reached yet");
+
+        clock.advance(Duration::from_secs(1));
+        let res = tokio::time::timeout(Duration::from_millis(50), &mut sleep).await;
+        assert!(res.is_ok(), "The deadline is reached");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::messages::RoutingNumber;
+use crate::UdpTransportError;
+use minicbor::{CborLen, Decode, Encode};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+/// Algorithm used to compress a message before it's split into datagrams,
+/// see [`UdpBindOptions::with_compression`](crate::UdpBindOptions::with_compression)
+///
+/// The algorithm is announced in every datagram of a compressed message, so a receiver
+/// decompresses the messages of the peers that use compression without any configuration,
+/// as long as the corresponding feature is enabled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Encode, Decode, CborLen)]
+#[cbor(index_only)]
+#[rustfmt::skip]
+pub enum UdpCompression {
+    /// LZ4, fast with a moderate ratio. Requires the `compression-lz4` feature
+    #[n(0)] Lz4,
+    /// Zstandard, slower with a better ratio. Requires the `compression-zstd` feature
+    #[n(1)] Zstd,
+}
+
+impl UdpCompression {
+    /// Is the feature for this algorithm enabled
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::Lz4 => cfg!(feature = "compression-lz4"),
+            Self::Zstd => cfg!(feature = "compression-zstd"),
+        }
+    }
+
+    /// Compress the data, return `None` if the result is not smaller than the data
+    pub(crate) fn compress(&self, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        let compressed: Result<Vec<u8>> = match self {
+            #[cfg(feature = "compression-lz4")]
+            Self::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+            #[cfg(feature = "compression-zstd")]
+            Self::Zstd => zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(|_| UdpTransportError::UnsupportedCompression(*self).into()),
+            #[cfg(not(all(feature = "compression-lz4", feature = "compression-zstd")))]
+            _ => Err(UdpTransportError::UnsupportedCompression(*self).into()),
+        };
+        let compressed = compressed?;
+
+        if compressed.len() < data.len() {
+            Ok(Some(compressed))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Decompress the data of a reassembled message, refusing to produce more than
+    /// `max_size` bytes
+    #[cfg_attr(
+        not(any(feature = "compression-lz4", feature = "compression-zstd")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn decompress(
+        &self,
+        routing_number: RoutingNumber,
+        data: &[u8],
+        max_size: usize,
+    ) -> Result<Vec<u8>> {
+        let invalid = || UdpTransportError::InvalidCompressedPayload(routing_number);
+
+        match self {
+            #[cfg(feature = "compression-lz4")]
+            Self::Lz4 => {
+                // The uncompressed size is prepended as a little-endian u32
+                let (size, compressed) = match data {
+                    [a, b, c, d, compressed @ ..] => {
+                        (u32::from_le_bytes([*a, *b, *c, *d]) as usize, compressed)
+                    }
+                    _ => return Err(invalid())?,
+                };
+                if size > max_size {
+                    return Err(invalid())?;
+                }
+                let decompressed = lz4_flex::decompress(compressed, size).map_err(|_| invalid())?;
+                if decompressed.len() != size {
+                    return Err(invalid())?;
+                }
+                Ok(decompressed)
+            }
+            #[cfg(feature = "compression-zstd")]
+            Self::Zstd => {
+                use std::io::Read;
+
+                let decoder = zstd::stream::read::Decoder::new(data).map_err(|_| invalid())?;
+                let mut decompressed = Vec::new();
+                decoder
+                    .take(max_size as u64 + 1)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|_| invalid())?;
+                if decompressed.len() > max_size {
+                    return Err(invalid())?;
+                }
+                Ok(decompressed)
+            }
+            #[cfg(not(all(feature = "compression-lz4", feature = "compression-zstd")))]
+            _ => Err(UdpTransportError::UnsupportedCompression(*self))?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UdpCompression;
+    use crate::messages::RoutingNumber;
+    use crate::MAX_MESSAGE_SIZE;
+
+    fn algorithms() -> Vec<UdpCompression> {
+        [UdpCompression::Lz4, UdpCompression::Zstd]
+            .into_iter()
+            .filter(|a| a.is_available())
+            .collect()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let data = "Hello, Ockam! ".repeat(1000).into_bytes();
+
+        for algorithm in algorithms() {
+            let compressed = algorithm.compress(&data).unwrap().unwrap();
+            assert!(compressed.len() < data.len());
+
+            let decompressed = algorithm
+                .decompress(RoutingNumber(0), &compressed, MAX_MESSAGE_SIZE)
+                .unwrap();
+            assert_eq!(decompressed, data);
+
+            // The size limit is enforced
+            assert!(algorithm
+                .decompress(RoutingNumber(0), &compressed, data.len() - 1)
+                .is_err());
+            // As well as the validity of the payload
+            assert!(algorithm
+                .decompress(RoutingNumber(0), &[0xff; 16], MAX_MESSAGE_SIZE)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_incompressible() {
+        let data: Vec<u8> = (0..64).map(|_| rand::random()).collect();
+
+        for algorithm in algorithms() {
+            assert!(algorithm.compress(&data).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_unavailable() {
+        for algorithm in [UdpCompression::Lz4, UdpCompression::Zstd] {
+            if !algorithm.is_available() {
+                assert!(algorithm.compress(b"data").is_err());
+                assert!(algorithm
+                    .decompress(RoutingNumber(0), b"data", MAX_MESSAGE_SIZE)
+                    .is_err());
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(missing_docs)]
 
 use crate::messages::RoutingNumber;
+use crate::UdpCompression;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Error;
 
@@ -33,6 +34,14 @@ pub enum UdpTransportError {
         routing_number: RoutingNumber,
         data_offset_end: usize,
     },
+    MessageTooLarge {
+        size: usize,
+        max_size: usize,
+    },
+    CompressionMismatch(RoutingNumber),
+    InvalidCompressedPayload(RoutingNumber),
+    UnsupportedCompression(UdpCompression),
+    InvalidReplayWindow,
 }
 
 impl ockam_core::compat::error::Error for UdpTransportError {}
@@ -100,6 +109,33 @@ impl core::fmt::Display for UdpTransportError {
                     "Message exceeded maximum limit. Routing number: {routing_number}, Data offset end: {data_offset_end}",
                 )
             }
+            Self::MessageTooLarge { size, max_size } => {
+                write!(
+                    f,
+                    "Message doesn't fit into a single datagram and fragmentation is disabled. Size: {size}, Max size: {max_size}",
+                )
+            }
+            Self::CompressionMismatch(routing_number) => {
+                write!(
+                    f,
+                    "Received parts with different compression for Routing number: {routing_number}",
+                )
+            }
+            Self::InvalidCompressedPayload(routing_number) => {
+                write!(
+                    f,
+                    "Received invalid compressed message for Routing number: {routing_number}",
+                )
+            }
+            Self::UnsupportedCompression(compression) => {
+                write!(
+                    f,
+                    "Compression {compression:?} is not supported, its feature is not enabled",
+                )
+            }
+            Self::InvalidReplayWindow => {
+                write!(f, "The replay protection window must not be empty")
+            }
         }
     }
 }
@@ -16,19 +16,41 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate core;
 
+mod clock;
+mod compression;
 mod error;
+mod local_info;
 mod messages;
 mod options;
 mod puncture;
 mod size_options;
+mod stats;
 mod transport;
 mod workers;
 
+pub use clock::*;
+pub use compression::UdpCompression;
 pub use error::*;
+pub use ipnet::IpNet;
+pub use local_info::*;
+#[cfg(feature = "fuzzing")]
+pub use messages::decode_frame;
+pub(crate) use options::FragmentRetransmission;
 pub use options::UdpBindOptions;
 pub use puncture::*;
 pub use size_options::*;
-pub use transport::{UdpBind, UdpBindArguments, UdpTransport, UdpTransportExtension};
+pub(crate) use stats::UdpBindActivity;
+pub use stats::{
+    MessageSizeHistogram, ReassemblyEntryInfo, UdpBindStats, UdpPeerStats, MAX_TRACKED_PEERS,
+    MESSAGE_SIZE_BUCKETS,
+};
+pub(crate) use transport::UdpBindState;
+#[cfg(feature = "benchmark")]
+pub use transport::{UdpBenchmarkOptions, UdpBenchmarkReport, UdpEchoWorker};
+pub use transport::{
+    UdpBind, UdpBindArguments, UdpBindConfig, UdpBindInfo, UdpSession, UdpTransport,
+    UdpTransportExtension, RESUME_PUNCTURE_TIMEOUT,
+};
 
 /// Transport type for UDP addresses
 pub const UDP: ockam_core::TransportType = ockam_core::TransportType::new(2);
@@ -0,0 +1,128 @@
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, LocalInfo, LocalMessage, Result};
+use std::net::SocketAddr;
+
+/// Identifier for [`UdpLocalInfo`] inside [`LocalInfo`]
+pub const UDP_LOCAL_INFO_IDENTIFIER: &str = "UDP_LOCAL_INFO_IDENTIFIER";
+
+/// [`LocalInfo`] marking a message sent to a UDP sender as a request to send a keepalive
+/// message instead of the message, see [`UdpSession::send_keepalive`](crate::UdpSession::send_keepalive)
+pub(crate) const UDP_KEEPALIVE_IDENTIFIER: &str = "UDP_KEEPALIVE_IDENTIFIER";
+
+/// UDP LocalInfo used for LocalMessage.
+///
+/// Carries the `SocketAddr` the datagram(s) were received from. It's informational only
+/// and doesn't affect routing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UdpLocalInfo {
+    source_address: SocketAddr,
+}
+
+impl UdpLocalInfo {
+    /// Constructor
+    pub fn new(source_address: SocketAddr) -> Self {
+        Self { source_address }
+    }
+
+    /// Address of the peer that sent the message
+    pub fn source_address(&self) -> SocketAddr {
+        self.source_address
+    }
+}
+
+impl UdpLocalInfo {
+    #[track_caller]
+    fn error_type_id() -> Error {
+        Error::new(
+            Origin::Transport,
+            Kind::Invalid,
+            "invalid local info identifier for udp",
+        )
+    }
+
+    #[track_caller]
+    fn error_format() -> Error {
+        Error::new(
+            Origin::Transport,
+            Kind::Invalid,
+            "invalid format for local info identifier for udp",
+        )
+    }
+
+    /// Try to decode `UdpLocalInfo` from general `LocalInfo`
+    pub fn from_local_info(value: &LocalInfo) -> Result<Self> {
+        if value.type_identifier() != UDP_LOCAL_INFO_IDENTIFIER {
+            return Err(Self::error_type_id());
+        }
+
+        let source_address = minicbor::decode::<String>(value.data())
+            .map_err(|_| Self::error_format())?
+            .parse::<SocketAddr>()
+            .map_err(|_| Self::error_format())?;
+
+        Ok(Self { source_address })
+    }
+
+    /// Encode `UdpLocalInfo` to general `LocalInfo`
+    pub fn to_local_info(&self) -> Result<LocalInfo> {
+        Ok(LocalInfo::new(
+            UDP_LOCAL_INFO_IDENTIFIER.into(),
+            ockam_core::cbor_encode_preallocate(self.source_address.to_string())?,
+        ))
+    }
+
+    /// Find `UdpLocalInfo` in a list of general `LocalInfo` of that `LocalMessage`
+    pub fn find_info(local_msg: &LocalMessage) -> Result<Self> {
+        Self::find_info_from_list(local_msg.local_info())
+    }
+
+    /// Find `UdpLocalInfo` in a list of general `LocalInfo`
+    pub fn find_info_from_list(local_info: &[LocalInfo]) -> Result<Self> {
+        match local_info
+            .iter()
+            .find(|x| x.type_identifier() == UDP_LOCAL_INFO_IDENTIFIER)
+        {
+            Some(local_info) => Self::from_local_info(local_info),
+            None => Err(Self::error_type_id()),
+        }
+    }
+
+    /// Mark a `LocalInfo` vector with `UdpLocalInfo` replacing any pre-existing entries
+    pub fn mark(
+        mut local_info: Vec<LocalInfo>,
+        source_address: SocketAddr,
+    ) -> Result<Vec<LocalInfo>> {
+        local_info.retain(|x| x.type_identifier() != UDP_LOCAL_INFO_IDENTIFIER);
+        local_info.push(Self::new(source_address).to_local_info()?);
+
+        Ok(local_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::route;
+
+    #[test]
+    fn test_local_info_roundtrip() -> Result<()> {
+        let source_address: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+
+        let local_message = LocalMessage::new()
+            .with_onward_route(route!["onward"])
+            .with_local_info(UdpLocalInfo::mark(vec![], source_address)?);
+
+        let info = UdpLocalInfo::find_info(&local_message)?;
+        assert_eq!(info.source_address(), source_address);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_info_missing() {
+        let local_message = LocalMessage::new().with_onward_route(route!["onward"]);
+        assert!(UdpLocalInfo::find_info(&local_message).is_err());
+    }
+}
@@ -33,6 +33,24 @@ impl<'a> UdpRoutingMessage<'a> {
         }
     }
 
+    /// Keepalive message, which doesn't go anywhere: it has no onward route and no payload
+    ///
+    /// It's sent as a regular message, so that the receivers of the previous versions,
+    /// which drop the messages without onward route, understand it as well.
+    pub fn keepalive() -> Self {
+        Self::new(
+            Route::default(),
+            Route::default(),
+            CowBytes::from(vec![]),
+            None,
+        )
+    }
+
+    /// Is this a keepalive message, see [`Self::keepalive`]
+    pub fn is_keepalive(&self) -> bool {
+        self.onward_route.is_empty()
+    }
+
     pub fn into_owned(self) -> UdpRoutingMessage<'static> {
         UdpRoutingMessage {
             onward_route: self.onward_route,
@@ -1,6 +1,7 @@
 use crate::messages::RoutingNumber;
+use crate::{UdpCompression, UdpTransportError};
 use minicbor::{CborLen, Decode, Encode};
-use ockam_core::CowBytes;
+use ockam_core::{CowBytes, Result};
 
 /// Current protocol version.
 pub const CURRENT_VERSION: Version = Version(1);
@@ -22,6 +23,13 @@ pub struct UdpTransportMessage<'a> {
     #[n(2)] pub offset: u16,
     #[n(3)] pub total: u16,
     #[b(4)] pub payload: CowBytes<'a>,
+    /// Only present if the sender uses replay protection
+    #[n(5)] pub sequence_number: Option<u64>,
+    /// Only present if the whole message was compressed before being split
+    #[n(6)] pub compression: Option<UdpCompression>,
+    /// Random number chosen by the sender when it starts, present with the sequence number.
+    /// A new epoch tells the receiver that the sender restarted its sequence numbers
+    #[n(7)] pub epoch: Option<u32>,
 }
 
 impl<'a> UdpTransportMessage<'a> {
@@ -39,14 +47,82 @@ impl<'a> UdpTransportMessage<'a> {
             offset,
             total,
             payload: payload.into(),
+            sequence_number: None,
+            compression: None,
+            epoch: None,
+        }
+    }
+
+    /// Specify the sequence number used for replay protection
+    pub fn with_sequence_number(self, sequence_number: u64) -> Self {
+        Self {
+            sequence_number: Some(sequence_number),
+            ..self
+        }
+    }
+
+    /// Specify the epoch of the sequence numbers used for replay protection
+    pub fn with_epoch(self, epoch: u32) -> Self {
+        Self {
+            epoch: Some(epoch),
+            ..self
+        }
+    }
+
+    /// Specify the algorithm used to compress the whole message
+    pub fn with_compression(self, compression: UdpCompression) -> Self {
+        Self {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    /// Smallest size of the whole message this datagram belongs to, according to its
+    /// number of parts. All the parts but the last one have the same length.
+    pub(crate) fn min_message_size(&self) -> usize {
+        let other_parts = self.total.saturating_sub(1) as usize;
+        if (self.offset as usize) + 1 < self.total as usize {
+            other_parts * self.payload.len() + 1
+        } else {
+            other_parts + self.payload.len()
         }
     }
 }
 
+/// Decode a datagram received from the network and check that it is a well-formed frame:
+/// a non-empty part of a message, within the announced number of parts
+///
+/// This is the entry point for untrusted bytes, it returns an error on malformed input and
+/// must never panic. It is exported with the `fuzzing` feature for the fuzz targets.
+pub fn decode_frame(bytes: &[u8]) -> Result<UdpTransportMessage<'_>> {
+    let frame: UdpTransportMessage = minicbor::decode(bytes)?;
+
+    if frame.total == 0 {
+        return Err(UdpTransportError::InvalidTotalNumber(frame.routing_number).into());
+    }
+
+    if frame.offset >= frame.total {
+        return Err(UdpTransportError::OutOfBounds {
+            routing_number: frame.routing_number,
+            total_number: frame.total,
+            offset: frame.offset,
+        }
+        .into());
+    }
+
+    if frame.payload.is_empty() {
+        return Err(UdpTransportError::EmptyPayload(frame.routing_number).into());
+    }
+
+    Ok(frame)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::messages::{RoutingNumber, UdpTransportMessage, Version};
-    use crate::UdpSizeOptions;
+    use crate::messages::{
+        decode_frame, RoutingNumber, UdpTransportMessage, Version, CURRENT_VERSION,
+    };
+    use crate::{UdpCompression, UdpSizeOptions, MAX_MESSAGE_SIZE};
 
     #[test]
     fn test_max_size_current_protocol() {
@@ -81,4 +157,114 @@ mod tests {
 
         assert_eq!(len, size_options.max_on_the_wire_packet_size);
     }
+
+    #[test]
+    fn test_max_size_with_sequence_number() {
+        let mut size_options = UdpSizeOptions::default();
+        size_options.reserve_sequence_number();
+
+        let msg = UdpTransportMessage::new(
+            Version(u8::MAX),
+            RoutingNumber(u16::MAX),
+            u16::MAX,
+            u16::MAX,
+            vec![0u8; size_options.max_payload_size_per_packet],
+        )
+        .with_sequence_number(u64::MAX)
+        .with_epoch(u32::MAX);
+
+        let len = ockam_core::cbor_encode_preallocate(msg).unwrap().len();
+
+        assert_eq!(len, size_options.max_on_the_wire_packet_size);
+    }
+
+    #[test]
+    fn test_max_size_with_compression() {
+        let mut size_options = UdpSizeOptions::default();
+        size_options.reserve_compression_flag();
+
+        let msg = UdpTransportMessage::new(
+            Version(u8::MAX),
+            RoutingNumber(u16::MAX),
+            u16::MAX,
+            u16::MAX,
+            vec![0u8; size_options.max_payload_size_per_packet],
+        )
+        .with_compression(UdpCompression::Zstd);
+
+        let len = ockam_core::cbor_encode_preallocate(msg).unwrap().len();
+
+        assert_eq!(len, size_options.max_on_the_wire_packet_size);
+
+        size_options.reserve_sequence_number();
+
+        let msg = UdpTransportMessage::new(
+            Version(u8::MAX),
+            RoutingNumber(u16::MAX),
+            u16::MAX,
+            u16::MAX,
+            vec![0u8; size_options.max_payload_size_per_packet],
+        )
+        .with_sequence_number(u64::MAX)
+        .with_epoch(u32::MAX)
+        .with_compression(UdpCompression::Zstd);
+
+        let len = ockam_core::cbor_encode_preallocate(msg).unwrap().len();
+
+        assert!(len <= size_options.max_on_the_wire_packet_size);
+    }
+
+    #[test]
+    fn test_min_message_size() {
+        let msg = UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 0, 1, vec![0u8; 10]);
+        assert_eq!(msg.min_message_size(), 10);
+
+        // The other parts have the same length, except the last one
+        let msg = UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 0, 3, vec![0u8; 10]);
+        assert_eq!(msg.min_message_size(), 21);
+
+        // The length of the other parts is unknown
+        let msg = UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 2, 3, vec![0u8; 10]);
+        assert_eq!(msg.min_message_size(), 12);
+
+        let msg = UdpTransportMessage::new(
+            CURRENT_VERSION,
+            RoutingNumber(0),
+            0,
+            u16::MAX,
+            vec![0u8; 1000],
+        );
+        assert!(msg.min_message_size() > MAX_MESSAGE_SIZE);
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let encode = |msg: UdpTransportMessage| ockam_core::cbor_encode_preallocate(msg).unwrap();
+
+        let frame = encode(UdpTransportMessage::new(
+            CURRENT_VERSION,
+            RoutingNumber(0),
+            1,
+            2,
+            vec![0u8; 10],
+        ));
+        assert_eq!(decode_frame(&frame).unwrap().offset, 1);
+
+        // Malformed frames are rejected
+        let invalid_frames = [
+            UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 0, 0, vec![0u8; 10]),
+            UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 0, 0, vec![]),
+            UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 2, 2, vec![0u8; 10]),
+            UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), u16::MAX, 1, vec![1]),
+            UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 0, 1, vec![]),
+        ];
+        for frame in invalid_frames {
+            assert!(decode_frame(&encode(frame)).is_err());
+        }
+
+        // As well as bytes which are not a frame
+        assert!(decode_frame(&[]).is_err());
+        assert!(decode_frame(&[0xff; 16]).is_err());
+        assert!(decode_frame(&frame[..frame.len() - 1]).is_err());
+    }
 }
@@ -1,8 +1,9 @@
 use crate::workers::Addresses;
-use crate::UdpSizeOptions;
+use crate::{Clock, SystemClock, UdpCompression, UdpSizeOptions};
+use ipnet::IpNet;
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
-use ockam_core::OutgoingAccessControl;
+use ockam_core::{MessagePriority, OutgoingAccessControl};
 
 /// Options for a UDP connection
 #[derive(Debug)]
@@ -10,6 +11,13 @@ pub struct UdpBindOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(crate) flow_control_id: FlowControlId,
     pub(crate) size_options: UdpSizeOptions,
+    pub(crate) replay_protection_window: Option<u64>,
+    pub(crate) no_fragmentation: bool,
+    pub(crate) compression: Option<UdpCompression>,
+    pub(crate) fragment_retransmission: Option<FragmentRetransmission>,
+    pub(crate) reassembly_memory_budget: Option<usize>,
+    pub(crate) source_allowlist: Option<Vec<IpNet>>,
+    pub(crate) clock: Arc<dyn Clock>,
 }
 
 impl UdpBindOptions {
@@ -20,6 +28,13 @@ impl UdpBindOptions {
             consumer: vec![],
             flow_control_id: FlowControls::generate_flow_control_id(),
             size_options: UdpSizeOptions::read_from_env(),
+            replay_protection_window: None,
+            no_fragmentation: false,
+            compression: None,
+            fragment_retransmission: None,
+            reassembly_memory_budget: None,
+            source_allowlist: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
@@ -30,12 +45,132 @@ impl UdpBindOptions {
         self
     }
 
+    /// Add sequence numbers to outgoing packets and drop incoming packets whose sequence number
+    /// was already seen or is older than `window_size` packets.
+    ///
+    /// Peers that don't send sequence numbers are still accepted, until they send
+    /// the first sequenced packet. Each bind picks a random epoch for its sequence numbers, a
+    /// peer sending a new epoch is considered restarted and gets a new window, while the
+    /// packets of its previous epochs are dropped.
+    ///
+    /// The bind fails with
+    /// [`UdpTransportError::InvalidReplayWindow`](crate::UdpTransportError::InvalidReplayWindow)
+    /// if `window_size` is 0.
+    pub fn with_replay_protection(mut self, window_size: u64) -> Self {
+        if self.replay_protection_window.is_none() {
+            self.size_options.reserve_sequence_number();
+        }
+        self.replay_protection_window = Some(window_size);
+
+        self
+    }
+
+    /// Send each message in exactly one datagram, messages that don't fit into
+    /// [`UdpSizeOptions::max_payload_size_per_packet`] are refused with
+    /// [`UdpTransportError::MessageTooLarge`](crate::UdpTransportError::MessageTooLarge)
+    /// instead of being split.
+    pub fn no_fragmentation(mut self) -> Self {
+        self.no_fragmentation = true;
+
+        self
+    }
+
+    /// Compress each message with the given algorithm before splitting it into datagrams,
+    /// the message is sent uncompressed if it doesn't get smaller
+    ///
+    /// The compressed datagrams are flagged, so that the peer decompresses the message once
+    /// it's reassembled. The peer doesn't need to enable compression, but it must be built
+    /// with the feature of the algorithm, otherwise the bind fails with
+    /// [`UdpTransportError::UnsupportedCompression`](crate::UdpTransportError::UnsupportedCompression).
+    pub fn with_compression(mut self, compression: UdpCompression) -> Self {
+        if self.compression.is_none() {
+            self.size_options.reserve_compression_flag();
+        }
+        self.compression = Some(compression);
+
+        self
+    }
+
+    /// Send each datagram of the messages with at least the given [`MessagePriority`]
+    /// `1 + retransmissions` times, to improve their delivery probability on lossy links
+    ///
+    /// All the datagrams of a message are sent once before being sent again, so that a burst
+    /// of losses doesn't drop all the copies of a datagram. The receiver ignores the copies
+    /// of the datagrams it already received. With
+    /// [`Self::with_replay_protection`], each copy has its own sequence number, so that it's
+    /// not dropped as a replay. The copies are counted in
+    /// [`UdpBindStats::packets_retransmitted`](crate::UdpBindStats::packets_retransmitted).
+    pub fn with_fragment_retransmission(
+        mut self,
+        retransmissions: u8,
+        min_priority: MessagePriority,
+    ) -> Self {
+        self.fragment_retransmission = Some(FragmentRetransmission {
+            retransmissions,
+            min_priority,
+        });
+
+        self
+    }
+
+    /// Limit the number of bytes buffered for the messages which are partially received, from
+    /// all the peers, to protect against floods of incomplete messages from many sources
+    ///
+    /// When the budget is exceeded, the oldest partially received messages are dropped, see
+    /// [`UdpBindStats::reassembly_evictions`](crate::UdpBindStats::reassembly_evictions).
+    /// With [`UdpTransport::bind_reuseport`](crate::UdpTransport::bind_reuseport), the budget
+    /// is split evenly between the sockets.
+    pub fn with_reassembly_memory_budget(mut self, bytes: usize) -> Self {
+        self.reassembly_memory_budget = Some(bytes);
+
+        self
+    }
+
+    /// Only accept the datagrams sent from one of the given networks
+    ///
+    /// The other datagrams are dropped before being parsed, see
+    /// [`UdpBindStats::dropped_unauthorized`](crate::UdpBindStats::dropped_unauthorized).
+    /// This includes the responses of STUN servers, which must be allowed explicitly.
+    pub fn with_source_allowlist(mut self, allowlist: Vec<IpNet>) -> Self {
+        self.source_allowlist = Some(allowlist);
+
+        self
+    }
+
+    /// Use the given [`Clock`] instead of the time of the system, for example a
+    /// [`MockClock`](crate::MockClock) in tests. It's also used by the punctures
+    /// created on this bind.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+
+        self
+    }
+
     /// Getter for freshly generated [`FlowControlId`]
     pub fn flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
     }
 }
 
+/// Retransmission of the datagrams of critical messages, see
+/// [`UdpBindOptions::with_fragment_retransmission`]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FragmentRetransmission {
+    pub(crate) retransmissions: u8,
+    pub(crate) min_priority: MessagePriority,
+}
+
+impl FragmentRetransmission {
+    /// Number of times each datagram of a message with that priority is sent
+    pub(crate) fn copies(&self, priority: MessagePriority) -> usize {
+        if priority >= self.min_priority {
+            1 + self.retransmissions as usize
+        } else {
+            1
+        }
+    }
+}
+
 impl Default for UdpBindOptions {
     fn default() -> Self {
         Self::new()
@@ -44,12 +179,14 @@ impl Default for UdpBindOptions {
 
 impl UdpBindOptions {
     pub(crate) fn setup_flow_control(&self, flow_controls: &FlowControls, addresses: &Addresses) {
-        flow_controls.add_producer(
-            addresses.receiver_address(),
-            &self.flow_control_id,
-            None,
-            vec![addresses.sender_address().clone()],
-        );
+        for receiver_address in addresses.receiver_addresses() {
+            flow_controls.add_producer(
+                receiver_address,
+                &self.flow_control_id,
+                None,
+                vec![addresses.sender_address().clone()],
+            );
+        }
 
         for id in &self.consumer {
             flow_controls.add_consumer(addresses.sender_address(), id);
@@ -1,3 +1,4 @@
+use ockam_core::compat::time::Duration;
 use ockam_core::{
     errcode::{Kind, Origin},
     Error,
@@ -16,6 +17,24 @@ pub enum PunctureError {
     NegotiationInvalidMessageType,
     /// We received an unexpected message type from Rendezvous service
     RendezvousResponseInvalidMessageType,
+    /// The STUN server didn't answer the Binding requests
+    StunServerNotReachable,
+    /// The STUN server answered with an error
+    StunErrorResponse,
+    /// The response of the STUN server doesn't contain a valid address
+    StunResponseInvalid,
+    /// The options of a puncture contradict the way it's created
+    ConflictingOptions,
+    /// The maximum number of concurrent punctures of the transport was reached
+    TooManyPunctures,
+    /// The other node didn't answer the exchange of the endpoints within the given time,
+    /// see [`PunctureSignaling`](crate::PunctureSignaling)
+    SignalingTimeout(Duration),
+    /// The puncture wasn't opened within the given time, the peer never answered the pings
+    PunchTimeout(Duration),
+    /// The peer didn't confirm its updated address within the given time, see
+    /// [`UdpPuncture::update_peer_address`](crate::UdpPuncture::update_peer_address)
+    ConfirmTimeout(Duration),
 }
 
 impl ockam_core::compat::error::Error for PunctureError {}
@@ -31,8 +50,16 @@ impl From<PunctureError> for Error {
         use PunctureError::*;
         let kind = match err {
             RendezvousServiceNotFound | PunctureNotOpen => Kind::NotFound,
+            StunServerNotReachable | SignalingTimeout(_) | PunchTimeout(_) | ConfirmTimeout(_) => {
+                Kind::Timeout
+            }
+            ConflictingOptions => Kind::Conflict,
+            TooManyPunctures => Kind::ResourceExhausted,
             Internal => Kind::Internal,
-            NegotiationInvalidMessageType | RendezvousResponseInvalidMessageType => Kind::Invalid,
+            NegotiationInvalidMessageType
+            | RendezvousResponseInvalidMessageType
+            | StunErrorResponse
+            | StunResponseInvalid => Kind::Invalid,
         };
         Error::new(Origin::Other, kind, err)
     }
@@ -3,8 +3,10 @@ mod negotiation;
 #[allow(clippy::module_inception)]
 mod puncture;
 mod rendezvous_service;
+mod stun;
 
 pub use error::*;
 pub use negotiation::*;
 pub use puncture::*;
 pub use rendezvous_service::{RendezvousClient, RendezvousService};
+pub(crate) use stun::*;
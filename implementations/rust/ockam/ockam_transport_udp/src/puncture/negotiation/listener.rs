@@ -54,6 +54,8 @@ impl UdpPunctureNegotiationListener {
         msg: UdpPunctureNegotiationMessageInitiate,
         return_route: Route,
     ) -> Result<()> {
+        udp.check_punctures_limit()?;
+
         // We create a new bind for each puncture. Ownership will be transferred to the
         // UdpPunctureReceiverWorker which is responsible for stopping it eventually
         // TODO: Consider limiting incoming access control for that bind
@@ -84,23 +86,30 @@ impl UdpPunctureNegotiationListener {
         // Let's start puncture as we received the initiates
         let my_remote_address =
             Address::random_tagged("UdpPunctureNegotiationWorker.remote.responder");
-        UdpPuncture::create(
-            &ctx,
-            udp_bind,
-            msg.initiator_udp_public_address,
-            my_remote_address.clone(),
-            initiator_remote_address,
-            options,
-            // We can't send messages to the remote address of `UdpPunctureReceiverWorker`
-            // on the other side, since it's not started yet, so we'll just send ping
-            // messages to the corresponding UDP transport worker of that node, the messages
-            // will be just dropped on that side, but the fact that we send them will keep
-            // the "connection" open
-            // After we receive the first ping, which guarantees
-            // that `UdpPunctureReceiverWorker` was started on the other side, we'll start
-            // sending messages to that worker
-            true,
-        )?;
+        let bind_address = udp_bind.sender_address().clone();
+        let res = udp.create_puncture(&ctx, udp_bind, |ctx, udp_bind| {
+            UdpPuncture::create(
+                ctx,
+                udp_bind,
+                msg.initiator_udp_public_address,
+                my_remote_address.clone(),
+                initiator_remote_address,
+                options,
+                // We can't send messages to the remote address of `UdpPunctureReceiverWorker`
+                // on the other side, since it's not started yet, so we'll just send ping
+                // messages to the corresponding UDP transport worker of that node, the messages
+                // will be just dropped on that side, but the fact that we send them will keep
+                // the "connection" open
+                // After we receive the first ping, which guarantees
+                // that `UdpPunctureReceiverWorker` was started on the other side, we'll start
+                // sending messages to that worker
+                true,
+            )
+        });
+        if let Err(err) = res {
+            udp.unbind(&bind_address)?;
+            return Err(err);
+        }
 
         // Send Acknowledge back, so that initiator will start the puncture as well
         ctx.send(
@@ -3,7 +3,9 @@ mod message;
 #[allow(clippy::module_inception)]
 mod negotiation;
 mod options;
+mod signaling;
 
 pub use listener::*;
 pub use negotiation::*;
 pub use options::*;
+pub use signaling::*;
@@ -1,10 +1,10 @@
-use crate::puncture::negotiation::message::{
-    UdpPunctureNegotiationMessageAcknowledge, UdpPunctureNegotiationMessageInitiate,
+use crate::puncture::negotiation::signaling::{
+    PunctureEndpoint, PunctureSignaling, RoutePunctureSignaling,
 };
 use crate::puncture::rendezvous_service::RendezvousClient;
 use crate::{UdpBindArguments, UdpBindOptions, UdpPuncture, UdpPunctureOptions, UdpTransport};
-use ockam_core::{Address, AllowAll, Result, Route};
-use ockam_node::{Context, MessageReceiveOptions};
+use ockam_core::{Address, Result, Route};
+use ockam_node::Context;
 use std::time::Duration;
 use tracing::{debug, error, info};
 
@@ -22,20 +22,19 @@ impl UdpPunctureNegotiation {
         rendezvous_route: Route,
         acknowledgment_timeout: Duration,
     ) -> Result<UdpPuncture> {
-        let next = onward_route.next()?.clone();
-
-        let address = Address::random_tagged("UdpPunctureNegotiator.initiator");
-        let mut child_ctx = ctx.new_detached(address, AllowAll, AllowAll)?;
+        let signaling = RoutePunctureSignaling::new(onward_route, acknowledgment_timeout);
+        Self::start_negotiation_with_signaling(ctx, udp, rendezvous_route, &signaling).await
+    }
 
-        if let Some(flow_control_id) = ctx
-            .flow_controls()
-            .find_flow_control_with_producer_address(&next)
-            .map(|x| x.flow_control_id().clone())
-        {
-            // To be able to receive the response
-            ctx.flow_controls()
-                .add_consumer(child_ctx.primary_address(), &flow_control_id);
-        }
+    /// Start a UDP puncture, exchanging the endpoints with the other node using the given
+    /// [`PunctureSignaling`]
+    pub async fn start_negotiation_with_signaling(
+        ctx: &Context,
+        udp: &UdpTransport,
+        rendezvous_route: Route,
+        signaling: &dyn PunctureSignaling,
+    ) -> Result<UdpPuncture> {
+        udp.check_punctures_limit()?;
 
         // We create a new bind for each puncture. Ownership will be transferred to the
         // UdpPunctureReceiverWorker which is responsible for stopping it eventually
@@ -49,7 +48,7 @@ impl UdpPunctureNegotiation {
 
         debug!(
             "Initializing UdpPunctureNegotiation Initiator at {}",
-            child_ctx.primary_address()
+            udp_bind.sender_address()
         );
         let client = RendezvousClient::new(&udp_bind, rendezvous_route);
         let my_udp_public_address = match client.get_my_address(ctx).await {
@@ -66,35 +65,22 @@ impl UdpPunctureNegotiation {
 
         info!(
             "UdpPunctureNegotiation Initiator {} got its public address: {}",
-            child_ctx.primary_address(),
+            udp_bind.sender_address(),
             my_udp_public_address
         );
 
-        // Send Initiate message to the responder, but don't start actual UDP puncture yet,
-        // until we receive Acknowledge from them
+        // Send our endpoint to the responder, but don't start actual UDP puncture yet,
+        // until we receive theirs
         let my_remote_address =
             Address::random_tagged("UdpPunctureNegotiationWorker.remote.initiator");
-        child_ctx
-            .send(
-                onward_route,
-                UdpPunctureNegotiationMessageInitiate {
-                    initiator_udp_public_address: my_udp_public_address,
-                    initiator_remote_address: my_remote_address.to_vec(),
-                },
-            )
-            .await?;
+        let my_endpoint = PunctureEndpoint::new(my_udp_public_address, my_remote_address.clone());
 
-        let response = match child_ctx
-            .receive_extended::<UdpPunctureNegotiationMessageAcknowledge>(
-                MessageReceiveOptions::new().with_timeout(acknowledgment_timeout),
-            )
-            .await
-        {
-            Ok(response) => response,
+        let their_endpoint = match signaling.exchange(ctx, my_endpoint).await {
+            Ok(their_endpoint) => their_endpoint,
             Err(err) => {
                 error!(
-                    "Error receiving response for Udp Puncture at: {}. {}",
-                    child_ctx.primary_address(),
+                    "Error exchanging the endpoints for Udp Puncture at: {}. {}",
+                    udp_bind.sender_address(),
                     err
                 );
 
@@ -103,33 +89,28 @@ impl UdpPunctureNegotiation {
             }
         };
 
-        let response = match response.into_body() {
-            Ok(response) => response,
-            Err(err) => {
-                error!(
-                    "Invalid response for Udp Puncture at: {}. {}",
-                    child_ctx.primary_address(),
-                    err
-                );
+        let options = UdpPunctureOptions::new();
 
-                udp.unbind(udp_bind.sender_address())?;
+        // Start puncture
+        let bind_address = udp_bind.sender_address().clone();
+        let puncture = match udp.create_puncture(ctx, udp_bind, |ctx, udp_bind| {
+            UdpPuncture::create(
+                ctx,
+                udp_bind,
+                their_endpoint.udp_address,
+                my_remote_address,
+                their_endpoint.remote_address,
+                options,
+                false,
+            )
+        }) {
+            Ok(puncture) => puncture,
+            Err(err) => {
+                udp.unbind(&bind_address)?;
                 return Err(err);
             }
         };
 
-        let options = UdpPunctureOptions::new();
-
-        // Start puncture
-        let puncture = UdpPuncture::create(
-            ctx,
-            udp_bind,
-            response.responder_udp_public_address,
-            my_remote_address.clone(),
-            Address::from(response.responder_remote_address),
-            options,
-            false,
-        )?;
-
         Ok(puncture)
     }
 }
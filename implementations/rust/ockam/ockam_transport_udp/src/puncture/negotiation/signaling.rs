@@ -0,0 +1,207 @@
+use crate::puncture::negotiation::message::{
+    UdpPunctureNegotiationMessageAcknowledge, UdpPunctureNegotiationMessageInitiate,
+};
+use crate::PunctureError;
+use ockam_core::errcode::Kind;
+use ockam_core::{async_trait, Address, AllowAll, Result, Route};
+use ockam_node::{Context, MessageReceiveOptions};
+use std::time::{Duration, Instant};
+use tracing::{debug, error};
+
+/// Endpoint of one side of a UDP puncture, exchanged with the other side before the puncture
+/// is started
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PunctureEndpoint {
+    /// Public address of the UDP bind, as seen by the Rendezvous service
+    pub udp_address: String,
+    /// Address of the puncture worker, messages received through the puncture are sent to it
+    pub remote_address: Address,
+}
+
+impl PunctureEndpoint {
+    /// Constructor
+    pub fn new(udp_address: impl Into<String>, remote_address: impl Into<Address>) -> Self {
+        Self {
+            udp_address: udp_address.into(),
+            remote_address: remote_address.into(),
+        }
+    }
+}
+
+/// Out-of-band exchange of the [`PunctureEndpoint`]s of both sides of a UDP puncture
+///
+/// Implement it to rely on an existing control plane, or use [`RoutePunctureSignaling`] to talk
+/// to the [`UdpPunctureNegotiationListener`](crate::UdpPunctureNegotiationListener) of the
+/// other node.
+#[async_trait]
+pub trait PunctureSignaling: Send + Sync + 'static {
+    /// Send our endpoint to the other side and return its endpoint
+    async fn exchange(
+        &self,
+        ctx: &Context,
+        my_endpoint: PunctureEndpoint,
+    ) -> Result<PunctureEndpoint>;
+}
+
+/// [`PunctureSignaling`] with the [`UdpPunctureNegotiationListener`](crate::UdpPunctureNegotiationListener)
+/// of the other node, usually reached through a secure channel (e.g. Relayed connection
+/// through the Ockam Orchestrator)
+pub struct RoutePunctureSignaling {
+    onward_route: Route,
+    acknowledgment_timeout: Duration,
+}
+
+impl RoutePunctureSignaling {
+    /// Constructor
+    pub fn new(onward_route: Route, acknowledgment_timeout: Duration) -> Self {
+        Self {
+            onward_route,
+            acknowledgment_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl PunctureSignaling for RoutePunctureSignaling {
+    async fn exchange(
+        &self,
+        ctx: &Context,
+        my_endpoint: PunctureEndpoint,
+    ) -> Result<PunctureEndpoint> {
+        let next = self.onward_route.next()?.clone();
+
+        let address = Address::random_tagged("UdpPunctureNegotiator.initiator");
+        let mut child_ctx = ctx.new_detached(address, AllowAll, AllowAll)?;
+
+        if let Some(flow_control_id) = ctx
+            .flow_controls()
+            .find_flow_control_with_producer_address(&next)
+            .map(|x| x.flow_control_id().clone())
+        {
+            // To be able to receive the response
+            ctx.flow_controls()
+                .add_consumer(child_ctx.primary_address(), &flow_control_id);
+        }
+
+        debug!(
+            "Sending UdpPunctureNegotiation Initiate from {}",
+            child_ctx.primary_address()
+        );
+
+        let started_at = Instant::now();
+        child_ctx
+            .send(
+                self.onward_route.clone(),
+                UdpPunctureNegotiationMessageInitiate {
+                    initiator_udp_public_address: my_endpoint.udp_address,
+                    initiator_remote_address: my_endpoint.remote_address.to_vec(),
+                },
+            )
+            .await?;
+
+        let response = match child_ctx
+            .receive_extended::<UdpPunctureNegotiationMessageAcknowledge>(
+                MessageReceiveOptions::new().with_timeout(self.acknowledgment_timeout),
+            )
+            .await
+            .and_then(|response| response.into_body())
+        {
+            Ok(response) => response,
+            Err(err) => {
+                let err = if err.code().kind == Kind::Timeout {
+                    PunctureError::SignalingTimeout(started_at.elapsed()).into()
+                } else {
+                    err
+                };
+                error!(
+                    "Error receiving response for Udp Puncture at: {}. {}",
+                    child_ctx.primary_address(),
+                    err
+                );
+                return Err(err);
+            }
+        };
+
+        Ok(PunctureEndpoint::new(
+            response.responder_udp_public_address,
+            Address::from(response.responder_remote_address),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PunctureEndpoint, PunctureSignaling, RoutePunctureSignaling};
+    use crate::puncture::negotiation::message::{
+        UdpPunctureNegotiationMessageAcknowledge, UdpPunctureNegotiationMessageInitiate,
+    };
+    use crate::PunctureError;
+    use ockam_core::errcode::Kind;
+    use ockam_core::{async_trait, route, Address, Result, Routed, Worker};
+    use ockam_node::{Context, NullWorker, WorkerBuilder};
+    use std::error::Error as _;
+    use std::time::Duration;
+
+    struct Responder;
+
+    #[async_trait]
+    impl Worker for Responder {
+        type Message = UdpPunctureNegotiationMessageInitiate;
+        type Context = Context;
+
+        async fn handle_message(
+            &mut self,
+            ctx: &mut Context,
+            msg: Routed<Self::Message>,
+        ) -> Result<()> {
+            let initiate = msg.body()?;
+            assert_eq!(initiate.initiator_udp_public_address, "1.2.3.4:5000");
+
+            ctx.reply(
+                &msg,
+                UdpPunctureNegotiationMessageAcknowledge {
+                    responder_udp_public_address: "5.6.7.8:6000".to_string(),
+                    responder_remote_address: Address::from_string("responder").to_vec(),
+                },
+            )
+            .await
+        }
+    }
+
+    #[ockam_macros::test]
+    async fn route_signaling_exchanges_endpoints(ctx: &mut Context) -> Result<()> {
+        WorkerBuilder::new(Responder)
+            .with_address("listener")
+            .start(ctx)?;
+
+        let signaling = RoutePunctureSignaling::new(route!["listener"], Duration::from_secs(5));
+        let their_endpoint = signaling
+            .exchange(ctx, PunctureEndpoint::new("1.2.3.4:5000", "initiator"))
+            .await?;
+
+        assert_eq!(
+            their_endpoint,
+            PunctureEndpoint::new("5.6.7.8:6000", "responder")
+        );
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn route_signaling_times_out(ctx: &mut Context) -> Result<()> {
+        ctx.start_worker("listener", NullWorker)?;
+
+        let signaling = RoutePunctureSignaling::new(route!["listener"], Duration::from_millis(100));
+        let err = signaling
+            .exchange(ctx, PunctureEndpoint::new("1.2.3.4:5000", "initiator"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.code().kind, Kind::Timeout);
+        let source = err.source().and_then(|e| e.downcast_ref::<PunctureError>());
+        assert!(matches!(
+            source,
+            Some(PunctureError::SignalingTimeout(elapsed)) if *elapsed >= Duration::from_millis(100)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::Clock;
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Address, AllowOnwardAddress, DenyAll, Mailboxes, Result};
+use ockam_node::Context;
+use tokio::task::JoinHandle;
+use tracing::{trace, warn};
+
+/// Heartbeat of a puncture, sent to its receiver worker after a delay measured with the
+/// [`Clock`] of the bind, so that the punctures can be tested with a
+/// [`MockClock`](crate::MockClock)
+///
+/// Like [`DelayedEvent`](ockam_node::DelayedEvent), only one heartbeat is scheduled at a
+/// time, and dropping this handle cancels the scheduled heartbeat.
+pub(crate) struct Heartbeat {
+    ctx: Arc<Context>,
+    destination_addr: Address,
+    clock: Arc<dyn Clock>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.cancel()
+    }
+}
+
+impl Heartbeat {
+    /// Create a heartbeat
+    pub(crate) fn create(
+        ctx: &Context,
+        destination_addr: Address,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self> {
+        let mailboxes = Mailboxes::primary(
+            Address::random_tagged("UdpPunctureHeartbeat"),
+            Arc::new(DenyAll),
+            Arc::new(AllowOnwardAddress(destination_addr.clone())),
+        );
+        let child_ctx = ctx.new_detached_with_mailboxes(mailboxes)?;
+
+        Ok(Self {
+            ctx: Arc::new(child_ctx),
+            destination_addr,
+            clock,
+            handle: None,
+        })
+    }
+
+    /// Address used to send the heartbeats to the receiver worker
+    pub(crate) fn address(&self) -> &Address {
+        self.ctx.primary_address()
+    }
+
+    /// Cancel the scheduled heartbeat
+    pub(crate) fn cancel(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort()
+        }
+    }
+
+    /// Schedule a heartbeat, cancelling the one already scheduled
+    pub(crate) fn schedule(&mut self, duration: Duration) {
+        self.cancel();
+
+        let sleep = self.clock.sleep(duration);
+        let ctx = self.ctx.clone();
+        let destination_addr = self.destination_addr.clone();
+
+        self.handle = Some(self.ctx.runtime().spawn(async move {
+            sleep.await;
+
+            match ctx.send(destination_addr.clone(), ()).await {
+                Ok(()) => trace!("Sent heartbeat message to {}", destination_addr),
+                Err(_) => warn!("Error sending heartbeat message to {}", destination_addr),
+            }
+        }));
+    }
+}
@@ -2,12 +2,17 @@ pub use addresses::*;
 pub use options::*;
 pub use puncture::*;
 pub(crate) use receiver::*;
+pub use state::*;
 
 mod addresses;
+mod heartbeat;
 mod message;
 mod notification;
 mod options;
+mod peer;
 #[allow(clippy::module_inception)]
 mod puncture;
 mod receiver;
+mod rtt;
 mod sender;
+mod state;
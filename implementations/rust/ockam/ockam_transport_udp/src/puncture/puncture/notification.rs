@@ -1,3 +1,4 @@
+use crate::PunctureError;
 use ockam_core::compat::time::Duration;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Route;
@@ -10,6 +11,8 @@ use tokio::sync::broadcast::error::RecvError;
 pub enum UdpPunctureNotification {
     Open(Route),
     Closed,
+    /// The puncture was closed because it wasn't open in time
+    Failed(PunctureError),
 }
 
 pub async fn wait_for_puncture(
@@ -30,6 +33,7 @@ pub async fn wait_for_puncture(
                             "UDP puncture was closed",
                         ))
                     }
+                    UdpPunctureNotification::Failed(err) => return Err(err.into()),
                 },
                 Err(err) => match err {
                     RecvError::Closed => {
@@ -45,11 +49,5 @@ pub async fn wait_for_puncture(
         }
     })
     .await
-    .map_err(|_| {
-        Error::new(
-            Origin::Transport,
-            Kind::Cancelled,
-            format!("Timeout {:?} elapsed waiting for UDP puncture", timeout),
-        )
-    })?
+    .map_err(|_| Error::from(PunctureError::PunchTimeout(timeout)))?
 }
@@ -10,6 +10,7 @@ use core::fmt::Formatter;
 pub struct UdpPunctureOptions {
     pub(crate) flow_control_id: FlowControlId,
     pub(crate) spawner_flow_control_id: Option<FlowControlId>,
+    pub(crate) redirect_first_message_to_transport: Option<bool>,
 }
 
 impl fmt::Debug for UdpPunctureOptions {
@@ -25,6 +26,7 @@ impl UdpPunctureOptions {
         Self {
             flow_control_id: FlowControls::generate_flow_control_id(),
             spawner_flow_control_id: None,
+            redirect_first_message_to_transport: None,
         }
     }
 
@@ -35,9 +37,22 @@ impl UdpPunctureOptions {
         Self {
             flow_control_id: FlowControls::generate_flow_control_id(),
             spawner_flow_control_id: Some(spawner_flow_control_id),
+            redirect_first_message_to_transport: None,
         }
     }
 
+    /// Require the first messages of the puncture to be sent to the peer's UDP transport, or
+    /// to its puncture worker
+    ///
+    /// Creating the puncture fails with [`PunctureError::ConflictingOptions`] if it's created
+    /// with another behaviour, for example when resuming it, which never redirects.
+    ///
+    /// [`PunctureError::ConflictingOptions`]: crate::PunctureError::ConflictingOptions
+    pub fn with_redirect_first_message_to_transport(mut self, redirect: bool) -> Self {
+        self.redirect_first_message_to_transport = Some(redirect);
+        self
+    }
+
     /// Freshly generated [`FlowControlId`]
     pub fn producer_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
@@ -0,0 +1,85 @@
+use crate::UDP;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{route, Address, Route};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// UDP address of the peer of a puncture, shared by the puncture workers and updated by
+/// [`UdpPuncture::update_peer_address`](crate::UdpPuncture::update_peer_address)
+#[derive(Clone, Debug)]
+pub(crate) struct PuncturePeer {
+    inner: Arc<Mutex<PuncturePeerAddress>>,
+}
+
+#[derive(Debug)]
+struct PuncturePeerAddress {
+    udp_address: String,
+    /// Incremented each time the address is updated
+    version: u64,
+    /// Time given to the peer to confirm the updated address
+    confirm_timeout: Duration,
+}
+
+impl PuncturePeer {
+    pub(crate) fn new(udp_address: String) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(PuncturePeerAddress {
+                udp_address,
+                version: 0,
+                confirm_timeout: Duration::ZERO,
+            })),
+        }
+    }
+
+    /// Current UDP address of the peer
+    pub(crate) fn udp_address(&self) -> String {
+        self.inner.lock().unwrap().udp_address.clone()
+    }
+
+    /// Version of the address, incremented at each update
+    pub(crate) fn version(&self) -> u64 {
+        self.inner.lock().unwrap().version
+    }
+
+    /// Time given to the peer to confirm the last update
+    pub(crate) fn confirm_timeout(&self) -> Duration {
+        self.inner.lock().unwrap().confirm_timeout
+    }
+
+    /// Repoint the puncture to a new address, which must be confirmed by the peer within
+    /// `confirm_timeout`
+    pub(crate) fn update(&self, udp_address: String, confirm_timeout: Duration) {
+        let mut peer = self.inner.lock().unwrap();
+        peer.udp_address = udp_address;
+        peer.version += 1;
+        peer.confirm_timeout = confirm_timeout;
+    }
+
+    /// Route to the puncture worker of the peer, through the given bind
+    pub(crate) fn route(
+        &self,
+        bind_sender_address: &Address,
+        recipient_address: &Address,
+    ) -> Route {
+        route![
+            bind_sender_address.clone(),
+            Address::new_with_string(UDP, self.udp_address()),
+            recipient_address.clone()
+        ]
+    }
+
+    /// Return false if the route of a message from the peer shows that it was sent from
+    /// another address than the current one. Addresses which are not socket addresses, for
+    /// example hostnames, can't be compared and are accepted
+    pub(crate) fn is_sender_of(&self, return_route: &Route) -> bool {
+        let Ok(peer_address) = self.udp_address().parse::<SocketAddr>() else {
+            return true;
+        };
+
+        return_route
+            .iter()
+            .find(|address| address.transport_type() == UDP)
+            .and_then(|address| address.address().parse::<SocketAddr>().ok())
+            .map_or(true, |sender_address| sender_address == peer_address)
+    }
+}
@@ -1,12 +1,15 @@
 use crate::puncture::puncture::notification::{wait_for_puncture, UdpPunctureNotification};
+use crate::puncture::puncture::peer::PuncturePeer;
+use crate::puncture::puncture::rtt::RttEstimator;
 use crate::puncture::puncture::Addresses;
 use crate::puncture::UdpPunctureReceiverWorker;
-use crate::{UdpBind, UdpPunctureOptions};
+use crate::{PunctureError, PunctureState, UdpBind, UdpPunctureOptions};
 use ockam_core::compat::time::Duration;
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::{Address, Result};
+use ockam_core::{Address, Result, Route};
 use ockam_node::Context;
 use tokio::sync::broadcast;
+use tracing::{error, info, warn};
 
 /// Individual puncture with a specified peer.
 ///
@@ -19,6 +22,9 @@ pub struct UdpPuncture {
     notify_puncture_open_receiver: broadcast::Receiver<UdpPunctureNotification>,
     addresses: Addresses,
     flow_control_id: FlowControlId,
+    state: PunctureState,
+    peer: PuncturePeer,
+    rtt: RttEstimator,
 }
 
 // TODO: PUNCTURE make keepalives adjustable
@@ -37,25 +43,92 @@ impl UdpPuncture {
         // See comments at the point of usage
         redirect_first_message_to_transport: bool,
     ) -> Result<UdpPuncture> {
+        let state = PunctureState {
+            bind_address: bind.bind_address().to_string(),
+            peer_udp_address,
+            my_remote_address,
+            their_remote_address,
+        };
+
+        Self::create_impl(
+            ctx,
+            bind,
+            state,
+            options,
+            redirect_first_message_to_transport,
+            None,
+        )
+    }
+
+    /// Resume a puncture with a peer which was already confirmed, reusing its NAT mapping
+    ///
+    /// The puncture is closed if the peer doesn't answer within `open_timeout`.
+    pub(crate) fn resume(
+        ctx: &Context,
+        bind: UdpBind,
+        state: PunctureState,
+        options: UdpPunctureOptions,
+        open_timeout: Duration,
+    ) -> Result<UdpPuncture> {
+        if bind.bind_address().to_string() != state.bind_address {
+            warn!(
+                "Resuming the puncture with {} from {}, while it was opened from {}. The NAT mapping may not be reused",
+                state.peer_udp_address,
+                bind.bind_address(),
+                state.bind_address
+            );
+        }
+
+        // The peer's puncture worker is already running, no need to redirect to its transport
+        Self::create_impl(ctx, bind, state, options, false, Some(open_timeout))
+    }
+
+    fn create_impl(
+        ctx: &Context,
+        bind: UdpBind,
+        state: PunctureState,
+        options: UdpPunctureOptions,
+        redirect_first_message_to_transport: bool,
+        open_timeout: Option<Duration>,
+    ) -> Result<UdpPuncture> {
+        if let Some(redirect) = options.redirect_first_message_to_transport {
+            if redirect != redirect_first_message_to_transport {
+                error!(
+                    peer = %state.peer_udp_address,
+                    "The puncture options require redirect_first_message_to_transport to be {}, but the puncture is created with {}",
+                    redirect,
+                    redirect_first_message_to_transport
+                );
+                return Err(PunctureError::ConflictingOptions)?;
+            }
+        }
+
         let flow_control_id = options.producer_flow_control_id();
 
-        let addresses = Addresses::generate(my_remote_address);
+        let addresses = Addresses::generate(state.my_remote_address.clone());
+        let peer = PuncturePeer::new(state.peer_udp_address.clone());
+        let rtt = RttEstimator::default();
         let (notify_puncture_open_sender, notify_puncture_open_receiver) = broadcast::channel(1);
         UdpPunctureReceiverWorker::create(
             ctx,
             bind,
-            peer_udp_address,
-            their_remote_address,
+            peer.clone(),
+            state.their_remote_address.clone(),
             addresses.clone(),
             notify_puncture_open_sender,
             options,
             redirect_first_message_to_transport,
+            open_timeout,
+            rtt.clone(),
         )?;
 
         Ok(UdpPuncture {
             notify_puncture_open_receiver,
             addresses,
             flow_control_id,
+            state,
+            peer,
+            rtt,
         })
     }
 
@@ -64,16 +137,69 @@ impl UdpPuncture {
     ///
     /// TODO: PUNCTURE optimize to return immediately if the puncture is open
     pub async fn wait_for_puncture(&mut self, timeout: Duration) -> Result<()> {
-        _ = wait_for_puncture(&mut self.notify_puncture_open_receiver, timeout).await?;
+        _ = self.wait_for_puncture_route(timeout).await?;
 
         Ok(())
     }
 
+    /// Wait until puncture succeeds and return the route to the peer
+    pub(crate) async fn wait_for_puncture_route(&mut self, timeout: Duration) -> Result<Route> {
+        wait_for_puncture(&mut self.notify_puncture_open_receiver, timeout).await
+    }
+
+    /// Repoint the puncture to a new UDP address of the peer, for example when the peer
+    /// switched networks and its NAT mapping changed
+    ///
+    /// The keepalives and the messages sent through the puncture go to the new address right
+    /// away. Only the pongs received from the new address confirm it: if the peer doesn't
+    /// answer within `timeout`, the puncture is closed and an error is returned.
+    pub async fn update_peer_address(
+        &mut self,
+        peer_udp_address: impl Into<String>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let peer_udp_address = peer_udp_address.into();
+        info!(
+            "Updating the puncture peer address from {} to {}",
+            self.state.peer_udp_address, peer_udp_address
+        );
+
+        // Drop the notifications received so far, they were sent for the previous address
+        self.notify_puncture_open_receiver = self.notify_puncture_open_receiver.resubscribe();
+        self.peer.update(peer_udp_address.clone(), timeout);
+        self.state.peer_udp_address = peer_udp_address;
+
+        // Give a bit more time than the receiver worker, which closes the puncture on timeout
+        self.wait_for_puncture(timeout + Duration::from_secs(2))
+            .await
+    }
+
+    /// Smoothed round-trip time to the peer, measured with the keepalive pings of the
+    /// puncture, `None` until the first pong is received
+    ///
+    /// This can be used to derive the timeouts of the messages sent through the puncture.
+    /// The estimate is reset when the peer address is updated.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt.get()
+    }
+
+    /// State needed to resume this puncture later, for example after a restart
+    ///
+    /// Should be exported once the puncture is open, see [`Self::wait_for_puncture`].
+    pub fn export_state(&self) -> PunctureState {
+        self.state.clone()
+    }
+
     /// Address of the Sender Worker
     pub fn sender_address(&self) -> Address {
         self.addresses.sender_address().clone()
     }
 
+    /// Address of the Receiver Worker, which runs as long as the puncture
+    pub(crate) fn receiver_address(&self) -> &Address {
+        self.addresses.receiver_address()
+    }
+
     /// Stop the receiver (which will shut down everything else as well)
     pub fn stop(&self, ctx: &Context) -> Result<()> {
         ctx.stop_address(self.addresses.receiver_address())
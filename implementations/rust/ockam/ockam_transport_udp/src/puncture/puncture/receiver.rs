@@ -1,5 +1,8 @@
+use crate::puncture::puncture::heartbeat::Heartbeat;
 use crate::puncture::puncture::message::PunctureMessage;
 use crate::puncture::puncture::notification::UdpPunctureNotification;
+use crate::puncture::puncture::peer::PuncturePeer;
+use crate::puncture::puncture::rtt::RttEstimator;
 use crate::puncture::puncture::sender::UdpPunctureSenderWorker;
 use crate::puncture::puncture::{Addresses, UdpPunctureOptions};
 use crate::{PunctureError, UdpBind, UDP};
@@ -8,7 +11,7 @@ use ockam_core::{
     route, Address, AllowAll, AllowSourceAddress, Any, Decodable, DenyAll, LocalMessage, Mailbox,
     Mailboxes, Result, Route, Routed, Worker,
 };
-use ockam_node::{Context, DelayedEvent, WorkerBuilder};
+use ockam_node::{Context, WorkerBuilder};
 use std::time::{Duration, Instant};
 use tokio::sync::broadcast::Sender;
 use tracing::log::warn;
@@ -32,13 +35,17 @@ pub(crate) struct UdpPunctureReceiverWorker {
     /// All Addresses used in this puncture
     addresses: Addresses,
     /// For generating internal heartbeat messages
-    heartbeat: DelayedEvent<()>,
+    heartbeat: Heartbeat,
     /// Is puncture open?
     puncture_open: bool,
     /// Notify that puncture is open those who wait for it
     notify_puncture_open_sender: Sender<UdpPunctureNotification>,
     /// Peer's UDP address
-    peer_udp_address: String,
+    peer: PuncturePeer,
+    /// Version of the peer's address used so far
+    peer_version: u64,
+    /// The peer's address was updated, only its pongs can confirm the puncture
+    confirming_peer_update: bool,
     /// Timestamp of most recent message received from peer
     peer_received_at: Instant,
     /// If we have received the first ping
@@ -50,6 +57,14 @@ pub(crate) struct UdpPunctureReceiverWorker {
     // that `UdpPunctureReceiverWorker` was started on the other side
     // See comments at the point of usage
     redirect_first_message_to_transport: bool,
+    /// The puncture is closed if it isn't open by then
+    open_deadline: Option<Instant>,
+    /// Time the puncture started waiting to be open, or for the peer to confirm its address
+    opening_since: Instant,
+    /// Time the last ping was sent to the peer's puncture worker, until its pong is received
+    ping_sent_at: Option<Instant>,
+    /// Round-trip time measured with the pings
+    rtt: RttEstimator,
 }
 
 impl UdpPunctureReceiverWorker {
@@ -57,14 +72,20 @@ impl UdpPunctureReceiverWorker {
     pub(crate) fn create(
         ctx: &Context,
         bind: UdpBind,
-        peer_udp_address: String,
+        peer: PuncturePeer,
         recipient_address: Address,
         addresses: Addresses,
         notify_puncture_open_sender: Sender<UdpPunctureNotification>,
         options: UdpPunctureOptions,
         redirect_first_message_to_transport: bool,
+        open_timeout: Option<Duration>,
+        rtt: RttEstimator,
     ) -> Result<()> {
-        let heartbeat = DelayedEvent::create(ctx, addresses.heartbeat_address().clone(), ())?;
+        let heartbeat = Heartbeat::create(
+            ctx,
+            addresses.heartbeat_address().clone(),
+            bind.clock().clone(),
+        )?;
 
         let remote_mailbox = Mailbox::new(
             addresses.remote_address().clone(),
@@ -89,7 +110,12 @@ impl UdpPunctureReceiverWorker {
             Arc::new(DenyAll),
         );
 
-        let sender_worker = UdpPunctureSenderWorker::new(notify_puncture_open_sender.subscribe());
+        let sender_worker = UdpPunctureSenderWorker::new(
+            notify_puncture_open_sender.subscribe(),
+            bind.sender_address().clone(),
+            recipient_address.clone(),
+            peer.clone(),
+        );
 
         WorkerBuilder::new(sender_worker)
             .with_address(addresses.sender_address().clone())
@@ -98,17 +124,24 @@ impl UdpPunctureReceiverWorker {
             .start(ctx)?;
 
         // Create and start worker
+        let now = bind.clock().now();
         let receiver_worker = Self {
             bind,
             addresses: addresses.clone(),
             heartbeat,
             puncture_open: false,
             notify_puncture_open_sender,
-            peer_udp_address,
-            peer_received_at: Instant::now(),
+            peer_version: peer.version(),
+            peer,
+            confirming_peer_update: false,
+            peer_received_at: now,
             first_ping_received: false,
             recipient_address,
             redirect_first_message_to_transport,
+            open_deadline: open_timeout.map(|timeout| now + timeout),
+            opening_since: now,
+            ping_sent_at: None,
+            rtt,
         };
 
         WorkerBuilder::new(receiver_worker)
@@ -125,22 +158,47 @@ impl UdpPunctureReceiverWorker {
     async fn set_puncture_open(&mut self) -> Result<()> {
         if !self.puncture_open {
             self.puncture_open = true;
+            self.bind.stats().record_puncture_succeeded();
 
-            info!("Puncture succeeded. Peer address={}", self.peer_udp_address);
+            info!(
+                "Puncture succeeded. Peer address={}",
+                self.peer.udp_address()
+            );
         }
 
         // Even if puncture was already open - let's notify everyone that it's still open
         let _ = self
             .notify_puncture_open_sender
-            .send(UdpPunctureNotification::Open(route![
-                self.bind.sender_address().clone(),
-                Address::new_with_string(UDP, self.peer_udp_address.clone()),
-                self.recipient_address.clone()
-            ]));
+            .send(UdpPunctureNotification::Open(
+                self.peer
+                    .route(self.bind.sender_address(), &self.recipient_address),
+            ));
 
         Ok(())
     }
 
+    /// Wait for the peer to confirm its new address if it was updated, see
+    /// [`UdpPuncture::update_peer_address`](crate::UdpPuncture::update_peer_address)
+    fn sync_peer(&mut self, now: Instant) {
+        let version = self.peer.version();
+        if version == self.peer_version {
+            return;
+        }
+        self.peer_version = version;
+
+        info!(
+            "Puncture peer address updated to {}. Waiting for the peer to confirm it",
+            self.peer.udp_address()
+        );
+        self.puncture_open = false;
+        self.confirming_peer_update = true;
+        self.ping_sent_at = None;
+        self.rtt.reset();
+        self.peer_received_at = now;
+        self.open_deadline = Some(now + self.peer.confirm_timeout());
+        self.opening_since = now;
+    }
+
     /// Handle messages from peer
     async fn handle_peer(
         &mut self,
@@ -153,7 +211,8 @@ impl UdpPunctureReceiverWorker {
 
         // Record contact with peer, but only for pong and payload message.
         // Ping message doesn't guarantee that the other side is reachable
-        let now = Instant::now();
+        let now = self.bind.clock().now();
+        self.sync_peer(now);
 
         // Handle message
         match msg {
@@ -168,8 +227,23 @@ impl UdpPunctureReceiverWorker {
                 .await?;
             }
             PunctureMessage::Pong => {
+                // Pongs sent to the previous address of the peer don't confirm the new one
+                if self.confirming_peer_update && !self.peer.is_sender_of(return_route) {
+                    trace!("Received Pong from the previous peer address. Ignoring it");
+                    return Ok(());
+                }
+
                 trace!("Received Pong from peer. Setting as puncture is open");
+                self.confirming_peer_update = false;
                 self.peer_received_at = now;
+
+                // Pongs carry no identifier, a pong is matched with the last ping. With
+                // a round-trip time longer than the heartbeat interval, the samples are
+                // underestimated
+                if let Some(ping_sent_at) = self.ping_sent_at.take() {
+                    self.rtt
+                        .add_sample(now.saturating_duration_since(ping_sent_at));
+                }
                 self.set_puncture_open().await?;
             }
             PunctureMessage::Payload {
@@ -201,21 +275,34 @@ impl UdpPunctureReceiverWorker {
         trace!(
             "Puncture Heartbeat: puncture_open = {:?}, Peer UDP Address = {:?}",
             self.puncture_open,
-            self.peer_udp_address
+            self.peer.udp_address()
         );
 
+        let now = self.bind.clock().now();
+        self.sync_peer(now);
+
         // If we have not heard from peer for a while, consider puncture as closed
-        if self.puncture_open && self.peer_received_at.elapsed() >= PUNCTURE_OPEN_TIMEOUT {
+        if self.puncture_open
+            && now.saturating_duration_since(self.peer_received_at) >= PUNCTURE_OPEN_TIMEOUT
+        {
             warn!("Haven't received pongs from the peer for more than {:?}. Shutting down the puncture.", PUNCTURE_OPEN_TIMEOUT);
+            return self.close(ctx, UdpPunctureNotification::Closed);
+        }
 
-            _ = self
-                .notify_puncture_open_sender
-                .send(UdpPunctureNotification::Closed);
-
-            // Shut down itself
-            ctx.stop_address(self.addresses.remote_address())?;
-
-            return Ok(());
+        // A resumed puncture, or a puncture with an updated peer address, should be open
+        // quickly, otherwise the NAT mapping has expired
+        if !self.puncture_open && self.open_deadline.is_some_and(|deadline| now >= deadline) {
+            warn!(
+                "Puncture to {} wasn't confirmed by the peer. Shutting down the puncture.",
+                self.peer.udp_address()
+            );
+            let elapsed = now.saturating_duration_since(self.opening_since);
+            let error = if self.confirming_peer_update {
+                PunctureError::ConfirmTimeout(elapsed)
+            } else {
+                PunctureError::PunchTimeout(elapsed)
+            };
+            return self.close(ctx, UdpPunctureNotification::Failed(error));
         }
 
         // Do keepalive pings to try and keep the puncture open
@@ -225,16 +312,16 @@ impl UdpPunctureReceiverWorker {
         // on the other side, until we receive the first ping, which guarantees
         // that `UdpPunctureReceiverWorker` was started on the other side
         let route = if !self.first_ping_received && self.redirect_first_message_to_transport {
+            // The peer's transport doesn't answer, no round-trip time can be measured
+            self.ping_sent_at = None;
             route![
                 self.bind.sender_address().clone(),
-                Address::new_with_string(UDP, self.peer_udp_address.clone())
+                Address::new_with_string(UDP, self.peer.udp_address())
             ]
         } else {
-            route![
-                self.bind.sender_address().clone(),
-                Address::new_with_string(UDP, self.peer_udp_address.clone()),
-                self.recipient_address.clone()
-            ]
+            self.ping_sent_at = Some(now);
+            self.peer
+                .route(self.bind.sender_address(), &self.recipient_address)
         };
 
         ctx.send_from_address(
@@ -247,12 +334,24 @@ impl UdpPunctureReceiverWorker {
         Ok(())
     }
 
+    /// Notify that the puncture failed and shut down
+    fn close(&self, ctx: &mut Context, notification: UdpPunctureNotification) -> Result<()> {
+        self.bind.stats().record_puncture_failed();
+
+        _ = self.notify_puncture_open_sender.send(notification);
+
+        // Shut down itself
+        ctx.stop_address(self.addresses.remote_address())?;
+
+        Ok(())
+    }
+
     /// Handle heartbeat messages
     async fn handle_heartbeat(&mut self, ctx: &mut Context) -> Result<()> {
         let res = self.handle_heartbeat_impl(ctx).await;
 
         // Schedule next heartbeat here in case something errors
-        self.heartbeat.schedule(HEARTBEAT_INTERVAL)?;
+        self.heartbeat.schedule(HEARTBEAT_INTERVAL);
 
         res
     }
@@ -264,7 +363,9 @@ impl Worker for UdpPunctureReceiverWorker {
     type Context = Context;
 
     async fn initialize(&mut self, _context: &mut Self::Context) -> Result<()> {
-        self.heartbeat.schedule(Duration::ZERO)
+        self.heartbeat.schedule(Duration::ZERO);
+
+        Ok(())
     }
 
     async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
@@ -0,0 +1,53 @@
+use ockam_core::compat::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Smoothed round-trip time of a puncture, measured with its keepalive pings, see
+/// [`UdpPuncture::rtt`](crate::UdpPuncture::rtt)
+///
+/// The samples are smoothed as in RFC 6298: `srtt = 7/8 * srtt + 1/8 * sample`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RttEstimator {
+    srtt: Arc<Mutex<Option<Duration>>>,
+}
+
+impl RttEstimator {
+    /// Current estimate, `None` until the first sample
+    pub(crate) fn get(&self) -> Option<Duration> {
+        *self.srtt.lock().unwrap()
+    }
+
+    /// Take a new round-trip time measurement into account
+    pub(crate) fn add_sample(&self, sample: Duration) {
+        let mut srtt = self.srtt.lock().unwrap();
+        *srtt = Some(match *srtt {
+            None => sample,
+            Some(srtt) => (srtt * 7 + sample) / 8,
+        });
+    }
+
+    /// Forget the previous measurements, when the path to the peer changes
+    pub(crate) fn reset(&self) {
+        *self.srtt.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smoothed_rtt() {
+        let rtt = RttEstimator::default();
+        assert_eq!(rtt.get(), None);
+
+        rtt.add_sample(Duration::from_millis(80));
+        assert_eq!(rtt.get(), Some(Duration::from_millis(80)));
+
+        rtt.add_sample(Duration::from_millis(160));
+        assert_eq!(rtt.get(), Some(Duration::from_millis(90)));
+
+        // The estimate is shared between the clones
+        rtt.clone().reset();
+        assert_eq!(rtt.get(), None);
+    }
+}
@@ -1,7 +1,8 @@
 use crate::puncture::puncture::message::PunctureMessage;
 use crate::puncture::puncture::notification::{wait_for_puncture, UdpPunctureNotification};
+use crate::puncture::puncture::peer::PuncturePeer;
 use crate::PunctureError;
-use ockam_core::{Any, Encodable, LocalMessage, Result, Route, Routed, Worker};
+use ockam_core::{Address, Any, Encodable, LocalMessage, Result, Routed, Worker};
 use ockam_node::Context;
 use std::time::Duration;
 use tokio::sync::broadcast::Receiver;
@@ -10,24 +11,42 @@ use tracing::trace;
 /// Worker that forwards messages from our node to the other side of the puncture.
 pub(crate) struct UdpPunctureSenderWorker {
     notify_puncture_open_receiver: Receiver<UdpPunctureNotification>,
-    peer_route: Option<Route>,
+    /// UDP transport sender used to reach the peer
+    bind_sender_address: Address,
+    /// The other node UdpPunctureWorker address
+    recipient_address: Address,
+    /// Peer's UDP address, which may be updated while the puncture is open
+    peer: PuncturePeer,
+    puncture_open: bool,
 }
 
 impl UdpPunctureSenderWorker {
-    pub fn new(notify_puncture_open_receiver: Receiver<UdpPunctureNotification>) -> Self {
+    pub fn new(
+        notify_puncture_open_receiver: Receiver<UdpPunctureNotification>,
+        bind_sender_address: Address,
+        recipient_address: Address,
+        peer: PuncturePeer,
+    ) -> Self {
         Self {
             notify_puncture_open_receiver,
-            peer_route: None,
+            bind_sender_address,
+            recipient_address,
+            peer,
+            puncture_open: false,
         }
     }
 
     async fn handle_local(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
         trace!("UDP puncture forward: Local => Remote: {:?}", msg);
 
+        if !self.puncture_open {
+            return Err(PunctureError::PunctureNotOpen)?;
+        }
+        // Read the peer's address for each message, so that it's repointed as soon as it's
+        // updated
         let peer_route = self
-            .peer_route
-            .clone()
-            .ok_or(PunctureError::PunctureNotOpen)?;
+            .peer
+            .route(&self.bind_sender_address, &self.recipient_address);
 
         let msg = msg.into_local_message();
 
@@ -56,8 +75,8 @@ impl Worker for UdpPunctureSenderWorker {
     type Context = Context;
 
     async fn initialize(&mut self, _ctx: &mut Self::Context) -> Result<()> {
-        self.peer_route =
-            Some(wait_for_puncture(&mut self.notify_puncture_open_receiver, Duration::MAX).await?);
+        wait_for_puncture(&mut self.notify_puncture_open_receiver, Duration::MAX).await?;
+        self.puncture_open = true;
 
         Ok(())
     }
@@ -0,0 +1,51 @@
+use minicbor::{CborLen, Decode, Encode};
+use ockam_core::{Address, Decodable, Encodable, Result};
+
+/// State of an established [`UdpPuncture`](crate::UdpPuncture), which can be persisted to
+/// resume the puncture after a restart with
+/// [`UdpTransport::resume_puncture`](crate::UdpTransport::resume_puncture)
+#[derive(Encode, Decode, CborLen, Debug, Clone, PartialEq, Eq)]
+#[rustfmt::skip]
+pub struct PunctureState {
+    /// Local UDP address the puncture was bound to. The NAT mapping can only be reused
+    /// when binding to the same address again
+    #[n(0)] pub bind_address: String,
+    /// Peer's public UDP address
+    #[n(1)] pub peer_udp_address: String,
+    /// Our puncture worker address, as known by the peer
+    #[n(2)] pub my_remote_address: Address,
+    /// The peer's puncture worker address
+    #[n(3)] pub their_remote_address: Address,
+}
+
+impl Encodable for PunctureState {
+    fn encode(self) -> Result<Vec<u8>> {
+        ockam_core::cbor_encode_preallocate(self)
+    }
+}
+
+impl Decodable for PunctureState {
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(minicbor::decode(data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_puncture_state_roundtrip() -> Result<()> {
+        let state = PunctureState {
+            bind_address: "0.0.0.0:4000".to_string(),
+            peer_udp_address: "1.2.3.4:5000".to_string(),
+            my_remote_address: Address::random_tagged("my"),
+            their_remote_address: Address::random_tagged("their"),
+        };
+
+        let decoded = <PunctureState as Decodable>::decode(&state.clone().encode()?)?;
+        assert_eq!(decoded, state);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use crate::PunctureError;
+use core::time::Duration;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_node::channel_types::{oneshot_channel, OneshotReceiver, OneshotSender};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tracing::trace;
+
+/// Initial retransmission timeout of a Binding request, doubled after each attempt
+/// (RFC 5389, section 7.2.1)
+pub(crate) const STUN_INITIAL_RTO: Duration = Duration::from_millis(500);
+/// Number of Binding requests sent before giving up
+pub(crate) const STUN_MAX_ATTEMPTS: u32 = 4;
+
+const MAGIC_COOKIE: u32 = 0x2112_A442;
+const HEADER_LEN: usize = 20;
+const BINDING_REQUEST: u16 = 0x0001;
+const BINDING_SUCCESS_RESPONSE: u16 = 0x0101;
+const BINDING_ERROR_RESPONSE: u16 = 0x0111;
+const ATTRIBUTE_MAPPED_ADDRESS: u16 = 0x0001;
+const ATTRIBUTE_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+const FAMILY_IPV4: u8 = 0x01;
+const FAMILY_IPV6: u8 = 0x02;
+
+/// Identifier of a STUN request, repeated in its response
+pub(crate) type TransactionId = [u8; 12];
+
+/// Binding requests waiting for the response of a STUN server
+///
+/// The responses are received on the socket of the bind, so they are read by its receivers,
+/// which pass them here instead of decoding them as transport messages.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StunTransactions {
+    pending: Arc<Mutex<HashMap<TransactionId, OneshotSender<Result<SocketAddr>>>>>,
+}
+
+impl StunTransactions {
+    /// Register a new transaction, the receiver gets the address returned by the server
+    pub(crate) fn start(&self) -> (TransactionId, OneshotReceiver<Result<SocketAddr>>) {
+        let transaction_id: TransactionId = rand::random();
+        let (sender, receiver) = oneshot_channel();
+        self.pending.lock().unwrap().insert(transaction_id, sender);
+        (transaction_id, receiver)
+    }
+
+    /// Forget a transaction, once answered or abandoned
+    pub(crate) fn finish(&self, transaction_id: &TransactionId) {
+        self.pending.lock().unwrap().remove(transaction_id);
+    }
+
+    /// Complete the transaction answered by the given datagram
+    ///
+    /// Return `false` if the datagram is not a STUN message, and must be handled as a
+    /// transport message.
+    pub(crate) fn handle_datagram(&self, datagram: &[u8]) -> bool {
+        if !is_stun_message(datagram) {
+            return false;
+        }
+
+        match decode_binding_response(datagram) {
+            Some((transaction_id, result)) => {
+                match self.pending.lock().unwrap().remove(&transaction_id) {
+                    Some(sender) => {
+                        let _ = sender.send(result);
+                    }
+                    None => trace!("Dropping a STUN response for an unknown transaction"),
+                }
+            }
+            None => trace!("Dropping a STUN message which is not a Binding response"),
+        }
+
+        true
+    }
+}
+
+/// Encode a Binding request, without any attribute
+pub(crate) fn encode_binding_request(transaction_id: &TransactionId) -> Vec<u8> {
+    let mut request = Vec::with_capacity(HEADER_LEN);
+    request.extend_from_slice(&BINDING_REQUEST.to_be_bytes());
+    request.extend_from_slice(&0u16.to_be_bytes());
+    request.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(transaction_id);
+    request
+}
+
+/// STUN messages start with two zero bits and contain the magic cookie, which can't be
+/// the case of the transport messages, encoded as CBOR arrays
+fn is_stun_message(datagram: &[u8]) -> bool {
+    datagram.len() >= HEADER_LEN
+        && datagram[0] & 0xC0 == 0
+        && datagram[4..8] == MAGIC_COOKIE.to_be_bytes()
+}
+
+/// Decode the transaction id of a Binding response, and the address it contains, `None`
+/// if the message is not a Binding response
+fn decode_binding_response(datagram: &[u8]) -> Option<(TransactionId, Result<SocketAddr>)> {
+    let message_type = u16::from_be_bytes([datagram[0], datagram[1]]);
+    let mut transaction_id = TransactionId::default();
+    transaction_id.copy_from_slice(&datagram[8..HEADER_LEN]);
+
+    let result = match message_type {
+        BINDING_SUCCESS_RESPONSE => decode_mapped_address(datagram, &transaction_id)
+            .ok_or(PunctureError::StunResponseInvalid),
+        BINDING_ERROR_RESPONSE => Err(PunctureError::StunErrorResponse),
+        _ => return None,
+    };
+
+    Some((transaction_id, result.map_err(Into::into)))
+}
+
+/// Address of a Binding success response, taken from the XOR-MAPPED-ADDRESS attribute,
+/// or from the MAPPED-ADDRESS attribute sent by older servers
+fn decode_mapped_address(datagram: &[u8], transaction_id: &TransactionId) -> Option<SocketAddr> {
+    let length = u16::from_be_bytes([datagram[2], datagram[3]]) as usize;
+    let attributes = datagram.get(HEADER_LEN..HEADER_LEN + length)?;
+
+    let mut mapped_address = None;
+    let mut offset = 0;
+    while offset + 4 <= attributes.len() {
+        let attribute_type = u16::from_be_bytes([attributes[offset], attributes[offset + 1]]);
+        let attribute_length =
+            u16::from_be_bytes([attributes[offset + 2], attributes[offset + 3]]) as usize;
+        let value = attributes.get(offset + 4..offset + 4 + attribute_length)?;
+
+        match attribute_type {
+            ATTRIBUTE_XOR_MAPPED_ADDRESS => {
+                return decode_address(value, Some(transaction_id));
+            }
+            ATTRIBUTE_MAPPED_ADDRESS => {
+                mapped_address = decode_address(value, None);
+            }
+            _ => {}
+        }
+
+        // Attributes are padded to a multiple of 4 bytes
+        offset += 4 + (attribute_length + 3) / 4 * 4;
+    }
+
+    mapped_address
+}
+
+/// Decode the value of a (XOR-)MAPPED-ADDRESS attribute, the transaction id is given for
+/// the XOR-MAPPED-ADDRESS attribute
+fn decode_address(value: &[u8], xor: Option<&TransactionId>) -> Option<SocketAddr> {
+    let family = *value.get(1)?;
+    let mut port = u16::from_be_bytes([*value.get(2)?, *value.get(3)?]);
+
+    // The address is XOR-ed with the magic cookie, followed by the transaction id for IPv6
+    let mut mask = [0u8; 16];
+    if let Some(transaction_id) = xor {
+        mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        mask[4..].copy_from_slice(transaction_id);
+        port ^= (MAGIC_COOKIE >> 16) as u16;
+    }
+
+    let ip = match family {
+        FAMILY_IPV4 => {
+            let mut octets = [0u8; 4];
+            for (i, octet) in octets.iter_mut().enumerate() {
+                *octet = value.get(4 + i)? ^ mask[i];
+            }
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        FAMILY_IPV6 => {
+            let mut octets = [0u8; 16];
+            for (i, octet) in octets.iter_mut().enumerate() {
+                *octet = value.get(4 + i)? ^ mask[i];
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{UdpBindArguments, UdpBindOptions, UdpTransport};
+    use ockam_node::Context;
+    use tokio::net::UdpSocket;
+
+    /// Encode the Binding success response of a STUN server
+    fn encode_binding_response(
+        transaction_id: &TransactionId,
+        address: SocketAddr,
+        xor: bool,
+    ) -> Vec<u8> {
+        let mut mask = [0u8; 16];
+        let mut port = address.port();
+        if xor {
+            mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+            mask[4..].copy_from_slice(transaction_id);
+            port ^= (MAGIC_COOKIE >> 16) as u16;
+        }
+
+        let (family, octets) = match address.ip() {
+            IpAddr::V4(ip) => (FAMILY_IPV4, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (FAMILY_IPV6, ip.octets().to_vec()),
+        };
+
+        let mut value = vec![0, family];
+        value.extend_from_slice(&port.to_be_bytes());
+        value.extend(octets.iter().zip(mask.iter()).map(|(o, m)| o ^ m));
+
+        let attribute_type = if xor {
+            ATTRIBUTE_XOR_MAPPED_ADDRESS
+        } else {
+            ATTRIBUTE_MAPPED_ADDRESS
+        };
+
+        let mut response = vec![];
+        response.extend_from_slice(&BINDING_SUCCESS_RESPONSE.to_be_bytes());
+        response.extend_from_slice(&((4 + value.len()) as u16).to_be_bytes());
+        response.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        response.extend_from_slice(transaction_id);
+        response.extend_from_slice(&attribute_type.to_be_bytes());
+        response.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        response.extend_from_slice(&value);
+        response
+    }
+
+    #[test]
+    fn test_decode_binding_response() {
+        let transaction_id: TransactionId = rand::random();
+        let addresses: [SocketAddr; 2] = [
+            "203.0.113.7:40000".parse().unwrap(),
+            "[2001:db8::1]:5000".parse().unwrap(),
+        ];
+
+        for address in addresses {
+            for xor in [true, false] {
+                let response = encode_binding_response(&transaction_id, address, xor);
+                assert!(is_stun_message(&response));
+
+                let (id, result) = decode_binding_response(&response).unwrap();
+                assert_eq!(id, transaction_id);
+                assert_eq!(result.unwrap(), address);
+            }
+        }
+    }
+
+    #[test]
+    fn test_binding_request_is_not_a_response() {
+        let transaction_id: TransactionId = rand::random();
+        let request = encode_binding_request(&transaction_id);
+        assert!(is_stun_message(&request));
+        assert!(decode_binding_response(&request).is_none());
+
+        // Transport messages are CBOR arrays
+        assert!(!is_stun_message(&[0x84; HEADER_LEN]));
+    }
+
+    #[tokio::test]
+    async fn test_transactions() {
+        let transactions = StunTransactions::default();
+        let (transaction_id, receiver) = transactions.start();
+
+        let address: SocketAddr = "198.51.100.1:1234".parse().unwrap();
+        let response = encode_binding_response(&transaction_id, address, true);
+        assert!(transactions.handle_datagram(&response));
+        assert_eq!(receiver.await.unwrap().unwrap(), address);
+
+        // The transaction is complete, a retransmitted response is dropped
+        assert!(transactions.handle_datagram(&response));
+        assert!(!transactions.handle_datagram(&[0x84; HEADER_LEN]));
+    }
+
+    #[ockam_macros::test]
+    async fn test_discover_external_address(ctx: &mut Context) -> Result<()> {
+        // A STUN server answering with the address the request comes from
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_address = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 64];
+            let (len, peer) = server.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(len, HEADER_LEN);
+
+            let mut transaction_id = TransactionId::default();
+            transaction_id.copy_from_slice(&buffer[8..HEADER_LEN]);
+            let response = encode_binding_response(&transaction_id, peer, true);
+            server.send_to(&response, peer).await.unwrap();
+        });
+
+        let udp = UdpTransport::create(ctx)?;
+        let bind = udp
+            .bind(UdpBindArguments::new(), UdpBindOptions::new())
+            .await?;
+
+        let external_address = bind
+            .discover_external_address(server_address.to_string())
+            .await?;
+        assert_eq!(external_address, bind.bind_address());
+        assert_eq!(bind.stats().packets_dropped(), 0);
+
+        Ok(())
+    }
+}
@@ -36,6 +36,28 @@ impl UdpSizeOptions {
         max_on_the_wire_size - encoding_overhead
     }
 
+    /// Leave room for the sequence number and its epoch appended to each packet when replay
+    /// protection is on
+    pub(crate) fn reserve_sequence_number(&mut self) {
+        // Encoding overhead for [`UdpTransportMessage::sequence_number`] and
+        // [`UdpTransportMessage::epoch`], including the [`UdpTransportMessage::compression`]
+        // encoded as null when it's absent
+        let sequence_number_encoding_overhead = 15usize;
+        self.max_payload_size_per_packet = self
+            .max_payload_size_per_packet
+            .saturating_sub(sequence_number_encoding_overhead);
+    }
+
+    /// Leave room for the compression flag appended to each packet when compression is on
+    pub(crate) fn reserve_compression_flag(&mut self) {
+        // Encoding overhead for [`UdpTransportMessage::compression`], including the
+        // [`UdpTransportMessage::sequence_number`] encoded as null when it's absent
+        let compression_encoding_overhead = 2usize;
+        self.max_payload_size_per_packet = self
+            .max_payload_size_per_packet
+            .saturating_sub(compression_encoding_overhead);
+    }
+
     /// Read values from environment with fallback to default values
     pub fn read_from_env() -> Self {
         let mut s = Self::default();
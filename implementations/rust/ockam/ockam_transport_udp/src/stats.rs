@@ -0,0 +1,805 @@
+use crate::Clock;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+#[cfg(feature = "metrics")]
+use ockam_core::compat::string::String;
+
+/// Upper bounds, in bytes, of the buckets of a [`MessageSizeHistogram`]. An additional last
+/// bucket counts the larger messages.
+pub const MESSAGE_SIZE_BUCKETS: [usize; 10] = [
+    256,
+    512,
+    1024,
+    2048,
+    4096,
+    8192,
+    16 * 1024,
+    32 * 1024,
+    64 * 1024,
+    1024 * 1024,
+];
+
+/// Distribution of the sizes of the messages sent or received by a [`UdpBind`](crate::UdpBind)
+///
+/// The size of a message is the length of the encoded routing message, before it is split
+/// into datagrams, so comparing it with the datagram size shows how often messages are
+/// fragmented.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageSizeHistogram {
+    counts: [u64; MESSAGE_SIZE_BUCKETS.len() + 1],
+    sum: u64,
+}
+
+impl MessageSizeHistogram {
+    /// Number of messages per bucket, with the upper bound of the bucket, `None` for the
+    /// last bucket
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<usize>, u64)> + '_ {
+        MESSAGE_SIZE_BUCKETS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+
+    /// Number of messages
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Total size of the messages, in bytes
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+}
+
+#[derive(Debug, Default)]
+struct AtomicMessageSizeHistogram {
+    counts: [AtomicU64; MESSAGE_SIZE_BUCKETS.len() + 1],
+    sum: AtomicU64,
+}
+
+impl AtomicMessageSizeHistogram {
+    fn record(&self, size: usize) {
+        let bucket = MESSAGE_SIZE_BUCKETS.partition_point(|bound| *bound < size);
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    fn accumulate(&self, other: &AtomicMessageSizeHistogram) {
+        for (total, count) in self.counts.iter().zip(other.counts.iter()) {
+            total.fetch_add(count.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+        self.sum
+            .fetch_add(other.sum.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> MessageSizeHistogram {
+        MessageSizeHistogram {
+            counts: core::array::from_fn(|i| self.counts[i].load(Ordering::Relaxed)),
+            sum: self.sum.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Maximum number of peers whose traffic is counted separately by a [`UdpBind`](crate::UdpBind),
+/// see [`UdpBindStats::peers`]. Once reached, the least recently active peer is forgotten.
+pub const MAX_TRACKED_PEERS: usize = 256;
+
+/// Traffic exchanged by a [`UdpBind`](crate::UdpBind) with one peer
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UdpPeerStats {
+    /// Number of UDP datagrams sent to the peer
+    pub packets_sent: u64,
+    /// Number of bytes sent to the peer
+    pub bytes_sent: u64,
+    /// Number of UDP datagrams received from the peer
+    pub packets_received: u64,
+    /// Number of bytes received from the peer
+    pub bytes_received: u64,
+    /// Number of UDP datagrams received from the peer that were dropped
+    pub packets_dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct TrackedPeers {
+    peers: HashMap<SocketAddr, (UdpPeerStats, u64)>,
+    /// Incremented on every update, the peer with the lowest value is the least recently
+    /// active one
+    clock: u64,
+}
+
+impl TrackedPeers {
+    fn update(&mut self, peer: SocketAddr, f: impl FnOnce(&mut UdpPeerStats)) {
+        self.clock += 1;
+        if !self.peers.contains_key(&peer) && self.peers.len() >= MAX_TRACKED_PEERS {
+            let least_recent = self
+                .peers
+                .iter()
+                .min_by_key(|(_, (_, last_update))| *last_update)
+                .map(|(peer, _)| *peer);
+            if let Some(least_recent) = least_recent {
+                self.peers.remove(&least_recent);
+            }
+        }
+
+        let (stats, last_update) = self.peers.entry(peer).or_default();
+        f(stats);
+        *last_update = self.clock;
+    }
+}
+
+#[derive(Debug, Default)]
+struct UdpBindCounters {
+    packets_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    packets_received: AtomicU64,
+    bytes_received: AtomicU64,
+    packets_dropped: AtomicU64,
+    messages_received: AtomicU64,
+    keepalives_received: AtomicU64,
+    oversized_messages_dropped: AtomicU64,
+    reassembly_evictions: AtomicU64,
+    dropped_unauthorized: AtomicU64,
+    send_failures: AtomicU64,
+    packets_retransmitted: AtomicU64,
+    punctures_succeeded: AtomicU64,
+    punctures_failed: AtomicU64,
+    sent_message_sizes: AtomicMessageSizeHistogram,
+    received_message_sizes: AtomicMessageSizeHistogram,
+    peers: Mutex<TrackedPeers>,
+}
+
+/// Counters of a [`UdpBind`](crate::UdpBind), shared with its sender and receiver
+#[derive(Clone, Debug, Default)]
+pub struct UdpBindStats {
+    counters: Arc<UdpBindCounters>,
+}
+
+impl UdpBindStats {
+    /// Number of UDP datagrams sent
+    pub fn packets_sent(&self) -> u64 {
+        self.counters.packets_sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes sent, as written on the wire
+    pub fn bytes_sent(&self) -> u64 {
+        self.counters.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of UDP datagrams received
+    pub fn packets_received(&self) -> u64 {
+        self.counters.packets_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes received, as read from the wire
+    pub fn bytes_received(&self) -> u64 {
+        self.counters.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of received UDP datagrams that were dropped (unexpected peer, replays, ...)
+    pub fn packets_dropped(&self) -> u64 {
+        self.counters.packets_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages fully reassembled and forwarded to their destination
+    pub fn messages_received(&self) -> u64 {
+        self.counters.messages_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of keepalive messages received, which are not delivered to any worker
+    pub fn keepalives_received(&self) -> u64 {
+        self.counters.keepalives_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of received messages that were dropped because they were announced
+    /// larger than [`MAX_MESSAGE_SIZE`](crate::MAX_MESSAGE_SIZE)
+    pub fn oversized_messages_dropped(&self) -> u64 {
+        self.counters
+            .oversized_messages_dropped
+            .load(Ordering::Relaxed)
+    }
+
+    /// Number of partially received messages that were evicted because the reassembly
+    /// memory budget was exceeded, see [`UdpBindOptions::with_reassembly_memory_budget`](crate::UdpBindOptions::with_reassembly_memory_budget)
+    pub fn reassembly_evictions(&self) -> u64 {
+        self.counters.reassembly_evictions.load(Ordering::Relaxed)
+    }
+
+    /// Number of received UDP datagrams that were dropped because their source is not in
+    /// the allowlist, see [`UdpBindOptions::with_source_allowlist`](crate::UdpBindOptions::with_source_allowlist)
+    ///
+    /// These datagrams are not counted in the other statistics, nor in the ones of the peers.
+    pub fn dropped_unauthorized(&self) -> u64 {
+        self.counters.dropped_unauthorized.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages the sender couldn't send: unknown peer, socket error, message
+    /// too large, ...
+    pub fn send_failures(&self) -> u64 {
+        self.counters.send_failures.load(Ordering::Relaxed)
+    }
+
+    /// Number of additional copies of UDP datagrams sent, see
+    /// [`UdpBindOptions::with_fragment_retransmission`](crate::UdpBindOptions::with_fragment_retransmission).
+    /// They are also counted in [`Self::packets_sent`]
+    pub fn packets_retransmitted(&self) -> u64 {
+        self.counters.packets_retransmitted.load(Ordering::Relaxed)
+    }
+
+    /// Number of punctures using this bind that were opened
+    pub fn punctures_succeeded(&self) -> u64 {
+        self.counters.punctures_succeeded.load(Ordering::Relaxed)
+    }
+
+    /// Number of punctures using this bind that were closed because the peer stopped answering
+    pub fn punctures_failed(&self) -> u64 {
+        self.counters.punctures_failed.load(Ordering::Relaxed)
+    }
+
+    /// Sizes of the messages sent, excluding keepalives
+    pub fn sent_message_sizes(&self) -> MessageSizeHistogram {
+        self.counters.sent_message_sizes.snapshot()
+    }
+
+    /// Sizes of the messages fully reassembled, see [`Self::messages_received`]
+    pub fn received_message_sizes(&self) -> MessageSizeHistogram {
+        self.counters.received_message_sizes.snapshot()
+    }
+
+    /// Traffic broken down by peer, for the [`MAX_TRACKED_PEERS`] most recently active peers
+    ///
+    /// The counters of a peer start again from zero if it is forgotten and becomes active
+    /// again. They are not included in the totals of a [`UdpTransport`](crate::UdpTransport).
+    pub fn peers(&self) -> HashMap<SocketAddr, UdpPeerStats> {
+        self.counters
+            .peers
+            .lock()
+            .unwrap()
+            .peers
+            .iter()
+            .map(|(peer, (stats, _))| (*peer, stats.clone()))
+            .collect()
+    }
+
+    /// Traffic exchanged with one peer, if it is tracked, see [`Self::peers`]
+    pub fn peer(&self, peer: &SocketAddr) -> Option<UdpPeerStats> {
+        self.counters
+            .peers
+            .lock()
+            .unwrap()
+            .peers
+            .get(peer)
+            .map(|(stats, _)| stats.clone())
+    }
+}
+
+impl UdpBindStats {
+    pub(crate) fn record_packet_sent(&self, len: usize, peer: SocketAddr) {
+        self.counters.packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .bytes_sent
+            .fetch_add(len as u64, Ordering::Relaxed);
+        self.update_peer(peer, |stats| {
+            stats.packets_sent += 1;
+            stats.bytes_sent += len as u64;
+        });
+    }
+
+    pub(crate) fn record_packet_received(&self, len: usize, peer: SocketAddr) {
+        self.counters
+            .packets_received
+            .fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .bytes_received
+            .fetch_add(len as u64, Ordering::Relaxed);
+        self.update_peer(peer, |stats| {
+            stats.packets_received += 1;
+            stats.bytes_received += len as u64;
+        });
+    }
+
+    pub(crate) fn record_packet_dropped(&self, peer: SocketAddr) {
+        self.counters
+            .packets_dropped
+            .fetch_add(1, Ordering::Relaxed);
+        self.update_peer(peer, |stats| stats.packets_dropped += 1);
+    }
+
+    fn update_peer(&self, peer: SocketAddr, f: impl FnOnce(&mut UdpPeerStats)) {
+        self.counters.peers.lock().unwrap().update(peer, f);
+    }
+
+    pub(crate) fn record_message_sent(&self, size: usize) {
+        self.counters.sent_message_sizes.record(size);
+    }
+
+    pub(crate) fn record_message_received(&self, size: usize) {
+        self.counters
+            .messages_received
+            .fetch_add(1, Ordering::Relaxed);
+        self.counters.received_message_sizes.record(size);
+    }
+
+    pub(crate) fn record_keepalive_received(&self) {
+        self.counters
+            .keepalives_received
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_oversized_message_dropped(&self) {
+        self.counters
+            .oversized_messages_dropped
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_reassembly_evictions(&self, count: usize) {
+        self.counters
+            .reassembly_evictions
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_unauthorized_dropped(&self) {
+        self.counters
+            .dropped_unauthorized
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_send_failure(&self) {
+        self.counters.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_packet_retransmitted(&self) {
+        self.counters
+            .packets_retransmitted
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of messages sent, and of messages which couldn't be sent, see
+    /// [`UdpBind::flush`](crate::UdpBind::flush)
+    pub(crate) fn flush_counters(&self) -> (u64, u64) {
+        (self.sent_message_sizes().count(), self.send_failures())
+    }
+
+    pub(crate) fn record_puncture_succeeded(&self) {
+        self.counters
+            .punctures_succeeded
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_puncture_failed(&self) {
+        self.counters
+            .punctures_failed
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add the counters of another bind to these ones
+    pub(crate) fn accumulate(&self, other: &UdpBindStats) {
+        let pairs = [
+            (&self.counters.packets_sent, &other.counters.packets_sent),
+            (&self.counters.bytes_sent, &other.counters.bytes_sent),
+            (
+                &self.counters.packets_received,
+                &other.counters.packets_received,
+            ),
+            (
+                &self.counters.bytes_received,
+                &other.counters.bytes_received,
+            ),
+            (
+                &self.counters.packets_dropped,
+                &other.counters.packets_dropped,
+            ),
+            (
+                &self.counters.messages_received,
+                &other.counters.messages_received,
+            ),
+            (
+                &self.counters.keepalives_received,
+                &other.counters.keepalives_received,
+            ),
+            (
+                &self.counters.oversized_messages_dropped,
+                &other.counters.oversized_messages_dropped,
+            ),
+            (
+                &self.counters.reassembly_evictions,
+                &other.counters.reassembly_evictions,
+            ),
+            (
+                &self.counters.dropped_unauthorized,
+                &other.counters.dropped_unauthorized,
+            ),
+            (&self.counters.send_failures, &other.counters.send_failures),
+            (
+                &self.counters.packets_retransmitted,
+                &other.counters.packets_retransmitted,
+            ),
+            (
+                &self.counters.punctures_succeeded,
+                &other.counters.punctures_succeeded,
+            ),
+            (
+                &self.counters.punctures_failed,
+                &other.counters.punctures_failed,
+            ),
+        ];
+        for (total, counter) in pairs {
+            total.fetch_add(counter.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+        self.counters
+            .sent_message_sizes
+            .accumulate(&other.counters.sent_message_sizes);
+        self.counters
+            .received_message_sizes
+            .accumulate(&other.counters.received_message_sizes);
+    }
+}
+
+#[derive(Debug)]
+struct UdpBindTimestamps {
+    /// The other timestamps are stored as the number of nanoseconds elapsed since this
+    /// instant, plus one, 0 meaning that nothing happened yet
+    created_at: Instant,
+    last_received: AtomicU64,
+    last_sent: AtomicU64,
+}
+
+/// Time of the last datagrams received and sent by a [`UdpBind`](crate::UdpBind), shared
+/// with its sender and receivers
+#[derive(Clone, Debug)]
+pub(crate) struct UdpBindActivity {
+    clock: Arc<dyn Clock>,
+    timestamps: Arc<UdpBindTimestamps>,
+}
+
+impl UdpBindActivity {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        let timestamps = UdpBindTimestamps {
+            created_at: clock.now(),
+            last_received: AtomicU64::new(0),
+            last_sent: AtomicU64::new(0),
+        };
+        Self {
+            clock,
+            timestamps: Arc::new(timestamps),
+        }
+    }
+
+    pub(crate) fn record_received(&self) {
+        self.timestamps
+            .last_received
+            .store(self.elapsed(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_sent(&self) {
+        self.timestamps
+            .last_sent
+            .store(self.elapsed(), Ordering::Relaxed);
+    }
+
+    /// Time of the last datagram received, and of the last datagram sent
+    pub(crate) fn last_activity(&self) -> (Option<Instant>, Option<Instant>) {
+        (
+            self.instant(&self.timestamps.last_received),
+            self.instant(&self.timestamps.last_sent),
+        )
+    }
+
+    fn elapsed(&self) -> u64 {
+        let elapsed = self
+            .clock
+            .now()
+            .saturating_duration_since(self.timestamps.created_at);
+        u64::try_from(elapsed.as_nanos())
+            .unwrap_or(u64::MAX - 1)
+            .saturating_add(1)
+    }
+
+    fn instant(&self, timestamp: &AtomicU64) -> Option<Instant> {
+        match timestamp.load(Ordering::Relaxed) {
+            0 => None,
+            elapsed => Some(self.timestamps.created_at + Duration::from_nanos(elapsed - 1)),
+        }
+    }
+}
+
+/// Render the sum of the given stats in the Prometheus text exposition format
+#[cfg(feature = "metrics")]
+pub(crate) fn render_prometheus<'a>(stats: impl IntoIterator<Item = &'a UdpBindStats>) -> String {
+    use core::fmt::Write;
+
+    let total = UdpBindStats::default();
+    for s in stats {
+        total.accumulate(s);
+    }
+
+    let metrics = [
+        (
+            "ockam_udp_packets_sent_total",
+            "Number of UDP datagrams sent",
+            total.packets_sent(),
+        ),
+        (
+            "ockam_udp_bytes_sent_total",
+            "Number of bytes sent over UDP",
+            total.bytes_sent(),
+        ),
+        (
+            "ockam_udp_packets_received_total",
+            "Number of UDP datagrams received",
+            total.packets_received(),
+        ),
+        (
+            "ockam_udp_bytes_received_total",
+            "Number of bytes received over UDP",
+            total.bytes_received(),
+        ),
+        (
+            "ockam_udp_packets_dropped_total",
+            "Number of received UDP datagrams that were dropped",
+            total.packets_dropped(),
+        ),
+        (
+            "ockam_udp_messages_received_total",
+            "Number of messages reassembled from UDP datagrams",
+            total.messages_received(),
+        ),
+        (
+            "ockam_udp_keepalives_received_total",
+            "Number of UDP keepalive messages received",
+            total.keepalives_received(),
+        ),
+        (
+            "ockam_udp_oversized_messages_dropped_total",
+            "Number of received messages dropped because they exceeded the maximum message size",
+            total.oversized_messages_dropped(),
+        ),
+        (
+            "ockam_udp_reassembly_evictions_total",
+            "Number of partially received messages evicted to stay within the reassembly memory budget",
+            total.reassembly_evictions(),
+        ),
+        (
+            "ockam_udp_dropped_unauthorized_total",
+            "Number of received UDP datagrams dropped because their source is not allowed",
+            total.dropped_unauthorized(),
+        ),
+        (
+            "ockam_udp_send_failures_total",
+            "Number of messages which couldn't be sent over UDP",
+            total.send_failures(),
+        ),
+        (
+            "ockam_udp_packets_retransmitted_total",
+            "Number of additional copies of UDP datagrams sent for critical messages",
+            total.packets_retransmitted(),
+        ),
+        (
+            "ockam_udp_punctures_succeeded_total",
+            "Number of UDP punctures that were opened",
+            total.punctures_succeeded(),
+        ),
+        (
+            "ockam_udp_punctures_failed_total",
+            "Number of UDP punctures that were closed because the peer stopped answering",
+            total.punctures_failed(),
+        ),
+    ];
+
+    let mut text = String::new();
+    for (name, help, value) in metrics {
+        // Writing to a String can't fail
+        let _ = writeln!(text, "# HELP {name} {help}");
+        let _ = writeln!(text, "# TYPE {name} counter");
+        let _ = writeln!(text, "{name} {value}");
+    }
+
+    let histograms = [
+        (
+            "ockam_udp_sent_message_size_bytes",
+            "Size of the messages sent over UDP",
+            total.sent_message_sizes(),
+        ),
+        (
+            "ockam_udp_received_message_size_bytes",
+            "Size of the messages reassembled from UDP datagrams",
+            total.received_message_sizes(),
+        ),
+    ];
+    for (name, help, histogram) in histograms {
+        let _ = writeln!(text, "# HELP {name} {help}");
+        let _ = writeln!(text, "# TYPE {name} histogram");
+        // Prometheus buckets are cumulative
+        let mut cumulative = 0;
+        for (bound, count) in histogram.buckets() {
+            cumulative += count;
+            match bound {
+                Some(bound) => {
+                    let _ = writeln!(text, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+                }
+                None => {
+                    let _ = writeln!(text, "{name}_bucket{{le=\"+Inf\"}} {cumulative}");
+                }
+            }
+        }
+        let _ = writeln!(text, "{name}_sum {}", histogram.sum());
+        let _ = writeln!(text, "{name}_count {}", histogram.count());
+    }
+    text
+}
+
+/// A message which is being reassembled by a [`UdpBind`](crate::UdpBind) receiver, see
+/// [`UdpBind::reassembly_snapshot`](crate::UdpBind::reassembly_snapshot)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReassemblyEntryInfo {
+    /// Peer sending the message
+    pub peer: SocketAddr,
+    /// Routing number of the message, incremented by the peer for each message
+    pub routing_number: u16,
+    /// Number of datagrams of the message received so far
+    pub fragments_received: u16,
+    /// Number of datagrams of the message
+    pub fragments_expected: u16,
+    /// Time elapsed since the first datagram of the message was received
+    pub age: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let stats1 = UdpBindStats::default();
+        stats1.record_packet_sent(10, peer(1));
+        stats1.record_packet_received(20, peer(1));
+        stats1.record_puncture_succeeded();
+        stats1.record_message_sent(100);
+
+        let stats2 = UdpBindStats::default();
+        stats2.record_packet_sent(5, peer(2));
+        stats2.record_packet_dropped(peer(2));
+        stats2.record_oversized_message_dropped();
+        stats2.record_reassembly_evictions(3);
+        stats2.record_unauthorized_dropped();
+        stats2.record_message_sent(300);
+        stats2.record_message_received(2000);
+        stats2.record_send_failure();
+        stats2.record_packet_retransmitted();
+
+        let total = UdpBindStats::default();
+        total.accumulate(&stats1);
+        total.accumulate(&stats2);
+
+        assert_eq!(total.packets_sent(), 2);
+        assert_eq!(total.bytes_sent(), 15);
+        assert_eq!(total.packets_received(), 1);
+        assert_eq!(total.bytes_received(), 20);
+        assert_eq!(total.packets_dropped(), 1);
+        assert_eq!(total.oversized_messages_dropped(), 1);
+        assert_eq!(total.reassembly_evictions(), 3);
+        assert_eq!(total.dropped_unauthorized(), 1);
+        assert_eq!(total.packets_retransmitted(), 1);
+        assert_eq!(total.punctures_succeeded(), 1);
+        assert_eq!(total.punctures_failed(), 0);
+        assert_eq!(total.messages_received(), 1);
+        assert_eq!(total.sent_message_sizes().count(), 2);
+        assert_eq!(total.sent_message_sizes().sum(), 400);
+        assert_eq!(total.received_message_sizes().count(), 1);
+        assert_eq!(total.send_failures(), 1);
+        assert_eq!(total.flush_counters(), (2, 1));
+    }
+
+    #[test]
+    fn test_message_size_histogram() {
+        let stats = UdpBindStats::default();
+        for size in [0, 256, 257, 1500, 1024 * 1024, 1024 * 1024 + 1] {
+            stats.record_message_sent(size);
+        }
+
+        let histogram = stats.sent_message_sizes();
+        let buckets = histogram.buckets().collect::<Vec<_>>();
+        assert_eq!(buckets.len(), MESSAGE_SIZE_BUCKETS.len() + 1);
+        assert_eq!(buckets[0], (Some(256), 2));
+        assert_eq!(buckets[1], (Some(512), 1));
+        assert_eq!(buckets[3], (Some(2048), 1));
+        assert_eq!(buckets[9], (Some(1024 * 1024), 1));
+        assert_eq!(buckets[10], (None, 1));
+        assert_eq!(histogram.count(), 6);
+        assert_eq!(histogram.sum(), 2 * 1024 * 1024 + 2014);
+        assert_eq!(stats.received_message_sizes().count(), 0);
+    }
+
+    #[test]
+    fn test_peers() {
+        let stats = UdpBindStats::default();
+        stats.record_packet_sent(10, peer(1));
+        stats.record_packet_sent(15, peer(1));
+        stats.record_packet_received(20, peer(1));
+        stats.record_packet_received(30, peer(2));
+        stats.record_packet_dropped(peer(2));
+
+        let peers = stats.peers();
+        assert_eq!(peers.len(), 2);
+        assert_eq!(
+            peers[&peer(1)],
+            UdpPeerStats {
+                packets_sent: 2,
+                bytes_sent: 25,
+                packets_received: 1,
+                bytes_received: 20,
+                packets_dropped: 0,
+            }
+        );
+        assert_eq!(stats.peer(&peer(2)).unwrap().packets_dropped, 1);
+        assert_eq!(stats.peer(&peer(3)), None);
+
+        // The least recently active peer is forgotten once the limit is reached
+        for port in 3..(MAX_TRACKED_PEERS as u16 + 2) {
+            stats.record_packet_received(1, peer(port));
+            stats.record_packet_sent(1, peer(2));
+        }
+        assert_eq!(stats.peers().len(), MAX_TRACKED_PEERS);
+        assert_eq!(stats.peer(&peer(1)), None);
+        assert!(stats.peer(&peer(2)).is_some());
+        assert!(stats.peer(&peer(MAX_TRACKED_PEERS as u16 + 1)).is_some());
+    }
+
+    #[test]
+    fn test_last_activity() {
+        let clock = MockClock::new();
+        let activity = UdpBindActivity::new(Arc::new(clock.clone()));
+        assert_eq!(activity.last_activity(), (None, None));
+
+        clock.advance(Duration::from_secs(1));
+        activity.record_sent();
+        let sent_at = clock.now();
+        assert_eq!(activity.last_activity(), (None, Some(sent_at)));
+
+        clock.advance(Duration::from_secs(2));
+        activity.record_received();
+        let received_at = clock.now();
+        assert_eq!(activity.last_activity(), (Some(received_at), Some(sent_at)));
+
+        // The activity is shared between the clones
+        clock.advance(Duration::from_secs(3));
+        activity.clone().record_sent();
+        assert_eq!(
+            activity.last_activity(),
+            (Some(received_at), Some(clock.now()))
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_render_prometheus() {
+        let stats1 = UdpBindStats::default();
+        stats1.record_packet_sent(10, peer(1));
+        let stats2 = UdpBindStats::default();
+        stats2.record_packet_sent(10, peer(2));
+        stats2.record_puncture_failed();
+        stats2.record_message_sent(300);
+
+        let text = render_prometheus([&stats1, &stats2]);
+
+        assert!(text.contains("# TYPE ockam_udp_packets_sent_total counter\n"));
+        assert!(text.contains("\nockam_udp_packets_sent_total 2\n"));
+        assert!(text.contains("\nockam_udp_bytes_sent_total 20\n"));
+        assert!(text.contains("\nockam_udp_punctures_failed_total 1\n"));
+        assert!(text.contains("# TYPE ockam_udp_sent_message_size_bytes histogram\n"));
+        assert!(text.contains("\nockam_udp_sent_message_size_bytes_bucket{le=\"256\"} 0\n"));
+        assert!(text.contains("\nockam_udp_sent_message_size_bytes_bucket{le=\"512\"} 1\n"));
+        assert!(text.contains("\nockam_udp_sent_message_size_bytes_bucket{le=\"+Inf\"} 1\n"));
+        assert!(text.contains("\nockam_udp_sent_message_size_bytes_sum 300\n"));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{UdpBind, UdpSession};
+use core::fmt;
+use core::fmt::Formatter;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::time::Duration;
+use ockam_core::errcode::Kind;
+use ockam_core::{Address, NeutralMessage, Result, Route, Routed, Worker};
+use ockam_node::{Context, WorkerBuilder};
+use std::time::Instant;
+use tracing::trace;
+
+/// Size of the sequence number at the beginning of each benchmark message
+const SEQUENCE_NUMBER_SIZE: usize = 8;
+
+/// [`Worker`] sending back every message it receives, to benchmark a UDP transport
+/// with [`UdpBind::benchmark`]
+pub struct UdpEchoWorker;
+
+impl UdpEchoWorker {
+    /// Start an echo worker at the given address, receiving the messages of the given bind
+    pub fn start(ctx: &Context, address: impl Into<Address>, bind: &UdpBind) -> Result<()> {
+        let address = address.into();
+        ctx.flow_controls()
+            .add_consumer(&address, bind.flow_control_id());
+
+        WorkerBuilder::new(UdpEchoWorker)
+            .with_address(address)
+            .start(ctx)
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for UdpEchoWorker {
+    type Message = NeutralMessage;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<NeutralMessage>,
+    ) -> Result<()> {
+        let return_route = msg.return_route().clone();
+        ctx.send(return_route, NeutralMessage::from(msg.into_payload()))
+            .await
+    }
+}
+
+/// Options for [`UdpBind::benchmark`]
+#[derive(Clone, Debug)]
+pub struct UdpBenchmarkOptions {
+    pub(crate) count: u64,
+    pub(crate) payload_size: usize,
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+}
+
+impl UdpBenchmarkOptions {
+    /// Send 100 messages of 1000 bytes, every 10 milliseconds
+    pub fn new() -> Self {
+        Self {
+            count: 100,
+            payload_size: 1000,
+            interval: Duration::from_millis(10),
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    /// Number of messages to send
+    pub fn with_count(mut self, count: u64) -> Self {
+        self.count = count;
+
+        self
+    }
+
+    /// Size of the payload of each message, messages larger than
+    /// [`UdpSizeOptions::max_payload_size_per_packet`](crate::UdpSizeOptions::max_payload_size_per_packet)
+    /// are fragmented into several datagrams
+    pub fn with_payload_size(mut self, payload_size: usize) -> Self {
+        self.payload_size = payload_size.max(SEQUENCE_NUMBER_SIZE);
+
+        self
+    }
+
+    /// Time between 2 messages
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+
+        self
+    }
+
+    /// Time to wait for the responses once all the messages are sent, the messages
+    /// which are not echoed by then are lost
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+}
+
+impl Default for UdpBenchmarkOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of [`UdpBind::benchmark`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UdpBenchmarkReport {
+    /// Number of messages sent
+    pub sent: u64,
+    /// Number of messages echoed back
+    pub received: u64,
+    /// Median round-trip time
+    pub p50: Option<Duration>,
+    /// 99th percentile of the round-trip time
+    pub p99: Option<Duration>,
+    /// Echoed payload bytes per second
+    pub throughput: f64,
+    /// Time between the first message sent and the last response received
+    pub elapsed: Duration,
+}
+
+impl UdpBenchmarkReport {
+    /// Number of messages which were not echoed back
+    pub fn lost(&self) -> u64 {
+        self.sent - self.received
+    }
+
+    /// Fraction of the messages which were not echoed back
+    pub fn loss_ratio(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+
+        self.lost() as f64 / self.sent as f64
+    }
+}
+
+impl fmt::Display for UdpBenchmarkReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sent: {}, received: {}, loss: {:.2}%, p50: {:?}, p99: {:?}, throughput: {:.0} B/s",
+            self.sent,
+            self.received,
+            self.loss_ratio() * 100.0,
+            self.p50,
+            self.p99,
+            self.throughput
+        )
+    }
+}
+
+impl UdpBind {
+    /// Measure the round-trip time, throughput and loss to an [`UdpEchoWorker`] started
+    /// at `echo_route` on the peer's side
+    pub async fn benchmark(
+        &self,
+        ctx: &Context,
+        peer_udp_address: impl AsRef<str>,
+        echo_route: impl Into<Route>,
+        options: UdpBenchmarkOptions,
+    ) -> Result<UdpBenchmarkReport> {
+        let echo_route = echo_route.into();
+        let mut session = self.connect(ctx, peer_udp_address).await?;
+        let mut round_trips = RoundTrips::new(options.count);
+
+        for sequence_number in 0..options.count {
+            let mut payload = vec![0; options.payload_size];
+            payload[..SEQUENCE_NUMBER_SIZE].copy_from_slice(&sequence_number.to_be_bytes());
+
+            round_trips.sent(sequence_number);
+            session
+                .send(echo_route.clone(), NeutralMessage::from(payload))
+                .await?;
+
+            // Collect the responses arriving before the next message is due
+            let next_send_at = Instant::now() + options.interval;
+            loop {
+                let remaining = next_send_at.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                match Self::receive_echo(&mut session, remaining).await? {
+                    Some(payload) => round_trips.received(&payload),
+                    None => break,
+                }
+            }
+        }
+
+        while round_trips.is_waiting() {
+            match Self::receive_echo(&mut session, options.timeout).await? {
+                Some(payload) => round_trips.received(&payload),
+                None => break,
+            }
+        }
+
+        Ok(round_trips.report(options.count))
+    }
+
+    /// Wait for the next echoed payload, `None` if it doesn't arrive in time
+    async fn receive_echo(session: &mut UdpSession, timeout: Duration) -> Result<Option<Vec<u8>>> {
+        match session.recv_with_timeout::<NeutralMessage>(timeout).await {
+            Ok(msg) => Ok(Some(msg.into_payload())),
+            Err(err) if err.code().kind == Kind::Timeout => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Round-trip times of the benchmark messages
+struct RoundTrips {
+    start: Instant,
+    sent_at: HashMap<u64, Instant>,
+    durations: Vec<Duration>,
+    received_bytes: usize,
+    last_received_at: Instant,
+}
+
+impl RoundTrips {
+    fn new(count: u64) -> Self {
+        let start = Instant::now();
+        Self {
+            start,
+            sent_at: HashMap::new(),
+            durations: Vec::with_capacity(count as usize),
+            received_bytes: 0,
+            last_received_at: start,
+        }
+    }
+
+    fn sent(&mut self, sequence_number: u64) {
+        self.sent_at.insert(sequence_number, Instant::now());
+    }
+
+    /// Record an echoed message, unexpected and duplicated messages are ignored
+    fn received(&mut self, payload: &[u8]) {
+        let sequence_number = match payload
+            .get(..SEQUENCE_NUMBER_SIZE)
+            .and_then(|bytes| bytes.try_into().ok())
+        {
+            Some(bytes) => u64::from_be_bytes(bytes),
+            None => {
+                trace!("Ignoring a benchmark response without a sequence number");
+                return;
+            }
+        };
+
+        match self.sent_at.remove(&sequence_number) {
+            Some(sent_at) => {
+                self.last_received_at = Instant::now();
+                self.durations.push(self.last_received_at - sent_at);
+                self.received_bytes += payload.len();
+            }
+            None => trace!(%sequence_number, "Ignoring an unexpected benchmark response"),
+        }
+    }
+
+    /// Some messages were not echoed yet
+    fn is_waiting(&self) -> bool {
+        !self.sent_at.is_empty()
+    }
+
+    fn report(mut self, sent: u64) -> UdpBenchmarkReport {
+        let elapsed = self.last_received_at - self.start;
+        let throughput = if elapsed.is_zero() {
+            0.0
+        } else {
+            self.received_bytes as f64 / elapsed.as_secs_f64()
+        };
+
+        self.durations.sort();
+        UdpBenchmarkReport {
+            sent,
+            received: self.durations.len() as u64,
+            p50: percentile(&self.durations, 50),
+            p99: percentile(&self.durations, 99),
+            throughput,
+            elapsed,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted durations
+fn percentile(sorted: &[Duration], percentile: usize) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = ((percentile * sorted.len() + 99) / 100).max(1);
+    Some(sorted[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UdpTransport;
+
+    #[test]
+    fn test_percentile() {
+        let durations: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&durations, 50), Some(Duration::from_millis(50)));
+        assert_eq!(percentile(&durations, 99), Some(Duration::from_millis(99)));
+        assert_eq!(
+            percentile(&durations[..1], 99),
+            Some(Duration::from_millis(1))
+        );
+        assert_eq!(percentile(&[], 50), None);
+    }
+
+    #[ockam_macros::test]
+    async fn test_benchmark(ctx: &mut Context) -> Result<()> {
+        let udp = UdpTransport::create(ctx)?;
+        let (bind1, bind2) = udp.connected_pair().await?;
+
+        UdpEchoWorker::start(ctx, "echo", &bind2)?;
+
+        let report = bind1
+            .benchmark(
+                ctx,
+                bind2.bind_address().to_string(),
+                "echo",
+                UdpBenchmarkOptions::new()
+                    .with_count(20)
+                    .with_payload_size(3000)
+                    .with_interval(Duration::from_millis(1)),
+            )
+            .await?;
+
+        assert_eq!(report.sent, 20);
+        assert_eq!(report.received, 20);
+        assert_eq!(report.lost(), 0);
+        assert!(report.p50.is_some());
+        assert!(report.p50 <= report.p99);
+        assert!(report.throughput > 0.0);
+
+        Ok(())
+    }
+}
@@ -1,15 +1,32 @@
-use crate::workers::{split_socket, Addresses, UdpReceiverProcessor, UdpSenderWorker};
-use crate::{UdpBindOptions, UdpTransport};
+use crate::puncture::{
+    encode_binding_request, StunTransactions, STUN_INITIAL_RTO, STUN_MAX_ATTEMPTS,
+};
+use crate::workers::{
+    split_socket, Addresses, FlushRequests, PendingRoutingMessageStorage, UdpReceiverProcessor,
+    UdpSenderWorker,
+};
+use crate::{
+    Clock, IpNet, PunctureError, ReassemblyEntryInfo, UdpBindActivity, UdpBindOptions,
+    UdpBindStats, UdpCompression, UdpSizeOptions, UdpTransport, UdpTransportError,
+    MAX_MESSAGE_SIZE,
+};
 use core::fmt;
 use core::fmt::Formatter;
 use core::str::FromStr;
+use ockam_core::compat::sync::{Arc, Weak};
+use ockam_core::compat::time::Duration;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::{Address, AllowAll, DenyAll, Error, Result};
+use ockam_core::{Address, AllowAll, DenyAll, Error, MessagePriority, Result};
 use ockam_node::compat::asynchronous::resolve_peer;
+use ockam_node::Context;
 use ockam_node::{ProcessorBuilder, WorkerBuilder, WorkerShutdownPriority};
 use ockam_transport_core::{parse_socket_addr, HostnamePort, TransportError};
+use socket2::SockRef;
+#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+use socket2::{Domain, Protocol, Socket, Type};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Instant;
 use tokio::net::UdpSocket;
 use tracing::{debug, error};
 
@@ -19,6 +36,10 @@ pub struct UdpBindArguments {
     peer_address: Option<SocketAddr>,
     /// Local bind address
     bind_address: SocketAddr,
+    /// Requested size of the socket receive buffer (`SO_RCVBUF`), the OS default otherwise
+    recv_buffer_size: Option<usize>,
+    /// Requested size of the socket send buffer (`SO_SNDBUF`), the OS default otherwise
+    send_buffer_size: Option<usize>,
 }
 
 impl Default for UdpBindArguments {
@@ -26,6 +47,8 @@ impl Default for UdpBindArguments {
         Self {
             peer_address: None,
             bind_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
+            recv_buffer_size: None,
+            send_buffer_size: None,
         }
     }
 }
@@ -65,6 +88,26 @@ impl UdpBindArguments {
 
         self
     }
+
+    /// Set the size of the socket receive buffer (`SO_RCVBUF`)
+    ///
+    /// The OS may adjust the requested size, see [`UdpBind::recv_buffer_size`] for the
+    /// applied one.
+    pub fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+
+        self
+    }
+
+    /// Set the size of the socket send buffer (`SO_SNDBUF`)
+    ///
+    /// The OS may adjust the requested size, see [`UdpBind::send_buffer_size`] for the
+    /// applied one.
+    pub fn with_send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+
+        self
+    }
 }
 
 impl UdpTransport {
@@ -74,6 +117,65 @@ impl UdpTransport {
         arguments: UdpBindArguments,
         options: UdpBindOptions,
     ) -> Result<UdpBind> {
+        Self::check_ipv4(&arguments)?;
+
+        // Bind new socket
+        let socket = UdpSocket::bind(arguments.bind_address)
+            .await
+            .map_err(|_| TransportError::BindFailed)?;
+
+        self.start_bind(arguments, options, socket, vec![])
+    }
+
+    /// Bind `sockets_count` sockets to the same local port with `SO_REUSEPORT`
+    ///
+    /// The kernel load-balances the incoming datagrams between the sockets, by peer, and each
+    /// socket has its own receiver processor, so that datagrams can be received on several
+    /// cores. All the messages are sent through the same sender worker, and the returned
+    /// [`UdpBind`] controls all the sockets.
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    pub async fn bind_reuseport(
+        &self,
+        arguments: UdpBindArguments,
+        options: UdpBindOptions,
+        sockets_count: usize,
+    ) -> Result<UdpBind> {
+        Self::check_ipv4(&arguments)?;
+
+        if sockets_count == 0 {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                "At least one UDP socket must be bound",
+            ));
+        }
+
+        // The other sockets must use the port assigned to the first one
+        let socket = Self::bind_reuseport_socket(arguments.bind_address)?;
+        let local_addr = socket
+            .local_addr()
+            .map_err(|_| Error::new(Origin::Transport, Kind::Io, "invalid local address"))?;
+        let additional_sockets = (1..sockets_count)
+            .map(|_| Self::bind_reuseport_socket(local_addr))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.start_bind(arguments, options, socket, additional_sockets)
+    }
+
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    fn bind_reuseport_socket(bind_address: SocketAddr) -> Result<UdpSocket> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+            .map_err(TransportError::from)?;
+        socket.set_reuse_port(true).map_err(TransportError::from)?;
+        socket.set_nonblocking(true).map_err(TransportError::from)?;
+        socket
+            .bind(&bind_address.into())
+            .map_err(|_| TransportError::BindFailed)?;
+
+        Ok(UdpSocket::from_std(socket.into()).map_err(TransportError::from)?)
+    }
+
+    fn check_ipv4(arguments: &UdpBindArguments) -> Result<()> {
         // This transport only supports IPv4
         if !arguments.bind_address.is_ipv4() {
             error!(local_addr = %arguments.bind_address, "This transport only supports IPv4");
@@ -82,10 +184,27 @@ impl UdpTransport {
             ))?;
         }
 
-        // Bind new socket
-        let socket = UdpSocket::bind(arguments.bind_address)
-            .await
-            .map_err(|_| TransportError::BindFailed)?;
+        Ok(())
+    }
+
+    /// Start a sender worker, sending through the first socket, and one receiver processor
+    /// per socket. The additional sockets must be bound to the same local address
+    pub(crate) fn start_bind(
+        &self,
+        arguments: UdpBindArguments,
+        options: UdpBindOptions,
+        socket: UdpSocket,
+        additional_sockets: Vec<UdpSocket>,
+    ) -> Result<UdpBind> {
+        if let Some(compression) = options.compression {
+            if !compression.is_available() {
+                return Err(UdpTransportError::UnsupportedCompression(compression))?;
+            }
+        }
+
+        if options.replay_protection_window == Some(0) {
+            return Err(UdpTransportError::InvalidReplayWindow)?;
+        }
 
         if let Some(_peer) = &arguments.peer_address {
             // TODO: Would be better to tie this socket to a specific peer when
@@ -99,12 +218,29 @@ impl UdpTransport {
             .local_addr()
             .map_err(|_| Error::new(Origin::Transport, Kind::Io, "invalid local address"))?;
 
-        // Split socket into sink and stream
-        let (socket_read, socket_write) = split_socket(socket);
-
-        let addresses = Addresses::generate();
+        let buffer_sizes = Self::set_buffer_sizes(&socket, &arguments)?;
+        for socket in &additional_sockets {
+            Self::set_buffer_sizes(socket, &arguments)?;
+        }
+        debug!(
+            %local_addr,
+            recv_buffer_size = buffer_sizes.recv_buffer_size,
+            send_buffer_size = buffer_sizes.send_buffer_size,
+            "UDP socket buffer sizes"
+        );
 
-        debug!("Creating UDP sender and receiver. Peer: {:?}, Local address: {}, Sender: {}, Receiver: {}",
+        let addresses = Addresses::generate_with_receivers(1 + additional_sockets.len());
+        let config = UdpBindConfig::new(&options, buffer_sizes, 1 + additional_sockets.len());
+        debug!(%local_addr, ?config, "UDP bind configuration");
+        let state = UdpBindState {
+            stats: UdpBindStats::default(),
+            activity: UdpBindActivity::new(options.clock.clone()),
+            stun_transactions: StunTransactions::default(),
+            flush_requests: FlushRequests::default(),
+        };
+
+        debug!("Creating UDP sender and {} receiver(s). Peer: {:?}, Local address: {}, Sender: {}, Receiver: {}",
+            1 + additional_sockets.len(),
             arguments.peer_address,
             local_addr,
             addresses.sender_address(),
@@ -115,11 +251,23 @@ impl UdpTransport {
         let receiver_outgoing_access_control =
             options.create_receiver_outgoing_access_control(self.ctx.flow_controls());
 
+        // Split sockets into sinks and streams, only the first one is used to send
+        let (socket_read, socket_write) = split_socket(socket);
+        let sockets_read: Vec<_> = core::iter::once(socket_read)
+            .chain(
+                additional_sockets
+                    .into_iter()
+                    .map(|socket| split_socket(socket).0),
+            )
+            .collect();
+        let sockets = sockets_read.iter().map(|s| s.downgrade()).collect();
+
         let sender = UdpSenderWorker::new(
             addresses.clone(),
             socket_write,
             arguments.peer_address,
-            options.size_options.max_payload_size_per_packet,
+            &options,
+            state.clone(),
         );
         WorkerBuilder::new(sender)
             .with_address(addresses.sender_address().clone())
@@ -127,34 +275,99 @@ impl UdpTransport {
             .with_outgoing_access_control(DenyAll)
             .start(&self.ctx)?;
 
-        let receiver = UdpReceiverProcessor::new(
-            addresses.clone(),
-            socket_read,
-            arguments.peer_address,
-            options.size_options.pending_messages_per_peer,
-            options.size_options.max_on_the_wire_packet_size,
-        );
-        ProcessorBuilder::new(receiver)
-            .with_address(addresses.receiver_address().clone())
-            .with_incoming_access_control(DenyAll)
-            .with_outgoing_access_control_arc(receiver_outgoing_access_control)
-            .with_shutdown_priority(WorkerShutdownPriority::Priority1)
-            .start(&self.ctx)?;
+        // Each receiver has its own reassembly storage
+        let mut pending_routing_messages = vec![];
+        let mut started_receivers = vec![];
+        for (receiver_address, socket_read) in addresses.receiver_addresses().zip(sockets_read) {
+            let receiver_pending_routing_messages = PendingRoutingMessageStorage::new(
+                options.size_options.pending_messages_per_peer,
+                options.clock.clone(),
+            )
+            .with_memory_budget(config.reassembly_memory_budget_per_receiver);
+            pending_routing_messages.push(receiver_pending_routing_messages.clone());
+
+            let receiver = UdpReceiverProcessor::new(
+                addresses.clone(),
+                socket_read,
+                arguments.peer_address,
+                receiver_pending_routing_messages,
+                &options,
+                state.clone(),
+            );
+            let started = ProcessorBuilder::new(receiver)
+                .with_address(receiver_address.clone())
+                .with_incoming_access_control(DenyAll)
+                .with_outgoing_access_control_arc(receiver_outgoing_access_control.clone())
+                .with_shutdown_priority(WorkerShutdownPriority::Priority1)
+                .start(&self.ctx);
+
+            if let Err(err) = started {
+                // Don't leave a partially started bind behind
+                for started_receiver in &started_receivers {
+                    _ = self.ctx.stop_address(started_receiver);
+                }
+                _ = self.ctx.stop_address(addresses.sender_address());
+                return Err(err);
+            }
+            started_receivers.push(receiver_address.clone());
+        }
 
-        let bind = UdpBind::new(
+        let bind = UdpBind {
             addresses,
-            arguments.peer_address,
-            local_addr,
+            peer: arguments.peer_address,
+            bind_address: local_addr,
             flow_control_id,
-        );
+            config,
+            pending_routing_messages,
+            clock: options.clock,
+            sockets,
+            state,
+        };
+
+        self.registry.lock().unwrap().add(&self.ctx, bind.clone());
 
         Ok(bind)
     }
 
+    /// Apply the requested socket buffer sizes and return the sizes actually used by the OS,
+    /// which may differ from the requested ones (Linux doubles them, and clamps them to
+    /// `net.core.rmem_max` and `net.core.wmem_max`)
+    fn set_buffer_sizes(
+        socket: &UdpSocket,
+        arguments: &UdpBindArguments,
+    ) -> Result<UdpSocketBufferSizes> {
+        let socket = SockRef::from(socket);
+
+        if let Some(size) = arguments.recv_buffer_size {
+            socket
+                .set_recv_buffer_size(size)
+                .map_err(TransportError::from)?;
+        }
+        if let Some(size) = arguments.send_buffer_size {
+            socket
+                .set_send_buffer_size(size)
+                .map_err(TransportError::from)?;
+        }
+
+        Ok(UdpSocketBufferSizes {
+            recv_buffer_size: socket.recv_buffer_size().map_err(TransportError::from)?,
+            send_buffer_size: socket.send_buffer_size().map_err(TransportError::from)?,
+        })
+    }
+
     /// Interrupt an active TCP connection given its Sender `Address`
     pub fn unbind(&self, address: &Address) -> Result<()> {
         self.ctx.stop_address(address)
     }
+
+    /// Binds created by this transport which are still running
+    ///
+    /// The returned sender addresses can be used to stop the binds with [`Self::unbind`].
+    pub fn binds(&self) -> Vec<UdpBindInfo> {
+        let mut registry = self.registry.lock().unwrap();
+        registry.remove_stopped(&self.ctx);
+        registry.binds().iter().map(UdpBind::info).collect()
+    }
 }
 
 /// Result of [`TcpTransport::listen`] call.
@@ -164,6 +377,120 @@ pub struct UdpBind {
     peer: Option<SocketAddr>,
     bind_address: SocketAddr,
     flow_control_id: FlowControlId,
+    config: UdpBindConfig,
+    /// Reassembly state of each receiver
+    pending_routing_messages: Vec<PendingRoutingMessageStorage>,
+    clock: Arc<dyn Clock>,
+    /// Sockets owned by the workers, they are closed once the workers are dropped
+    sockets: Vec<Weak<UdpSocket>>,
+    state: UdpBindState,
+}
+
+/// State shared by a [`UdpBind`] with its sender worker and receiver processors
+#[derive(Clone, Debug)]
+pub(crate) struct UdpBindState {
+    pub(crate) stats: UdpBindStats,
+    pub(crate) activity: UdpBindActivity,
+    /// Binding requests sent to STUN servers, answered through the receivers
+    pub(crate) stun_transactions: StunTransactions,
+    /// Flushes waiting for the sender, see [`UdpBind::flush`]
+    pub(crate) flush_requests: FlushRequests,
+}
+
+/// Maximum time to wait for the workers to release their sockets in [`UdpBind::close`]
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Interval between two checks of the sockets in [`UdpBind::close`]
+const CLOSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Description of a running [`UdpBind`], see [`UdpTransport::binds`]
+#[derive(Clone, Debug)]
+pub struct UdpBindInfo {
+    /// Local bind address
+    pub bind_address: SocketAddr,
+    /// Peer if the bind communicates with one specific peer
+    pub peer: Option<SocketAddr>,
+    /// Sender worker address
+    pub sender_address: Address,
+    /// Addresses of the receiver processors, one per socket
+    pub receiver_addresses: Vec<Address>,
+    /// Flow control id
+    pub flow_control_id: FlowControlId,
+    /// Counters of the datagrams sent and received, shared with the running bind
+    pub stats: UdpBindStats,
+}
+
+/// Result of [`UdpBind::flush`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UdpFlushReport {
+    /// Number of messages written to the socket while flushing
+    pub flushed: u64,
+    /// Number of messages which couldn't be sent while flushing
+    pub dropped: u64,
+}
+
+/// Sizes of the socket buffers, as reported by the OS
+#[derive(Clone, Copy, Debug)]
+struct UdpSocketBufferSizes {
+    recv_buffer_size: usize,
+    send_buffer_size: usize,
+}
+
+/// Configuration used by a running [`UdpBind`], once the defaults, the environment variables
+/// and the [`UdpBindOptions`] are applied, see [`UdpBind::effective_config`]
+#[derive(Clone, Debug)]
+pub struct UdpBindConfig {
+    /// Sizes of the datagrams, with the room reserved for the sequence number and the
+    /// compression flag already subtracted from the payload size
+    pub size_options: UdpSizeOptions,
+    /// Largest message which can be sent, a single datagram payload without fragmentation
+    pub max_message_size: usize,
+    /// Whether the messages are refused instead of being split into several datagrams
+    pub no_fragmentation: bool,
+    /// Size of the replay protection window, `None` if replay protection is disabled
+    pub replay_protection_window: Option<u64>,
+    /// Algorithm used to compress the messages, `None` if they are sent uncompressed
+    pub compression: Option<UdpCompression>,
+    /// Number of retransmissions of each datagram, and the minimum priority of the messages
+    /// which are retransmitted. `None` if the datagrams are sent once
+    pub fragment_retransmission: Option<(u8, MessagePriority)>,
+    /// Bytes buffered for partially received messages by each receiver, `None` if unlimited
+    pub reassembly_memory_budget_per_receiver: Option<usize>,
+    /// Networks the datagrams are accepted from, `None` if they are accepted from anywhere
+    pub source_allowlist: Option<Vec<IpNet>>,
+    /// Number of sockets, each with its own receiver, see [`UdpTransport::bind_reuseport`]
+    pub receivers: usize,
+    /// Size of the socket receive buffer applied by the OS
+    pub recv_buffer_size: usize,
+    /// Size of the socket send buffer applied by the OS
+    pub send_buffer_size: usize,
+}
+
+impl UdpBindConfig {
+    fn new(options: &UdpBindOptions, buffer_sizes: UdpSocketBufferSizes, receivers: usize) -> Self {
+        let max_message_size = if options.no_fragmentation {
+            options.size_options.max_payload_size_per_packet
+        } else {
+            MAX_MESSAGE_SIZE
+        };
+
+        Self {
+            size_options: options.size_options,
+            max_message_size,
+            no_fragmentation: options.no_fragmentation,
+            replay_protection_window: options.replay_protection_window,
+            compression: options.compression,
+            fragment_retransmission: options
+                .fragment_retransmission
+                .map(|r| (r.retransmissions, r.min_priority)),
+            reassembly_memory_budget_per_receiver: options
+                .reassembly_memory_budget
+                .map(|budget| budget / receivers),
+            source_allowlist: options.source_allowlist.clone(),
+            receivers,
+            recv_buffer_size: buffer_sizes.recv_buffer_size,
+            send_buffer_size: buffer_sizes.send_buffer_size,
+        }
+    }
 }
 
 impl fmt::Display for UdpBind {
@@ -181,26 +508,16 @@ impl fmt::Display for UdpBind {
 }
 
 impl UdpBind {
-    /// Constructor
-    pub(crate) fn new(
-        addresses: Addresses,
-        peer: Option<SocketAddr>,
-        bind_address: SocketAddr,
-        flow_control_id: FlowControlId,
-    ) -> Self {
-        Self {
-            addresses,
-            peer,
-            bind_address,
-            flow_control_id,
-        }
-    }
-
     /// Receiver processor Address
     pub fn receiver_address(&self) -> &Address {
         self.addresses.receiver_address()
     }
 
+    /// Addresses of the receiver processors, one per socket, see [`UdpTransport::bind_reuseport`]
+    pub fn receiver_addresses(&self) -> Vec<Address> {
+        self.addresses.receiver_addresses().cloned().collect()
+    }
+
     /// Sender worker address
     pub fn sender_address(&self) -> &Address {
         self.addresses.sender_address()
@@ -220,6 +537,198 @@ impl UdpBind {
     pub fn flow_control_id(&self) -> &FlowControlId {
         &self.flow_control_id
     }
+
+    /// Counters of the datagrams sent and received through this bind
+    pub fn stats(&self) -> &UdpBindStats {
+        &self.state.stats
+    }
+
+    /// Time of the last datagram received, and of the last datagram sent through this bind,
+    /// `None` if no datagram was received or sent yet
+    ///
+    /// This can be used to detect binds which have gone silent, for example because a NAT
+    /// mapping was lost. The times are given by the [`Clock`] of the bind.
+    pub fn last_activity(&self) -> (Option<Instant>, Option<Instant>) {
+        self.state.activity.last_activity()
+    }
+
+    /// Description of this bind
+    pub fn info(&self) -> UdpBindInfo {
+        UdpBindInfo {
+            bind_address: self.bind_address,
+            peer: self.peer,
+            sender_address: self.sender_address().clone(),
+            receiver_addresses: self.receiver_addresses(),
+            flow_control_id: self.flow_control_id.clone(),
+            stats: self.state.stats.clone(),
+        }
+    }
+
+    /// Source of the current time, see [`UdpBindOptions::with_clock`]
+    pub(crate) fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Messages which are partially received, waiting for their other datagrams
+    ///
+    /// This is meant to diagnose fragmentation and datagram loss issues: the state of the
+    /// receivers is copied and is not modified.
+    pub fn reassembly_snapshot(&self) -> Vec<ReassemblyEntryInfo> {
+        self.pending_routing_messages
+            .iter()
+            .flat_map(|pending_routing_messages| pending_routing_messages.snapshot())
+            .collect()
+    }
+
+    /// Size of the socket receive buffer applied by the OS
+    pub fn recv_buffer_size(&self) -> usize {
+        self.config.recv_buffer_size
+    }
+
+    /// Size of the socket send buffer applied by the OS
+    pub fn send_buffer_size(&self) -> usize {
+        self.config.send_buffer_size
+    }
+
+    /// Configuration actually used by this bind, to check that the [`UdpBindOptions`] and
+    /// the environment variables took effect
+    pub fn effective_config(&self) -> &UdpBindConfig {
+        &self.config
+    }
+
+    /// Ask a STUN server (RFC 5389) for the address and port of this bind, as seen from
+    /// outside the NATs between this bind and the server
+    ///
+    /// The returned server-reflexive address can be sent to a peer, to open a puncture
+    /// without a Rendezvous service. The Binding request is sent again, with a doubling
+    /// timeout, if the server doesn't answer.
+    pub async fn discover_external_address(
+        &self,
+        stun_server: impl AsRef<str>,
+    ) -> Result<SocketAddr> {
+        let stun_server = resolve_peer(&HostnamePort::from_str(stun_server.as_ref())?).await?;
+
+        let socket = self
+            .sockets
+            .first()
+            .and_then(Weak::upgrade)
+            .ok_or_else(|| {
+                Error::new(
+                    Origin::Transport,
+                    Kind::Invalid,
+                    format!("The UDP bind {} is closed", self.bind_address),
+                )
+            })?;
+
+        let (transaction_id, mut response) = self.state.stun_transactions.start();
+        let request = encode_binding_request(&transaction_id);
+
+        let mut result = Err(PunctureError::StunServerNotReachable.into());
+        let mut timeout = STUN_INITIAL_RTO;
+        for _ in 0..STUN_MAX_ATTEMPTS {
+            if let Err(err) = socket.send_to(&request, stun_server).await {
+                result = Err(TransportError::from(err).into());
+                break;
+            }
+
+            match tokio::time::timeout(timeout, &mut response).await {
+                Ok(Ok(response)) => {
+                    result = response;
+                    break;
+                }
+                // The transactions are dropped with the bind
+                Ok(Err(_)) => {
+                    result = Err(Error::new(
+                        Origin::Transport,
+                        Kind::Shutdown,
+                        format!("The UDP bind {} was closed", self.bind_address),
+                    ));
+                    break;
+                }
+                Err(_) => timeout *= 2,
+            }
+        }
+
+        self.state.stun_transactions.finish(&transaction_id);
+
+        if let Ok(external_address) = &result {
+            debug!(bind_address = %self.bind_address, %external_address, %stun_server, "UDP external address discovered");
+        }
+
+        result
+    }
+
+    /// Wait until the messages queued to the sender worker when this is called are written
+    /// to the socket, without stopping the bind
+    ///
+    /// This is useful before a checkpoint, a controlled handover or the exit of the process.
+    /// Keepalives are counted as messages. The datagrams may still be lost on the network.
+    pub async fn flush(&self, ctx: &Context, timeout: Duration) -> Result<UdpFlushReport> {
+        let (sent_before, failed_before) = self.state.stats.flush_counters();
+
+        let (flush_id, marker, response) = self.state.flush_requests.start();
+        let result = match ctx
+            .send_with_local_info(self.sender_address().clone(), (), vec![marker])
+            .await
+        {
+            Ok(()) => tokio::time::timeout(timeout, response).await,
+            Err(err) => {
+                self.state.flush_requests.finish(flush_id);
+                return Err(err);
+            }
+        };
+        self.state.flush_requests.finish(flush_id);
+
+        match result {
+            Ok(Ok((sent_after, failed_after))) => Ok(UdpFlushReport {
+                flushed: sent_after.saturating_sub(sent_before),
+                dropped: failed_after.saturating_sub(failed_before),
+            }),
+            // The sender may have been stopped before reaching the marker
+            Ok(Err(_)) | Err(_) => Err(Error::new(
+                Origin::Transport,
+                Kind::Timeout,
+                format!(
+                    "The messages queued to the UDP bind {} were not flushed in time",
+                    self.bind_address
+                ),
+            )),
+        }
+    }
+
+    /// Stop the sender worker and the receiver processors, and wait until their sockets
+    /// are closed, so that the local port can be bound again as soon as this returns
+    ///
+    /// Dropping a [`UdpBind`] or calling [`UdpTransport::unbind`] is best-effort: the workers
+    /// are stopped in the background and the port may still be in use for a short while.
+    pub async fn close(self, ctx: &Context) -> Result<()> {
+        // The sender stops the receivers during its shutdown
+        match ctx.stop_address(self.sender_address()) {
+            Ok(()) => {}
+            // Already stopped, the sockets may still be closing
+            Err(err) if err.code().kind == Kind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        while self.sockets.iter().any(|socket| socket.strong_count() > 0) {
+            if Instant::now() >= deadline {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::Timeout,
+                    format!(
+                        "The UDP sockets bound to {} were not closed in time",
+                        self.bind_address
+                    ),
+                ));
+            }
+            tokio::time::sleep(CLOSE_POLL_INTERVAL).await;
+        }
+
+        debug!(bind_address = %self.bind_address, "UDP bind closed");
+
+        Ok(())
+    }
 }
 
 impl From<UdpBind> for Address {
@@ -23,6 +23,8 @@ impl UdpTransport {
     pub fn create(ctx: &Context) -> Result<Self> {
         let udp = Self {
             ctx: Arc::new(ctx.try_clone()?),
+            registry: Default::default(),
+            punctures: Default::default(),
         };
         // make the UDP transport available in the list of supported transports for
         // later address resolution when socket addresses will need to be instantiated as UDP
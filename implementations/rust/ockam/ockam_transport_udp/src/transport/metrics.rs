@@ -0,0 +1,55 @@
+use crate::stats::render_prometheus;
+use crate::UdpTransport;
+use ockam_core::compat::string::String;
+
+impl UdpTransport {
+    /// Render the counters of all the binds created by this transport, including
+    /// puncture successes and failures, in the Prometheus text exposition format
+    pub fn metrics_text(&self) -> String {
+        let mut registry = self.registry.lock().unwrap();
+        registry.remove_stopped(&self.ctx);
+        render_prometheus(registry.stats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::UdpTransport;
+    use ockam_core::{route, AllowAll, Result};
+    use ockam_node::{Context, MessageReceiveOptions};
+    use std::time::Duration;
+
+    #[ockam_macros::test]
+    async fn test_metrics_text(ctx: &mut Context) -> Result<()> {
+        let udp = UdpTransport::create(ctx)?;
+        let (bind1, bind2) = udp.connected_pair().await?;
+
+        let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll)?;
+        ctx.flow_controls()
+            .add_consumer(&"receiver".into(), bind2.flow_control_id());
+
+        ctx.send(
+            route![bind1.sender_address().clone(), "receiver"],
+            "Hello".to_string(),
+        )
+        .await?;
+        receiver
+            .receive_extended::<String>(
+                MessageReceiveOptions::new().with_timeout(Duration::from_secs(5)),
+            )
+            .await?;
+
+        let text = udp.metrics_text();
+        assert!(text.contains("# TYPE ockam_udp_packets_sent_total counter\n"));
+        assert!(text.contains("\nockam_udp_packets_sent_total 1\n"));
+        assert!(text.contains("\nockam_udp_packets_received_total 1\n"));
+        assert!(text.contains("\nockam_udp_messages_received_total 1\n"));
+
+        // The counters of stopped binds are still reported
+        udp.unbind(bind1.sender_address())?;
+        let text = udp.metrics_text();
+        assert!(text.contains("\nockam_udp_packets_sent_total 1\n"));
+
+        Ok(())
+    }
+}
@@ -1,18 +1,33 @@
+#[cfg(feature = "benchmark")]
+mod benchmark;
 mod bind;
 mod lifecycle;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod puncture;
+mod registry;
+mod session;
+#[cfg(any(test, feature = "test-utils"))]
+mod test_utils;
 
+#[cfg(feature = "benchmark")]
+pub use benchmark::*;
+pub(crate) use bind::UdpBindState;
 pub use bind::*;
+pub use puncture::RESUME_PUNCTURE_TIMEOUT;
+pub use session::*;
 
-use ockam_core::compat::sync::Arc;
+use ockam_core::compat::sync::{Arc, Mutex};
 use ockam_core::{async_trait, Result};
 use ockam_node::{Context, HasContext};
+use registry::{UdpBindsRegistry, UdpPuncturesRegistry};
 
 /// UDP Transport
 #[derive(Clone, Debug)]
 pub struct UdpTransport {
     ctx: Arc<Context>,
-    // TODO: Add registry,
+    registry: Arc<Mutex<UdpBindsRegistry>>,
+    punctures: Arc<Mutex<UdpPuncturesRegistry>>,
 }
 
 /// This trait adds a `create_udp_transport` method to any struct returning a Context.
@@ -1,8 +1,16 @@
-use crate::{UdpBind, UdpPuncture, UdpPunctureOptions, UdpTransport};
+use crate::{PunctureState, UdpBind, UdpPuncture, UdpPunctureOptions, UdpTransport};
+use ockam_core::compat::time::Duration;
 use ockam_core::{Address, Result};
+use ockam_node::Context;
+
+/// Time given to the peer of a resumed puncture to answer
+pub const RESUME_PUNCTURE_TIMEOUT: Duration = Duration::from_secs(5);
 
 impl UdpTransport {
     /// Start a new puncture
+    ///
+    /// Fails if the maximum number of concurrent punctures is reached, see
+    /// [`Self::set_max_concurrent_punctures`].
     pub fn puncture(
         &self,
         bind: UdpBind,
@@ -12,19 +20,90 @@ impl UdpTransport {
         options: UdpPunctureOptions,
         redirect_first_message_to_transport: bool,
     ) -> Result<UdpPuncture> {
-        UdpPuncture::create(
-            &self.ctx,
-            bind,
-            peer_udp_address,
-            my_remote_address,
-            their_remote_address,
-            options,
-            redirect_first_message_to_transport,
-        )
+        self.create_puncture(&self.ctx, bind, |ctx, bind| {
+            UdpPuncture::create(
+                ctx,
+                bind,
+                peer_udp_address,
+                my_remote_address,
+                their_remote_address,
+                options,
+                redirect_first_message_to_transport,
+            )
+        })
+    }
+
+    /// Resume a puncture exported with [`UdpPuncture::export_state`], without negotiating
+    /// it again
+    ///
+    /// The bind should use the same local address as the exported puncture, so that the
+    /// NAT mapping is reused. If the peer doesn't answer within [`RESUME_PUNCTURE_TIMEOUT`],
+    /// for example because the mapping expired, the puncture is closed and
+    /// [`UdpPuncture::wait_for_puncture`] returns an error.
+    pub fn resume_puncture(
+        &self,
+        bind: UdpBind,
+        state: PunctureState,
+        options: UdpPunctureOptions,
+    ) -> Result<UdpPuncture> {
+        self.create_puncture(&self.ctx, bind, |ctx, bind| {
+            UdpPuncture::resume(ctx, bind, state, options, RESUME_PUNCTURE_TIMEOUT)
+        })
     }
 
     /// Stop a puncture
     pub fn stop_puncture(&self, puncture: UdpPuncture) -> Result<()> {
         puncture.stop(&self.ctx)
     }
+
+    /// Limit the number of punctures running at the same time, `None` removes the limit
+    ///
+    /// Once the limit is reached, starting a puncture fails with a
+    /// [`Kind::ResourceExhausted`](ockam_core::errcode::Kind::ResourceExhausted) error until
+    /// a running puncture is stopped. Punctures which are already running are not affected.
+    pub fn set_max_concurrent_punctures(&self, max_punctures: Option<usize>) {
+        self.punctures
+            .lock()
+            .unwrap()
+            .set_max_punctures(max_punctures);
+    }
+
+    /// Maximum number of punctures running at the same time, see
+    /// [`Self::set_max_concurrent_punctures`]
+    pub fn max_concurrent_punctures(&self) -> Option<usize> {
+        self.punctures.lock().unwrap().max_punctures()
+    }
+
+    /// Number of punctures started by this transport which are still running
+    pub fn active_punctures_count(&self) -> usize {
+        let mut punctures = self.punctures.lock().unwrap();
+        punctures.remove_stopped(&self.ctx);
+        punctures.count()
+    }
+
+    /// Fail if the maximum number of concurrent punctures is reached, to avoid negotiating
+    /// a puncture which can't be started
+    pub(crate) fn check_punctures_limit(&self) -> Result<()> {
+        let mut punctures = self.punctures.lock().unwrap();
+        punctures.remove_stopped(&self.ctx);
+        punctures.check_limit()
+    }
+
+    /// Start a puncture with the given function and register it, if the maximum number of
+    /// concurrent punctures is not reached
+    pub(crate) fn create_puncture(
+        &self,
+        ctx: &Context,
+        bind: UdpBind,
+        create: impl FnOnce(&Context, UdpBind) -> Result<UdpPuncture>,
+    ) -> Result<UdpPuncture> {
+        let mut punctures = self.punctures.lock().unwrap();
+        punctures.remove_stopped(&self.ctx);
+        punctures.check_limit()?;
+
+        let puncture = create(ctx, bind)?;
+        punctures.add(puncture.receiver_address().clone());
+
+        Ok(puncture)
+    }
 }
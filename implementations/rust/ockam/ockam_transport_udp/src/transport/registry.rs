@@ -0,0 +1,96 @@
+use crate::{PunctureError, UdpBind, UdpBindStats};
+use ockam_core::compat::vec::Vec;
+use ockam_core::{Address, Result};
+use ockam_node::Context;
+
+/// Binds created by a [`UdpTransport`](crate::UdpTransport)
+#[derive(Debug, Default)]
+pub(crate) struct UdpBindsRegistry {
+    binds: Vec<UdpBind>,
+    /// Counters of the binds that were stopped, so that totals never decrease
+    stopped_binds_stats: UdpBindStats,
+}
+
+impl UdpBindsRegistry {
+    /// Register a new bind
+    pub(crate) fn add(&mut self, ctx: &Context, bind: UdpBind) {
+        self.remove_stopped(ctx);
+        self.binds.push(bind);
+    }
+
+    /// Forget the binds whose sender worker is not running anymore
+    pub(crate) fn remove_stopped(&mut self, ctx: &Context) {
+        let stopped_binds_stats = &self.stopped_binds_stats;
+        self.binds.retain(|bind| {
+            let running = ctx
+                .is_worker_registered_at(bind.sender_address())
+                .unwrap_or(false);
+            if !running {
+                stopped_binds_stats.accumulate(bind.stats());
+            }
+            running
+        });
+    }
+
+    /// Registered binds, including the stopped ones which were not removed yet
+    pub(crate) fn binds(&self) -> &[UdpBind] {
+        &self.binds
+    }
+
+    /// Counters of all the binds, including the ones that were stopped
+    #[cfg(feature = "metrics")]
+    pub(crate) fn stats(&self) -> impl Iterator<Item = &UdpBindStats> {
+        self.binds
+            .iter()
+            .map(|bind| bind.stats())
+            .chain(core::iter::once(&self.stopped_binds_stats))
+    }
+}
+
+/// Punctures created by a [`UdpTransport`](crate::UdpTransport)
+#[derive(Debug, Default)]
+pub(crate) struct UdpPuncturesRegistry {
+    /// Maximum number of punctures running at the same time, unlimited if `None`
+    max_punctures: Option<usize>,
+    /// Addresses of the receiver workers of the punctures
+    receivers: Vec<Address>,
+}
+
+impl UdpPuncturesRegistry {
+    /// Set the maximum number of punctures running at the same time
+    pub(crate) fn set_max_punctures(&mut self, max_punctures: Option<usize>) {
+        self.max_punctures = max_punctures;
+    }
+
+    /// Maximum number of punctures running at the same time
+    pub(crate) fn max_punctures(&self) -> Option<usize> {
+        self.max_punctures
+    }
+
+    /// Register a new puncture, given the address of its receiver worker
+    pub(crate) fn add(&mut self, receiver_address: Address) {
+        self.receivers.push(receiver_address);
+    }
+
+    /// Forget the punctures whose receiver worker is not running anymore
+    pub(crate) fn remove_stopped(&mut self, ctx: &Context) {
+        // The receiver address is a secondary address of the worker
+        self.receivers
+            .retain(|receiver_address| ctx.address_exists(receiver_address).unwrap_or(false));
+    }
+
+    /// Number of registered punctures, including the stopped ones which were not removed yet
+    pub(crate) fn count(&self) -> usize {
+        self.receivers.len()
+    }
+
+    /// Fail if no other puncture can be started
+    pub(crate) fn check_limit(&self) -> Result<()> {
+        match self.max_punctures {
+            Some(max_punctures) if self.receivers.len() >= max_punctures => {
+                Err(PunctureError::TooManyPunctures)?
+            }
+            _ => Ok(()),
+        }
+    }
+}
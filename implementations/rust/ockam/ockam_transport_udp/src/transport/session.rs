@@ -0,0 +1,260 @@
+use crate::local_info::UDP_KEEPALIVE_IDENTIFIER;
+use crate::{UdpBind, UdpLocalInfo, UdpPuncture, UDP};
+use core::str::FromStr;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::time::Duration;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{
+    Address, AllowAll, Error, LocalInfo, Message, Result, Route, RouteBuilder, Routed,
+};
+use ockam_node::compat::asynchronous::resolve_peer;
+use ockam_node::{Context, MessageReceiveOptions, DEFAULT_TIMEOUT};
+use ockam_transport_core::HostnamePort;
+use std::net::SocketAddr;
+use tracing::{debug, trace, warn};
+
+/// Connected-socket style access to one peer of a [`UdpBind`]
+///
+/// Messages are sent to the peer through the bind, and only messages received
+/// from that peer are returned by [`UdpSession::recv`], the others are dropped.
+/// Obtained with [`UdpBind::connect`].
+pub struct UdpSession {
+    ctx: Context,
+    bind: UdpBind,
+    peer: Arc<Mutex<SessionPeer>>,
+}
+
+/// Peer of a [`UdpSession`], shared with the task resolving its hostname again
+#[derive(Debug)]
+struct SessionPeer {
+    hostname_port: HostnamePort,
+    address: SocketAddr,
+}
+
+impl SessionPeer {
+    fn set(&mut self, hostname_port: HostnamePort, address: SocketAddr) {
+        if address != self.address {
+            debug!(old_peer = %self.address, new_peer = %address, "UDP session peer changed");
+        }
+        self.hostname_port = hostname_port;
+        self.address = address;
+    }
+}
+
+impl UdpBind {
+    /// Create a [`UdpSession`] exchanging messages with the given peer through this bind
+    pub async fn connect(
+        &self,
+        ctx: &Context,
+        peer_udp_address: impl AsRef<str>,
+    ) -> Result<UdpSession> {
+        let hostname_port = HostnamePort::from_str(peer_udp_address.as_ref())?;
+        let peer = resolve_peer(&hostname_port).await?;
+        UdpSession::check_peer(self, &peer)?;
+
+        let address = Address::random_tagged("UdpSession");
+        let session_ctx = ctx.new_detached(address.clone(), AllowAll, AllowAll)?;
+        ctx.flow_controls()
+            .add_consumer(&address, self.flow_control_id());
+
+        debug!(%peer, %address, "Created UDP session");
+
+        Ok(UdpSession {
+            ctx: session_ctx,
+            bind: self.clone(),
+            peer: Arc::new(Mutex::new(SessionPeer {
+                hostname_port,
+                address: peer,
+            })),
+        })
+    }
+}
+
+impl UdpSession {
+    /// Current peer
+    pub fn peer(&self) -> SocketAddr {
+        self.peer.lock().unwrap().address
+    }
+
+    /// Resolve the hostname of the peer again every `interval`, and switch to its new address
+    /// when it changes, so that the session survives a change of the peer IP address
+    ///
+    /// After a [`reconnect`](Self::reconnect), the new peer is resolved. The resolution stops
+    /// once the session is dropped. This is meant to be called once per session.
+    pub fn with_resolution_interval(self, interval: Duration) -> Self {
+        let peer = Arc::downgrade(&self.peer);
+        let bind = self.bind.clone();
+
+        self.ctx.runtime().spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let hostname_port = match peer.upgrade() {
+                    Some(peer) => peer.lock().unwrap().hostname_port.clone(),
+                    None => break,
+                };
+
+                let address = match resolve_peer(&hostname_port).await {
+                    Ok(address) => address,
+                    Err(e) => {
+                        warn!(%hostname_port, %e, "Can't resolve the UDP session peer again");
+                        continue;
+                    }
+                };
+
+                match peer.upgrade() {
+                    Some(peer) => Self::update_resolved_peer(&bind, &peer, hostname_port, address),
+                    None => break,
+                }
+            }
+        });
+
+        self
+    }
+
+    /// Switch to the new address of the peer, unless the peer was changed meanwhile
+    fn update_resolved_peer(
+        bind: &UdpBind,
+        peer: &Mutex<SessionPeer>,
+        hostname_port: HostnamePort,
+        address: SocketAddr,
+    ) {
+        if let Err(e) = Self::check_peer(bind, &address) {
+            warn!(%hostname_port, %e, "Ignoring the new address of the UDP session peer");
+            return;
+        }
+
+        let mut peer = peer.lock().unwrap();
+        if peer.hostname_port == hostname_port {
+            peer.set(hostname_port, address);
+        }
+    }
+
+    /// Address receiving the messages of this session
+    pub fn address(&self) -> &Address {
+        self.ctx.primary_address()
+    }
+
+    /// Send a message to the given route on the peer's side
+    pub async fn send<M>(&self, onward_route: impl Into<Route>, msg: M) -> Result<()>
+    where
+        M: Message + Send + 'static,
+    {
+        self.ctx
+            .send(self.peer_route().append_route(onward_route).build(), msg)
+            .await
+    }
+
+    /// Send a keepalive message to the peer, to keep the NAT mappings open
+    ///
+    /// The peer's receiver counts it, see [`UdpBindStats::keepalives_received`](crate::UdpBindStats::keepalives_received),
+    /// but doesn't deliver it to any worker. The keepalive is an empty message without
+    /// onward route, which the peers running a previous version drop as well.
+    pub async fn send_keepalive(&self) -> Result<()> {
+        let keepalive = LocalInfo::new(UDP_KEEPALIVE_IDENTIFIER.into(), vec![]);
+        self.ctx
+            .send_with_local_info(self.peer_route().build(), (), vec![keepalive])
+            .await
+    }
+
+    /// Route to the UDP sender, sending to the peer
+    fn peer_route(&self) -> RouteBuilder {
+        let route = Route::new().append(self.bind.sender_address().clone());
+        // A bind with a fixed peer doesn't expect the peer address in the route
+        if self.bind.peer().is_some() {
+            route
+        } else {
+            route.append(Address::new_with_string(UDP, self.peer().to_string()))
+        }
+    }
+
+    /// Wait for the next message from the peer, with the default timeout
+    pub async fn recv<M: Message>(&mut self) -> Result<Routed<M>> {
+        self.recv_with_timeout(DEFAULT_TIMEOUT).await
+    }
+
+    /// Wait for the next message from the peer
+    pub async fn recv_with_timeout<M: Message>(&mut self, timeout: Duration) -> Result<Routed<M>> {
+        tokio::time::timeout(timeout, self.recv_from_peer())
+            .await
+            .map_err(|_| {
+                Error::new(
+                    Origin::Transport,
+                    Kind::Timeout,
+                    format!(
+                        "Timeout {timeout:?} elapsed waiting for a message from {}",
+                        self.peer()
+                    ),
+                )
+            })?
+    }
+
+    async fn recv_from_peer<M: Message>(&mut self) -> Result<Routed<M>> {
+        loop {
+            let msg = self
+                .ctx
+                .receive_extended::<M>(MessageReceiveOptions::new().without_timeout())
+                .await?;
+
+            let peer = self.peer();
+            match UdpLocalInfo::find_info(msg.local_message()) {
+                Ok(info) if info.source_address() == peer => return Ok(msg),
+                Ok(info) => {
+                    trace!(
+                        %peer,
+                        source = %info.source_address(),
+                        "Dropping message from another peer"
+                    );
+                }
+                Err(_) => {
+                    trace!(%peer, "Dropping message not received via UDP");
+                }
+            }
+        }
+    }
+
+    /// Switch to another peer, for example when its address changed
+    pub async fn reconnect(&mut self, peer_udp_address: impl AsRef<str>) -> Result<()> {
+        let hostname_port = HostnamePort::from_str(peer_udp_address.as_ref())?;
+        let peer = resolve_peer(&hostname_port).await?;
+        Self::check_peer(&self.bind, &peer)?;
+
+        self.peer.lock().unwrap().set(hostname_port, peer);
+
+        Ok(())
+    }
+
+    /// Wait until the puncture is open and switch to the peer address it was opened with
+    pub async fn reconnect_after_puncture(
+        &mut self,
+        puncture: &mut UdpPuncture,
+        timeout: Duration,
+    ) -> Result<()> {
+        let peer_route = puncture.wait_for_puncture_route(timeout).await?;
+
+        let peer_udp_address = peer_route
+            .iter()
+            .find(|address| address.transport_type() == UDP)
+            .ok_or_else(|| {
+                Error::new(
+                    Origin::Transport,
+                    Kind::Invalid,
+                    format!("No UDP address in the puncture route {peer_route}"),
+                )
+            })?;
+
+        self.reconnect(peer_udp_address.address()).await
+    }
+
+    /// A bind with a fixed peer can only talk to that peer
+    fn check_peer(bind: &UdpBind, peer: &SocketAddr) -> Result<()> {
+        match bind.peer() {
+            Some(bind_peer) if &bind_peer != peer => Err(Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!("The UDP bind only communicates with {bind_peer}, not with {peer}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{UdpBind, UdpBindArguments, UdpBindOptions, UdpTransport};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+impl UdpTransport {
+    /// Bind two sockets on the loopback interface, each one having the other as its peer.
+    ///
+    /// A message sent to the first bind's sender address is received by the second bind's
+    /// receiver and vice versa, so tests only need to add a consumer for the flow control
+    /// of the receiving side.
+    pub async fn connected_pair(&self) -> Result<(UdpBind, UdpBind)> {
+        // Use the bound sockets directly, so that no other process can take their ports
+        // between the moment we get them and the moment the binds are started
+        let socket1 = Self::bind_loopback_socket().await?;
+        let socket2 = Self::bind_loopback_socket().await?;
+        let address1 = Self::local_address(&socket1)?;
+        let address2 = Self::local_address(&socket2)?;
+
+        let bind1 = self.start_bind(
+            UdpBindArguments::new()
+                .with_bind_socket_address(address1)
+                .with_peer_socket_address(address2),
+            UdpBindOptions::new(),
+            socket1,
+            vec![],
+        )?;
+        let bind2 = self.start_bind(
+            UdpBindArguments::new()
+                .with_bind_socket_address(address2)
+                .with_peer_socket_address(address1),
+            UdpBindOptions::new(),
+            socket2,
+            vec![],
+        )?;
+
+        Ok((bind1, bind2))
+    }
+
+    async fn bind_loopback_socket() -> Result<UdpSocket> {
+        UdpSocket::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| Error::new(Origin::Transport, Kind::Io, e))
+    }
+
+    fn local_address(socket: &UdpSocket) -> Result<SocketAddr> {
+        socket
+            .local_addr()
+            .map_err(|e| Error::new(Origin::Transport, Kind::Io, e))
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+    use crate::UdpTransport;
+    use ockam_core::{route, AllowAll, Result};
+    use ockam_node::{Context, MessageReceiveOptions};
+    use std::time::Duration;
+
+    #[ockam_macros::test]
+    async fn connected_pair__send_message__should_be_received(ctx: &mut Context) -> Result<()> {
+        let udp = UdpTransport::create(ctx)?;
+        let (bind1, bind2) = udp.connected_pair().await?;
+
+        assert_eq!(bind1.peer(), Some(bind2.bind_address()));
+        assert_eq!(bind2.peer(), Some(bind1.bind_address()));
+
+        let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll)?;
+        ctx.flow_controls()
+            .add_consumer(&"receiver".into(), bind2.flow_control_id());
+
+        ctx.send(
+            route![bind1.sender_address().clone(), "receiver"],
+            "Hello".to_string(),
+        )
+        .await?;
+
+        let msg = receiver
+            .receive_extended::<String>(
+                MessageReceiveOptions::new().with_timeout(Duration::from_secs(5)),
+            )
+            .await?;
+
+        assert_eq!(msg.into_body()?, "Hello");
+
+        Ok(())
+    }
+}
@@ -4,6 +4,8 @@ use ockam_core::Address;
 pub(crate) struct Addresses {
     sender_address: Address,
     receiver_address: Address,
+    /// Receivers of the other sockets bound to the same port
+    additional_receiver_addresses: Vec<Address>,
 }
 
 impl Addresses {
@@ -14,12 +16,27 @@ impl Addresses {
         Self {
             sender_address,
             receiver_address,
+            additional_receiver_addresses: vec![],
         }
     }
+
+    /// Generate addresses for one sender and `receivers_count` receivers
+    pub(crate) fn generate_with_receivers(receivers_count: usize) -> Self {
+        let mut addresses = Self::generate();
+        addresses.additional_receiver_addresses = (1..receivers_count)
+            .map(|_| Address::random_tagged("UdpReceiver"))
+            .collect();
+
+        addresses
+    }
+
     pub fn sender_address(&self) -> &Address {
         &self.sender_address
     }
     pub fn receiver_address(&self) -> &Address {
         &self.receiver_address
     }
+    pub fn receiver_addresses(&self) -> impl Iterator<Item = &Address> {
+        core::iter::once(&self.receiver_address).chain(self.additional_receiver_addresses.iter())
+    }
 }
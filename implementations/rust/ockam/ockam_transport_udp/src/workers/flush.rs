@@ -0,0 +1,65 @@
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{LocalInfo, LocalMessage};
+use ockam_node::channel_types::{oneshot_channel, OneshotReceiver, OneshotSender};
+use tracing::trace;
+
+/// [`LocalInfo`] marking a message sent to a UDP sender as a flush marker, see
+/// [`UdpBind::flush`](crate::UdpBind::flush). Its data is the id of the flush request.
+const UDP_FLUSH_IDENTIFIER: &str = "UDP_FLUSH_IDENTIFIER";
+
+/// Senders of the counters of the pending flushes, by flush id
+type PendingFlushes = HashMap<u64, OneshotSender<(u64, u64)>>;
+
+/// Flushes requested with [`UdpBind::flush`](crate::UdpBind::flush), waiting for the sender
+///
+/// A flush sends a marker message to the sender. Since the sender handles its messages in
+/// order, all the messages queued before the marker were handled once it reaches the
+/// marker, which then completes the request with the counters of the bind at that point.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FlushRequests {
+    pending: Arc<Mutex<PendingFlushes>>,
+}
+
+impl FlushRequests {
+    /// Register a new flush, return the [`LocalInfo`] of the marker and the receiver of the
+    /// number of messages sent and dropped by the sender when it handled the marker
+    pub(crate) fn start(&self) -> (u64, LocalInfo, OneshotReceiver<(u64, u64)>) {
+        let flush_id: u64 = rand::random();
+        let (sender, receiver) = oneshot_channel();
+        self.pending.lock().unwrap().insert(flush_id, sender);
+
+        let marker = LocalInfo::new(UDP_FLUSH_IDENTIFIER.into(), flush_id.to_be_bytes().to_vec());
+        (flush_id, marker, receiver)
+    }
+
+    /// Forget a flush, once completed or abandoned
+    pub(crate) fn finish(&self, flush_id: u64) {
+        self.pending.lock().unwrap().remove(&flush_id);
+    }
+
+    /// Complete the flush marked by the given message
+    ///
+    /// Return `false` if the message is not a flush marker, and must be sent.
+    pub(crate) fn handle_message(&self, msg: &LocalMessage, counters: (u64, u64)) -> bool {
+        let Some(marker) = msg
+            .local_info()
+            .iter()
+            .find(|local_info| local_info.type_identifier() == UDP_FLUSH_IDENTIFIER)
+        else {
+            return false;
+        };
+
+        match <[u8; 8]>::try_from(marker.data()).map(u64::from_be_bytes) {
+            Ok(flush_id) => match self.pending.lock().unwrap().remove(&flush_id) {
+                Some(sender) => {
+                    let _ = sender.send(counters);
+                }
+                None => trace!("Dropping a marker of an abandoned flush"),
+            },
+            Err(_) => trace!("Dropping an invalid flush marker"),
+        }
+
+        true
+    }
+}
@@ -1,11 +1,17 @@
 mod addresses;
+mod flush;
 mod receiver;
+mod replay_protection;
 mod sender;
 mod socket_split;
 
 pub(crate) use addresses::*;
+pub(crate) use flush::*;
 pub(crate) use receiver::*;
+pub(crate) use replay_protection::*;
 pub(crate) use sender::*;
 pub(crate) use socket_split::*;
 
 mod pending_messages;
+
+pub(crate) use pending_messages::PendingRoutingMessageStorage;
@@ -11,13 +11,18 @@ pub(crate) use transport_messages_iterator::*;
 #[allow(non_snake_case)]
 #[cfg(test)]
 mod tests {
-    use crate::messages::{RoutingNumber, UdpRoutingMessage, UdpTransportMessage};
-    use crate::workers::pending_messages::{PendingMessage, TransportMessagesIterator};
-    use crate::{UdpSizeOptions, MAX_MESSAGE_SIZE};
+    use crate::messages::{RoutingNumber, UdpRoutingMessage, UdpTransportMessage, CURRENT_VERSION};
+    use crate::workers::pending_messages::{
+        PendingMessage, PendingRoutingMessageStorage, TransportMessagesIterator,
+    };
+    use crate::{MockClock, UdpCompression, UdpSizeOptions, MAX_MESSAGE_SIZE};
+    use core::time::Duration;
     use ockam_core::compat::rand::RngCore;
+    use ockam_core::compat::sync::Arc;
     use ockam_core::{route, Result};
     use rand::prelude::SliceRandom;
     use rand::thread_rng;
+    use std::time::Instant;
 
     #[test]
     fn small_message__reassemble__should_succeed() -> Result<()> {
@@ -38,7 +43,7 @@ mod tests {
 
         let next = iterator.next().transpose()?.unwrap();
         let packet: UdpTransportMessage = minicbor::decode(&next)?;
-        let mut pending_message = PendingMessage::new(vec![]);
+        let mut pending_message = PendingMessage::new(vec![], Instant::now());
         assert!(pending_message.try_assemble().is_none());
         pending_message.add_transport_message(packet)?;
         let message_received = pending_message.try_assemble().unwrap();
@@ -60,7 +65,7 @@ mod tests {
 
         let routing_number = RoutingNumber::default();
 
-        let mut pending_message = PendingMessage::new(vec![]);
+        let mut pending_message = PendingMessage::new(vec![], Instant::now());
 
         let mut iterator =
             TransportMessagesIterator::new(routing_number, &message, max_payload_size_per_packet)?;
@@ -92,7 +97,7 @@ mod tests {
 
         let routing_number = RoutingNumber::default();
 
-        let mut pending_message = PendingMessage::new(vec![]);
+        let mut pending_message = PendingMessage::new(vec![], Instant::now());
 
         let mut iterator = TransportMessagesIterator::new(
             routing_number,
@@ -122,6 +127,9 @@ mod tests {
                 offset: self.offset,
                 total: self.total,
                 payload: self.payload.into_owned().into(),
+                sequence_number: self.sequence_number,
+                compression: self.compression,
+                epoch: self.epoch,
             }
         }
     }
@@ -136,7 +144,7 @@ mod tests {
 
         let routing_number = RoutingNumber::default();
 
-        let mut pending_message = PendingMessage::new(vec![]);
+        let mut pending_message = PendingMessage::new(vec![], Instant::now());
 
         let mut iterator = TransportMessagesIterator::new(
             routing_number,
@@ -165,4 +173,327 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn partial_message__snapshot__should_show_received_fragments() -> Result<()> {
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+        let mut payload = vec![0; 2 * max_payload_size_per_packet];
+        thread_rng().fill_bytes(&mut payload);
+
+        let message =
+            UdpRoutingMessage::new(route!["onward"], route!["return"], payload.into(), None);
+
+        let routing_number = RoutingNumber::new(7);
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let clock = MockClock::new();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(clock.clone()));
+        assert!(storage.snapshot().is_empty());
+
+        let mut iterator =
+            TransportMessagesIterator::new(routing_number, &message, max_payload_size_per_packet)?;
+        let total = iterator.total();
+
+        let next = iterator.next().transpose()?.unwrap();
+        let packet: UdpTransportMessage = minicbor::decode(&next)?;
+        assert!(storage
+            .add_transport_message_and_try_assemble(peer, packet)?
+            .is_none());
+
+        let snapshot = storage.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].peer, peer);
+        assert_eq!(snapshot[0].routing_number, 7);
+        assert_eq!(snapshot[0].fragments_received, 1);
+        assert_eq!(snapshot[0].fragments_expected, total);
+        assert_eq!(snapshot[0].age, Duration::ZERO);
+
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(storage.snapshot()[0].age, Duration::from_secs(3));
+
+        while let Some(next) = iterator.next().transpose()? {
+            let packet: UdpTransportMessage = minicbor::decode(&next)?;
+            storage.add_transport_message_and_try_assemble(peer, packet)?;
+        }
+
+        assert!(storage.snapshot().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_bounds_offset__add__should_fail() {
+        let mut pending_message = PendingMessage::new(vec![], Instant::now());
+        let packet =
+            UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), u16::MAX, 1, vec![1]);
+
+        assert!(pending_message.add_transport_message(packet).is_err());
+        assert!(pending_message.try_assemble().is_none());
+    }
+
+    #[test]
+    fn retransmitted_parts__reassemble__should_be_ignored() -> Result<()> {
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+        let mut payload = vec![0; 2 * max_payload_size_per_packet];
+        thread_rng().fill_bytes(&mut payload);
+
+        let message =
+            UdpRoutingMessage::new(route!["onward"], route!["return"], payload.into(), None);
+
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(MockClock::new()));
+
+        let datagrams = TransportMessagesIterator::new(
+            RoutingNumber::default(),
+            &message,
+            max_payload_size_per_packet,
+        )?
+        .collect::<Result<Vec<_>>>()?;
+
+        // Each part is received twice, the message is delivered once
+        let mut messages_received = vec![];
+        for datagram in datagrams.iter().flat_map(|d| [d, d]) {
+            let packet: UdpTransportMessage = minicbor::decode(datagram)?;
+            if let Some(message) = storage.add_transport_message_and_try_assemble(peer, packet)? {
+                messages_received.push(message);
+            }
+        }
+
+        assert_eq!(messages_received, vec![message]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn compression_mismatch__add__should_fail() {
+        let mut pending_message = PendingMessage::new(vec![], Instant::now());
+        let packet = UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 0, 2, vec![1, 2])
+            .with_compression(UdpCompression::Lz4);
+        pending_message.add_transport_message(packet).unwrap();
+
+        let packet = UdpTransportMessage::new(CURRENT_VERSION, RoutingNumber(0), 1, 2, vec![3]);
+        assert!(pending_message.add_transport_message(packet).is_err());
+        assert!(pending_message.try_assemble().is_none());
+    }
+
+    #[cfg(feature = "compression-lz4")]
+    #[test]
+    fn compressed_message__reassemble__should_succeed() -> Result<()> {
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+        let payload = "Hello, Ockam! ".repeat(100).into_bytes();
+
+        let message =
+            UdpRoutingMessage::new(route!["onward"], route!["return"], payload.into(), None);
+
+        let routing_number = RoutingNumber::default();
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(MockClock::new()));
+
+        let uncompressed_total =
+            TransportMessagesIterator::new(routing_number, &message, max_payload_size_per_packet)?
+                .total();
+        let iterator =
+            TransportMessagesIterator::new(routing_number, &message, max_payload_size_per_packet)?
+                .with_compression(Some(UdpCompression::Lz4))?;
+        assert!(iterator.total() < uncompressed_total);
+
+        let mut message_received = None;
+        for next in iterator {
+            let next = next?;
+            let packet: UdpTransportMessage = minicbor::decode(&next)?;
+            assert_eq!(packet.compression, Some(UdpCompression::Lz4));
+            message_received = storage.add_transport_message_and_try_assemble(peer, packet)?;
+        }
+
+        assert_eq!(message_received.unwrap(), message);
+
+        Ok(())
+    }
+
+    #[test]
+    fn keepalive__add__should_be_assembled_without_onward_route() -> Result<()> {
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(MockClock::new()));
+
+        // A keepalive is a regular message, which the receivers of the previous versions
+        // assemble and drop since it has no onward route
+        let iterator = TransportMessagesIterator::new(
+            RoutingNumber::new(1),
+            &UdpRoutingMessage::keepalive(),
+            UdpSizeOptions::default().max_payload_size_per_packet,
+        )?;
+        assert_eq!(iterator.total(), 1);
+
+        let mut message_received = None;
+        for next in iterator {
+            let next = next?;
+            let packet: UdpTransportMessage = minicbor::decode(&next)?;
+            message_received = storage.add_transport_message_and_try_assemble(peer, packet)?;
+        }
+
+        let message_received = message_received.unwrap();
+        assert!(message_received.is_keepalive());
+        assert!(message_received.onward_route.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn restarted_peer__older_routing_number__should_be_assembled() -> Result<()> {
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(MockClock::new()));
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+
+        let message = UdpRoutingMessage::new(
+            route!["onward"],
+            route!["return"],
+            "Hello, Ockam!".as_bytes().into(),
+            None,
+        );
+
+        // A message slightly late is dropped, but a restarted peer starts from a new random
+        // routing number, which can be far behind the previous ones
+        for (routing_number, assembled) in [(1000, true), (990, false), (100, true), (101, true)] {
+            let mut iterator = TransportMessagesIterator::new(
+                RoutingNumber::new(routing_number),
+                &message,
+                max_payload_size_per_packet,
+            )?;
+            let next = iterator.next().transpose()?.unwrap();
+            let packet: UdpTransportMessage = minicbor::decode(&next)?;
+            let message_received = storage.add_transport_message_and_try_assemble(peer, packet)?;
+            assert_eq!(message_received.is_some(), assembled);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_datagram__far_behind__should_not_discard_pending_parts() -> Result<()> {
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(MockClock::new()));
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+        let mut payload = vec![0; 2 * max_payload_size_per_packet];
+        thread_rng().fill_bytes(&mut payload);
+
+        let message =
+            UdpRoutingMessage::new(route!["onward"], route!["return"], payload.into(), None);
+
+        let parts = TransportMessagesIterator::new(
+            RoutingNumber::new(1000),
+            &message,
+            max_payload_size_per_packet,
+        )?
+        .collect::<Result<Vec<_>>>()?;
+        assert!(parts.len() > 1);
+
+        let stale_parts = TransportMessagesIterator::new(
+            RoutingNumber::new(500),
+            &message,
+            max_payload_size_per_packet,
+        )?
+        .collect::<Result<Vec<_>>>()?;
+
+        let (last, first) = parts.split_last().unwrap();
+        for part in first {
+            let packet: UdpTransportMessage = minicbor::decode(part)?;
+            assert!(storage
+                .add_transport_message_and_try_assemble(peer, packet)?
+                .is_none());
+        }
+
+        // A single datagram far behind is not enough to consider that the peer restarted
+        let packet: UdpTransportMessage = minicbor::decode(&stale_parts[0])?;
+        assert!(storage
+            .add_transport_message_and_try_assemble(peer, packet)?
+            .is_none());
+
+        let packet: UdpTransportMessage = minicbor::decode(last)?;
+        let message_received = storage
+            .add_transport_message_and_try_assemble(peer, packet)?
+            .unwrap();
+        assert_eq!(message_received.payload, message.payload);
+
+        Ok(())
+    }
+
+    #[test]
+    fn discarded_message__next_parts__should_be_ignored() -> Result<()> {
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+        let mut payload = vec![0; 2 * max_payload_size_per_packet];
+        thread_rng().fill_bytes(&mut payload);
+
+        let message =
+            UdpRoutingMessage::new(route!["onward"], route!["return"], payload.into(), None);
+
+        let routing_number = RoutingNumber::new(3);
+        let peer = "127.0.0.1:4000".parse().unwrap();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(MockClock::new()));
+
+        let mut iterator =
+            TransportMessagesIterator::new(routing_number, &message, max_payload_size_per_packet)?;
+
+        let next = iterator.next().transpose()?.unwrap();
+        let packet: UdpTransportMessage = minicbor::decode(&next)?;
+        storage.add_transport_message_and_try_assemble(peer, packet)?;
+        assert_eq!(storage.snapshot().len(), 1);
+
+        storage.discard(peer, routing_number);
+        assert!(storage.snapshot().is_empty());
+
+        while let Some(next) = iterator.next().transpose()? {
+            let packet: UdpTransportMessage = minicbor::decode(&next)?;
+            assert!(storage
+                .add_transport_message_and_try_assemble(peer, packet)?
+                .is_none());
+        }
+        assert!(storage.snapshot().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn exceeded_memory_budget__enforce__should_evict_oldest_messages() -> Result<()> {
+        let max_payload_size_per_packet = UdpSizeOptions::default().max_payload_size_per_packet;
+        let memory_budget = 2 * max_payload_size_per_packet + max_payload_size_per_packet / 2;
+
+        let clock = MockClock::new();
+        let storage = PendingRoutingMessageStorage::new(16, Arc::new(clock.clone()))
+            .with_memory_budget(Some(memory_budget));
+
+        // The first part of a large message from 3 peers, ~3 packets in total
+        let peers: Vec<std::net::SocketAddr> = (0..3)
+            .map(|i| format!("127.0.0.1:{}", 4000 + i).parse().unwrap())
+            .collect();
+        for peer in &peers {
+            let mut payload = vec![0; 2 * max_payload_size_per_packet];
+            thread_rng().fill_bytes(&mut payload);
+            let message =
+                UdpRoutingMessage::new(route!["onward"], route!["return"], payload.into(), None);
+
+            let mut iterator = TransportMessagesIterator::new(
+                RoutingNumber::new(1),
+                &message,
+                max_payload_size_per_packet,
+            )?;
+            let next = iterator.next().transpose()?.unwrap();
+            let packet: UdpTransportMessage = minicbor::decode(&next)?;
+            storage.add_transport_message_and_try_assemble(*peer, packet)?;
+
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(storage.snapshot().len(), 3);
+        assert!(storage.buffered_bytes() > memory_budget);
+
+        assert_eq!(storage.enforce_memory_budget(), 1);
+        assert!(storage.buffered_bytes() <= memory_budget);
+
+        let mut remaining: Vec<_> = storage.snapshot().into_iter().map(|e| e.peer).collect();
+        remaining.sort();
+        assert_eq!(remaining, peers[1..]);
+
+        // Nothing else to evict
+        assert_eq!(storage.enforce_memory_budget(), 0);
+
+        Ok(())
+    }
 }
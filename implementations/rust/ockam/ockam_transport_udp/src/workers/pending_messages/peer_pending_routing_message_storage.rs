@@ -1,9 +1,13 @@
 use crate::messages::{RoutingNumber, UdpRoutingMessage, UdpTransportMessage};
 use crate::workers::pending_messages::{PendingMessage, PendingMessageState};
+use crate::{ReassemblyEntryInfo, MAX_MESSAGE_SIZE};
 use core::cmp::min;
+use ockam_core::compat::boxed::Box;
 use ockam_core::compat::collections::VecDeque;
 use ockam_core::Result;
-use tracing::{error, trace};
+use std::net::SocketAddr;
+use std::time::Instant;
+use tracing::{debug, error, trace};
 
 /// Pending routing messages for a certain peer
 /// This storage will cache packets (until they fit into the cache) and assemble them into
@@ -20,6 +24,11 @@ pub(crate) struct PeerPendingRoutingMessageStorage {
     // Messages with following routing numbers:
     // [self.oldest_routing_number, ..., self.oldest_routing_number + max_pending_messages - 1]
     pending_messages: Vec<PendingMessageState>,
+    // Messages numbered far behind the ones we can accept, assembled separately: the peer may
+    // have restarted with a new random routing number, which is only confirmed once one of
+    // these messages is complete. Until then, a stale datagram doesn't affect the messages in
+    // progress
+    restarted: Option<Box<PeerPendingRoutingMessageStorage>>,
 }
 
 impl PeerPendingRoutingMessageStorage {
@@ -33,12 +42,123 @@ impl PeerPendingRoutingMessageStorage {
             oldest_routing_number: routing_number,
             max_pending_messages,
             pending_messages,
+            restarted: None,
         }
     }
 
+    /// Messages which are partially received
+    pub(crate) fn snapshot(
+        &self,
+        peer: SocketAddr,
+        now: Instant,
+    ) -> impl Iterator<Item = ReassemblyEntryInfo> + '_ {
+        self.pending_messages
+            .iter()
+            .chain(
+                self.restarted
+                    .iter()
+                    .flat_map(|restarted| restarted.pending_messages.iter()),
+            )
+            .filter_map(move |state| match state {
+                PendingMessageState::InProgress(pending_message) => {
+                    Some(pending_message.info(peer, now))
+                }
+                PendingMessageState::NotReceived | PendingMessageState::FullyHandled => None,
+            })
+    }
+
+    /// Number of bytes buffered for the messages which are partially received
+    pub(crate) fn buffered_bytes(&self) -> usize {
+        let restarted = self
+            .restarted
+            .as_ref()
+            .map_or(0, |restarted| restarted.buffered_bytes());
+
+        self.pending_messages
+            .iter()
+            .map(|state| match state {
+                PendingMessageState::InProgress(pending_message) => {
+                    pending_message.buffered_bytes()
+                }
+                PendingMessageState::NotReceived | PendingMessageState::FullyHandled => 0,
+            })
+            .sum::<usize>()
+            + restarted
+    }
+
+    /// Routing number and reception time of the first part of the oldest message which is
+    /// partially received
+    pub(crate) fn oldest_in_progress(&self) -> Option<(Instant, RoutingNumber)> {
+        let restarted = self
+            .restarted
+            .as_ref()
+            .and_then(|restarted| restarted.oldest_in_progress());
+
+        self.pending_messages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, state)| match state {
+                PendingMessageState::InProgress(pending_message) => Some((
+                    pending_message.created_at(),
+                    RoutingNumber(self.oldest_routing_number + index as u16),
+                )),
+                PendingMessageState::NotReceived | PendingMessageState::FullyHandled => None,
+            })
+            .chain(restarted)
+            .min_by_key(|(created_at, _)| *created_at)
+    }
+
+    /// Drop the parts of a message received so far and ignore its next parts
+    pub(crate) fn discard(&mut self, routing_number: RoutingNumber) {
+        self.discard_impl(routing_number, true)
+    }
+
+    /// Drop the parts of a message received so far, ignore its next parts, and release its
+    /// buffer instead of keeping it for the next messages
+    pub(crate) fn evict(&mut self, routing_number: RoutingNumber) {
+        self.discard_impl(routing_number, false)
+    }
+
+    fn discard_impl(&mut self, routing_number: RoutingNumber, reuse_buffer: bool) {
+        if routing_number < self.oldest_routing_number {
+            if let Some(restarted) = &mut self.restarted {
+                restarted.discard_impl(routing_number, reuse_buffer);
+            }
+            return;
+        }
+
+        let index = (routing_number - self.oldest_routing_number) as usize;
+        let Some(pending_message_state) = self.pending_messages.get_mut(index) else {
+            // Not received yet, its next parts will be checked as well
+            return;
+        };
+
+        if let PendingMessageState::InProgress(pending_message) = pending_message_state.take() {
+            if reuse_buffer {
+                // Put the buffer back to reuse in the future
+                self.buffer_queue.push_back(pending_message.drop_message());
+            }
+        }
+        *pending_message_state = PendingMessageState::FullyHandled;
+    }
+
+    /// Forget the messages of the peer and continue with the messages assembled since its
+    /// restart
+    fn restart(&mut self, restarted: PeerPendingRoutingMessageStorage) {
+        let previous = core::mem::replace(self, restarted);
+        for pending_message_state in previous.pending_messages {
+            if let PendingMessageState::InProgress(pending_message) = pending_message_state {
+                // Put the buffer back to reuse in the future
+                self.buffer_queue.push_back(pending_message.drop_message());
+            }
+        }
+        self.buffer_queue.extend(previous.buffer_queue);
+    }
+
     pub(crate) fn add_transport_message_and_try_assemble(
         &mut self,
         transport_message: UdpTransportMessage<'_>,
+        now: Instant,
     ) -> Result<Option<UdpRoutingMessage<'static>>> {
         trace!(
             "Received routing message {}, offset {}",
@@ -46,6 +166,50 @@ impl PeerPendingRoutingMessageStorage {
             transport_message.offset
         );
 
+        // A message far behind the ones we can accept either arrived very late, or comes from
+        // the peer which restarted with a new random routing number
+        if transport_message.routing_number < self.oldest_routing_number
+            && self.oldest_routing_number - transport_message.routing_number
+                > self.max_pending_messages
+        {
+            let routing_number = transport_message.routing_number;
+            let max_pending_messages = self.max_pending_messages;
+            // Start a new candidate if this message is behind the current one as well
+            let restarted = match &mut self.restarted {
+                Some(restarted) if routing_number >= restarted.oldest_routing_number => restarted,
+                restarted => {
+                    restarted.insert(Box::new(Self::new(routing_number, max_pending_messages)))
+                }
+            };
+
+            let res = restarted.add_and_try_assemble(transport_message, now)?;
+            if res.is_some() {
+                debug!(
+                    "Assembled routing message {} far behind {}, the peer restarted",
+                    routing_number, self.oldest_routing_number
+                );
+                if let Some(restarted) = self.restarted.take() {
+                    self.restart(*restarted);
+                }
+            }
+
+            return Ok(res);
+        }
+
+        let res = self.add_and_try_assemble(transport_message, now)?;
+        if res.is_some() {
+            // The peer still uses the routing numbers we accept, it didn't restart
+            self.restarted = None;
+        }
+
+        Ok(res)
+    }
+
+    fn add_and_try_assemble(
+        &mut self,
+        transport_message: UdpTransportMessage<'_>,
+        now: Instant,
+    ) -> Result<Option<UdpRoutingMessage<'static>>> {
         // self.oldest_routing_number is the oldest message we can accept,
         // older than that are ignored
         if transport_message.routing_number < self.oldest_routing_number {
@@ -117,21 +281,44 @@ impl PeerPendingRoutingMessageStorage {
             PendingMessageState::NotReceived => {
                 let buffer = self.buffer_queue.pop_front().unwrap_or_default();
 
-                PendingMessage::new(buffer)
+                PendingMessage::new(buffer, now)
             }
             PendingMessageState::InProgress(m) => m,
             PendingMessageState::FullyHandled => {
-                // Already send out.
+                // Already send out, or discarded
+                self.pending_messages[diff] = PendingMessageState::FullyHandled;
                 return Ok(None);
             }
         };
 
+        if pending_message.is_duplicate(&transport_message) {
+            trace!(
+                "Ignoring a copy of routing message {}, offset {}",
+                transport_message.routing_number,
+                transport_message.offset
+            );
+            self.pending_messages[diff] = PendingMessageState::InProgress(pending_message);
+            return Ok(None);
+        }
+
+        let routing_number = transport_message.routing_number;
         pending_message.add_transport_message(transport_message)?;
 
         match pending_message.try_assemble() {
             Some(routing_message_binary) => {
-                let res = match minicbor::decode::<UdpRoutingMessage>(&routing_message_binary) {
-                    Ok(routing_message) => Some(routing_message.into_owned()),
+                let routing_message_binary = match pending_message.compression() {
+                    Some(compression) => compression.decompress(
+                        routing_number,
+                        &routing_message_binary,
+                        MAX_MESSAGE_SIZE,
+                    ),
+                    None => Ok(routing_message_binary),
+                };
+
+                let res = match routing_message_binary.and_then(|binary| {
+                    Ok(minicbor::decode::<UdpRoutingMessage>(&binary)?.into_owned())
+                }) {
+                    Ok(routing_message) => Some(routing_message),
                     Err(err) => {
                         error!("Error while decoding UDP message {}", err);
                         None
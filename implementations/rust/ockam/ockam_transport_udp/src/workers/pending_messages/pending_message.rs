@@ -1,8 +1,10 @@
 use crate::messages::{RoutingNumber, UdpTransportMessage};
-use crate::{UdpTransportError, MAX_MESSAGE_SIZE};
+use crate::{ReassemblyEntryInfo, UdpCompression, UdpTransportError, MAX_MESSAGE_SIZE};
 use ockam_core::compat::collections::HashSet;
 use ockam_core::Result;
 use std::mem;
+use std::net::SocketAddr;
+use std::time::Instant;
 use tracing::trace;
 
 pub(crate) struct PendingMessage {
@@ -18,10 +20,14 @@ pub(crate) struct PendingMessage {
     binary: Vec<u8>,
     // Last part is treated differently, because it can be arbitrary length
     last_part: Option<Vec<u8>>,
+    // When the first part was received
+    created_at: Instant,
+    // Algorithm used to compress the whole message, all parts must announce the same
+    compression: Option<UdpCompression>,
 }
 
 impl PendingMessage {
-    pub(crate) fn new(binary: Vec<u8>) -> Self {
+    pub(crate) fn new(binary: Vec<u8>, created_at: Instant) -> Self {
         Self {
             routing_number: RoutingNumber(0),
             total: 0,
@@ -29,13 +35,48 @@ impl PendingMessage {
             not_received_parts: Default::default(),
             binary,
             last_part: None,
+            created_at,
+            compression: None,
         }
     }
 
+    pub(crate) fn info(&self, peer: SocketAddr, now: Instant) -> ReassemblyEntryInfo {
+        ReassemblyEntryInfo {
+            peer,
+            routing_number: self.routing_number.0,
+            fragments_received: self.total - self.not_received_parts.len() as u16,
+            fragments_expected: self.total,
+            age: now.saturating_duration_since(self.created_at),
+        }
+    }
+
+    /// Number of bytes of the message buffered so far
+    pub(crate) fn buffered_bytes(&self) -> usize {
+        self.binary.len() + self.last_part.as_ref().map_or(0, Vec::len)
+    }
+
+    /// When the first part of the message was received
+    pub(crate) fn created_at(&self) -> Instant {
+        self.created_at
+    }
+
+    /// Algorithm used to compress the message, if any
+    pub(crate) fn compression(&self) -> Option<UdpCompression> {
+        self.compression
+    }
+
+    /// Is this part already received, for example a retransmitted copy
+    pub(crate) fn is_duplicate(&self, transport_message: &UdpTransportMessage<'_>) -> bool {
+        self.total != 0
+            && transport_message.offset < self.total
+            && !self.not_received_parts.contains(&transport_message.offset)
+    }
+
     fn initialize_fields_if_needed(&mut self, transport_message: &UdpTransportMessage<'_>) {
         if self.total == 0 {
             self.total = transport_message.total;
             self.routing_number = transport_message.routing_number;
+            self.compression = transport_message.compression;
 
             for i in 0..self.total {
                 self.not_received_parts.insert(i);
@@ -72,7 +113,8 @@ impl PendingMessage {
     }
 
     fn is_last_message(&self, transport_message: &UdpTransportMessage<'_>) -> bool {
-        transport_message.offset + 1 == self.total
+        // The offset is not checked yet, it can be u16::MAX
+        u32::from(transport_message.offset) + 1 == u32::from(self.total)
     }
 
     pub(crate) fn add_transport_message(
@@ -101,6 +143,10 @@ impl PendingMessage {
             })?;
         }
 
+        if self.compression != transport_message.compression {
+            return Err(UdpTransportError::CompressionMismatch(self.routing_number))?;
+        }
+
         if self.total <= transport_message.offset {
             return Err(UdpTransportError::OutOfBounds {
                 routing_number: self.routing_number,
@@ -1,38 +1,164 @@
-use crate::messages::{UdpRoutingMessage, UdpTransportMessage};
+use crate::messages::{RoutingNumber, UdpRoutingMessage, UdpTransportMessage};
 use crate::workers::pending_messages::PeerPendingRoutingMessageStorage;
+use crate::{Clock, ReassemblyEntryInfo};
+use core::fmt::{Debug, Formatter};
 use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::{Arc, Mutex};
 use ockam_core::Result;
 use std::net::SocketAddr;
+use tracing::debug;
 
 /// Pending routing messages that we haven't yet assembled for all peers
+///
+/// The storage is shared between a receiver and its [`UdpBind`](crate::UdpBind), which
+/// can inspect it.
 /// TODO: Clearing everything for a socket after long inactivity would be nice
+#[derive(Clone)]
 pub(crate) struct PendingRoutingMessageStorage {
-    storage: HashMap<SocketAddr, PeerPendingRoutingMessageStorage>,
+    storage: Arc<Mutex<PeersPendingRoutingMessages>>,
     max_pending_messages_per_peer: u16,
+    /// Maximum number of bytes buffered for all the peers, see
+    /// [`UdpBindOptions::with_reassembly_memory_budget`](crate::UdpBindOptions::with_reassembly_memory_budget)
+    memory_budget: Option<usize>,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Default)]
+struct PeersPendingRoutingMessages {
+    peers: HashMap<SocketAddr, PeerPendingRoutingMessageStorage>,
+    /// Number of bytes buffered for all the peers
+    buffered_bytes: usize,
+}
+
+impl PeersPendingRoutingMessages {
+    /// Run a function on the storage of a peer, keeping track of the buffered bytes
+    fn update_peer<T>(
+        &mut self,
+        peer: &SocketAddr,
+        f: impl FnOnce(&mut PeerPendingRoutingMessageStorage) -> T,
+    ) -> Option<T> {
+        let peer_pending_messages = self.peers.get_mut(peer)?;
+
+        let before = peer_pending_messages.buffered_bytes();
+        let result = f(peer_pending_messages);
+        let after = peer_pending_messages.buffered_bytes();
+        self.buffered_bytes = self.buffered_bytes - before + after;
+
+        Some(result)
+    }
+}
+
+impl Debug for PendingRoutingMessageStorage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PendingRoutingMessageStorage")
+            .field(
+                "max_pending_messages_per_peer",
+                &self.max_pending_messages_per_peer,
+            )
+            .field("memory_budget", &self.memory_budget)
+            .finish()
+    }
 }
 
 impl PendingRoutingMessageStorage {
-    pub(crate) fn new(max_pending_messages_per_peer: u16) -> Self {
+    pub(crate) fn new(max_pending_messages_per_peer: u16, clock: Arc<dyn Clock>) -> Self {
         Self {
             storage: Default::default(),
             max_pending_messages_per_peer,
+            memory_budget: None,
+            clock,
         }
     }
 
+    /// Evict the oldest partially received messages when more than `memory_budget` bytes
+    /// are buffered
+    pub(crate) fn with_memory_budget(mut self, memory_budget: Option<usize>) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
     pub(crate) fn add_transport_message_and_try_assemble(
-        &mut self,
+        &self,
         peer: SocketAddr,
         transport_message: UdpTransportMessage<'_>,
     ) -> Result<Option<UdpRoutingMessage<'static>>> {
         let routing_number = transport_message.routing_number;
+        let now = self.clock.now();
 
-        let peer_pending_messages = self.storage.entry(peer).or_insert_with(|| {
+        let mut storage = self.storage.lock().unwrap();
+        storage.peers.entry(peer).or_insert_with(|| {
             PeerPendingRoutingMessageStorage::new(
                 routing_number,
                 self.max_pending_messages_per_peer,
             )
         });
 
-        peer_pending_messages.add_transport_message_and_try_assemble(transport_message)
+        storage
+            .update_peer(&peer, |peer_pending_messages| {
+                peer_pending_messages.add_transport_message_and_try_assemble(transport_message, now)
+            })
+            .unwrap_or(Ok(None))
+    }
+
+    /// Drop the parts of a message received so far and ignore its next parts
+    pub(crate) fn discard(&self, peer: SocketAddr, routing_number: RoutingNumber) {
+        self.storage
+            .lock()
+            .unwrap()
+            .update_peer(&peer, |peer_pending_messages| {
+                peer_pending_messages.discard(routing_number)
+            });
+    }
+
+    /// Evict the oldest partially received messages, of all peers, until the buffered
+    /// bytes fit into the memory budget. Return the number of evicted messages
+    pub(crate) fn enforce_memory_budget(&self) -> usize {
+        let Some(memory_budget) = self.memory_budget else {
+            return 0;
+        };
+
+        let mut storage = self.storage.lock().unwrap();
+        let mut evicted = 0;
+        while storage.buffered_bytes > memory_budget {
+            let oldest = storage
+                .peers
+                .iter()
+                .filter_map(|(peer, peer_pending_messages)| {
+                    peer_pending_messages
+                        .oldest_in_progress()
+                        .map(|(created_at, routing_number)| (created_at, *peer, routing_number))
+                })
+                .min_by_key(|(created_at, _, _)| *created_at);
+
+            let Some((_, peer, routing_number)) = oldest else {
+                break;
+            };
+
+            storage.update_peer(&peer, |peer_pending_messages| {
+                peer_pending_messages.evict(routing_number)
+            });
+            debug!(%peer, %routing_number, "Evicted a partially received message, the reassembly memory budget is exceeded");
+            evicted += 1;
+        }
+
+        evicted
+    }
+
+    /// Messages which are partially received, for all peers
+    pub(crate) fn snapshot(&self) -> Vec<ReassemblyEntryInfo> {
+        let now = self.clock.now();
+        self.storage
+            .lock()
+            .unwrap()
+            .peers
+            .iter()
+            .flat_map(|(peer, peer_pending_messages)| peer_pending_messages.snapshot(*peer, now))
+            .collect()
+    }
+
+    /// Number of bytes buffered for the messages which are partially received
+    #[cfg(test)]
+    pub(crate) fn buffered_bytes(&self) -> usize {
+        self.storage.lock().unwrap().buffered_bytes
     }
 }
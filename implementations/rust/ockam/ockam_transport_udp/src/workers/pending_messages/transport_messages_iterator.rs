@@ -1,15 +1,21 @@
 use crate::messages::{RoutingNumber, UdpRoutingMessage, UdpTransportMessage, CURRENT_VERSION};
-use crate::MAX_MESSAGE_SIZE;
+use crate::{UdpCompression, MAX_MESSAGE_SIZE};
 use ockam_core::Result;
 use ockam_transport_core::TransportError;
 use tracing::trace;
 
+#[derive(Clone)]
 pub(crate) struct TransportMessagesIterator {
     current_routing_number: RoutingNumber,
     offset: u16,
     total: u16,
     data: Vec<u8>,
+    // Size of the encoded routing message, before compression
+    message_len: usize,
     max_payload_size_per_packet: usize,
+    sequence_number: Option<u64>,
+    epoch: Option<u32>,
+    compression: Option<UdpCompression>,
 }
 
 impl TransportMessagesIterator {
@@ -24,20 +30,70 @@ impl TransportMessagesIterator {
             return Err(TransportError::MessageLengthExceeded)?;
         }
 
-        let total = routing_message.len() / max_payload_size_per_packet + 1;
-
-        let total: u16 = total
-            .try_into()
-            .map_err(|_| TransportError::MessageLengthExceeded)?;
-
         Ok(Self {
             current_routing_number,
             offset: 0,
-            total,
+            total: Self::total_for(routing_message.len(), max_payload_size_per_packet)?,
+            message_len: routing_message.len(),
             data: routing_message,
             max_payload_size_per_packet,
+            sequence_number: None,
+            epoch: None,
+            compression: None,
         })
     }
+
+    fn total_for(data_len: usize, max_payload_size_per_packet: usize) -> Result<u16> {
+        let total = data_len / max_payload_size_per_packet + 1;
+
+        Ok(total
+            .try_into()
+            .map_err(|_| TransportError::MessageLengthExceeded)?)
+    }
+
+    /// Compress the routing message before splitting it, unless it doesn't get smaller
+    pub(crate) fn with_compression(mut self, compression: Option<UdpCompression>) -> Result<Self> {
+        let compression = match compression {
+            Some(compression) => compression,
+            None => return Ok(self),
+        };
+
+        if let Some(compressed) = compression.compress(&self.data)? {
+            self.total = Self::total_for(compressed.len(), self.max_payload_size_per_packet)?;
+            self.data = compressed;
+            self.compression = Some(compression);
+        }
+
+        Ok(self)
+    }
+
+    /// Attach sequence numbers starting from the given one to the produced packets
+    pub(crate) fn with_sequence_number(mut self, sequence_number: Option<u64>) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+
+    /// Attach the epoch of the sequence numbers to the produced packets
+    pub(crate) fn with_epoch(mut self, epoch: Option<u32>) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Total number of packets
+    pub(crate) fn total(&self) -> u16 {
+        self.total
+    }
+
+    /// Size of the data split into packets, which is the encoded routing message,
+    /// possibly compressed
+    pub(crate) fn data_len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Size of the encoded routing message, before compression
+    pub(crate) fn message_len(&self) -> usize {
+        self.message_len
+    }
 }
 
 impl Iterator for TransportMessagesIterator {
@@ -55,7 +111,7 @@ impl Iterator for TransportMessagesIterator {
             data_offset_begin + self.max_payload_size_per_packet
         };
 
-        let part = UdpTransportMessage::new(
+        let mut part = UdpTransportMessage::new(
             CURRENT_VERSION,
             self.current_routing_number,
             self.offset,
@@ -63,6 +119,18 @@ impl Iterator for TransportMessagesIterator {
             &self.data[data_offset_begin..data_offset_end],
         );
 
+        if let Some(sequence_number) = self.sequence_number {
+            part = part.with_sequence_number(sequence_number);
+        }
+
+        if let Some(epoch) = self.epoch {
+            part = part.with_epoch(epoch);
+        }
+
+        if let Some(compression) = self.compression {
+            part = part.with_compression(compression);
+        }
+
         trace!(
             "Sending Routing Message {}. Offset {}",
             self.current_routing_number,
@@ -72,6 +140,7 @@ impl Iterator for TransportMessagesIterator {
         match ockam_core::cbor_encode_preallocate(part) {
             Ok(res) => {
                 self.offset += 1;
+                self.sequence_number = self.sequence_number.map(|n| n.wrapping_add(1));
                 Some(Ok(res))
             }
             Err(err) => Some(Err(err)),
@@ -1,11 +1,16 @@
-use super::{Addresses, UdpSocketRead};
-use crate::messages::UdpTransportMessage;
+use super::{Addresses, ReplayProtection, UdpSocketRead};
+use crate::messages::decode_frame;
+use crate::puncture::StunTransactions;
 use crate::workers::pending_messages::PendingRoutingMessageStorage;
-use crate::UDP;
+use crate::{
+    UdpBindActivity, UdpBindOptions, UdpBindState, UdpBindStats, UdpLocalInfo, MAX_MESSAGE_SIZE,
+    UDP,
+};
+use ipnet::IpNet;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{async_trait, Address, Error, LocalMessage, Processor, Result, RouteBuilder};
 use ockam_node::Context;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use tracing::{trace, warn};
 
 /// A listener for the UDP transport
@@ -26,6 +31,14 @@ pub(crate) struct UdpReceiverProcessor {
     /// Pending routing messages that we haven't yet assembled fully
     pending_routing_messages: PendingRoutingMessageStorage,
     max_on_the_wire_packet_size: usize,
+    /// Will be Some if replay protection is enabled
+    replay_protection: Option<ReplayProtection>,
+    /// Will be Some if only some sources are allowed to send datagrams
+    source_allowlist: Option<Vec<IpNet>>,
+    stats: UdpBindStats,
+    activity: UdpBindActivity,
+    /// Binding requests sent to STUN servers from the socket of this receiver
+    stun_transactions: StunTransactions,
 }
 
 impl UdpReceiverProcessor {
@@ -33,18 +46,23 @@ impl UdpReceiverProcessor {
         addresses: Addresses,
         socket_read: UdpSocketRead,
         peer: Option<SocketAddr>,
-        max_pending_messages_per_peer: u16,
-        max_on_the_wire_packet_size: usize,
+        pending_routing_messages: PendingRoutingMessageStorage,
+        options: &UdpBindOptions,
+        state: UdpBindState,
     ) -> Self {
+        let max_on_the_wire_packet_size = options.size_options.max_on_the_wire_packet_size;
         Self {
             addresses,
             socket_read,
             buffer: vec![0; max_on_the_wire_packet_size],
             peer,
-            pending_routing_messages: PendingRoutingMessageStorage::new(
-                max_pending_messages_per_peer,
-            ),
+            pending_routing_messages,
             max_on_the_wire_packet_size,
+            replay_protection: options.replay_protection_window.map(ReplayProtection::new),
+            source_allowlist: options.source_allowlist.clone(),
+            stats: state.stats,
+            activity: state.activity,
+            stun_transactions: state.stun_transactions,
         }
     }
 }
@@ -65,24 +83,83 @@ impl Processor for UdpReceiverProcessor {
             .await
             .map_err(|e| Error::new(Origin::Transport, Kind::Io, e))?;
 
+        // Checked before recording anything for the source, so that a flood from many
+        // unauthorized sources doesn't grow the statistics of the peers
+        if let Some(source_allowlist) = &self.source_allowlist {
+            // IPv4 sources may be reported as IPv4-mapped IPv6 addresses
+            let ip = match addr.ip() {
+                IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4),
+                ip => ip,
+            };
+            if !source_allowlist.iter().any(|net| net.contains(&ip)) {
+                trace!("Dropping a packet from: {}, which is not allowed", addr);
+                self.stats.record_unauthorized_dropped();
+                // Drop the packet before parsing anything
+                return Ok(true);
+            }
+        }
+
+        self.stats.record_packet_received(len, addr);
+
+        self.activity.record_received();
+
+        // Responses to `UdpBind::discover_external_address`, which may come from another
+        // address than the peer
+        if self.stun_transactions.handle_datagram(&self.buffer[..len]) {
+            return Ok(true);
+        }
+
         if let Some(peer) = &self.peer {
             if peer != &addr {
                 warn!(
                     "Dropping a packet from: {}, because expected address was: {}",
                     addr, peer
                 );
+                self.stats.record_packet_dropped(addr);
                 // Drop the packet, we don't expect data from that peer
                 return Ok(true);
             }
         }
 
-        let transport_message: UdpTransportMessage = minicbor::decode(&self.buffer[..len])?;
+        let transport_message = decode_frame(&self.buffer[..len])?;
+
+        if let Some(replay_protection) = &mut self.replay_protection {
+            if !replay_protection.check(
+                addr,
+                transport_message.sequence_number,
+                transport_message.epoch,
+            ) {
+                self.stats.record_packet_dropped(addr);
+                // Drop the packet, it's a replay or too old to tell
+                return Ok(true);
+            }
+        }
+
+        // Don't allocate anything for a message which can't be received
+        let min_message_size = transport_message.min_message_size();
+        if min_message_size > MAX_MESSAGE_SIZE {
+            warn!(
+                "Dropping message {} from: {}, because it has at least {} bytes, which is more than {}",
+                transport_message.routing_number, addr, min_message_size, MAX_MESSAGE_SIZE
+            );
+            self.stats.record_packet_dropped(addr);
+            self.stats.record_oversized_message_dropped();
+            self.pending_routing_messages
+                .discard(addr, transport_message.routing_number);
+            return Ok(true);
+        }
 
         // Let's save newly received message and see if we can assemble a Routing Message
-        let routing_message = match self
+        let routing_message = self
             .pending_routing_messages
-            .add_transport_message_and_try_assemble(addr, transport_message)?
-        {
+            .add_transport_message_and_try_assemble(addr, transport_message)?;
+
+        let evicted = self.pending_routing_messages.enforce_memory_budget();
+        if evicted > 0 {
+            self.stats.record_reassembly_evictions(evicted);
+        }
+
+        let routing_message = match routing_message {
             Some(routing_message) => routing_message,
             None => {
                 // We need more data to assemble a routing message
@@ -90,10 +167,16 @@ impl Processor for UdpReceiverProcessor {
             }
         };
 
-        if routing_message.onward_route.is_empty() {
+        // Keepalives only keep the NAT mappings open, there is nothing to deliver
+        if routing_message.is_keepalive() {
+            trace!("Received a keepalive from: {}", addr);
+            self.stats.record_keepalive_received();
             return Ok(true);
         }
 
+        self.stats
+            .record_message_received(minicbor::len(&routing_message));
+
         let return_route = RouteBuilder::default().append(self.addresses.sender_address().clone());
 
         let return_route = if self.peer.is_some() {
@@ -110,6 +193,10 @@ impl Processor for UdpReceiverProcessor {
 
         local_message = local_message.set_return_route(return_route.into());
 
+        // Let the destination know where the datagram came from
+        let local_info = UdpLocalInfo::mark(local_message.local_info().to_vec(), addr)?;
+        local_message = local_message.with_local_info(local_info);
+
         trace!(onward_route = %local_message.onward_route(),
             return_route = %local_message.return_route(),
             "Forwarding UDP message");
@@ -0,0 +1,288 @@
+use ockam_core::compat::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use tracing::{debug, trace, warn};
+
+/// Sliding window of sequence numbers we have already received from a certain peer
+pub(crate) struct ReplayWindow {
+    window_size: u64,
+    // Highest sequence number received so far
+    highest: Option<u64>,
+    // seen[i] is true if we received (highest - i)
+    seen: VecDeque<bool>,
+}
+
+impl ReplayWindow {
+    pub(crate) fn new(window_size: u64) -> Self {
+        Self {
+            window_size,
+            highest: None,
+            seen: Default::default(),
+        }
+    }
+
+    /// Return true if the sequence number wasn't seen before and is not too old, and record it
+    pub(crate) fn check_and_update(&mut self, sequence_number: u64) -> bool {
+        let highest = match self.highest {
+            Some(highest) => highest,
+            None => {
+                self.highest = Some(sequence_number);
+                self.seen.push_front(true);
+                return true;
+            }
+        };
+
+        if sequence_number > highest {
+            let shift = sequence_number - highest;
+
+            if shift >= self.window_size {
+                self.seen.clear();
+            } else {
+                for _ in 1..shift {
+                    self.seen.push_front(false);
+                }
+            }
+            self.seen.push_front(true);
+            self.seen.truncate(self.window_size as usize);
+            self.highest = Some(sequence_number);
+
+            return true;
+        }
+
+        let diff = highest - sequence_number;
+
+        if diff >= self.window_size {
+            // Too old to tell whether it's a replay
+            return false;
+        }
+
+        let diff = diff as usize;
+        if diff >= self.seen.len() {
+            self.seen.resize(diff + 1, false);
+        }
+
+        if self.seen[diff] {
+            return false;
+        }
+
+        self.seen[diff] = true;
+
+        true
+    }
+}
+
+/// Maximum number of previous epochs remembered for a peer
+const MAX_PREVIOUS_EPOCHS: usize = 16;
+
+/// Replay window of the current epoch of a peer, and the epochs it used before
+///
+/// A peer picks a random epoch every time it starts numbering its datagrams from the beginning.
+/// A datagram with a new epoch starts a new window, while the datagrams of the previous epochs
+/// are dropped, so that replaying them can't reset the window.
+struct PeerReplayWindow {
+    epoch: Option<u32>,
+    window: ReplayWindow,
+    previous_epochs: VecDeque<Option<u32>>,
+}
+
+impl PeerReplayWindow {
+    fn new(window_size: u64, epoch: Option<u32>) -> Self {
+        Self {
+            epoch,
+            window: ReplayWindow::new(window_size),
+            previous_epochs: Default::default(),
+        }
+    }
+
+    /// Return true if the sequence number wasn't seen before in its epoch and is not too old,
+    /// and record it
+    fn check_and_update(&mut self, epoch: Option<u32>, sequence_number: u64) -> bool {
+        if epoch != self.epoch {
+            if self.previous_epochs.contains(&epoch) {
+                return false;
+            }
+
+            debug!(
+                "Starting a new replay window, epoch: {:?} after: {:?}",
+                epoch, self.epoch
+            );
+            self.previous_epochs.push_back(self.epoch);
+            if self.previous_epochs.len() > MAX_PREVIOUS_EPOCHS {
+                self.previous_epochs.pop_front();
+            }
+            self.epoch = epoch;
+            self.window = ReplayWindow::new(self.window.window_size);
+        }
+
+        self.window.check_and_update(sequence_number)
+    }
+}
+
+/// Replay protection for all peers of a UDP receiver
+///
+/// Protection is negotiated implicitly: peers that never send a sequence number are accepted as
+/// is, but as soon as a peer sends a sequenced datagram, every following datagram from that peer
+/// must carry a fresh sequence number.
+pub(crate) struct ReplayProtection {
+    window_size: u64,
+    windows: HashMap<SocketAddr, PeerReplayWindow>,
+}
+
+impl ReplayProtection {
+    pub(crate) fn new(window_size: u64) -> Self {
+        Self {
+            window_size,
+            windows: Default::default(),
+        }
+    }
+
+    /// Return true if the datagram should be accepted
+    pub(crate) fn check(
+        &mut self,
+        peer: SocketAddr,
+        sequence_number: Option<u64>,
+        epoch: Option<u32>,
+    ) -> bool {
+        match sequence_number {
+            Some(sequence_number) => {
+                let window_size = self.window_size;
+                let accepted = self
+                    .windows
+                    .entry(peer)
+                    .or_insert_with(|| PeerReplayWindow::new(window_size, epoch))
+                    .check_and_update(epoch, sequence_number);
+
+                if !accepted {
+                    warn!(
+                        "Dropping a replayed or outdated packet from: {}, sequence number: {}, epoch: {:?}",
+                        peer, sequence_number, epoch
+                    );
+                }
+
+                accepted
+            }
+            None => {
+                if self.windows.contains_key(&peer) {
+                    warn!(
+                        "Dropping a packet without sequence number from: {}, which uses replay protection",
+                        peer
+                    );
+                    false
+                } else {
+                    trace!("Accepting a packet without sequence number from: {}", peer);
+                    true
+                }
+            }
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_window__in_order__should_accept() {
+        let mut window = ReplayWindow::new(4);
+
+        for i in 0..10 {
+            assert!(window.check_and_update(i));
+        }
+    }
+
+    #[test]
+    fn replay_window__duplicate__should_reject() {
+        let mut window = ReplayWindow::new(4);
+
+        assert!(window.check_and_update(5));
+        assert!(!window.check_and_update(5));
+        assert!(window.check_and_update(6));
+        assert!(!window.check_and_update(5));
+        assert!(!window.check_and_update(6));
+    }
+
+    #[test]
+    fn replay_window__out_of_order_within_window__should_accept_once() {
+        let mut window = ReplayWindow::new(4);
+
+        assert!(window.check_and_update(10));
+        assert!(window.check_and_update(8));
+        assert!(window.check_and_update(7));
+        assert!(!window.check_and_update(8));
+        assert!(window.check_and_update(9));
+        assert!(!window.check_and_update(9));
+    }
+
+    #[test]
+    fn replay_window__too_old__should_reject() {
+        let mut window = ReplayWindow::new(4);
+
+        assert!(window.check_and_update(10));
+        assert!(!window.check_and_update(6));
+        assert!(window.check_and_update(20));
+        assert!(!window.check_and_update(10));
+        assert!(window.check_and_update(17));
+    }
+
+    #[test]
+    fn replay_window__replay_after_window_advanced__should_reject() {
+        let mut window = ReplayWindow::new(4);
+
+        for i in 1..=20 {
+            assert!(window.check_and_update(i));
+        }
+
+        // The first datagrams are replayed, none of them resets the window
+        for i in 1..=16 {
+            assert!(!window.check_and_update(i));
+        }
+        assert!(window.check_and_update(21));
+    }
+
+    #[test]
+    fn replay_protection__new_epoch__should_reset() {
+        let mut protection = ReplayProtection::new(4);
+        let peer: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+
+        for i in 1..=20 {
+            assert!(protection.check(peer, Some(i), Some(1)));
+        }
+
+        // The peer restarted with a new epoch, its sequence numbers start from 1 again
+        assert!(protection.check(peer, Some(1), Some(2)));
+        assert!(!protection.check(peer, Some(1), Some(2)));
+        assert!(protection.check(peer, Some(2), Some(2)));
+    }
+
+    #[test]
+    fn replay_protection__previous_epoch__should_reject() {
+        let mut protection = ReplayProtection::new(4);
+        let peer: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+
+        for i in 1..=20 {
+            assert!(protection.check(peer, Some(i), Some(1)));
+        }
+        assert!(protection.check(peer, Some(1), Some(2)));
+
+        // The datagrams of the previous epoch are replayed, including recent ones
+        for i in 1..=20 {
+            assert!(!protection.check(peer, Some(i), Some(1)));
+        }
+        assert!(protection.check(peer, Some(2), Some(2)));
+    }
+
+    #[test]
+    fn replay_protection__unsequenced_after_sequenced__should_reject() {
+        let mut protection = ReplayProtection::new(4);
+        let peer1: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let peer2: SocketAddr = "127.0.0.1:2000".parse().unwrap();
+
+        assert!(protection.check(peer1, None, None));
+        assert!(protection.check(peer1, Some(1), Some(1)));
+        assert!(!protection.check(peer1, None, None));
+        assert!(!protection.check(peer1, Some(1), Some(1)));
+
+        assert!(protection.check(peer2, None, None));
+        assert!(protection.check(peer2, Some(1), Some(1)));
+    }
+}
@@ -1,15 +1,19 @@
-use super::{Addresses, UdpSocketWrite};
+use super::{Addresses, FlushRequests, UdpSocketWrite};
+use crate::local_info::UDP_KEEPALIVE_IDENTIFIER;
 use crate::messages::{RoutingNumber, UdpRoutingMessage};
 use crate::workers::pending_messages::TransportMessagesIterator;
-use crate::UDP;
+use crate::{
+    FragmentRetransmission, UdpBindActivity, UdpBindOptions, UdpBindState, UdpBindStats,
+    UdpCompression, UdpTransportError, UDP,
+};
 use core::str::FromStr;
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{async_trait, Any, Error, Result, Routed, Worker};
+use ockam_core::{async_trait, Any, Error, LocalMessage, MessagePriority, Result, Routed, Worker};
 use ockam_node::compat::asynchronous::resolve_peer;
 use ockam_node::Context;
 use ockam_transport_core::{HostnamePort, TransportError};
 use std::net::SocketAddr;
-use tracing::{error, trace, warn};
+use tracing::{debug, error, trace, warn};
 
 /// A sender for the UDP transport
 ///
@@ -24,6 +28,22 @@ pub(crate) struct UdpSenderWorker {
     /// Current number of the packet
     current_routing_number: RoutingNumber,
     max_payload_size_per_packet: usize,
+    /// Next sequence number, if replay protection is enabled
+    sequence_number: Option<u64>,
+    /// Random epoch of the sequence numbers, chosen when the bind starts, so that the receiver
+    /// can tell a restart of this sender from a replay of its previous datagrams
+    epoch: Option<u32>,
+    /// Refuse messages that don't fit into one packet
+    no_fragmentation: bool,
+    /// Compress the messages before splitting them
+    compression: Option<UdpCompression>,
+    /// Send the datagrams of critical messages several times
+    fragment_retransmission: Option<FragmentRetransmission>,
+    /// Priority of the last sent message, mapped to the Type of Service of the datagrams
+    current_priority: MessagePriority,
+    stats: UdpBindStats,
+    activity: UdpBindActivity,
+    flush_requests: FlushRequests,
 }
 
 impl UdpSenderWorker {
@@ -32,36 +52,62 @@ impl UdpSenderWorker {
         addresses: Addresses,
         socket_write: UdpSocketWrite,
         peer: Option<SocketAddr>,
-        max_payload_size_per_packet: usize,
+        options: &UdpBindOptions,
+        state: UdpBindState,
     ) -> Self {
+        let replay_protection = options.replay_protection_window.is_some();
         Self {
             addresses,
             socket_write,
             peer,
             current_routing_number: RoutingNumber::default(),
-            max_payload_size_per_packet,
+            max_payload_size_per_packet: options.size_options.max_payload_size_per_packet,
+            sequence_number: replay_protection.then_some(1),
+            epoch: replay_protection.then(rand::random),
+            no_fragmentation: options.no_fragmentation,
+            compression: options.compression,
+            fragment_retransmission: options.fragment_retransmission,
+            current_priority: MessagePriority::default(),
+            stats: state.stats,
+            activity: state.activity,
+            flush_requests: state.flush_requests,
         }
     }
-}
 
-#[async_trait]
-impl Worker for UdpSenderWorker {
-    type Message = Any;
-    type Context = Context;
+    /// Mark the next datagrams with the Type of Service corresponding to the message priority
+    ///
+    /// The Type of Service is set on the socket since it is only used by this worker, this is
+    /// best effort: the message is still sent if the platform doesn't support it.
+    fn set_priority(&mut self, priority: MessagePriority) {
+        if priority == self.current_priority {
+            return;
+        }
 
-    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
-        let _ = ctx.stop_address(self.addresses.receiver_address());
+        match self.socket_write.set_tos(priority.tos() as u32) {
+            Ok(()) => self.current_priority = priority,
+            Err(e) => debug!(?priority, %e, "Can't set the Type of Service of the UDP socket"),
+        }
+    }
 
-        Ok(())
+    async fn send_datagram(&mut self, datagram: &[u8], peer: SocketAddr) -> Result<()> {
+        match self.socket_write.send_to(datagram, peer).await {
+            Ok(_) => {
+                self.stats.record_packet_sent(datagram.len(), peer);
+                self.activity.record_sent();
+                trace!("Successful send to {}", peer);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed send to {}: {:?}", peer, e);
+                Err(Error::new(Origin::Transport, Kind::Io, e))
+            }
+        }
     }
 
-    async fn handle_message(
-        &mut self,
-        _ctx: &mut Context,
-        msg: Routed<Self::Message>,
-    ) -> Result<()> {
-        // Parse message and remove our address from its routing
-        let mut msg = msg.into_local_message();
+    async fn send_message(&mut self, mut msg: LocalMessage) -> Result<()> {
+        // Remove our address from its routing
+        let priority = msg.priority();
+        self.set_priority(priority);
         msg = msg.pop_front_onward_route()?;
         trace!("Sending message to {:?}", msg.onward_route());
 
@@ -88,24 +134,70 @@ impl Worker for UdpSenderWorker {
             return Err(TransportError::InvalidAddress(peer.to_string()))?;
         }
 
+        let keepalive = msg
+            .local_info()
+            .iter()
+            .any(|local_info| local_info.type_identifier() == UDP_KEEPALIVE_IDENTIFIER);
+        let routing_message = if keepalive {
+            trace!("Sending a keepalive to {}", peer);
+            UdpRoutingMessage::keepalive()
+        } else {
+            UdpRoutingMessage::from(msg)
+        };
+
         // Serialize a [`LocalMessage`] into a vector of smaller messages suitable for 1 UDP datagram
         let messages = TransportMessagesIterator::new(
             self.current_routing_number,
-            &UdpRoutingMessage::from(msg),
+            &routing_message,
             self.max_payload_size_per_packet,
-        )?;
+        )?
+        .with_compression(self.compression)?
+        .with_sequence_number(self.sequence_number)
+        .with_epoch(self.epoch);
+
+        if self.no_fragmentation && messages.total() > 1 {
+            warn!(
+                size = messages.data_len(),
+                "Message doesn't fit into a single datagram"
+            );
+            return Err(UdpTransportError::MessageTooLarge {
+                size: messages.data_len(),
+                max_size: self.max_payload_size_per_packet,
+            })?;
+        }
 
         self.current_routing_number.increment();
+        if !keepalive {
+            self.stats.record_message_sent(messages.message_len());
+        }
 
-        for message in messages {
-            let message = message?;
-            match self.socket_write.send_to(&message, peer).await {
-                Ok(_) => {
-                    trace!("Successful send to {}", peer);
-                }
-                Err(e) => {
-                    error!("Failed send to {}: {:?}", peer, e);
-                    return Err(Error::new(Origin::Transport, Kind::Io, e))?;
+        let copies = self
+            .fragment_retransmission
+            .map_or(1, |retransmission| retransmission.copies(priority));
+
+        let first_sequence_number = self.sequence_number;
+        let total = messages.total() as u64;
+        if let Some(sequence_number) = &mut self.sequence_number {
+            *sequence_number = sequence_number.wrapping_add(copies as u64 * total);
+        }
+
+        if copies == 1 {
+            for message in messages {
+                self.send_datagram(&message?, peer).await?;
+            }
+            return Ok(());
+        }
+
+        // The copies of a datagram have the same routing number and offset, so that the
+        // receiver ignores them once reassembled. Each copy has its own sequence number though,
+        // otherwise the replay protection of the receiver would drop the copies of a lost datagram
+        for copy in 0..copies {
+            let sequence_number =
+                first_sequence_number.map(|n| n.wrapping_add(copy as u64 * total));
+            for datagram in messages.clone().with_sequence_number(sequence_number) {
+                self.send_datagram(&datagram?, peer).await?;
+                if copy > 0 {
+                    self.stats.record_packet_retransmitted();
                 }
             }
         }
@@ -113,3 +205,37 @@ impl Worker for UdpSenderWorker {
         Ok(())
     }
 }
+
+#[async_trait]
+impl Worker for UdpSenderWorker {
+    type Message = Any;
+    type Context = Context;
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        for receiver_address in self.addresses.receiver_addresses() {
+            let _ = ctx.stop_address(receiver_address);
+        }
+
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        _ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let msg = msg.into_local_message();
+        if self
+            .flush_requests
+            .handle_message(&msg, self.stats.flush_counters())
+        {
+            return Ok(());
+        }
+
+        let result = self.send_message(msg).await;
+        if result.is_err() {
+            self.stats.record_send_failure();
+        }
+        result
+    }
+}
@@ -1,4 +1,5 @@
-use ockam_core::compat::sync::Arc;
+use ockam_core::compat::sync::{Arc, Weak};
+use socket2::SockRef;
 use std::io;
 use std::net::SocketAddr;
 use tokio::net::{ToSocketAddrs, UdpSocket};
@@ -16,6 +17,11 @@ impl UdpSocketRead {
     pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
         self.0.recv_from(buf).await
     }
+
+    /// Reference to the socket which doesn't keep it open
+    pub fn downgrade(&self) -> Weak<UdpSocket> {
+        Arc::downgrade(&self.0)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -25,4 +31,33 @@ impl UdpSocketWrite {
     pub async fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], target: A) -> io::Result<usize> {
         self.0.send_to(buf, target).await
     }
+
+    /// Set the IPv4 Type of Service of the next datagrams
+    pub fn set_tos(&self, tos: u32) -> io::Result<()> {
+        SockRef::from(self.0.as_ref()).set_tos(tos)
+    }
+
+    /// IPv4 Type of Service of the sent datagrams
+    #[cfg(test)]
+    pub fn tos(&self) -> io::Result<u32> {
+        SockRef::from(self.0.as_ref()).tos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::MessagePriority;
+
+    #[tokio::test]
+    async fn test_set_tos() -> io::Result<()> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        let (_socket_read, socket_write) = split_socket(socket);
+
+        let tos = MessagePriority::Urgent.tos() as u32;
+        socket_write.set_tos(tos)?;
+        assert_eq!(socket_write.tos()?, tos);
+
+        Ok(())
+    }
 }
@@ -1,9 +1,15 @@
 use ockam_core::compat::rand::{self, Rng};
-use ockam_core::{route, Result, Routed, Worker};
-use ockam_node::{Context, MessageSendReceiveOptions};
+use ockam_core::errcode::Kind;
+use ockam_core::{route, Address, AllowAll, MessagePriority, Result, Routed, Worker};
+use ockam_node::{Context, MessageReceiveOptions, MessageSendReceiveOptions, NodeBuilder};
 use ockam_transport_core::MAXIMUM_MESSAGE_LENGTH;
-use ockam_transport_udp::{UdpBindArguments, UdpBindOptions, UdpTransport, UDP};
+use ockam_transport_udp::{
+    MockClock, PunctureError, PunctureState, UdpBind, UdpBindArguments, UdpBindOptions,
+    UdpPunctureOptions, UdpTransport, MAX_MESSAGE_SIZE, RESUME_PUNCTURE_TIMEOUT, UDP,
+};
+use std::error::Error as _;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, trace};
 
@@ -425,6 +431,1125 @@ async fn send_receive_large_message(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
+#[ockam_macros::test]
+async fn send_receive_with_replay_protection(ctx: &mut Context) -> Result<()> {
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_replay_protection(64),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_replay_protection(64),
+        )
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    for len in [256, MAXIMUM_MESSAGE_LENGTH] {
+        let msg: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(len)
+            .map(char::from)
+            .collect();
+
+        let r = route![
+            bind1.sender_address().clone(),
+            (UDP, bind2.bind_address().to_string()),
+            "echoer"
+        ];
+        let reply = ctx
+            .send_and_receive_extended::<String>(
+                r,
+                msg.clone(),
+                MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+            )
+            .await?
+            .into_body()?;
+
+        assert_eq!(reply, msg, "Should receive the same message");
+    }
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_with_replay_protection_after_restart(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_replay_protection(64),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_replay_protection(64),
+        )
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let peer_address = bind2.bind_address().to_string();
+    echo_messages(ctx, &bind1, &peer_address).await?;
+
+    // The sender restarts on the same local address, its sequence numbers start again in a
+    // new epoch
+    let bind_address = bind1.bind_address();
+    bind1.close(ctx).await?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new().with_bind_socket_address(bind_address),
+            UdpBindOptions::new().with_replay_protection(64),
+        )
+        .await?;
+    echo_messages(ctx, &bind1, &peer_address).await?;
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn bind_with_empty_replay_window_fails(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let res = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_replay_protection(0),
+        )
+        .await;
+    assert!(res.is_err(), "An empty replay window should be rejected");
+
+    Ok(())
+}
+
+#[cfg(feature = "compression-lz4")]
+#[ockam_macros::test]
+async fn send_receive_with_compression(ctx: &mut Context) -> Result<()> {
+    use ockam_transport_udp::UdpCompression;
+
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    // Only the first bind compresses, the second one still decompresses the messages
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_compression(UdpCompression::Lz4),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let msg = "Hello, Ockam! ".repeat(1000);
+
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, bind2.bind_address().to_string()),
+        "echoer"
+    ];
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            r,
+            msg.clone(),
+            MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+        )
+        .await?
+        .into_body()?;
+
+    assert_eq!(reply, msg, "Should receive the same message");
+    // The reply is not compressed
+    assert!(bind1.stats().packets_sent() < bind2.stats().packets_sent());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_with_fragment_retransmission(ctx: &mut Context) -> Result<()> {
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_fragment_retransmission(2, MessagePriority::High),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, bind2.bind_address().to_string()),
+        "echoer"
+    ];
+
+    for priority in [MessagePriority::Normal, MessagePriority::Urgent] {
+        let msg: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(2048)
+            .map(char::from)
+            .collect();
+
+        let packets_sent = bind1.stats().packets_sent();
+        let packets_retransmitted = bind1.stats().packets_retransmitted();
+
+        let mut child_ctx = ctx.new_detached(Address::random_local(), AllowAll, AllowAll)?;
+        child_ctx.set_message_priority(priority);
+        let reply = child_ctx
+            .send_and_receive_extended::<String>(
+                r.clone(),
+                msg.clone(),
+                MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+            )
+            .await?
+            .into_body()?;
+
+        assert_eq!(reply, msg, "Should receive the same message once");
+
+        // Only the datagrams of the urgent message are sent 3 times
+        let packets_sent = bind1.stats().packets_sent() - packets_sent;
+        let packets_retransmitted = bind1.stats().packets_retransmitted() - packets_retransmitted;
+        if priority == MessagePriority::Urgent {
+            assert_eq!(packets_retransmitted * 3, packets_sent * 2);
+        } else {
+            assert_eq!(packets_retransmitted, 0);
+        }
+    }
+    assert_eq!(bind2.stats().messages_received(), 2);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_with_fragment_retransmission_and_replay_protection(
+    ctx: &mut Context,
+) -> Result<()> {
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new()
+                .with_replay_protection(64)
+                .with_fragment_retransmission(2, MessagePriority::High),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_replay_protection(64),
+        )
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, bind2.bind_address().to_string()),
+        "echoer"
+    ];
+
+    let msg: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(2048)
+        .map(char::from)
+        .collect();
+
+    let mut child_ctx = ctx.new_detached(Address::random_local(), AllowAll, AllowAll)?;
+    child_ctx.set_message_priority(MessagePriority::Urgent);
+    let reply = child_ctx
+        .send_and_receive_extended::<String>(
+            r,
+            msg.clone(),
+            MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+        )
+        .await?
+        .into_body()?;
+
+    assert_eq!(reply, msg, "Should receive the same message once");
+    assert!(bind1.stats().packets_retransmitted() > 0);
+
+    // The copies pass the replay protection, and are ignored by the reassembly
+    assert_eq!(bind2.stats().packets_dropped(), 0);
+    assert_eq!(bind2.stats().messages_received(), 1);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_without_fragmentation(ctx: &mut Context) -> Result<()> {
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(false))?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().no_fragmentation(),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().no_fragmentation(),
+        )
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, bind2.bind_address().to_string()),
+        "echoer"
+    ];
+
+    // A message fitting into one datagram is delivered
+    let msg = "Hello, Ockam!".to_string();
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            r.clone(),
+            msg.clone(),
+            MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+        )
+        .await?
+        .into_body()?;
+    assert_eq!(reply, msg, "Should receive the same message");
+
+    // A message which would need to be split is dropped by the sender
+    let msg: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(MAXIMUM_MESSAGE_LENGTH)
+        .map(char::from)
+        .collect();
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            r,
+            msg,
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_secs(1)),
+        )
+        .await;
+    assert!(reply.is_err(), "Should not receive the message");
+    assert_eq!(bind1.stats().packets_sent(), 1);
+    assert_eq!(bind1.stats().sent_message_sizes().count(), 1);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_with_session(ctx: &mut Context) -> Result<()> {
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(false))?;
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind3 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let mut session = bind1.connect(ctx, bind2.bind_address().to_string()).await?;
+    assert_eq!(session.peer(), bind2.bind_address());
+
+    // A message from another peer is dropped by the session
+    ctx.send(
+        route![
+            bind3.sender_address().clone(),
+            (UDP, bind1.bind_address().to_string()),
+            session.address().clone()
+        ],
+        "Intruder".to_string(),
+    )
+    .await?;
+
+    session.send("echoer", "Hello".to_string()).await?;
+    let reply = session
+        .recv_with_timeout::<String>(TIMEOUT)
+        .await?
+        .into_body()?;
+    assert_eq!(reply, "Hello");
+
+    // After the peer changed, only its messages are received
+    session.reconnect(bind3.bind_address().to_string()).await?;
+    ctx.send(
+        route![
+            bind3.sender_address().clone(),
+            (UDP, bind1.bind_address().to_string()),
+            session.address().clone()
+        ],
+        "Hello again".to_string(),
+    )
+    .await?;
+    let msg = session
+        .recv_with_timeout::<String>(TIMEOUT)
+        .await?
+        .into_body()?;
+    assert_eq!(msg, "Hello again");
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn session_with_hostname_and_resolution_interval(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(false))?;
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind3 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind2.flow_control_id());
+
+    let mut session = bind1
+        .connect(ctx, format!("localhost:{}", bind2.bind_address().port()))
+        .await?
+        .with_resolution_interval(Duration::from_millis(50));
+    assert_eq!(session.peer(), bind2.bind_address());
+
+    // The peer is resolved again, and stays the same
+    ctx.sleep(Duration::from_millis(150)).await;
+    assert_eq!(session.peer(), bind2.bind_address());
+
+    session.send("echoer", "Hello".to_string()).await?;
+    let reply = session
+        .recv_with_timeout::<String>(TIMEOUT)
+        .await?
+        .into_body()?;
+    assert_eq!(reply, "Hello");
+
+    // After a reconnection, the new hostname is resolved
+    session
+        .reconnect(format!("localhost:{}", bind3.bind_address().port()))
+        .await?;
+    ctx.sleep(Duration::from_millis(150)).await;
+    assert_eq!(session.peer(), bind3.bind_address());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_keepalive_with_session(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let session = bind1.connect(ctx, bind2.bind_address().to_string()).await?;
+    session.send_keepalive().await?;
+    session.send_keepalive().await?;
+    ctx.sleep(Duration::from_millis(250)).await;
+
+    assert_eq!(bind1.stats().packets_sent(), 2);
+    assert_eq!(bind2.stats().keepalives_received(), 2);
+    assert_eq!(bind2.stats().messages_received(), 0);
+    assert_eq!(bind2.stats().packets_dropped(), 0);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn resume_puncture_with_unreachable_peer(ctx: &mut Context) -> Result<()> {
+    // Transport
+    let transport = UdpTransport::create(ctx)?;
+
+    // The puncture only times out when the clock is advanced
+    let clock = MockClock::new();
+    let bind = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_clock(clock.clone()),
+        )
+        .await?;
+    let peer_bind = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    // There is no puncture worker on the peer's side to answer
+    let state = PunctureState {
+        bind_address: bind.bind_address().to_string(),
+        peer_udp_address: peer_bind.bind_address().to_string(),
+        my_remote_address: Address::random_tagged("my_puncture"),
+        their_remote_address: Address::random_tagged("their_puncture"),
+    };
+
+    let mut puncture =
+        transport.resume_puncture(bind.clone(), state.clone(), UdpPunctureOptions::new())?;
+    assert_eq!(puncture.export_state(), state);
+
+    advance_clock(
+        ctx,
+        &clock,
+        RESUME_PUNCTURE_TIMEOUT + Duration::from_secs(2),
+    )
+    .await;
+
+    let res = puncture.wait_for_puncture(TIMEOUT).await;
+    assert!(res.is_err(), "The puncture should be closed");
+    assert_eq!(bind.stats().punctures_failed(), 1);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn resumed_puncture_carries_traffic(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(false))?;
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let address1 = Address::random_tagged("puncture1");
+    let address2 = Address::random_tagged("puncture2");
+    let mut puncture1 = transport.puncture(
+        bind1.clone(),
+        bind2.bind_address().to_string(),
+        address1.clone(),
+        address2.clone(),
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    let mut puncture2 = transport.puncture(
+        bind2,
+        bind1.bind_address().to_string(),
+        address2,
+        address1,
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    puncture1.wait_for_puncture(TIMEOUT).await?;
+    puncture2.wait_for_puncture(TIMEOUT).await?;
+
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), puncture2.flow_control_id());
+
+    // Our side of the puncture restarts on the same local address, the peer keeps its side
+    let state = puncture1.export_state();
+    transport.stop_puncture(puncture1)?;
+    let bind_address = bind1.bind_address();
+    bind1.close(ctx).await?;
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new().with_bind_socket_address(bind_address),
+            UdpBindOptions::new(),
+        )
+        .await?;
+
+    let mut resumed = transport.resume_puncture(bind1, state, UdpPunctureOptions::new())?;
+    resumed.wait_for_puncture(TIMEOUT).await?;
+
+    // The messages reach the peer's worker and the replies come back
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            route![resumed.sender_address(), "echoer"],
+            "Hello".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+        )
+        .await?
+        .into_body()?;
+    assert_eq!(reply, "Hello");
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn puncture_closed_when_peer_stops_answering(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    // The heartbeats of the punctures are only sent when the clock is advanced
+    let clock = MockClock::new();
+    let bind1 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_clock(clock.clone()),
+        )
+        .await?;
+    let bind2 = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_clock(clock.clone()),
+        )
+        .await?;
+
+    let address1 = Address::random_tagged("puncture1");
+    let address2 = Address::random_tagged("puncture2");
+    let mut puncture1 = transport.puncture(
+        bind1.clone(),
+        bind2.bind_address().to_string(),
+        address1.clone(),
+        address2.clone(),
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    let mut puncture2 = transport.puncture(
+        bind2,
+        bind1.bind_address().to_string(),
+        address2,
+        address1,
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+
+    // The first pings may be sent before the other puncture is started
+    for puncture in [&mut puncture1, &mut puncture2] {
+        let mut open = false;
+        for _ in 0..5 {
+            if puncture
+                .wait_for_puncture(Duration::from_millis(100))
+                .await
+                .is_ok()
+            {
+                open = true;
+                break;
+            }
+            clock.advance(Duration::from_secs(1));
+        }
+        assert!(open, "The puncture should be open");
+    }
+
+    // Nobody answers the pings of the first puncture anymore
+    transport.stop_puncture(puncture2)?;
+    advance_clock(ctx, &clock, Duration::from_secs(15)).await;
+
+    assert_eq!(bind1.stats().punctures_failed(), 1);
+    assert_eq!(transport.active_punctures_count(), 0);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn bind_effective_config(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let default_bind = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let default_config = default_bind.effective_config();
+    assert!(!default_config.no_fragmentation);
+    assert_eq!(default_config.max_message_size, MAX_MESSAGE_SIZE);
+    assert_eq!(default_config.replay_protection_window, None);
+    assert_eq!(default_config.receivers, 1);
+
+    let bind = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new()
+                .with_replay_protection(64)
+                .no_fragmentation()
+                .with_fragment_retransmission(2, MessagePriority::High)
+                .with_reassembly_memory_budget(1024),
+        )
+        .await?;
+    let config = bind.effective_config();
+
+    // The room needed by the sequence numbers is taken from the payload of the datagrams
+    assert!(
+        config.size_options.max_payload_size_per_packet
+            < default_config.size_options.max_payload_size_per_packet
+    );
+    assert!(config.no_fragmentation);
+    assert_eq!(
+        config.max_message_size,
+        config.size_options.max_payload_size_per_packet
+    );
+    assert_eq!(config.replay_protection_window, Some(64));
+    assert_eq!(
+        config.fragment_retransmission,
+        Some((2, MessagePriority::High))
+    );
+    assert_eq!(config.reassembly_memory_budget_per_receiver, Some(1024));
+    assert_eq!(config.recv_buffer_size, bind.recv_buffer_size());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn bind_with_buffer_sizes(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    const BUFFER_SIZE: usize = 64 * 1024;
+    let bind = transport
+        .bind(
+            UdpBindArguments::new()
+                .with_recv_buffer_size(BUFFER_SIZE)
+                .with_send_buffer_size(BUFFER_SIZE),
+            UdpBindOptions::new(),
+        )
+        .await?;
+
+    // The OS may adjust the requested sizes, but they are small enough to not be clamped
+    assert!(bind.recv_buffer_size() >= BUFFER_SIZE);
+    assert!(bind.send_buffer_size() >= BUFFER_SIZE);
+
+    // Messages still go through
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), bind.flow_control_id());
+
+    let route = route![
+        bind.sender_address().clone(),
+        (UDP, bind.bind_address().to_string()),
+        "echoer"
+    ];
+    let res: Routed<String> = ctx
+        .send_and_receive_extended(
+            route,
+            String::from("Hola"),
+            MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+        )
+        .await?;
+    assert_eq!(res.into_body()?, "Hola");
+
+    Ok(())
+}
+
+#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+#[ockam_macros::test]
+async fn send_receive_with_reuseport(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let server = transport
+        .bind_reuseport(UdpBindArguments::new(), UdpBindOptions::new(), 4)
+        .await?;
+    assert_eq!(server.receiver_addresses().len(), 4);
+
+    ctx.start_worker("echoer", Echoer::new(false))?;
+    ctx.flow_controls()
+        .add_consumer(&"echoer".into(), server.flow_control_id());
+
+    // The datagrams of different clients may be received by different sockets
+    for _ in 0..8 {
+        let client = transport
+            .bind(UdpBindArguments::new(), UdpBindOptions::new())
+            .await?;
+        let route = route![
+            client.sender_address().clone(),
+            (UDP, server.bind_address().to_string()),
+            "echoer"
+        ];
+        let res: Routed<String> = ctx
+            .send_and_receive_extended(
+                route,
+                String::from("Hola"),
+                MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+            )
+            .await?;
+        assert_eq!(res.into_body()?, "Hola");
+        transport.unbind(client.sender_address())?;
+    }
+
+    transport.unbind(server.sender_address())?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    for receiver_address in server.receiver_addresses() {
+        assert!(!ctx.is_worker_registered_at(&receiver_address)?);
+    }
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn close_bind_and_rebind_same_port(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let bind = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind_address = bind.bind_address();
+    let sender_address = bind.sender_address().clone();
+    let receiver_address = bind.receiver_address().clone();
+
+    bind.close(ctx).await?;
+
+    assert!(!ctx.is_worker_registered_at(&sender_address)?);
+    assert!(!ctx.is_worker_registered_at(&receiver_address)?);
+
+    // Without the reuseport option, binding succeeds only if the previous socket is closed
+    let bind = transport
+        .bind(
+            UdpBindArguments::new().with_bind_socket_address(bind_address),
+            UdpBindOptions::new(),
+        )
+        .await?;
+    assert_eq!(bind.bind_address(), bind_address);
+
+    bind.close(ctx).await?;
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn list_and_unbind_all_binds(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+    assert!(transport.binds().is_empty());
+
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let binds = transport.binds();
+    assert_eq!(binds.len(), 2);
+    for (info, bind) in binds.iter().zip([&bind1, &bind2]) {
+        assert_eq!(info.bind_address, bind.bind_address());
+        assert_eq!(&info.sender_address, bind.sender_address());
+        assert_eq!(info.receiver_addresses, bind.receiver_addresses());
+        assert_eq!(&info.flow_control_id, bind.flow_control_id());
+        assert_eq!(info.peer, None);
+    }
+
+    // Stopped binds are not listed anymore
+    for info in binds {
+        transport.unbind(&info.sender_address)?;
+    }
+    assert!(transport.binds().is_empty());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn puncture_with_conflicting_options(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let bind = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let peer_bind = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let res = transport.puncture(
+        bind.clone(),
+        peer_bind.bind_address().to_string(),
+        Address::random_tagged("my_puncture"),
+        Address::random_tagged("their_puncture"),
+        UdpPunctureOptions::new().with_redirect_first_message_to_transport(false),
+        true,
+    );
+    assert_eq!(res.err().unwrap().code().kind, Kind::Conflict);
+
+    // A resumed puncture never redirects its first messages
+    let state = PunctureState {
+        bind_address: bind.bind_address().to_string(),
+        peer_udp_address: peer_bind.bind_address().to_string(),
+        my_remote_address: Address::random_tagged("my_puncture"),
+        their_remote_address: Address::random_tagged("their_puncture"),
+    };
+    let res = transport.resume_puncture(
+        bind,
+        state,
+        UdpPunctureOptions::new().with_redirect_first_message_to_transport(true),
+    );
+    assert_eq!(res.err().unwrap().code().kind, Kind::Conflict);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn update_puncture_peer_address(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let unused_bind = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let address1 = Address::random_tagged("puncture1");
+    let address2 = Address::random_tagged("puncture2");
+    let mut puncture1 = transport.puncture(
+        bind1.clone(),
+        bind2.bind_address().to_string(),
+        address1.clone(),
+        address2.clone(),
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    let mut puncture2 = transport.puncture(
+        bind2,
+        bind1.bind_address().to_string(),
+        address2,
+        address1,
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    puncture1.wait_for_puncture(TIMEOUT).await?;
+    puncture2.wait_for_puncture(TIMEOUT).await?;
+
+    // The round-trip time is measured with the pong which opened the puncture
+    let rtt = puncture1.rtt().unwrap();
+    assert!(rtt < TIMEOUT);
+
+    // The peer confirms the new address
+    puncture2
+        .update_peer_address(bind1.bind_address().to_string(), TIMEOUT)
+        .await?;
+    assert_eq!(
+        puncture2.export_state().peer_udp_address,
+        bind1.bind_address().to_string()
+    );
+
+    // Nobody answers on this address
+    let res = puncture1
+        .update_peer_address(
+            unused_bind.bind_address().to_string(),
+            Duration::from_secs(2),
+        )
+        .await;
+    let err = res.expect_err("The new address should not be confirmed");
+    assert_eq!(err.code().kind, Kind::Timeout);
+    assert!(matches!(
+        err.source().and_then(|e| e.downcast_ref::<PunctureError>()),
+        Some(PunctureError::ConfirmTimeout(_))
+    ));
+    assert_eq!(bind1.stats().punctures_failed(), 1);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn max_concurrent_punctures(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+    transport.set_max_concurrent_punctures(Some(1));
+    assert_eq!(transport.max_concurrent_punctures(), Some(1));
+
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let puncture = transport.puncture(
+        bind1.clone(),
+        bind2.bind_address().to_string(),
+        Address::random_tagged("my_puncture"),
+        Address::random_tagged("their_puncture"),
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    assert_eq!(transport.active_punctures_count(), 1);
+
+    let res = transport.puncture(
+        bind2.clone(),
+        bind1.bind_address().to_string(),
+        Address::random_tagged("my_puncture"),
+        Address::random_tagged("their_puncture"),
+        UdpPunctureOptions::new(),
+        false,
+    );
+    assert_eq!(res.err().unwrap().code().kind, Kind::ResourceExhausted);
+
+    // Stopping a puncture frees a slot
+    transport.stop_puncture(puncture)?;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(transport.active_punctures_count(), 0);
+
+    let _puncture = transport.puncture(
+        bind2,
+        bind1.bind_address().to_string(),
+        Address::random_tagged("my_puncture"),
+        Address::random_tagged("their_puncture"),
+        UdpPunctureOptions::new(),
+        false,
+    )?;
+    assert_eq!(transport.active_punctures_count(), 1);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_with_source_allowlist(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    ctx.start_worker("echoer", Echoer::new(true))?;
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let denying_bind = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_source_allowlist(vec!["10.0.0.0/8".parse().unwrap()]),
+        )
+        .await?;
+    let allowing_bind = transport
+        .bind(
+            UdpBindArguments::new(),
+            UdpBindOptions::new().with_source_allowlist(vec!["127.0.0.0/8".parse().unwrap()]),
+        )
+        .await?;
+
+    for bind in [&denying_bind, &allowing_bind] {
+        ctx.flow_controls()
+            .add_consumer(&"echoer".into(), bind.flow_control_id());
+    }
+
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, denying_bind.bind_address().to_string()),
+        "echoer"
+    ];
+    let result = ctx
+        .send_and_receive_extended::<String>(
+            r,
+            "Hello".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(result.is_err());
+    assert_eq!(denying_bind.stats().dropped_unauthorized(), 1);
+    assert_eq!(denying_bind.stats().messages_received(), 0);
+    assert_eq!(denying_bind.stats().packets_received(), 0);
+    assert!(denying_bind.stats().peers().is_empty());
+
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, allowing_bind.bind_address().to_string()),
+        "echoer"
+    ];
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            r,
+            "Hello".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+        )
+        .await?
+        .into_body()?;
+    assert_eq!(reply, "Hello");
+    assert_eq!(allowing_bind.stats().dropped_unauthorized(), 0);
+
+    Ok(())
+}
+
+/// The node runs on a single thread, so that the sender can't handle the queued messages
+/// between the check of the counters and the start of the flush
+#[test]
+fn flush_sends_the_queued_messages() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (mut ctx, mut executor) = NodeBuilder::new()
+        .no_exit_on_panic()
+        .with_runtime(Arc::new(runtime))
+        .build();
+    executor.execute(async move {
+        let res = flush_sends_the_queued_messages_impl(&mut ctx).await;
+        ctx.shutdown_node().await?;
+        res
+    })?
+}
+
+async fn flush_sends_the_queued_messages_impl(ctx: &mut Context) -> Result<()> {
+    let transport = UdpTransport::create(ctx)?;
+
+    let bind1 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+    let bind2 = transport
+        .bind(UdpBindArguments::new(), UdpBindOptions::new())
+        .await?;
+
+    let mut receiver_ctx = ctx.new_detached(Address::random_local(), AllowAll, AllowAll)?;
+    ctx.flow_controls()
+        .add_consumer(receiver_ctx.primary_address(), bind2.flow_control_id());
+
+    for _ in 0..10 {
+        let r = route![
+            bind1.sender_address().clone(),
+            (UDP, bind2.bind_address().to_string()),
+            receiver_ctx.primary_address().clone()
+        ];
+        ctx.send(r, "Hello".to_string()).await?;
+    }
+    // A message which can't be sent
+    let r = route![
+        bind1.sender_address().clone(),
+        (UDP, "127.0.0.1:0"),
+        "unknown"
+    ];
+    ctx.send(r, "Hello".to_string()).await?;
+
+    // Nothing runs between this line and the start of the flush
+    let sent_before_flush = bind1.stats().sent_message_sizes().count();
+    let report = bind1.flush(ctx, TIMEOUT).await?;
+    assert!(report.flushed > 0);
+    assert_eq!(report.flushed, 10 - sent_before_flush);
+    assert_eq!(report.dropped, 1);
+    assert_eq!(bind1.stats().sent_message_sizes().count(), 10);
+    assert_eq!(bind1.stats().send_failures(), 1);
+
+    for _ in 0..10 {
+        let msg = receiver_ctx
+            .receive_extended::<String>(MessageReceiveOptions::new().with_timeout(TIMEOUT))
+            .await?
+            .into_body()?;
+        assert_eq!(msg, "Hello");
+    }
+
+    // Nothing is left to flush
+    let report = bind1.flush(ctx, TIMEOUT).await?;
+    assert_eq!(report.flushed, 0);
+    assert_eq!(report.dropped, 0);
+
+    // A stopped bind can't be flushed
+    bind1.clone().close(ctx).await?;
+    assert!(bind1.flush(ctx, Duration::from_millis(100)).await.is_err());
+
+    Ok(())
+}
+
 pub struct Echoer {
     check_sender_is_the_same: bool,
     prev_src_addr: Option<String>,
@@ -482,3 +1607,29 @@ impl Worker for Echoer {
         ctx.send(msg.return_route().clone(), msg.into_body()?).await
     }
 }
+
+/// Advance the clock second by second, giving the punctures some time to handle their
+/// heartbeats in between
+async fn advance_clock(ctx: &Context, clock: &MockClock, duration: Duration) {
+    for _ in 0..duration.as_secs() {
+        clock.advance(Duration::from_secs(1));
+        ctx.sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Send a few messages to the echoer of a peer and check the replies
+async fn echo_messages(ctx: &Context, bind: &UdpBind, peer_address: &str) -> Result<()> {
+    for _ in 0..3 {
+        let reply = ctx
+            .send_and_receive_extended::<String>(
+                route![bind.sender_address().clone(), (UDP, peer_address), "echoer"],
+                "Hello".to_string(),
+                MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+            )
+            .await?
+            .into_body()?;
+        assert_eq!(reply, "Hello");
+    }
+
+    Ok(())
+}